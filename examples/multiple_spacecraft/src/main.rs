@@ -14,14 +14,13 @@ use multibody::{
         SensorBuilder, gps::GpsBuilder, magnetometer::MagnetometerBuilder,
         rate_gyro::RateGyroBuilder, star_tracker::StarTrackerBuilder,
     },
-    software::Software,
+    software::{Software, SoftwareSchedule},
     system::{MultibodySystem, MultibodySystemBuilder},
 };
 use nadir_diffeq::{
     OdeProblem,
-    events::{PeriodicEvent, PostSimEvent, SaveEvent},
+    events::{PostSimEvent, SaveEvent},
     solvers::OdeSolver,
-    state::state_vector::StateVector,
     stepping::AdaptiveStepControl,
 };
 use rotations::{
@@ -268,42 +267,21 @@ fn main() -> Result<(), Box<dyn Error>> {
         std::process::exit(1);
     }
 
+    // each spacecraft's software runs at 1 Hz, stepped by the system's software events
     let software1 = Software::new("fsw1", lib_path_ref)
         .with_actuator_indices(vec![0, 1, 2])
-        .with_sensor_indices(vec![0, 1, 2, 3]);
+        .with_sensor_indices(vec![0, 1, 2, 3])
+        .with_schedule(SoftwareSchedule::new(1.0)?);
 
     let software2 = Software::new("fsw2", lib_path_ref)
         .with_actuator_indices(vec![3, 4, 5])
-        .with_sensor_indices(vec![4, 5, 6, 7]);
+        .with_sensor_indices(vec![4, 5, 6, 7])
+        .with_schedule(SoftwareSchedule::new(1.0)?);
 
     sys.add_software(software1);
     sys.add_software(software2);
 
     let problem = OdeProblem::new(sys.nominal()?)
-        .with_periodic_event(PeriodicEvent::new(
-            1.0,
-            0.0,
-            |sys: &mut MultibodySystem, _x: &mut StateVector, _t| {
-                sys.software[0]
-                    .step(
-                        &sys.sensors,
-                        &mut sys.actuators,
-                    )
-                    .unwrap();
-            },
-        ))
-        .with_periodic_event(PeriodicEvent::new(
-            1.0,
-            0.0,
-            |sys: &mut MultibodySystem, _x: &mut StateVector, _t| {
-                sys.software[1]
-                    .step(
-                        &sys.sensors,
-                        &mut sys.actuators,
-                    )
-                    .unwrap();
-            },
-        ))
         .with_saving(current_dir()?.join("results"))
         .with_save_event(SaveEvent::new(
             MultibodySystem::init_fn,
@@ -37,8 +37,11 @@ pub struct GpsTelemetry {
     time: f64,
     position: [f64; 3],
     velocity: [f64; 3],
+    pseudorange: [f64; 12],
+    prn: [u8; 12],
+    num_pseudoranges: u8,
     valid: u8,
-    _padding: [u8; 7],
+    _padding: [u8; 2],
 }
 
 #[derive(Debug, Default)]
@@ -50,7 +53,7 @@ impl GpsFsw {
             .time = Time::from_sec_j2k(
             self.telemetry
                 .time,
-            time::TimeSystem::TAI,
+            time::TimeSystem::GPS,
         );
         self.state
             .position = self
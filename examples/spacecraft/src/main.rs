@@ -14,22 +14,22 @@ use multibody::{
         SensorBuilder, gps::GpsBuilder, magnetometer::MagnetometerBuilder,
         rate_gyro::RateGyroBuilder, star_tracker::StarTrackerBuilder,
     },
-    software::Software,
+    software::{NativeSoftware, SoftwareSchedule},
     system::{MultibodySystem, MultibodySystemBuilder},
 };
 use nadir_diffeq::{
     OdeProblem,
-    events::{PeriodicEvent, PostSimEvent, SaveEvent},
+    events::{PostSimEvent, SaveEvent},
     monte_carlo::{MonteCarloProblem, MonteCarloSolver},
     solvers::OdeSolver,
-    state::state_vector::StateVector,
     stepping::AdaptiveStepControl,
 };
 use rotations::{
     Rotation,
     prelude::{AlignedAxes, Axis, AxisPair, UnitQuaternion},
 };
-use std::{env::current_dir, error::Error, f64::consts::PI, path::Path};
+use software::SpacecraftFsw;
+use std::{env::current_dir, error::Error, f64::consts::PI};
 use time::Time;
 use transforms::{
     Transform,
@@ -331,45 +331,17 @@ fn main() -> Result<(), Box<dyn Error>> {
     sys.add_actuator(rw3);
     sys.add_actuator(rw4);
 
-    // Add the software
-    let lib_name = match std::env::consts::OS {
-        "windows" => "software.dll",
-        "macos" => "libsoftware.dylib",
-        _ => "libsoftware.so", //Linux
-    };
-
-    let lib_path = format!(
-        "../../target/release/{}",
-        lib_name
-    );
-    let lib_path_ref = Path::new(&lib_path);
-
-    if !lib_path_ref.exists() {
-        eprintln!(
-            "Error: Dynamic library not found at {}\n\
-             Please compile it first with: `cargo build --release --lib` in the src\\software crate.",
-            lib_path
-        );
-        std::process::exit(1);
-    }
-
-    let software = Software::new("fsw", lib_path_ref)
+    // Add the software, compiled with the sim and run in process at 10 Hz
+    // Its steps are scheduled automatically when the problem is built
+    let software = NativeSoftware::new("fsw", SpacecraftFsw::default)
         .with_actuator_indices(vec![0, 1, 2, 3])
-        .with_sensor_indices(vec![0, 1, 2, 3]);
-    sys.add_software(software);
+        .with_sensor_indices(vec![0, 1, 2, 3])
+        .with_schedule(SoftwareSchedule::new(10.0)?);
+    sys.add_native_software(software);
+    // To run the software in its own process instead, build the fsw binary in src/software and add
+    // ProcessSoftware::new("fsw", "target/debug/fsw") with the same indices and schedule
+    // using sys.add_process_software
     // let problem = OdeProblem::new(sys)
-    //     .with_periodic_event(PeriodicEvent::new(
-    //         0.1,
-    //         0.0,
-    //         |sys: &mut MultibodySystem, _x: &mut StateVector, _t| {
-    //             sys.software[0]
-    //                 .step(
-    //                     &sys.sensors,
-    //                     &mut sys.actuators,
-    //                 )
-    //                 .unwrap();
-    //         },
-    //     ))
     //     .with_saving(current_dir()?.join("results"))
     //     .with_save_event(SaveEvent::new(
     //         MultibodySystem::init_fn,
@@ -387,18 +359,6 @@ fn main() -> Result<(), Box<dyn Error>> {
     // )?;
 
     let problem = MonteCarloProblem::new(sys, 16)
-        .with_periodic_event(PeriodicEvent::new(
-            0.1,
-            0.0,
-            |sys: &mut MultibodySystem, _x: &mut StateVector, _t| {
-                sys.software[0]
-                    .step(
-                        &sys.sensors,
-                        &mut sys.actuators,
-                    )
-                    .unwrap();
-            },
-        ))
         .with_saving(current_dir()?.join("results"))
         .with_save_event(SaveEvent::new(
             MultibodySystem::init_fn,
@@ -1,7 +1,10 @@
 use super::control::ControlFsw;
-use multibody::HardwareBuffer;
+use multibody::{
+    schema::{BufferSchema, SchemaErrors},
+    HardwareBuffer,
+};
 use nadir_result::{NadirResult, ResultManager};
-use reaction_wheel::ReactionWheelFsw;
+use reaction_wheel::{ReactionWheelCommand, ReactionWheelFsw};
 pub mod reaction_wheel;
 
 #[derive(Debug, Default)]
@@ -10,6 +13,20 @@ pub struct ActuatorFsw {
 }
 
 impl ActuatorFsw {
+    /// Checks the command structs against the layouts the sim publishes, in write_buffers order
+    pub fn check_schemas(&self, schemas: &[BufferSchema]) -> Result<(), SchemaErrors> {
+        if schemas.len() != 4 {
+            return Err(SchemaErrors::SchemaCount(
+                4,
+                schemas.len(),
+            ));
+        }
+        for schema in schemas {
+            schema.check_layout::<ReactionWheelCommand>(&ReactionWheelCommand::OFFSETS)?;
+        }
+        Ok(())
+    }
+
     pub fn run(&mut self, control: &ControlFsw) {
         self.rw
             .run(control);
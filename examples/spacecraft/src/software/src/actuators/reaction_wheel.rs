@@ -3,6 +3,7 @@ use bytemuck::{Pod, Zeroable};
 use multibody::HardwareBuffer;
 use nadir_result::{NadirResult, ResultManager};
 use nalgebra::Matrix4x3;
+use std::mem::offset_of;
 
 #[derive(Debug, Default, Pod, Zeroable, Copy, Clone)]
 #[repr(C)]
@@ -13,6 +14,13 @@ pub struct ReactionWheelCommand {
 }
 
 impl ReactionWheelCommand {
+    /// Field offsets in declaration order, to check against the sim's schema
+    pub const OFFSETS: [usize; 3] = [
+        offset_of!(ReactionWheelCommand, value),
+        offset_of!(ReactionWheelCommand, command),
+        offset_of!(ReactionWheelCommand, _padding),
+    ];
+
     const TORQUE: u8 = 0;
     const CURRENT: u8 = 1;
     const SPEED: u8 = 2;
@@ -0,0 +1,8 @@
+//! Runs the spacecraft flight software as its own process, for use with multibody's ProcessSoftware
+use software::SpacecraftFsw;
+use std::error::Error;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    multibody::software::ipc::serve(SpacecraftFsw::default())?;
+    Ok(())
+}
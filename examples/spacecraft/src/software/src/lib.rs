@@ -1,10 +1,11 @@
 use actuators::ActuatorFsw;
 use control::ControlFsw;
 use guidance::GuidanceFsw;
-use multibody::HardwareBuffer;
+use multibody::{schema::BufferSchema, software::FlightSoftware, HardwareBuffer};
 use nadir_result::NadirResult;
 use navigation::NavigationFsw;
 use sensors::SensorFsw;
+use std::error::Error;
 
 pub mod actuators;
 mod control;
@@ -69,6 +70,46 @@ impl SpacecraftFsw {
     }
 }
 
+// Allows the software to be run in process without building the dylib
+impl FlightSoftware for SpacecraftFsw {
+    fn step(
+        &mut self,
+        sensors: &[HardwareBuffer],
+        actuators: &mut [HardwareBuffer],
+    ) -> Result<(), Box<dyn Error>> {
+        SpacecraftFsw::step(self, sensors, actuators);
+        Ok(())
+    }
+
+    fn check_schemas(
+        &mut self,
+        sensors: &[BufferSchema],
+        actuators: &[BufferSchema],
+    ) -> Result<(), Box<dyn Error>> {
+        self.sensors
+            .check_schemas(sensors)?;
+        self.actuators
+            .check_schemas(actuators)?;
+        Ok(())
+    }
+
+    fn initialize_results(
+        &mut self,
+        results: &mut nadir_result::ResultManager,
+    ) -> Result<(), Box<dyn Error>> {
+        SpacecraftFsw::initialize_results(self, results);
+        Ok(())
+    }
+
+    fn write_results(
+        &self,
+        results: &mut nadir_result::ResultManager,
+    ) -> Result<(), Box<dyn Error>> {
+        SpacecraftFsw::write_results(self, results);
+        Ok(())
+    }
+}
+
 // Export FFI-compatible functions for dynamic loading
 #[no_mangle]
 pub extern "C" fn initialize_software() -> *mut std::ffi::c_void {
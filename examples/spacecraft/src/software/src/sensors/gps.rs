@@ -2,6 +2,7 @@ use bytemuck::{Pod, Zeroable};
 use multibody::HardwareBuffer;
 use nadir_result::{NadirResult, ResultManager};
 use nalgebra::Vector3;
+use std::mem::offset_of;
 use time::Time;
 
 #[derive(Debug, Default)]
@@ -37,8 +38,25 @@ pub struct GpsTelemetry {
     time: f64,
     position: [f64; 3],
     velocity: [f64; 3],
+    pseudorange: [f64; 12],
+    prn: [u8; 12],
+    num_pseudoranges: u8,
     valid: u8,
-    _padding: [u8; 7],
+    _padding: [u8; 2],
+}
+
+impl GpsTelemetry {
+    /// Field offsets in declaration order, to check against the sim's schema
+    pub const OFFSETS: [usize; 8] = [
+        offset_of!(GpsTelemetry, time),
+        offset_of!(GpsTelemetry, position),
+        offset_of!(GpsTelemetry, velocity),
+        offset_of!(GpsTelemetry, pseudorange),
+        offset_of!(GpsTelemetry, prn),
+        offset_of!(GpsTelemetry, num_pseudoranges),
+        offset_of!(GpsTelemetry, valid),
+        offset_of!(GpsTelemetry, _padding),
+    ];
 }
 
 #[derive(Debug, Default)]
@@ -50,7 +68,7 @@ impl GpsFsw {
             .time = Time::from_sec_j2k(
             self.telemetry
                 .time,
-            time::TimeSystem::TAI,
+            time::TimeSystem::GPS,
         );
         self.state
             .position = self
@@ -3,6 +3,7 @@ use multibody::HardwareBuffer;
 use nadir_result::{NadirResult, ResultManager};
 use nalgebra::Vector3;
 use rotations::{prelude::UnitQuaternion, RotationTrait};
+use std::mem::offset_of;
 
 #[derive(Clone, Debug, Default)]
 pub struct RateGyroFsw {
@@ -27,6 +28,15 @@ pub struct RateGyroTelemetry {
     _padding: [u8; 7],
 }
 
+impl RateGyroTelemetry {
+    /// Field offsets in declaration order, to check against the sim's schema
+    pub const OFFSETS: [usize; 3] = [
+        offset_of!(RateGyroTelemetry, w),
+        offset_of!(RateGyroTelemetry, valid),
+        offset_of!(RateGyroTelemetry, _padding),
+    ];
+}
+
 #[derive(Clone, Debug, Default)]
 struct Parameters {
     imu_to_body: UnitQuaternion,
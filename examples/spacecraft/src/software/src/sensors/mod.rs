@@ -1,8 +1,11 @@
-use gps::GpsFsw;
-use imu::RateGyroFsw;
-use multibody::HardwareBuffer;
+use gps::{GpsFsw, GpsTelemetry};
+use imu::{RateGyroFsw, RateGyroTelemetry};
+use multibody::{
+    schema::{BufferSchema, SchemaErrors},
+    HardwareBuffer,
+};
 use nadir_result::{NadirResult, ResultManager};
-use star_tracker::StarTrackerFsw;
+use star_tracker::{StarTrackerFsw, StarTrackerTelemetry};
 
 pub mod gps;
 pub mod imu;
@@ -16,6 +19,20 @@ pub struct SensorFsw {
 }
 
 impl SensorFsw {
+    /// Checks the telemetry structs against the layouts the sim publishes, in read_buffers order
+    pub fn check_schemas(&self, schemas: &[BufferSchema]) -> Result<(), SchemaErrors> {
+        if schemas.len() != 3 {
+            return Err(SchemaErrors::SchemaCount(
+                3,
+                schemas.len(),
+            ));
+        }
+        schemas[0].check_layout::<GpsTelemetry>(&GpsTelemetry::OFFSETS)?;
+        schemas[1].check_layout::<StarTrackerTelemetry>(&StarTrackerTelemetry::OFFSETS)?;
+        schemas[2].check_layout::<RateGyroTelemetry>(&RateGyroTelemetry::OFFSETS)?;
+        Ok(())
+    }
+
     pub fn read_buffers(&mut self, sensor_data: &[HardwareBuffer]) {
         self.gps
             .read_buffer(&sensor_data[0]);
@@ -2,6 +2,7 @@ use bytemuck::{Pod, Zeroable};
 use multibody::HardwareBuffer;
 use nadir_result::{NadirResult, ResultManager};
 use rotations::prelude::UnitQuaternion;
+use std::mem::offset_of;
 
 #[derive(Debug, Default)]
 pub struct StarTrackerFsw {
@@ -31,6 +32,15 @@ pub struct StarTrackerTelemetry {
     _padding: [u8; 7],
 }
 
+impl StarTrackerTelemetry {
+    /// Field offsets in declaration order, to check against the sim's schema
+    pub const OFFSETS: [usize; 3] = [
+        offset_of!(StarTrackerTelemetry, q_st),
+        offset_of!(StarTrackerTelemetry, valid),
+        offset_of!(StarTrackerTelemetry, _padding),
+    ];
+}
+
 impl StarTrackerFsw {
     pub fn run(&mut self) {
         self.state
@@ -0,0 +1,36 @@
+// Generated by nadir from the sim telemetry schemas, do not edit
+#ifndef NADIR_TELEMETRY_H
+#define NADIR_TELEMETRY_H
+
+#include <stdint.h>
+
+typedef struct {
+    double time; // s since J2000, GPS time
+    double position[3]; // m
+    double velocity[3]; // m/s
+    double pseudorange[12]; // m
+    uint8_t prn[12];
+    uint8_t num_pseudoranges;
+    uint8_t valid;
+    uint8_t _padding[2];
+} GpsTelemetry;
+_Static_assert(sizeof(GpsTelemetry) == 168, "GpsTelemetry layout mismatch");
+
+typedef struct {
+    double q[4]; // [x, y, z, w]
+    uint8_t valid;
+    uint8_t _padding[7];
+} StarTrackerTelemetry;
+_Static_assert(sizeof(StarTrackerTelemetry) == 40, "StarTrackerTelemetry layout mismatch");
+
+#define REACTION_WHEEL_COMMAND_TORQUE 0
+#define REACTION_WHEEL_COMMAND_CURRENT 1
+#define REACTION_WHEEL_COMMAND_SPEED 2
+typedef struct {
+    double value; // Nm, A or rad/s by command
+    uint8_t command;
+    uint8_t _padding[7];
+} ReactionWheelCommand;
+_Static_assert(sizeof(ReactionWheelCommand) == 16, "ReactionWheelCommand layout mismatch");
+
+#endif // NADIR_TELEMETRY_H
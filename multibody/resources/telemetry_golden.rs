@@ -0,0 +1,40 @@
+// Generated by nadir from the sim telemetry schemas, do not edit
+use bytemuck::{Pod, Zeroable};
+
+#[derive(Clone, Copy, Debug, Default, Pod, Zeroable)]
+#[repr(C)]
+pub struct GpsTelemetry {
+    pub time: f64, // s since J2000, GPS time
+    pub position: [f64; 3], // m
+    pub velocity: [f64; 3], // m/s
+    pub pseudorange: [f64; 12], // m
+    pub prn: [u8; 12],
+    pub num_pseudoranges: u8,
+    pub valid: u8,
+    pub _padding: [u8; 2],
+}
+const _: () = assert!(size_of::<GpsTelemetry>() == 168);
+
+#[derive(Clone, Copy, Debug, Default, Pod, Zeroable)]
+#[repr(C)]
+pub struct StarTrackerTelemetry {
+    pub q: [f64; 4], // [x, y, z, w]
+    pub valid: u8,
+    pub _padding: [u8; 7],
+}
+const _: () = assert!(size_of::<StarTrackerTelemetry>() == 40);
+
+#[derive(Clone, Copy, Debug, Default, Pod, Zeroable)]
+#[repr(C)]
+pub struct ReactionWheelCommand {
+    pub value: f64, // Nm, A or rad/s by command
+    pub command: u8,
+    pub _padding: [u8; 7],
+}
+
+impl ReactionWheelCommand {
+    pub const TORQUE: u8 = 0;
+    pub const CURRENT: u8 = 1;
+    pub const SPEED: u8 = 2;
+}
+const _: () = assert!(size_of::<ReactionWheelCommand>() == 16);
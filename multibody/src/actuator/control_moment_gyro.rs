@@ -0,0 +1,683 @@
+use crate::{
+    HardwareBuffer,
+    actuator::ActuatorModel,
+    body::BodyConnection,
+    schema::{BufferSchema, FieldType},
+};
+use bytemuck::{Pod, Zeroable};
+use nadir_diffeq::{saving::StateWriter, state::state_vector::StateVector};
+use nalgebra::{Vector3, Vector6};
+use rand::rngs::SmallRng;
+use rotations::{
+    RotationTrait,
+    prelude::{QuaternionErrors, UnitQuaternion, UnitQuaternionBuilder},
+};
+use serde::{Deserialize, Serialize};
+use spatial_algebra::Force;
+use thiserror::Error;
+use uncertainty::{UncertainValue, Uncertainty};
+
+use super::ActuatorErrors;
+
+#[derive(Debug, Error)]
+pub enum ControlMomentGyroErrors {
+    #[error("gimbal bandwidth must be greater than 0")]
+    NonPositiveBandwidth,
+    #[error("coulomb friction should be greater than 0")]
+    NegativeCoulomb,
+    #[error("max gimbal rate must be greater than 0")]
+    NonPositiveRateMax,
+    #[error("viscous friction should be greater than 0")]
+    NegativeViscous,
+    #[error("rotor momentum must be greater than 0")]
+    SmallMomentum,
+    #[error("gimbal inertia must be greater than 0")]
+    SmallInertia,
+    #[error("{0}")]
+    Quaternion(#[from] QuaternionErrors),
+}
+
+/// Gimbal rate command (rad/s) for each gimbal axis.
+/// Single gimbal CMGs only read the first element, double gimbal CMGs read [outer, inner]
+#[derive(Default, Debug, Clone, Copy, Pod, Zeroable)]
+#[repr(C)]
+pub struct ControlMomentGyroCommand {
+    pub gimbal_rate: [f64; 2],
+}
+
+impl ControlMomentGyroCommand {
+    pub fn schema() -> BufferSchema {
+        BufferSchema::new("ControlMomentGyroCommand").with_array(
+            "gimbal_rate",
+            FieldType::F64,
+            2,
+            "rad/s",
+        )
+    }
+}
+
+/// The gimbal arrangement of the CMG, expressed in the actuator frame
+/// Single: gimbal about +Z, rotor spin axis along +X at zero gimbal angle
+/// Double: outer gimbal about +Z, inner gimbal about the outer gimbal's +Y,
+/// rotor spin axis along +X at zero gimbal angles
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq)]
+pub enum GimbalType {
+    Single,
+    Double,
+}
+
+impl GimbalType {
+    fn num_axes(&self) -> usize {
+        match self {
+            GimbalType::Single => 1,
+            GimbalType::Double => 2,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+struct GimbalFrictionBuilder {
+    coulomb: UncertainValue,
+    viscous: UncertainValue,
+}
+
+impl GimbalFrictionBuilder {
+    fn sample(&self, nominal: bool, rng: &mut SmallRng) -> GimbalFriction {
+        GimbalFriction {
+            coulomb: self
+                .coulomb
+                .sample(nominal, rng),
+            viscous: self
+                .viscous
+                .sample(nominal, rng),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct GimbalFriction {
+    coulomb: f64, // Nm
+    viscous: f64, // Nm/(rad/s)
+}
+
+impl GimbalFriction {
+    fn calculate(&self, rate: f64) -> f64 {
+        if rate == 0.0 {
+            0.0
+        } else {
+            -rate.signum() * self.coulomb - self.viscous * rate
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct ControlMomentGyroParametersBuilder {
+    friction: GimbalFrictionBuilder,
+    gimbal_bandwidth: UncertainValue, // rad/s
+    gimbal_inertia: UncertainValue,   // kg-m^2
+    gimbal_rate_max: Option<UncertainValue>,
+    gimbal_type: GimbalType,
+    misalignment: Option<UnitQuaternionBuilder>,
+    rotor_momentum: UncertainValue, // Nms
+}
+
+impl ControlMomentGyroParametersBuilder {
+    fn new(
+        gimbal_type: GimbalType,
+        rotor_momentum: f64,
+        gimbal_inertia: f64,
+    ) -> Result<Self, ControlMomentGyroErrors> {
+        if rotor_momentum < f64::EPSILON {
+            return Err(ControlMomentGyroErrors::SmallMomentum);
+        }
+        if gimbal_inertia < f64::EPSILON {
+            return Err(ControlMomentGyroErrors::SmallInertia);
+        }
+        Ok(Self {
+            friction: GimbalFrictionBuilder::default(),
+            gimbal_bandwidth: UncertainValue::new(20.0),
+            gimbal_inertia: UncertainValue::new(gimbal_inertia),
+            gimbal_rate_max: None,
+            gimbal_type,
+            misalignment: None,
+            rotor_momentum: UncertainValue::new(rotor_momentum),
+        })
+    }
+
+    fn sample(
+        &self,
+        nominal: bool,
+        rng: &mut SmallRng,
+    ) -> Result<ControlMomentGyroParameters, ControlMomentGyroErrors> {
+        let misalignment = if let Some(misalignment) = &self.misalignment {
+            Some(misalignment.sample(nominal, rng)?)
+        } else {
+            None
+        };
+        let gimbal_rate_max = self
+            .gimbal_rate_max
+            .as_ref()
+            .map(|rate_max| rate_max.sample(nominal, rng));
+        Ok(ControlMomentGyroParameters {
+            friction: self
+                .friction
+                .sample(nominal, rng),
+            gimbal_bandwidth: self
+                .gimbal_bandwidth
+                .sample(nominal, rng),
+            gimbal_inertia: self
+                .gimbal_inertia
+                .sample(nominal, rng),
+            gimbal_rate_max,
+            gimbal_type: self.gimbal_type,
+            misalignment,
+            rotor_momentum: self
+                .rotor_momentum
+                .sample(nominal, rng),
+        })
+    }
+}
+
+#[derive(Debug)]
+struct ControlMomentGyroParameters {
+    friction: GimbalFriction,
+    gimbal_bandwidth: f64, // rad/s
+    gimbal_inertia: f64,   // kg-m^2
+    gimbal_rate_max: Option<f64>,
+    gimbal_type: GimbalType,
+    misalignment: Option<UnitQuaternion>,
+    rotor_momentum: f64, // Nms
+}
+
+#[derive(Debug)]
+pub struct ControlMomentGyroState {
+    pub command: ControlMomentGyroCommand,
+    gimbal_acceleration: [f64; 2], // rad/s^2
+    pub gimbal_angle: [f64; 2],    // rad
+    pub gimbal_rate: [f64; 2],     // rad/s
+    /// Gimbal torque directions in the body frame, one per gimbal axis
+    gimbal_torque_axes: [Vector3<f64>; 2],
+    momentum_body: Vector3<f64>, // Nms
+    /// Singularity metric of all CMGs on the host body, sqrt(det(C C^T)) of the unit gimbal torque axes
+    pub singularity: f64,
+    torque_body: Vector3<f64>, // Nm
+}
+
+impl ControlMomentGyroState {
+    fn new(gimbal_angle: [f64; 2]) -> Self {
+        Self {
+            command: ControlMomentGyroCommand::default(),
+            gimbal_acceleration: [0.0; 2],
+            gimbal_angle,
+            gimbal_rate: [0.0; 2],
+            gimbal_torque_axes: [Vector3::zeros(); 2],
+            momentum_body: Vector3::zeros(),
+            singularity: 0.0,
+            torque_body: Vector3::zeros(),
+        }
+    }
+}
+
+/// A control moment gyroscope with a constant speed rotor and single or double gimbals.
+/// Each gimbal is driven by a rate servo with the specified bandwidth, limited by the max
+/// gimbal rate and opposed by gimbal friction. Gimbal and rotor transverse inertias are not
+/// coupled into the host body dynamics, only the rotor momentum is.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ControlMomentGyroBuilder {
+    parameters: ControlMomentGyroParametersBuilder,
+    initial_gimbal_angle: [UncertainValue; 2],
+}
+
+impl ControlMomentGyroBuilder {
+    pub fn new_single_gimbal(
+        rotor_momentum: f64,
+        gimbal_inertia: f64,
+    ) -> Result<Self, ControlMomentGyroErrors> {
+        Ok(Self {
+            parameters: ControlMomentGyroParametersBuilder::new(
+                GimbalType::Single,
+                rotor_momentum,
+                gimbal_inertia,
+            )?,
+            initial_gimbal_angle: [UncertainValue::new(0.0), UncertainValue::new(0.0)],
+        })
+    }
+
+    pub fn new_double_gimbal(
+        rotor_momentum: f64,
+        gimbal_inertia: f64,
+    ) -> Result<Self, ControlMomentGyroErrors> {
+        Ok(Self {
+            parameters: ControlMomentGyroParametersBuilder::new(
+                GimbalType::Double,
+                rotor_momentum,
+                gimbal_inertia,
+            )?,
+            initial_gimbal_angle: [UncertainValue::new(0.0), UncertainValue::new(0.0)],
+        })
+    }
+
+    pub fn set_coulomb(&mut self, coulomb: f64) -> Result<(), ControlMomentGyroErrors> {
+        if coulomb < 0.0 {
+            return Err(ControlMomentGyroErrors::NegativeCoulomb);
+        }
+        self.parameters
+            .friction
+            .coulomb
+            .nominal = coulomb;
+        Ok(())
+    }
+
+    pub fn with_coulomb(mut self, coulomb: f64) -> Result<Self, ControlMomentGyroErrors> {
+        self.set_coulomb(coulomb)?;
+        Ok(self)
+    }
+
+    /// Bandwidth (rad/s) of the gimbal rate servo, defaults to 20 rad/s
+    pub fn set_gimbal_bandwidth(&mut self, bandwidth: f64) -> Result<(), ControlMomentGyroErrors> {
+        if bandwidth <= 0.0 {
+            return Err(ControlMomentGyroErrors::NonPositiveBandwidth);
+        }
+        self.parameters
+            .gimbal_bandwidth
+            .nominal = bandwidth;
+        Ok(())
+    }
+
+    pub fn with_gimbal_bandwidth(
+        mut self,
+        bandwidth: f64,
+    ) -> Result<Self, ControlMomentGyroErrors> {
+        self.set_gimbal_bandwidth(bandwidth)?;
+        Ok(self)
+    }
+
+    pub fn set_gimbal_rate_max(&mut self, rate_max: f64) -> Result<(), ControlMomentGyroErrors> {
+        if rate_max <= 0.0 {
+            return Err(ControlMomentGyroErrors::NonPositiveRateMax);
+        }
+        self.parameters
+            .gimbal_rate_max = Some(UncertainValue::new(rate_max));
+        Ok(())
+    }
+
+    pub fn with_gimbal_rate_max(mut self, rate_max: f64) -> Result<Self, ControlMomentGyroErrors> {
+        self.set_gimbal_rate_max(rate_max)?;
+        Ok(self)
+    }
+
+    /// Initial gimbal angles (rad), [outer, inner] for double gimbal CMGs
+    pub fn set_initial_gimbal_angle(&mut self, angle: [f64; 2]) {
+        self.initial_gimbal_angle[0].nominal = angle[0];
+        self.initial_gimbal_angle[1].nominal = angle[1];
+    }
+
+    pub fn with_initial_gimbal_angle(mut self, angle: [f64; 2]) -> Self {
+        self.set_initial_gimbal_angle(angle);
+        self
+    }
+
+    pub fn set_misalignment(&mut self, misalignment: UnitQuaternionBuilder) {
+        self.parameters
+            .misalignment = Some(misalignment);
+    }
+
+    pub fn with_misalignment(mut self, misalignment: UnitQuaternionBuilder) -> Self {
+        self.parameters
+            .misalignment = Some(misalignment);
+        self
+    }
+
+    pub fn set_viscous(&mut self, viscous: f64) -> Result<(), ControlMomentGyroErrors> {
+        if viscous < 0.0 {
+            return Err(ControlMomentGyroErrors::NegativeViscous);
+        }
+        self.parameters
+            .friction
+            .viscous
+            .nominal = viscous;
+        Ok(())
+    }
+
+    pub fn with_viscous(mut self, viscous: f64) -> Result<Self, ControlMomentGyroErrors> {
+        self.set_viscous(viscous)?;
+        Ok(self)
+    }
+}
+
+impl Uncertainty for ControlMomentGyroBuilder {
+    type Error = ControlMomentGyroErrors;
+    type Output = ControlMomentGyro;
+
+    fn sample(&self, nominal: bool, rng: &mut SmallRng) -> Result<Self::Output, Self::Error> {
+        let parameters = self
+            .parameters
+            .sample(nominal, rng)?;
+        let initial_gimbal_angle = [
+            self.initial_gimbal_angle[0].sample(nominal, rng),
+            self.initial_gimbal_angle[1].sample(nominal, rng),
+        ];
+        let headers = match parameters.gimbal_type {
+            GimbalType::Single => SINGLE_GIMBAL_HEADERS,
+            GimbalType::Double => DOUBLE_GIMBAL_HEADERS,
+        };
+        Ok(ControlMomentGyro {
+            parameters,
+            state: ControlMomentGyroState::new(initial_gimbal_angle),
+            headers,
+        })
+    }
+}
+
+const SINGLE_GIMBAL_HEADERS: &[&str] = &[
+    "gimbal_angle",
+    "gimbal_rate",
+    "gimbal_rate_command",
+    "momentum(body)[x]",
+    "momentum(body)[y]",
+    "momentum(body)[z]",
+    "torque(body)[x]",
+    "torque(body)[y]",
+    "torque(body)[z]",
+    "singularity",
+];
+
+const DOUBLE_GIMBAL_HEADERS: &[&str] = &[
+    "gimbal_angle[outer]",
+    "gimbal_angle[inner]",
+    "gimbal_rate[outer]",
+    "gimbal_rate[inner]",
+    "gimbal_rate_command[outer]",
+    "gimbal_rate_command[inner]",
+    "momentum(body)[x]",
+    "momentum(body)[y]",
+    "momentum(body)[z]",
+    "torque(body)[x]",
+    "torque(body)[y]",
+    "torque(body)[z]",
+    "singularity",
+];
+
+#[derive(Debug)]
+pub struct ControlMomentGyro {
+    parameters: ControlMomentGyroParameters,
+    pub state: ControlMomentGyroState,
+    headers: &'static [&'static str],
+}
+
+impl ControlMomentGyro {
+    /// Unit gimbal torque directions in the body frame, one per gimbal axis.
+    /// Used to determine the singularity metric of a CMG array.
+    pub fn gimbal_torque_axes(&self) -> &[Vector3<f64>] {
+        &self
+            .state
+            .gimbal_torque_axes[..self
+            .parameters
+            .gimbal_type
+            .num_axes()]
+    }
+
+    /// Rotor spin axis and its partial derivatives with respect to each gimbal angle,
+    /// in the actuator frame
+    fn spin_axis(
+        &self,
+    ) -> (
+        Vector3<f64>,
+        [Vector3<f64>; 2],
+    ) {
+        let angle = &self
+            .state
+            .gimbal_angle;
+        match self
+            .parameters
+            .gimbal_type
+        {
+            GimbalType::Single => {
+                let (s, c) = angle[0].sin_cos();
+                (
+                    Vector3::new(c, s, 0.0),
+                    [Vector3::new(-s, c, 0.0), Vector3::zeros()],
+                )
+            }
+            GimbalType::Double => {
+                let (sa, ca) = angle[0].sin_cos();
+                let (sb, cb) = angle[1].sin_cos();
+                (
+                    Vector3::new(ca * cb, sa * cb, -sb),
+                    [Vector3::new(-sa * cb, ca * cb, 0.0), Vector3::new(-ca * sb, -sa * sb, -cb)],
+                )
+            }
+        }
+    }
+
+    fn to_body(&self, connection: &BodyConnection, v: &Vector3<f64>) -> Vector3<f64> {
+        let v = connection
+            .transform
+            .rotation
+            .transform(v);
+        if let Some(misalignment) = &self
+            .parameters
+            .misalignment
+        {
+            misalignment.transform(&v)
+        } else {
+            v
+        }
+    }
+}
+
+impl ActuatorModel for ControlMomentGyro {
+    fn update(&mut self, _t: f64, connection: &BodyConnection) -> Result<(), ActuatorErrors> {
+        let num_axes = self
+            .parameters
+            .gimbal_type
+            .num_axes();
+
+        // Gimbal rate servo, the commanded rate is limited and the servo torque is opposed by friction
+        for i in 0..num_axes {
+            let mut rate_command = self
+                .state
+                .command
+                .gimbal_rate[i];
+            if let Some(rate_max) = self
+                .parameters
+                .gimbal_rate_max
+            {
+                rate_command = rate_command.clamp(-rate_max, rate_max);
+            }
+            let rate = self
+                .state
+                .gimbal_rate[i];
+            let inertia = self
+                .parameters
+                .gimbal_inertia;
+            let servo_torque = inertia
+                * self
+                    .parameters
+                    .gimbal_bandwidth
+                * (rate_command - rate);
+            let friction_torque = self
+                .parameters
+                .friction
+                .calculate(rate);
+            self.state
+                .gimbal_acceleration[i] = (servo_torque + friction_torque) / inertia;
+        }
+
+        // Rotor momentum and its rate of change due to gimbaling
+        let h = self
+            .parameters
+            .rotor_momentum;
+        let (spin_axis, partials) = self.spin_axis();
+        let mut momentum_rate_act = Vector3::zeros();
+        for (i, partial) in partials
+            .iter()
+            .enumerate()
+            .take(num_axes)
+        {
+            momentum_rate_act += h
+                * partial
+                * self
+                    .state
+                    .gimbal_rate[i];
+            // the outer gimbal's partial vanishes when the inner gimbal is at ±90 deg, where the
+            // outer gimbal can't produce torque, so leave its axis as zero rather than NaN
+            let axis = self.to_body(connection, partial);
+            self.state
+                .gimbal_torque_axes[i] = axis
+                .try_normalize(f64::EPSILON)
+                .unwrap_or_else(Vector3::zeros);
+        }
+        let momentum_body = self.to_body(connection, &(h * spin_axis));
+        let momentum_rate_body = self.to_body(connection, &momentum_rate_act);
+
+        // The body reacts to the change in rotor momentum, including the gyroscopic coupling
+        // with the body rate since internal momentum is not otherwise accounted for
+        let mut body = connection
+            .body
+            .borrow_mut();
+        let angular_rate_body = body
+            .state
+            .angular_rate_body;
+        let torque_body = -momentum_rate_body - angular_rate_body.cross(&momentum_body);
+
+        self.state
+            .momentum_body = momentum_body;
+        self.state
+            .torque_body = torque_body;
+
+        body.state
+            .internal_momentum_body += momentum_body;
+        body.state
+            .actuator_force_body += Force::from(Vector6::new(
+            torque_body[0],
+            torque_body[1],
+            torque_body[2],
+            0.0,
+            0.0,
+            0.0,
+        ));
+        Ok(())
+    }
+
+    fn writer_save_fn(&self, writer: &mut StateWriter) {
+        let num_axes = self
+            .parameters
+            .gimbal_type
+            .num_axes();
+        let mut i = 0;
+        for values in [
+            &self
+                .state
+                .gimbal_angle,
+            &self
+                .state
+                .gimbal_rate,
+            &self
+                .state
+                .command
+                .gimbal_rate,
+        ] {
+            for value in &values[..num_axes] {
+                writer.float_buffer[i] = *value;
+                i += 1;
+            }
+        }
+        for vector in [
+            &self
+                .state
+                .momentum_body,
+            &self
+                .state
+                .torque_body,
+        ] {
+            for value in vector.iter() {
+                writer.float_buffer[i] = *value;
+                i += 1;
+            }
+        }
+        writer.float_buffer[i] = self
+            .state
+            .singularity;
+        writer
+            .write_record()
+            .unwrap();
+    }
+
+    fn writer_headers(&self) -> &[&str] {
+        self.headers
+    }
+
+    fn state_derivative(&self, derivative: &mut [f64]) {
+        let num_axes = self
+            .parameters
+            .gimbal_type
+            .num_axes();
+        for i in 0..num_axes {
+            derivative[2 * i] = self
+                .state
+                .gimbal_rate[i];
+            derivative[2 * i + 1] = self
+                .state
+                .gimbal_acceleration[i];
+        }
+    }
+
+    fn state_vector_init(&self) -> StateVector {
+        let num_axes = self
+            .parameters
+            .gimbal_type
+            .num_axes();
+        let mut state = Vec::with_capacity(2 * num_axes);
+        for i in 0..num_axes {
+            state.push(
+                self.state
+                    .gimbal_angle[i],
+            );
+            state.push(
+                self.state
+                    .gimbal_rate[i],
+            );
+        }
+        StateVector::new(state)
+    }
+
+    fn state_vector_read(&mut self, state: &[f64]) {
+        let num_axes = self
+            .parameters
+            .gimbal_type
+            .num_axes();
+        for i in 0..num_axes {
+            self.state
+                .gimbal_angle[i] = state[2 * i];
+            self.state
+                .gimbal_rate[i] = state[2 * i + 1];
+        }
+    }
+
+    fn command_schema(&self) -> BufferSchema {
+        ControlMomentGyroCommand::schema()
+    }
+
+    fn read_command(&mut self, cmd: &HardwareBuffer) -> Result<(), ActuatorErrors> {
+        self.state
+            .command = cmd.read::<ControlMomentGyroCommand>()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::mem::offset_of;
+
+    #[test]
+    fn test_command_layout() {
+        ControlMomentGyroCommand::schema()
+            .check_layout::<ControlMomentGyroCommand>(&[offset_of!(
+                ControlMomentGyroCommand,
+                gimbal_rate
+            )])
+            .unwrap();
+    }
+}
@@ -0,0 +1,865 @@
+use crate::{
+    HardwareBuffer,
+    actuator::ActuatorModel,
+    body::BodyConnection,
+    schema::{BufferSchema, FieldType},
+};
+use bytemuck::{Pod, Zeroable};
+use nadir_diffeq::{saving::StateWriter, state::state_vector::StateVector};
+use rand::rngs::SmallRng;
+use serde::{Deserialize, Serialize};
+use std::f64::consts::PI;
+use thiserror::Error;
+use uncertainty::{UncertainValue, Uncertainty};
+
+use super::ActuatorErrors;
+
+#[derive(Debug, Error)]
+pub enum JointMotorErrors {
+    #[error("backlash width and gear mesh damping must be greater than or equal to 0")]
+    NegativeBacklash,
+    #[error("servo gains must be greater than or equal to 0")]
+    NegativeServoGains,
+    #[error("invalid command for joint motor")]
+    InvalidCommand,
+    #[error("dc motor torque constant, resistance and bus voltage must be greater than 0")]
+    NonPositiveDcMotor,
+    #[error("gear ratio must be greater than 0")]
+    NonPositiveGearRatio,
+    #[error("gear mesh stiffness and rotor inertia must be greater than 0")]
+    NonPositiveMesh,
+    #[error("max torque must be greater than 0")]
+    NonPositiveMaxTorque,
+    #[error("stepper step angle, holding torque and max step rate must be greater than 0")]
+    NonPositiveStepper,
+    #[error("position and rate commands require a servo for a dc motor")]
+    ServoRequired,
+    #[error("stepper motors can only be commanded in position or rate")]
+    StepperTorqueCommand,
+    #[error("joint motor requires a joint with a single degree of freedom")]
+    UnsupportedJoint,
+}
+
+/// Command for a joint motor, all values are on the output (joint) side of the gear train
+/// TORQUE: output torque (Nm) or force (N) for prismatic joints
+/// POSITION: joint angle (rad) or position (m)
+/// RATE: joint rate (rad/s) or velocity (m/s)
+#[derive(Clone, Copy, Debug, Default, Pod, Zeroable)]
+#[repr(C)]
+pub struct JointMotorCommand {
+    pub value: f64,
+    pub command: u8,
+    _padding: [u8; 7],
+}
+
+impl JointMotorCommand {
+    pub fn schema() -> BufferSchema {
+        BufferSchema::new("JointMotorCommand")
+            .with_field(
+                "value",
+                FieldType::F64,
+                "Nm, rad or rad/s by command, N, m or m/s for prismatic joints",
+            )
+            .with_field("command", FieldType::U8, "")
+            .with_padding(7)
+            .with_constant(
+                "torque",
+                FieldType::U8,
+                Self::TORQUE as u64,
+            )
+            .with_constant(
+                "position",
+                FieldType::U8,
+                Self::POSITION as u64,
+            )
+            .with_constant(
+                "rate",
+                FieldType::U8,
+                Self::RATE as u64,
+            )
+    }
+
+    pub const TORQUE: u8 = 0;
+    pub const POSITION: u8 = 1;
+    pub const RATE: u8 = 2;
+
+    pub fn torque(torque: f64) -> Self {
+        Self { value: torque, command: Self::TORQUE, _padding: [0; 7] }
+    }
+
+    pub fn position(position: f64) -> Self {
+        Self { value: position, command: Self::POSITION, _padding: [0; 7] }
+    }
+
+    pub fn rate(rate: f64) -> Self {
+        Self { value: rate, command: Self::RATE, _padding: [0; 7] }
+    }
+}
+
+/// Output side PD servo used by a dc motor for position and rate commands
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ServoBuilder {
+    kp: UncertainValue,
+    kd: UncertainValue,
+}
+
+impl ServoBuilder {
+    pub fn new(kp: f64, kd: f64) -> Result<Self, JointMotorErrors> {
+        if kp < 0.0 || kd < 0.0 {
+            return Err(JointMotorErrors::NegativeServoGains);
+        }
+        Ok(Self { kp: UncertainValue::new(kp), kd: UncertainValue::new(kd) })
+    }
+
+    fn sample(&self, nominal: bool, rng: &mut SmallRng) -> Servo {
+        Servo {
+            kp: self
+                .kp
+                .sample(nominal, rng),
+            kd: self
+                .kd
+                .sample(nominal, rng),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Servo {
+    kp: f64,
+    kd: f64,
+}
+
+/// Brushed dc motor with a voltage limited driver, current is the steady state i = (V - ke w) / R
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct DcMotorBuilder {
+    bus_voltage: UncertainValue, // V
+    resistance: UncertainValue,  // ohm
+    servo: Option<ServoBuilder>,
+    torque_constant: UncertainValue, // Nm/A, equal to the back emf constant in V/(rad/s)
+}
+
+impl DcMotorBuilder {
+    pub fn new(
+        torque_constant: f64,
+        resistance: f64,
+        bus_voltage: f64,
+    ) -> Result<Self, JointMotorErrors> {
+        if torque_constant <= 0.0 || resistance <= 0.0 || bus_voltage <= 0.0 {
+            return Err(JointMotorErrors::NonPositiveDcMotor);
+        }
+        Ok(Self {
+            bus_voltage: UncertainValue::new(bus_voltage),
+            resistance: UncertainValue::new(resistance),
+            servo: None,
+            torque_constant: UncertainValue::new(torque_constant),
+        })
+    }
+
+    pub fn set_servo(&mut self, servo: ServoBuilder) {
+        self.servo = Some(servo);
+    }
+
+    pub fn with_servo(mut self, servo: ServoBuilder) -> Self {
+        self.set_servo(servo);
+        self
+    }
+
+    fn sample(&self, nominal: bool, rng: &mut SmallRng) -> DcMotor {
+        DcMotor {
+            bus_voltage: self
+                .bus_voltage
+                .sample(nominal, rng),
+            resistance: self
+                .resistance
+                .sample(nominal, rng),
+            servo: self
+                .servo
+                .as_ref()
+                .map(|servo| servo.sample(nominal, rng)),
+            torque_constant: self
+                .torque_constant
+                .sample(nominal, rng),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct DcMotor {
+    bus_voltage: f64,
+    resistance: f64,
+    servo: Option<Servo>,
+    torque_constant: f64,
+}
+
+/// Two phase hybrid stepper motor driven in full steps.
+/// Torque is T = T_hold * sin(pi / (2 * step_angle) * (rotor_command - rotor_angle)),
+/// so synchronization is lost if the rotor lags the commanded step by more than two steps.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct StepperMotorBuilder {
+    holding_torque: UncertainValue, // Nm
+    max_step_rate: UncertainValue,  // steps/s
+    step_angle: UncertainValue,     // rad
+}
+
+impl StepperMotorBuilder {
+    pub fn new(
+        step_angle: f64,
+        holding_torque: f64,
+        max_step_rate: f64,
+    ) -> Result<Self, JointMotorErrors> {
+        if step_angle <= 0.0 || holding_torque <= 0.0 || max_step_rate <= 0.0 {
+            return Err(JointMotorErrors::NonPositiveStepper);
+        }
+        Ok(Self {
+            holding_torque: UncertainValue::new(holding_torque),
+            max_step_rate: UncertainValue::new(max_step_rate),
+            step_angle: UncertainValue::new(step_angle),
+        })
+    }
+
+    fn sample(&self, nominal: bool, rng: &mut SmallRng) -> StepperMotor {
+        StepperMotor {
+            holding_torque: self
+                .holding_torque
+                .sample(nominal, rng),
+            max_step_rate: self
+                .max_step_rate
+                .sample(nominal, rng),
+            step_angle: self
+                .step_angle
+                .sample(nominal, rng),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct StepperMotor {
+    holding_torque: f64,
+    max_step_rate: f64,
+    step_angle: f64,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub enum JointMotorDriveBuilders {
+    Dc(DcMotorBuilder),
+    Stepper(StepperMotorBuilder),
+}
+
+impl From<DcMotorBuilder> for JointMotorDriveBuilders {
+    fn from(builder: DcMotorBuilder) -> Self {
+        JointMotorDriveBuilders::Dc(builder)
+    }
+}
+
+impl From<StepperMotorBuilder> for JointMotorDriveBuilders {
+    fn from(builder: StepperMotorBuilder) -> Self {
+        JointMotorDriveBuilders::Stepper(builder)
+    }
+}
+
+#[derive(Debug)]
+enum JointMotorDrives {
+    Dc(DcMotor),
+    Stepper(StepperMotor),
+}
+
+/// Compliant gear mesh with a dead band. Adds the motor rotor as a separate degree of freedom,
+/// otherwise the motor is rigidly coupled to the joint through the gear ratio.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct BacklashBuilder {
+    damping: UncertainValue,       // output side, Nms/rad or Ns/m
+    rotor_inertia: UncertainValue, // motor side, kgm^2
+    stiffness: UncertainValue,     // output side, Nm/rad or N/m
+    width: UncertainValue,         // output side, total dead band in rad or m
+}
+
+impl BacklashBuilder {
+    pub fn new(
+        width: f64,
+        stiffness: f64,
+        damping: f64,
+        rotor_inertia: f64,
+    ) -> Result<Self, JointMotorErrors> {
+        if width < 0.0 || damping < 0.0 {
+            return Err(JointMotorErrors::NegativeBacklash);
+        }
+        if stiffness <= 0.0 || rotor_inertia <= 0.0 {
+            return Err(JointMotorErrors::NonPositiveMesh);
+        }
+        Ok(Self {
+            damping: UncertainValue::new(damping),
+            rotor_inertia: UncertainValue::new(rotor_inertia),
+            stiffness: UncertainValue::new(stiffness),
+            width: UncertainValue::new(width),
+        })
+    }
+
+    fn sample(&self, nominal: bool, rng: &mut SmallRng) -> Backlash {
+        Backlash {
+            damping: self
+                .damping
+                .sample(nominal, rng),
+            rotor_inertia: self
+                .rotor_inertia
+                .sample(nominal, rng),
+            stiffness: self
+                .stiffness
+                .sample(nominal, rng),
+            width: self
+                .width
+                .sample(nominal, rng),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Backlash {
+    damping: f64,
+    rotor_inertia: f64,
+    stiffness: f64,
+    width: f64,
+}
+
+impl Backlash {
+    /// Output side mesh force given the gear output and joint states
+    fn calculate_mesh_torque(&self, deflection: f64, deflection_rate: f64) -> f64 {
+        let half_width = 0.5 * self.width;
+        let engaged = if deflection > half_width {
+            deflection - half_width
+        } else if deflection < -half_width {
+            deflection + half_width
+        } else {
+            return 0.0;
+        };
+        let torque = self.stiffness * engaged + self.damping * deflection_rate;
+        // teeth can only push, damping can not pull the mesh back together
+        if torque * engaged > 0.0 {
+            torque
+        } else {
+            0.0
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct JointMotorParametersBuilder {
+    backlash: Option<BacklashBuilder>,
+    drive: JointMotorDriveBuilders,
+    gear_ratio: UncertainValue,
+    initial_position: f64,
+    max_torque: Option<UncertainValue>,
+}
+
+impl JointMotorParametersBuilder {
+    fn sample(&self, nominal: bool, rng: &mut SmallRng) -> JointMotorParameters {
+        let drive = match &self.drive {
+            JointMotorDriveBuilders::Dc(motor) => JointMotorDrives::Dc(motor.sample(nominal, rng)),
+            JointMotorDriveBuilders::Stepper(motor) => {
+                JointMotorDrives::Stepper(motor.sample(nominal, rng))
+            }
+        };
+        JointMotorParameters {
+            backlash: self
+                .backlash
+                .as_ref()
+                .map(|backlash| backlash.sample(nominal, rng)),
+            drive,
+            gear_ratio: self
+                .gear_ratio
+                .sample(nominal, rng),
+            max_torque: self
+                .max_torque
+                .as_ref()
+                .map(|max_torque| max_torque.sample(nominal, rng)),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct JointMotorParameters {
+    backlash: Option<Backlash>,
+    drive: JointMotorDrives,
+    gear_ratio: f64,
+    max_torque: Option<f64>, // motor side
+}
+
+#[derive(Debug)]
+pub struct JointMotorState {
+    pub command: JointMotorCommand,
+    current: f64, // A
+    deflection: f64,
+    joint_position: f64,
+    joint_rate: f64,
+    motor_acceleration: f64,
+    motor_angle: f64,
+    motor_rate: f64,
+    motor_torque: f64,
+    output_torque: f64,
+    /// commanded rotor angle of a stepper motor, continuous between steps
+    step_command: f64,
+    step_rate: f64,
+    voltage: f64, // V
+}
+
+impl JointMotorState {
+    fn new(motor_angle: f64) -> Self {
+        Self {
+            command: JointMotorCommand::default(),
+            current: 0.0,
+            deflection: 0.0,
+            joint_position: 0.0,
+            joint_rate: 0.0,
+            motor_acceleration: 0.0,
+            motor_angle,
+            motor_rate: 0.0,
+            motor_torque: 0.0,
+            output_torque: 0.0,
+            step_command: motor_angle,
+            step_rate: 0.0,
+            voltage: 0.0,
+        }
+    }
+}
+
+/// A motor driving the inner joint of the body it is connected to, such as a solar array drive,
+/// antenna pointing mechanism or robotic arm joint. The output torque is applied as a
+/// generalized force across the joint, reacting equally on the inner and outer bodies.
+/// The connection transform is not used.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct JointMotorBuilder {
+    parameters: JointMotorParametersBuilder,
+}
+
+impl JointMotorBuilder {
+    pub fn new(drive: JointMotorDriveBuilders) -> Self {
+        Self {
+            parameters: JointMotorParametersBuilder {
+                backlash: None,
+                drive,
+                gear_ratio: UncertainValue::new(1.0),
+                initial_position: 0.0,
+                max_torque: None,
+            },
+        }
+    }
+
+    pub fn new_dc(
+        torque_constant: f64,
+        resistance: f64,
+        bus_voltage: f64,
+    ) -> Result<Self, JointMotorErrors> {
+        Ok(Self::new(
+            DcMotorBuilder::new(
+                torque_constant,
+                resistance,
+                bus_voltage,
+            )?
+            .into(),
+        ))
+    }
+
+    pub fn new_stepper(
+        step_angle: f64,
+        holding_torque: f64,
+        max_step_rate: f64,
+    ) -> Result<Self, JointMotorErrors> {
+        Ok(Self::new(
+            StepperMotorBuilder::new(
+                step_angle,
+                holding_torque,
+                max_step_rate,
+            )?
+            .into(),
+        ))
+    }
+
+    pub fn set_backlash(&mut self, backlash: BacklashBuilder) {
+        self.parameters
+            .backlash = Some(backlash);
+    }
+
+    pub fn with_backlash(mut self, backlash: BacklashBuilder) -> Self {
+        self.set_backlash(backlash);
+        self
+    }
+
+    /// Ratio of motor rotation to joint motion
+    pub fn set_gear_ratio(&mut self, gear_ratio: f64) -> Result<(), JointMotorErrors> {
+        if gear_ratio <= 0.0 {
+            return Err(JointMotorErrors::NonPositiveGearRatio);
+        }
+        self.parameters
+            .gear_ratio = UncertainValue::new(gear_ratio);
+        Ok(())
+    }
+
+    pub fn with_gear_ratio(mut self, gear_ratio: f64) -> Result<Self, JointMotorErrors> {
+        self.set_gear_ratio(gear_ratio)?;
+        Ok(self)
+    }
+
+    /// Initial joint position, should match the joint state so the motor starts at rest
+    pub fn set_initial_position(&mut self, position: f64) {
+        self.parameters
+            .initial_position = position;
+    }
+
+    pub fn with_initial_position(mut self, position: f64) -> Self {
+        self.set_initial_position(position);
+        self
+    }
+
+    /// Motor side torque limit (Nm)
+    pub fn set_max_torque(&mut self, max_torque: f64) -> Result<(), JointMotorErrors> {
+        if max_torque <= 0.0 {
+            return Err(JointMotorErrors::NonPositiveMaxTorque);
+        }
+        self.parameters
+            .max_torque = Some(UncertainValue::new(
+            max_torque,
+        ));
+        Ok(())
+    }
+
+    pub fn with_max_torque(mut self, max_torque: f64) -> Result<Self, JointMotorErrors> {
+        self.set_max_torque(max_torque)?;
+        Ok(self)
+    }
+}
+
+impl Uncertainty for JointMotorBuilder {
+    type Error = JointMotorErrors;
+    type Output = JointMotor;
+
+    fn sample(&self, nominal: bool, rng: &mut SmallRng) -> Result<Self::Output, Self::Error> {
+        let parameters = self
+            .parameters
+            .sample(nominal, rng);
+        let state = JointMotorState::new(
+            parameters.gear_ratio
+                * self
+                    .parameters
+                    .initial_position,
+        );
+        Ok(JointMotor { parameters, state })
+    }
+}
+
+#[derive(Debug)]
+pub struct JointMotor {
+    parameters: JointMotorParameters,
+    pub state: JointMotorState,
+}
+
+impl JointMotor {
+    /// Motor side torque from the drive given the joint and motor states
+    fn calculate_motor_torque(
+        &mut self,
+        position: f64,
+        rate: f64,
+        motor_angle: f64,
+        motor_rate: f64,
+    ) -> Result<f64, JointMotorErrors> {
+        let command = self
+            .state
+            .command;
+        let gear_ratio = self
+            .parameters
+            .gear_ratio;
+        match &self
+            .parameters
+            .drive
+        {
+            JointMotorDrives::Dc(motor) => {
+                let output_request = match command.command {
+                    JointMotorCommand::TORQUE => command.value,
+                    JointMotorCommand::POSITION => {
+                        let servo = motor
+                            .servo
+                            .as_ref()
+                            .ok_or(JointMotorErrors::ServoRequired)?;
+                        servo.kp * (command.value - position) - servo.kd * rate
+                    }
+                    JointMotorCommand::RATE => {
+                        let servo = motor
+                            .servo
+                            .as_ref()
+                            .ok_or(JointMotorErrors::ServoRequired)?;
+                        servo.kd * (command.value - rate)
+                    }
+                    _ => return Err(JointMotorErrors::InvalidCommand),
+                };
+                // driver applies the voltage for the requested current, limited by the bus
+                let current_request = output_request / gear_ratio / motor.torque_constant;
+                let voltage = (motor.resistance * current_request
+                    + motor.torque_constant * motor_rate)
+                    .clamp(
+                        -motor.bus_voltage,
+                        motor.bus_voltage,
+                    );
+                let current = (voltage - motor.torque_constant * motor_rate) / motor.resistance;
+                self.state
+                    .voltage = voltage;
+                self.state
+                    .current = current;
+                self.state
+                    .step_rate = 0.0;
+                Ok(motor.torque_constant * current)
+            }
+            JointMotorDrives::Stepper(motor) => {
+                let max_rate = motor.max_step_rate * motor.step_angle;
+                self.state
+                    .step_rate = match command.command {
+                    JointMotorCommand::POSITION => {
+                        let error = gear_ratio * command.value
+                            - self
+                                .state
+                                .step_command;
+                        if error.abs() > 0.5 * motor.step_angle {
+                            error.signum() * max_rate
+                        } else {
+                            0.0
+                        }
+                    }
+                    JointMotorCommand::RATE => {
+                        (gear_ratio * command.value).clamp(-max_rate, max_rate)
+                    }
+                    JointMotorCommand::TORQUE => {
+                        return Err(JointMotorErrors::StepperTorqueCommand);
+                    }
+                    _ => return Err(JointMotorErrors::InvalidCommand),
+                };
+                // the driver energizes the phases for the nearest full step
+                let step = (self
+                    .state
+                    .step_command
+                    / motor.step_angle)
+                    .round()
+                    * motor.step_angle;
+                Ok(motor.holding_torque
+                    * (PI / (2.0 * motor.step_angle) * (step - motor_angle)).sin())
+            }
+        }
+    }
+}
+
+impl ActuatorModel for JointMotor {
+    fn update(&mut self, _t: f64, connection: &BodyConnection) -> Result<(), ActuatorErrors> {
+        let inner_joint = connection
+            .body
+            .borrow()
+            .inner_joint
+            .upgrade()
+            .expect("validation should catch this");
+        let mut joint = inner_joint.borrow_mut();
+        let (position, rate) = joint
+            .model
+            .single_dof_state()
+            .ok_or(JointMotorErrors::UnsupportedJoint)?;
+
+        let gear_ratio = self
+            .parameters
+            .gear_ratio;
+        // the rotor is rigidly coupled to the joint unless there is backlash
+        let (motor_angle, motor_rate) = match &self
+            .parameters
+            .backlash
+        {
+            Some(_) => (
+                self.state
+                    .motor_angle,
+                self.state
+                    .motor_rate,
+            ),
+            None => (
+                gear_ratio * position,
+                gear_ratio * rate,
+            ),
+        };
+
+        let mut motor_torque = self.calculate_motor_torque(
+            position,
+            rate,
+            motor_angle,
+            motor_rate,
+        )?;
+        if let Some(max_torque) = self
+            .parameters
+            .max_torque
+        {
+            motor_torque = motor_torque.clamp(-max_torque, max_torque);
+        }
+
+        let output_torque = match &self
+            .parameters
+            .backlash
+        {
+            Some(backlash) => {
+                let deflection = motor_angle / gear_ratio - position;
+                let mesh_torque = backlash.calculate_mesh_torque(
+                    deflection,
+                    motor_rate / gear_ratio - rate,
+                );
+                self.state
+                    .deflection = deflection;
+                self.state
+                    .motor_acceleration =
+                    (motor_torque - mesh_torque / gear_ratio) / backlash.rotor_inertia;
+                mesh_torque
+            }
+            None => {
+                self.state
+                    .motor_angle = motor_angle;
+                self.state
+                    .motor_rate = motor_rate;
+                gear_ratio * motor_torque
+            }
+        };
+
+        // Update state
+        self.state
+            .joint_position = position;
+        self.state
+            .joint_rate = rate;
+        self.state
+            .motor_torque = motor_torque;
+        self.state
+            .output_torque = output_torque;
+
+        // Update joint
+        joint
+            .model
+            .add_actuator_tau(output_torque);
+        Ok(())
+    }
+
+    fn writer_save_fn(&self, writer: &mut StateWriter) {
+        writer.float_buffer[0] = self
+            .state
+            .command
+            .value;
+        writer.float_buffer[1] = self
+            .state
+            .joint_position;
+        writer.float_buffer[2] = self
+            .state
+            .joint_rate;
+        writer.float_buffer[3] = self
+            .state
+            .motor_angle;
+        writer.float_buffer[4] = self
+            .state
+            .motor_rate;
+        writer.float_buffer[5] = self
+            .state
+            .motor_torque;
+        writer.float_buffer[6] = self
+            .state
+            .output_torque;
+        writer.float_buffer[7] = self
+            .state
+            .current;
+        writer.float_buffer[8] = self
+            .state
+            .voltage;
+        writer.float_buffer[9] = self
+            .state
+            .step_command;
+        writer.float_buffer[10] = self
+            .state
+            .deflection;
+        writer
+            .write_record()
+            .unwrap();
+    }
+
+    fn writer_headers(&self) -> &[&str] {
+        &[
+            "command",
+            "joint_position",
+            "joint_rate",
+            "motor_angle",
+            "motor_rate",
+            "motor_torque",
+            "output_torque",
+            "current",
+            "voltage",
+            "step_command",
+            "deflection",
+        ]
+    }
+
+    fn state_derivative(&self, derivative: &mut [f64]) {
+        derivative[0] = self
+            .state
+            .step_rate;
+        if self
+            .parameters
+            .backlash
+            .is_some()
+        {
+            derivative[1] = self
+                .state
+                .motor_rate;
+            derivative[2] = self
+                .state
+                .motor_acceleration;
+        } else {
+            derivative[1] = 0.0;
+            derivative[2] = 0.0;
+        }
+    }
+
+    fn state_vector_init(&self) -> StateVector {
+        StateVector::new(vec![
+            self.state
+                .step_command,
+            self.state
+                .motor_angle,
+            self.state
+                .motor_rate,
+        ])
+    }
+
+    fn state_vector_read(&mut self, state: &[f64]) {
+        self.state
+            .step_command = state[0];
+        if self
+            .parameters
+            .backlash
+            .is_some()
+        {
+            self.state
+                .motor_angle = state[1];
+            self.state
+                .motor_rate = state[2];
+        }
+    }
+
+    fn command_schema(&self) -> BufferSchema {
+        JointMotorCommand::schema()
+    }
+
+    fn read_command(&mut self, cmd: &HardwareBuffer) -> Result<(), ActuatorErrors> {
+        self.state
+            .command = cmd.read::<JointMotorCommand>()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::mem::offset_of;
+
+    #[test]
+    fn test_command_layout() {
+        JointMotorCommand::schema()
+            .check_layout::<JointMotorCommand>(&[
+                offset_of!(JointMotorCommand, value),
+                offset_of!(JointMotorCommand, command),
+                offset_of!(JointMotorCommand, _padding),
+            ])
+            .unwrap();
+    }
+}
@@ -1,4 +1,10 @@
-use crate::{HardwareBuffer, actuator::ActuatorModel, body::BodyConnection};
+use crate::{
+    HardwareBuffer,
+    actuator::ActuatorModel,
+    body::{BodyConnection, MagneticDipole, NT_TO_TESLA},
+    schema::{BufferSchema, FieldType},
+};
+use bytemuck::{Pod, Zeroable};
 use nadir_diffeq::{saving::StateWriter, state::state_vector::StateVector};
 use nalgebra::{Vector3, Vector6};
 use rand::rngs::SmallRng;
@@ -15,21 +21,151 @@ use super::ActuatorErrors;
 
 #[derive(Debug, Error)]
 pub enum MagneticTorquerErrors {
-    #[error("invalid command for reaction wheel")]
+    #[error("duty cycle commands require a coil model")]
+    CoilRequired,
+    #[error("invalid command for magnetic torquer")]
     InvalidCommand,
+    #[error("residual dipole must be greater than or equal to 0")]
+    NegativeResidual,
+    #[error("coil inductance, resistance and supply voltage must be greater than 0")]
+    NonPositiveCoil,
+    #[error("max dipole must be greater than 0")]
+    NonPositiveMaxDipole,
     #[error("{0}")]
     QuaternionErrors(#[from] QuaternionErrors),
 }
 
+/// Command for a magnetic torquer
+/// CURRENT: coil current (A)
+/// DIPOLE: dipole moment (Am^2), converted to current with current_to_moment
+/// DUTY_CYCLE: PWM duty cycle [-1, 1] of the coil supply voltage, requires a coil model
+#[derive(Clone, Copy, Debug, Default, Pod, Zeroable)]
+#[repr(C)]
+pub struct MagneticTorquerCommand {
+    pub value: f64,
+    pub command: u8,
+    _padding: [u8; 7],
+}
+
+impl MagneticTorquerCommand {
+    pub fn schema() -> BufferSchema {
+        BufferSchema::new("MagneticTorquerCommand")
+            .with_field(
+                "value",
+                FieldType::F64,
+                "A, Am^2 or duty cycle by command",
+            )
+            .with_field("command", FieldType::U8, "")
+            .with_padding(7)
+            .with_constant(
+                "current",
+                FieldType::U8,
+                Self::CURRENT as u64,
+            )
+            .with_constant(
+                "dipole",
+                FieldType::U8,
+                Self::DIPOLE as u64,
+            )
+            .with_constant(
+                "duty_cycle",
+                FieldType::U8,
+                Self::DUTY_CYCLE as u64,
+            )
+    }
+
+    pub const CURRENT: u8 = 0;
+    pub const DIPOLE: u8 = 1;
+    pub const DUTY_CYCLE: u8 = 2;
+
+    pub fn current(current: f64) -> Self {
+        Self { value: current, command: Self::CURRENT, _padding: [0; 7] }
+    }
+
+    pub fn dipole(dipole: f64) -> Self {
+        Self { value: dipole, command: Self::DIPOLE, _padding: [0; 7] }
+    }
+
+    pub fn duty_cycle(duty_cycle: f64) -> Self {
+        Self {
+            value: duty_cycle,
+            command: Self::DUTY_CYCLE,
+            _padding: [0; 7],
+        }
+    }
+}
+
+/// Coil electrical model, L di/dt = V - R i.
+/// PWM is modeled by its average voltage, ripple at the PWM frequency is neglected
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct CoilBuilder {
+    inductance: UncertainValue,     // H
+    resistance: UncertainValue,     // ohm
+    supply_voltage: UncertainValue, // V
+}
+
+impl CoilBuilder {
+    pub fn new(
+        inductance: f64,
+        resistance: f64,
+        supply_voltage: f64,
+    ) -> Result<Self, MagneticTorquerErrors> {
+        if inductance <= 0.0 || resistance <= 0.0 || supply_voltage <= 0.0 {
+            return Err(MagneticTorquerErrors::NonPositiveCoil);
+        }
+        Ok(Self {
+            inductance: UncertainValue::new(inductance),
+            resistance: UncertainValue::new(resistance),
+            supply_voltage: UncertainValue::new(supply_voltage),
+        })
+    }
+
+    fn sample(&self, nominal: bool, rng: &mut SmallRng) -> Coil {
+        Coil {
+            inductance: self
+                .inductance
+                .sample(nominal, rng),
+            resistance: self
+                .resistance
+                .sample(nominal, rng),
+            supply_voltage: self
+                .supply_voltage
+                .sample(nominal, rng),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Coil {
+    inductance: f64,
+    resistance: f64,
+    supply_voltage: f64,
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize)]
 struct MagneticTorquerParametersBuilder {
+    #[serde(default)]
+    coil: Option<CoilBuilder>,
     current_to_moment: UncertainValue,
+    #[serde(default)]
+    max_dipole: Option<UncertainValue>,
     misalignment: Option<UnitQuaternionBuilder>,
+    #[serde(default)]
+    report_to_magnetometers: bool,
+    #[serde(default)]
+    residual_dipole: Option<UncertainValue>,
 }
 
 impl MagneticTorquerParametersBuilder {
     pub fn new(current_to_moment: UncertainValue) -> Result<Self, MagneticTorquerErrors> {
-        Ok(Self { current_to_moment, misalignment: None })
+        Ok(Self {
+            coil: None,
+            current_to_moment,
+            max_dipole: None,
+            misalignment: None,
+            report_to_magnetometers: false,
+            residual_dipole: None,
+        })
     }
 
     pub fn sample(
@@ -45,38 +181,73 @@ impl MagneticTorquerParametersBuilder {
         } else {
             None
         };
+        let coil = self
+            .coil
+            .as_ref()
+            .map(|coil| coil.sample(nominal, rng));
+        let max_dipole = self
+            .max_dipole
+            .as_ref()
+            .map(|max_dipole| max_dipole.sample(nominal, rng));
+        let residual_dipole = self
+            .residual_dipole
+            .as_ref()
+            .map_or(0.0, |residual| {
+                residual.sample(nominal, rng)
+            });
 
-        Ok(MagneticTorquerParameters { current_to_moment, misalignment })
+        Ok(MagneticTorquerParameters {
+            coil,
+            current_to_moment,
+            max_dipole,
+            misalignment,
+            report_to_magnetometers: self.report_to_magnetometers,
+            residual_dipole,
+        })
     }
 }
 
 #[derive(Debug)]
 struct MagneticTorquerParameters {
+    coil: Option<Coil>,
     current_to_moment: f64,
+    max_dipole: Option<f64>,
     misalignment: Option<UnitQuaternion>,
+    report_to_magnetometers: bool,
+    residual_dipole: f64,
 }
 
 #[derive(Debug)]
 pub struct MagneticTorquerState {
-    pub command: f64,
+    pub command: MagneticTorquerCommand,
     current: f64, // A
-    moment: f64,
+    current_rate: f64,
+    moment: f64, // Am^2
+    /// direction of the last magnetization, the residual dipole remains along it
+    remanence: f64,
     torque_act: Vector3<f64>,
     torque_body: Vector3<f64>,
+    voltage: f64, // V
 }
 
 impl MagneticTorquerState {
     pub fn new() -> Self {
         Self {
-            command: 0.0,
+            command: MagneticTorquerCommand::default(),
             current: 0.0,
+            current_rate: 0.0,
             moment: 0.0,
+            remanence: 0.0,
             torque_act: Vector3::zeros(),
             torque_body: Vector3::zeros(),
+            voltage: 0.0,
         }
     }
 }
 
+/// A magnetic torquer bar producing a dipole along +Z of the actuator frame.
+/// Optionally includes coil L/R dynamics, core saturation, a residual dipole and
+/// reporting of its dipole to magnetometers on the same body.
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct MagneticTorquerBuilder {
     parameters: MagneticTorquerParametersBuilder,
@@ -89,6 +260,33 @@ impl MagneticTorquerBuilder {
         })
     }
 
+    pub fn set_coil(&mut self, coil: CoilBuilder) {
+        self.parameters
+            .coil = Some(coil);
+    }
+
+    pub fn with_coil(mut self, coil: CoilBuilder) -> Self {
+        self.set_coil(coil);
+        self
+    }
+
+    /// Dipole (Am^2) the core saturates to
+    pub fn set_max_dipole(&mut self, max_dipole: f64) -> Result<(), MagneticTorquerErrors> {
+        if max_dipole <= 0.0 {
+            return Err(MagneticTorquerErrors::NonPositiveMaxDipole);
+        }
+        self.parameters
+            .max_dipole = Some(UncertainValue::new(
+            max_dipole,
+        ));
+        Ok(())
+    }
+
+    pub fn with_max_dipole(mut self, max_dipole: f64) -> Result<Self, MagneticTorquerErrors> {
+        self.set_max_dipole(max_dipole)?;
+        Ok(self)
+    }
+
     pub fn set_misalignment(&mut self, misalignment: UnitQuaternionBuilder) {
         self.parameters
             .misalignment = Some(misalignment);
@@ -99,6 +297,32 @@ impl MagneticTorquerBuilder {
             .misalignment = Some(misalignment);
         self
     }
+
+    /// Adds the torquer dipole to the field measured by magnetometers on the same body
+    pub fn set_report_to_magnetometers(&mut self, report: bool) {
+        self.parameters
+            .report_to_magnetometers = report;
+    }
+
+    pub fn with_report_to_magnetometers(mut self, report: bool) -> Self {
+        self.set_report_to_magnetometers(report);
+        self
+    }
+
+    /// Residual dipole (Am^2) of the core that remains along the last magnetized direction
+    pub fn set_residual_dipole(&mut self, residual: f64) -> Result<(), MagneticTorquerErrors> {
+        if residual < 0.0 {
+            return Err(MagneticTorquerErrors::NegativeResidual);
+        }
+        self.parameters
+            .residual_dipole = Some(UncertainValue::new(residual));
+        Ok(())
+    }
+
+    pub fn with_residual_dipole(mut self, residual: f64) -> Result<Self, MagneticTorquerErrors> {
+        self.set_residual_dipole(residual)?;
+        Ok(self)
+    }
 }
 
 impl Uncertainty for MagneticTorquerBuilder {
@@ -120,57 +344,130 @@ pub struct MagneticTorquer {
     pub state: MagneticTorquerState,
 }
 
-impl ActuatorModel for MagneticTorquer {
-    fn update(&mut self, connection: &BodyConnection) -> Result<(), ActuatorErrors> {
-        // Determine moment
-        let moment = self
+impl MagneticTorquer {
+    /// Determines the coil current (and its rate of change for a coil model) from the command
+    fn update_current(&mut self) -> Result<(), MagneticTorquerErrors> {
+        let command = self
             .state
-            .current
+            .command;
+        let current_command = match command.command {
+            MagneticTorquerCommand::CURRENT => Some(command.value),
+            MagneticTorquerCommand::DIPOLE => Some(
+                command.value
+                    / self
+                        .parameters
+                        .current_to_moment,
+            ),
+            MagneticTorquerCommand::DUTY_CYCLE => None,
+            _ => return Err(MagneticTorquerErrors::InvalidCommand),
+        };
+
+        match (
+            &self
+                .parameters
+                .coil,
+            current_command,
+        ) {
+            (Some(coil), current_command) => {
+                // the driver applies the voltage for the steady state current, limited by the supply
+                let voltage = match current_command {
+                    Some(current) => coil.resistance * current,
+                    None => command.value * coil.supply_voltage,
+                }
+                .clamp(
+                    -coil.supply_voltage,
+                    coil.supply_voltage,
+                );
+                self.state
+                    .voltage = voltage;
+                self.state
+                    .current_rate = (voltage
+                    - coil.resistance
+                        * self
+                            .state
+                            .current)
+                    / coil.inductance;
+            }
+            (None, Some(current)) => {
+                self.state
+                    .current = current;
+            }
+            (None, None) => return Err(MagneticTorquerErrors::CoilRequired),
+        }
+        Ok(())
+    }
+
+    /// Dipole moment (Am^2) from the coil current, including saturation and the residual dipole
+    fn calculate_moment(&mut self) -> f64 {
+        let current = self
+            .state
+            .current;
+        let linear = current
             * self
                 .parameters
                 .current_to_moment;
+        let moment = match self
+            .parameters
+            .max_dipole
+        {
+            Some(max_dipole) => max_dipole * (linear / max_dipole).tanh(),
+            None => linear,
+        };
+        if moment != 0.0 {
+            self.state
+                .remanence = moment.signum();
+        }
+        moment
+            + self
+                .parameters
+                .residual_dipole
+                * self
+                    .state
+                    .remanence
+    }
+}
 
-        let magnetic_field_body = connection
-            .body
-            .borrow()
-            .state
-            .magnetic_field_body;
-        let mut magnetic_field_torquer = connection
-            .transform
-            .rotation
-            .transform(&magnetic_field_body);
+impl ActuatorModel for MagneticTorquer {
+    fn update(&mut self, _t: f64, connection: &BodyConnection) -> Result<(), ActuatorErrors> {
+        self.update_current()?;
+        let moment = self.calculate_moment();
+        let moment_act = Vector3::new(0.0, 0.0, moment);
 
-        // Apply misalignment
+        // dipole in the body frame, undoing misalignment then the mounting rotation
+        let mut moment_body = moment_act;
         if let Some(misalignment) = &self
             .parameters
             .misalignment
         {
-            magnetic_field_torquer = misalignment.transform(&magnetic_field_torquer);
+            moment_body = misalignment
+                .inv()
+                .transform(&moment_body);
         }
+        moment_body = connection
+            .transform
+            .rotation
+            .inv()
+            .transform(&moment_body);
 
-        let torque_act = Vector3::new(0.0, 0.0, moment).cross(&magnetic_field_torquer);
-        let torque_body = if let Some(misalignment) = &self
+        let mut body = connection
+            .body
+            .borrow_mut();
+        // the body field is in nT, the torque needs tesla
+        let magnetic_field_body = body
+            .state
+            .magnetic_field_body
+            * NT_TO_TESLA;
+        let torque_body = moment_body.cross(&magnetic_field_body);
+        let mut torque_act = connection
+            .transform
+            .rotation
+            .transform(&torque_body);
+        if let Some(misalignment) = &self
             .parameters
             .misalignment
         {
-            // inverse now to get back to body frame
-            connection
-                .transform
-                .rotation
-                .inv()
-                .transform(
-                    &misalignment
-                        .inv()
-                        .transform(&torque_act),
-                )
-        } else {
-            // no misalignment
-            connection
-                .transform
-                .rotation
-                .inv()
-                .transform(&torque_act)
-        };
+            torque_act = misalignment.transform(&torque_act);
+        }
 
         // Update state
         self.state
@@ -181,17 +478,24 @@ impl ActuatorModel for MagneticTorquer {
             .torque_body = torque_body;
 
         // Update body
-        let mut body = connection
-            .body
-            .borrow_mut();
+        if self
+            .parameters
+            .report_to_magnetometers
+        {
+            body.magnetic_dipoles
+                .push(MagneticDipole {
+                    moment: moment_body,
+                    position: connection
+                        .transform
+                        .translation
+                        .vec(),
+                });
+        }
         body.state
             .actuator_force_body += Force::from(Vector6::new(
-            self.state
-                .torque_body[0],
-            self.state
-                .torque_body[1],
-            self.state
-                .torque_body[2],
+            torque_body[0],
+            torque_body[1],
+            torque_body[2],
             0.0,
             0.0,
             0.0,
@@ -202,7 +506,8 @@ impl ActuatorModel for MagneticTorquer {
     fn writer_save_fn(&self, writer: &mut StateWriter) {
         writer.float_buffer[0] = self
             .state
-            .command;
+            .command
+            .value;
         writer.float_buffer[1] = self
             .state
             .current;
@@ -224,6 +529,12 @@ impl ActuatorModel for MagneticTorquer {
         writer.float_buffer[7] = self
             .state
             .torque_body[2];
+        writer.float_buffer[8] = self
+            .state
+            .moment;
+        writer.float_buffer[9] = self
+            .state
+            .voltage;
         writer
             .write_record()
             .unwrap();
@@ -237,22 +548,81 @@ impl ActuatorModel for MagneticTorquer {
             "torque(act)[y]",
             "torque(act)[z]",
             "torque(body)[x]",
-            "torque(body)[x]",
-            "torque(body)[x]",
+            "torque(body)[y]",
+            "torque(body)[z]",
+            "dipole",
+            "voltage",
         ]
     }
 
-    fn state_derivative(&self, _derivative: &mut [f64]) {}
+    fn state_derivative(&self, derivative: &mut [f64]) {
+        if self
+            .parameters
+            .coil
+            .is_some()
+        {
+            derivative[0] = self
+                .state
+                .current_rate;
+        }
+    }
 
     fn state_vector_init(&self) -> StateVector {
-        StateVector::new(vec![])
+        if self
+            .parameters
+            .coil
+            .is_some()
+        {
+            StateVector::new(vec![
+                self.state
+                    .current,
+            ])
+        } else {
+            StateVector::new(vec![])
+        }
+    }
+
+    fn state_vector_read(&mut self, state: &[f64]) {
+        if self
+            .parameters
+            .coil
+            .is_some()
+        {
+            self.state
+                .current = state[0];
+        }
     }
 
-    fn state_vector_read(&mut self, _state: &[f64]) {}
+    fn command_schema(&self) -> BufferSchema {
+        MagneticTorquerCommand::schema()
+    }
 
     fn read_command(&mut self, cmd: &HardwareBuffer) -> Result<(), ActuatorErrors> {
         self.state
-            .command = cmd.read::<f64>()?;
+            .command = cmd.read::<MagneticTorquerCommand>()?;
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::mem::offset_of;
+
+    #[test]
+    fn test_command_layout() {
+        MagneticTorquerCommand::schema()
+            .check_layout::<MagneticTorquerCommand>(&[
+                offset_of!(MagneticTorquerCommand, value),
+                offset_of!(
+                    MagneticTorquerCommand,
+                    command
+                ),
+                offset_of!(
+                    MagneticTorquerCommand,
+                    _padding
+                ),
+            ])
+            .unwrap();
+    }
+}
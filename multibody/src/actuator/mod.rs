@@ -13,13 +13,21 @@ use uncertainty::Uncertainty;
 
 use crate::{
     BufferError, HardwareBuffer,
+    actuator::control_moment_gyro::{
+        ControlMomentGyro, ControlMomentGyroBuilder, ControlMomentGyroErrors,
+    },
+    actuator::joint_motor::{JointMotor, JointMotorBuilder, JointMotorErrors},
     actuator::magnetic_torquer_bar::{
         MagneticTorquer, MagneticTorquerBuilder, MagneticTorquerErrors,
     },
     body::{BodyConnection, BodyConnectionBuilder},
+    fault::{ActuatorFaultState, Fault},
+    schema::BufferSchema,
     system::Id,
 };
 
+pub mod control_moment_gyro;
+pub mod joint_motor;
 pub mod magnetic_torquer_bar;
 pub mod reaction_wheel;
 pub mod thruster;
@@ -56,6 +64,8 @@ impl ActuatorBuilder {
                 .clone(),
             model,
             connection,
+            faults: Vec::new(),
+            command_stuck: false,
             writer_id: None,
             state_start: 0,
             state_end: 0,
@@ -72,6 +82,10 @@ pub enum ActuatorErrors {
     #[error("{0}")]
     BufferError(#[from] BufferError),
     #[error("{0}")]
+    ControlMomentGyroErrors(#[from] ControlMomentGyroErrors),
+    #[error("{0}")]
+    JointMotorErrors(#[from] JointMotorErrors),
+    #[error("{0}")]
     MagneticTorquerErrors(#[from] MagneticTorquerErrors),
     #[error("{0}")]
     ReactionWheelErrors(#[from] ReactionWheelErrors),
@@ -80,7 +94,15 @@ pub enum ActuatorErrors {
 }
 
 pub trait ActuatorModel {
-    fn update(&mut self, connection: &BodyConnection) -> Result<(), ActuatorErrors>;
+    fn update(&mut self, t: f64, connection: &BodyConnection) -> Result<(), ActuatorErrors>;
+    /// Applies the combined effect of active faults, called at the end of each accepted step when
+    /// faults are attached
+    fn apply_faults(&mut self, _faults: &ActuatorFaultState) {}
+    /// Time of the next discontinuity in the actuator output after t, such as a valve opening.
+    /// Called at the end of each accepted step, see OdeModel::next_discontinuity
+    fn next_discontinuity(&mut self, _t: f64) -> Option<f64> {
+        None
+    }
     /// Populates derivative with the appropriate values for the actuator state derivative
     fn state_derivative(&self, _derivative: &mut [f64]) {}
     /// Initializes a vector of f64 values representing state vector for the ODE integration
@@ -90,6 +112,8 @@ pub trait ActuatorModel {
     /// Reads a state vector into the sim state
     fn state_vector_read(&mut self, state: &[f64]);
     fn read_command(&mut self, buffer: &HardwareBuffer) -> Result<(), ActuatorErrors>;
+    /// Layout of the command read by read_command
+    fn command_schema(&self) -> BufferSchema;
     fn writer_headers(&self) -> &[&str];
     fn writer_save_fn(&self, writer: &mut StateWriter);
 }
@@ -99,6 +123,10 @@ pub struct Actuator {
     pub name: String,
     pub model: ActuatorModels,
     pub connection: BodyConnection,
+    /// Faults injected into this actuator, attached by the system at build time
+    pub faults: Vec<Fault>,
+    /// Set while a command stuck fault is active, new commands are ignored
+    command_stuck: bool,
     /// Id of the result writer in sys.writers
     writer_id: Option<WriterId>,
     state_start: usize,
@@ -106,9 +134,20 @@ pub struct Actuator {
 }
 
 impl Actuator {
-    pub fn update(&mut self) -> Result<(), ActuatorErrors> {
+    pub fn update(&mut self, t: f64) -> Result<(), ActuatorErrors> {
         self.model
-            .update(&self.connection)
+            .update(t, &self.connection)
+    }
+
+    /// Latches any condition triggered faults whose condition is met, called on accepted steps
+    pub fn check_fault_conditions(&mut self, t: f64) {
+        let body = self
+            .connection
+            .body
+            .borrow();
+        for fault in &mut self.faults {
+            fault.check_condition(t, &body);
+        }
     }
 
     pub fn state_vector_init(&mut self, x0: &mut StateVector) {
@@ -133,7 +172,46 @@ impl Actuator {
             .state_derivative(derivative);
     }
 
+    pub fn command_schema(&self) -> BufferSchema {
+        self.model
+            .command_schema()
+    }
+
+    /// Faults are switched on and off here rather than in update, so that every stage of a
+    /// step sees the same faults, and their start and end times are reported as
+    /// discontinuities so that steps end on them.
+    pub fn next_discontinuity(&mut self, t: f64) -> Option<f64> {
+        if !self
+            .faults
+            .is_empty()
+        {
+            for fault in &mut self.faults {
+                fault.update(t);
+            }
+            let fault_state = ActuatorFaultState::from_faults(&self.faults);
+            self.command_stuck = fault_state.command_stuck;
+            self.model
+                .apply_faults(&fault_state);
+        }
+        let fault_transition = self
+            .faults
+            .iter()
+            .filter_map(|fault| fault.next_transition(t))
+            .reduce(f64::min);
+        [
+            self.model
+                .next_discontinuity(t),
+            fault_transition,
+        ]
+        .into_iter()
+        .flatten()
+        .reduce(f64::min)
+    }
+
     pub fn read_command(&mut self, buffer: &HardwareBuffer) -> Result<(), ActuatorErrors> {
+        if self.command_stuck {
+            return Ok(());
+        }
         self.model
             .read_command(buffer)
     }
@@ -152,20 +230,21 @@ impl Actuator {
     }
 
     pub fn writer_save_fn(&self, manager: &mut WriterManager) {
-        if let Some(id) = &self.writer_id {
-            if let Some(writer) = manager
+        if let Some(id) = &self.writer_id
+            && let Some(writer) = manager
                 .writers
                 .get_mut(id)
-            {
-                self.model
-                    .writer_save_fn(writer);
-            }
+        {
+            self.model
+                .writer_save_fn(writer);
         }
     }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum ActuatorModelBuilders {
+    ControlMomentGyro(ControlMomentGyroBuilder),
+    JointMotor(JointMotorBuilder),
     MagneticTorquer(MagneticTorquerBuilder),
     ReactionWheel(ReactionWheelBuilder),
     Thruster(ThrusterBuilder),
@@ -178,6 +257,12 @@ impl ActuatorModelBuilders {
         rng: &mut SmallRng,
     ) -> Result<ActuatorModels, ActuatorErrors> {
         match self {
+            ActuatorModelBuilders::ControlMomentGyro(builder) => {
+                Ok(ActuatorModels::ControlMomentGyro(builder.sample(nominal, rng)?))
+            }
+            ActuatorModelBuilders::JointMotor(builder) => Ok(ActuatorModels::JointMotor(
+                builder.sample(nominal, rng)?,
+            )),
             ActuatorModelBuilders::MagneticTorquer(builder) => {
                 Ok(ActuatorModels::MagneticTorquer(builder.sample(nominal, rng)?))
             }
@@ -191,6 +276,18 @@ impl ActuatorModelBuilders {
     }
 }
 
+impl From<ControlMomentGyroBuilder> for ActuatorModelBuilders {
+    fn from(builder: ControlMomentGyroBuilder) -> Self {
+        ActuatorModelBuilders::ControlMomentGyro(builder)
+    }
+}
+
+impl From<JointMotorBuilder> for ActuatorModelBuilders {
+    fn from(builder: JointMotorBuilder) -> Self {
+        ActuatorModelBuilders::JointMotor(builder)
+    }
+}
+
 impl From<MagneticTorquerBuilder> for ActuatorModelBuilders {
     fn from(builder: MagneticTorquerBuilder) -> Self {
         ActuatorModelBuilders::MagneticTorquer(builder)
@@ -211,6 +308,8 @@ impl From<ThrusterBuilder> for ActuatorModelBuilders {
 
 #[derive(Debug)]
 pub enum ActuatorModels {
+    ControlMomentGyro(ControlMomentGyro),
+    JointMotor(JointMotor),
     MagneticTorquer(MagneticTorquer),
     ReactionWheel(ReactionWheel),
     Thruster(Thruster),
@@ -219,6 +318,8 @@ pub enum ActuatorModels {
 impl ActuatorModel for ActuatorModels {
     fn writer_save_fn(&self, writer: &mut StateWriter) {
         match self {
+            ActuatorModels::ControlMomentGyro(act) => act.writer_save_fn(writer),
+            ActuatorModels::JointMotor(act) => act.writer_save_fn(writer),
             ActuatorModels::MagneticTorquer(act) => act.writer_save_fn(writer),
             ActuatorModels::ReactionWheel(act) => act.writer_save_fn(writer),
             ActuatorModels::Thruster(act) => act.writer_save_fn(writer),
@@ -227,6 +328,8 @@ impl ActuatorModel for ActuatorModels {
 
     fn writer_headers(&self) -> &[&str] {
         match self {
+            ActuatorModels::ControlMomentGyro(act) => act.writer_headers(),
+            ActuatorModels::JointMotor(act) => act.writer_headers(),
             ActuatorModels::MagneticTorquer(act) => act.writer_headers(),
             ActuatorModels::ReactionWheel(act) => act.writer_headers(),
             ActuatorModels::Thruster(act) => act.writer_headers(),
@@ -235,6 +338,8 @@ impl ActuatorModel for ActuatorModels {
 
     fn state_derivative(&self, derivative: &mut [f64]) {
         match self {
+            ActuatorModels::ControlMomentGyro(act) => act.state_derivative(derivative),
+            ActuatorModels::JointMotor(act) => act.state_derivative(derivative),
             ActuatorModels::MagneticTorquer(act) => act.state_derivative(derivative),
             ActuatorModels::ReactionWheel(act) => act.state_derivative(derivative),
             ActuatorModels::Thruster(act) => act.state_derivative(derivative),
@@ -243,6 +348,8 @@ impl ActuatorModel for ActuatorModels {
 
     fn state_vector_init(&self) -> StateVector {
         match self {
+            ActuatorModels::ControlMomentGyro(act) => act.state_vector_init(),
+            ActuatorModels::JointMotor(act) => act.state_vector_init(),
             ActuatorModels::MagneticTorquer(act) => act.state_vector_init(),
             ActuatorModels::ReactionWheel(act) => act.state_vector_init(),
             ActuatorModels::Thruster(act) => act.state_vector_init(),
@@ -251,22 +358,58 @@ impl ActuatorModel for ActuatorModels {
 
     fn state_vector_read(&mut self, state: &[f64]) {
         match self {
+            ActuatorModels::ControlMomentGyro(act) => act.state_vector_read(state),
+            ActuatorModels::JointMotor(act) => act.state_vector_read(state),
             ActuatorModels::MagneticTorquer(act) => act.state_vector_read(state),
             ActuatorModels::ReactionWheel(act) => act.state_vector_read(state),
             ActuatorModels::Thruster(act) => act.state_vector_read(state),
         }
     }
 
-    fn update(&mut self, connection: &BodyConnection) -> Result<(), ActuatorErrors> {
+    fn update(&mut self, t: f64, connection: &BodyConnection) -> Result<(), ActuatorErrors> {
+        match self {
+            ActuatorModels::ControlMomentGyro(act) => act.update(t, connection),
+            ActuatorModels::JointMotor(act) => act.update(t, connection),
+            ActuatorModels::MagneticTorquer(act) => act.update(t, connection),
+            ActuatorModels::ReactionWheel(act) => act.update(t, connection),
+            ActuatorModels::Thruster(act) => act.update(t, connection),
+        }
+    }
+
+    fn apply_faults(&mut self, faults: &ActuatorFaultState) {
+        match self {
+            ActuatorModels::ControlMomentGyro(act) => act.apply_faults(faults),
+            ActuatorModels::JointMotor(act) => act.apply_faults(faults),
+            ActuatorModels::MagneticTorquer(act) => act.apply_faults(faults),
+            ActuatorModels::ReactionWheel(act) => act.apply_faults(faults),
+            ActuatorModels::Thruster(act) => act.apply_faults(faults),
+        }
+    }
+
+    fn next_discontinuity(&mut self, t: f64) -> Option<f64> {
+        match self {
+            ActuatorModels::ControlMomentGyro(act) => act.next_discontinuity(t),
+            ActuatorModels::JointMotor(act) => act.next_discontinuity(t),
+            ActuatorModels::MagneticTorquer(act) => act.next_discontinuity(t),
+            ActuatorModels::ReactionWheel(act) => act.next_discontinuity(t),
+            ActuatorModels::Thruster(act) => act.next_discontinuity(t),
+        }
+    }
+
+    fn command_schema(&self) -> BufferSchema {
         match self {
-            ActuatorModels::MagneticTorquer(act) => act.update(connection),
-            ActuatorModels::ReactionWheel(act) => act.update(connection),
-            ActuatorModels::Thruster(act) => act.update(connection),
+            ActuatorModels::ControlMomentGyro(act) => act.command_schema(),
+            ActuatorModels::JointMotor(act) => act.command_schema(),
+            ActuatorModels::MagneticTorquer(act) => act.command_schema(),
+            ActuatorModels::ReactionWheel(act) => act.command_schema(),
+            ActuatorModels::Thruster(act) => act.command_schema(),
         }
     }
 
     fn read_command(&mut self, cmd: &HardwareBuffer) -> Result<(), ActuatorErrors> {
         match self {
+            ActuatorModels::ControlMomentGyro(act) => act.read_command(cmd),
+            ActuatorModels::JointMotor(act) => act.read_command(cmd),
             ActuatorModels::MagneticTorquer(act) => act.read_command(cmd),
             ActuatorModels::ReactionWheel(act) => act.read_command(cmd),
             ActuatorModels::Thruster(act) => act.read_command(cmd),
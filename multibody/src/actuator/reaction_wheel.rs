@@ -1,4 +1,10 @@
-use crate::{HardwareBuffer, actuator::ActuatorModel, body::BodyConnection};
+use crate::{
+    HardwareBuffer,
+    actuator::ActuatorModel,
+    body::BodyConnection,
+    fault::ActuatorFaultState,
+    schema::{BufferSchema, FieldType},
+};
 use bytemuck::{Pod, Zeroable};
 use nadir_diffeq::{saving::StateWriter, state::state_vector::StateVector};
 use nalgebra::{Vector3, Vector6};
@@ -36,6 +42,16 @@ pub enum ReactionWheelErrors {
     NegativeMaxTorque,
     #[error("max torque must be greater than 0")]
     SmallInertia,
+    #[error("speed controller gains must be greater than or equal to 0")]
+    NegativeSpeedGain,
+    #[error("tachometer resolution must be greater than or equal to 0")]
+    NegativeTachometerResolution,
+    #[error("speed command requires a speed controller")]
+    SpeedControllerNotSet,
+    #[error("imbalance must be greater than or equal to 0")]
+    NegativeImbalance,
+    #[error("motor resistance and bus voltage must be greater than 0")]
+    NonPositiveMotor,
     #[error("{0}")]
     Quaternion(#[from] QuaternionErrors),
 }
@@ -49,6 +65,32 @@ pub struct ReactionWheelCommand {
 }
 
 impl ReactionWheelCommand {
+    pub fn schema() -> BufferSchema {
+        BufferSchema::new("ReactionWheelCommand")
+            .with_field(
+                "value",
+                FieldType::F64,
+                "Nm, A or rad/s by command",
+            )
+            .with_field("command", FieldType::U8, "")
+            .with_padding(7)
+            .with_constant(
+                "torque",
+                FieldType::U8,
+                Self::TORQUE as u64,
+            )
+            .with_constant(
+                "current",
+                FieldType::U8,
+                Self::CURRENT as u64,
+            )
+            .with_constant(
+                "speed",
+                FieldType::U8,
+                Self::SPEED as u64,
+            )
+    }
+
     const TORQUE: u8 = 0;
     const CURRENT: u8 = 1;
     const SPEED: u8 = 2;
@@ -148,12 +190,214 @@ impl ReactionWheelFriction {
     }
 }
 
+/// Internal PI speed loop used for SPEED commands, closed on a tachometer measurement
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct SpeedControllerBuilder {
+    kp: UncertainValue,                    // Nm/(rad/s)
+    ki: UncertainValue,                    // Nm/rad
+    tachometer_resolution: UncertainValue, // rad/s
+}
+
+impl SpeedControllerBuilder {
+    pub fn new(kp: f64, ki: f64) -> Result<Self, ReactionWheelErrors> {
+        if kp < 0.0 || ki < 0.0 {
+            return Err(ReactionWheelErrors::NegativeSpeedGain);
+        }
+        Ok(Self {
+            kp: UncertainValue::new(kp),
+            ki: UncertainValue::new(ki),
+            tachometer_resolution: UncertainValue::new(0.0),
+        })
+    }
+
+    /// Quantization (rad/s) of the tachometer speed measurement, 0 for a perfect measurement
+    pub fn set_tachometer_resolution(
+        &mut self,
+        resolution: f64,
+    ) -> Result<(), ReactionWheelErrors> {
+        if resolution < 0.0 {
+            return Err(ReactionWheelErrors::NegativeTachometerResolution);
+        }
+        self.tachometer_resolution
+            .nominal = resolution;
+        Ok(())
+    }
+
+    pub fn with_tachometer_resolution(
+        mut self,
+        resolution: f64,
+    ) -> Result<Self, ReactionWheelErrors> {
+        self.set_tachometer_resolution(resolution)?;
+        Ok(self)
+    }
+
+    fn sample(&self, nominal: bool, rng: &mut SmallRng) -> SpeedController {
+        SpeedController {
+            kp: self
+                .kp
+                .sample(nominal, rng),
+            ki: self
+                .ki
+                .sample(nominal, rng),
+            tachometer_resolution: self
+                .tachometer_resolution
+                .sample(nominal, rng),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct SpeedController {
+    kp: f64,
+    ki: f64,
+    tachometer_resolution: f64,
+}
+
+impl SpeedController {
+    fn measure(&self, velocity: f64) -> f64 {
+        if self.tachometer_resolution > 0.0 {
+            (velocity / self.tachometer_resolution).round() * self.tachometer_resolution
+        } else {
+            velocity
+        }
+    }
+}
+
+/// Rotor imbalance producing disturbances at the wheel speed.
+/// Static imbalance (kg-m) produces a radial force, dynamic imbalance (kg-m^2) produces a
+/// transverse torque, both rotating with the rotor in the wheel plane
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ImbalanceBuilder {
+    dynamic_imbalance: UncertainValue, // kg-m^2
+    dynamic_phase: UncertainValue,     // rad, dynamic relative to static imbalance
+    static_imbalance: UncertainValue,  // kg-m
+}
+
+impl ImbalanceBuilder {
+    pub fn new(static_imbalance: f64, dynamic_imbalance: f64) -> Result<Self, ReactionWheelErrors> {
+        if static_imbalance < 0.0 || dynamic_imbalance < 0.0 {
+            return Err(ReactionWheelErrors::NegativeImbalance);
+        }
+        Ok(Self {
+            dynamic_imbalance: UncertainValue::new(dynamic_imbalance),
+            dynamic_phase: UncertainValue::new(0.0),
+            static_imbalance: UncertainValue::new(static_imbalance),
+        })
+    }
+
+    pub fn set_dynamic_phase(&mut self, phase: f64) {
+        self.dynamic_phase
+            .nominal = phase;
+    }
+
+    pub fn with_dynamic_phase(mut self, phase: f64) -> Self {
+        self.set_dynamic_phase(phase);
+        self
+    }
+
+    fn sample(&self, nominal: bool, rng: &mut SmallRng) -> Imbalance {
+        Imbalance {
+            dynamic_imbalance: self
+                .dynamic_imbalance
+                .sample(nominal, rng),
+            dynamic_phase: self
+                .dynamic_phase
+                .sample(nominal, rng),
+            static_imbalance: self
+                .static_imbalance
+                .sample(nominal, rng),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Imbalance {
+    dynamic_imbalance: f64,
+    dynamic_phase: f64,
+    static_imbalance: f64,
+}
+
+impl Imbalance {
+    /// Disturbance force and torque in the wheel frame at the rotor angle and speed
+    fn calculate(&self, angle: f64, velocity: f64) -> (Vector3<f64>, Vector3<f64>) {
+        let w2 = velocity * velocity;
+        let force = self.static_imbalance * w2 * Vector3::new(angle.cos(), angle.sin(), 0.0);
+        let phase = angle + self.dynamic_phase;
+        let torque = self.dynamic_imbalance * w2 * Vector3::new(phase.cos(), phase.sin(), 0.0);
+        (force, torque)
+    }
+}
+
+/// Steady state DC motor model, the winding inductance is neglected.
+/// Current is limited by the back emf and the bus voltage. The back emf constant (V/(rad/s)) is
+/// the wheel torque constant (Nm/A), which are equal in SI units.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct MotorBuilder {
+    bus_voltage: UncertainValue, // V
+    resistance: UncertainValue,  // ohm
+}
+
+impl MotorBuilder {
+    pub fn new(resistance: f64, bus_voltage: f64) -> Result<Self, ReactionWheelErrors> {
+        if resistance <= 0.0 || bus_voltage <= 0.0 {
+            return Err(ReactionWheelErrors::NonPositiveMotor);
+        }
+        Ok(Self {
+            bus_voltage: UncertainValue::new(bus_voltage),
+            resistance: UncertainValue::new(resistance),
+        })
+    }
+
+    fn sample(&self, nominal: bool, rng: &mut SmallRng) -> Motor {
+        Motor {
+            bus_voltage: self
+                .bus_voltage
+                .sample(nominal, rng),
+            resistance: self
+                .resistance
+                .sample(nominal, rng),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Motor {
+    bus_voltage: f64,
+    resistance: f64,
+}
+
+impl Motor {
+    /// Returns the achieved current (A) and the applied voltage (V) for a commanded current.
+    /// The commanded current is returned unchanged when the bus voltage can supply it.
+    fn drive(&self, torque_constant: f64, current_command: f64, velocity: f64) -> (f64, f64) {
+        let back_emf = torque_constant * velocity;
+        let voltage = self.resistance * current_command + back_emf;
+        if voltage.abs() <= self.bus_voltage {
+            return (current_command, voltage);
+        }
+        let voltage = voltage.clamp(
+            -self.bus_voltage,
+            self.bus_voltage,
+        );
+        (
+            (voltage - back_emf) / self.resistance,
+            voltage,
+        )
+    }
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize)]
 struct ReactionWheelParametersBuilder {
     delay: Option<UncertainValue>, //sec
     friction: ReactionWheelFrictionBuilder,
+    #[serde(default)]
+    imbalance: Option<ImbalanceBuilder>,
     inertia: UncertainValue, // kg-m^2
     misalignment: Option<UnitQuaternionBuilder>,
+    #[serde(default)]
+    motor: Option<MotorBuilder>,
+    #[serde(default)]
+    speed_controller: Option<SpeedControllerBuilder>,
     torque_constant: UncertainValue,
     torque_max: Option<UncertainValue>,
     torque_speed_curve: Option<TorqueSpeedCurveBuilder>,
@@ -161,14 +405,17 @@ struct ReactionWheelParametersBuilder {
 
 impl ReactionWheelParametersBuilder {
     pub fn new(inertia: f64, torque_constant: f64) -> Result<Self, ReactionWheelErrors> {
-        if inertia < std::f64::EPSILON {
+        if inertia < f64::EPSILON {
             return Err(ReactionWheelErrors::SmallInertia);
         }
         Ok(Self {
             delay: None,
             friction: ReactionWheelFrictionBuilder::default(),
+            imbalance: None,
             inertia: UncertainValue::new(inertia),
             misalignment: None,
+            motor: None,
+            speed_controller: None,
             torque_constant: UncertainValue::new(torque_constant),
             torque_max: None,
             torque_speed_curve: None,
@@ -185,24 +432,37 @@ impl ReactionWheelParametersBuilder {
         } else {
             None
         };
-        let torque_max = if let Some(torque_max) = &self.torque_max {
-            Some(torque_max.sample(nominal, rng))
-        } else {
-            None
-        };
-        let torque_speed_curve = if let Some(torque_speed_curve) = &self.torque_speed_curve {
-            Some(torque_speed_curve.sample(nominal, rng))
-        } else {
-            None
-        };
+        let torque_max = self
+            .torque_max
+            .as_ref()
+            .map(|torque_max| torque_max.sample(nominal, rng));
+        let torque_speed_curve = self
+            .torque_speed_curve
+            .as_ref()
+            .map(|torque_speed_curve| torque_speed_curve.sample(nominal, rng));
+        let imbalance = self
+            .imbalance
+            .as_ref()
+            .map(|imbalance| imbalance.sample(nominal, rng));
+        let motor = self
+            .motor
+            .as_ref()
+            .map(|motor| motor.sample(nominal, rng));
+        let speed_controller = self
+            .speed_controller
+            .as_ref()
+            .map(|controller| controller.sample(nominal, rng));
         Ok(ReactionWheelParameters {
             friction: self
                 .friction
                 .sample(nominal, rng),
+            imbalance,
             inertia: self
                 .inertia
                 .sample(nominal, rng),
             misalignment,
+            motor,
+            speed_controller,
             torque_constant: self
                 .torque_constant
                 .sample(nominal, rng),
@@ -215,8 +475,11 @@ impl ReactionWheelParametersBuilder {
 #[derive(Debug)]
 struct ReactionWheelParameters {
     friction: ReactionWheelFriction,
+    imbalance: Option<Imbalance>,
     inertia: f64, // kg-m^2
     misalignment: Option<UnitQuaternion>,
+    motor: Option<Motor>,
+    speed_controller: Option<SpeedController>,
     torque_constant: f64,
     torque_max: Option<f64>,
     torque_speed_curve: Option<TorqueSpeedCurve>,
@@ -225,23 +488,41 @@ struct ReactionWheelParameters {
 #[derive(Debug)]
 pub struct ReactionWheelState {
     acceleration: f64, //rad/sec^2
+    angle: f64,        // rad, rotor angle for imbalance phasing
+    bus_power: f64,    // W
     pub command: ReactionWheelCommand,
-    current: f64,                // A
-    momentum: f64,               //Nms
-    momentum_body: Vector3<f64>, //Nms
-    velocity: f64,               // m/s
-    torque: f64,                 // Nm
-    torque_body: Vector3<f64>,   // Nm
+    current: f64,                        // A
+    imbalance_force_body: Vector3<f64>,  // N
+    imbalance_torque_body: Vector3<f64>, // Nm
+    fault_friction: f64,                 // Nm
+    momentum: f64,                       //Nms
+    momentum_body: Vector3<f64>,         //Nms
+    speed_command: f64,                  // rad/s
+    speed_integral: f64,                 // rad, integral of the speed loop error
+    speed_integral_rate: f64,            // rad/s
+    speed_measured: f64,                 // rad/s, tachometer
+    velocity: f64,                       // m/s
+    torque: f64,                         // Nm
+    torque_body: Vector3<f64>,           // Nm
 }
 
 impl ReactionWheelState {
     pub fn new(initial_speed: f64, initial_momentum: f64) -> Self {
         Self {
             acceleration: 0.0,
+            angle: 0.0,
+            bus_power: 0.0,
             command: ReactionWheelCommand::default(),
             current: 0.0,
+            imbalance_force_body: Vector3::zeros(),
+            imbalance_torque_body: Vector3::zeros(),
+            fault_friction: 0.0,
             momentum: initial_momentum,
             momentum_body: Vector3::zeros(),
+            speed_command: 0.0,
+            speed_integral: 0.0,
+            speed_integral_rate: 0.0,
+            speed_measured: initial_speed,
             velocity: initial_speed,
             torque: 0.0,
             torque_body: Vector3::zeros(),
@@ -317,6 +598,40 @@ impl ReactionWheelBuilder {
         Ok(self)
     }
 
+    /// Adds speed dependent disturbance forces and torques from rotor imbalance
+    pub fn set_imbalance(&mut self, imbalance: ImbalanceBuilder) {
+        self.parameters
+            .imbalance = Some(imbalance);
+    }
+
+    pub fn with_imbalance(mut self, imbalance: ImbalanceBuilder) -> Self {
+        self.set_imbalance(imbalance);
+        self
+    }
+
+    /// Limits the motor current by the back emf and bus voltage, using the torque constant as
+    /// the back emf constant
+    pub fn set_motor(&mut self, motor: MotorBuilder) {
+        self.parameters
+            .motor = Some(motor);
+    }
+
+    pub fn with_motor(mut self, motor: MotorBuilder) -> Self {
+        self.set_motor(motor);
+        self
+    }
+
+    /// Enables SPEED commands using an internal PI speed loop
+    pub fn set_speed_controller(&mut self, controller: SpeedControllerBuilder) {
+        self.parameters
+            .speed_controller = Some(controller);
+    }
+
+    pub fn with_speed_controller(mut self, controller: SpeedControllerBuilder) -> Self {
+        self.set_speed_controller(controller);
+        self
+    }
+
     pub fn set_speed(&mut self, speed: f64) {
         self.initial_speed
             .nominal = speed;
@@ -521,7 +836,18 @@ pub struct ReactionWheel {
 }
 
 impl ActuatorModel for ReactionWheel {
-    fn update(&mut self, connection: &BodyConnection) -> Result<(), ActuatorErrors> {
+    fn apply_faults(&mut self, faults: &ActuatorFaultState) {
+        self.state
+            .fault_friction = faults.wheel_friction;
+    }
+
+    fn update(&mut self, _t: f64, connection: &BodyConnection) -> Result<(), ActuatorErrors> {
+        // the speed loop integrator only runs in SPEED mode
+        self.state
+            .speed_integral_rate = 0.0;
+        // speed loop error and unlimited torque, for anti-windup once the achieved torque is known
+        let mut speed_loop = None;
+
         // Determine initial torque based on command type
         let mut torque = match self
             .state
@@ -552,7 +878,38 @@ impl ActuatorModel for ReactionWheel {
                         .value
             }
             ReactionWheelCommand::SPEED => {
-                todo!()
+                let controller = self
+                    .parameters
+                    .speed_controller
+                    .as_ref()
+                    .ok_or(ReactionWheelErrors::SpeedControllerNotSet)?;
+                let speed_command = self
+                    .state
+                    .command
+                    .value;
+                let speed_measured = controller.measure(
+                    self.state
+                        .velocity,
+                );
+                let error = speed_command - speed_measured;
+                let desired_torque = controller.kp * error
+                    + controller.ki
+                        * self
+                            .state
+                            .speed_integral;
+                speed_loop = Some((error, desired_torque));
+                self.state
+                    .speed_command = speed_command;
+                self.state
+                    .speed_measured = speed_measured;
+                if let Some(torque_max) = self
+                    .parameters
+                    .torque_max
+                {
+                    desired_torque.clamp(-torque_max, torque_max)
+                } else {
+                    desired_torque
+                }
             }
             _ => {
                 return Err(ReactionWheelErrors::InvalidCommand.into());
@@ -571,6 +928,55 @@ impl ActuatorModel for ReactionWheel {
             );
         }
 
+        // Motor current and bus power for the final motor torque, limited by the back emf and bus
+        // voltage if a motor model is provided. Friction below acts on the rotor and doesn't draw
+        // current from the bus.
+        let torque_constant = self
+            .parameters
+            .torque_constant;
+        let current_command = if torque_constant != 0.0 {
+            torque / torque_constant
+        } else {
+            0.0
+        };
+        let velocity = self
+            .state
+            .velocity;
+        if let Some(motor) = &self
+            .parameters
+            .motor
+        {
+            let (current, voltage) = motor.drive(
+                torque_constant,
+                current_command,
+                velocity,
+            );
+            if current != current_command {
+                torque = torque_constant * current;
+            }
+            self.state
+                .current = current;
+            self.state
+                .bus_power = voltage * current;
+        } else {
+            self.state
+                .current = current_command;
+            self.state
+                .bus_power = torque * velocity;
+        }
+
+        // stop integrating while the achieved motor torque is limited by the max torque, the
+        // torque-speed curve or the bus voltage in the direction of the error (anti-windup)
+        if let Some((error, desired_torque)) = speed_loop {
+            self.state
+                .speed_integral_rate =
+                if torque != desired_torque && error.signum() == desired_torque.signum() {
+                    0.0
+                } else {
+                    error
+                };
+        }
+
         // Apply friction adjustments if provided
         if self
             .state
@@ -608,6 +1014,30 @@ impl ActuatorModel for ReactionWheel {
             };
         }
 
+        // Additional friction from an active fault opposes wheel speed, or at zero speed
+        // opposes the applied torque without reversing it
+        let fault_friction = self
+            .state
+            .fault_friction;
+        if fault_friction > 0.0 {
+            if self
+                .state
+                .velocity
+                != 0.0
+            {
+                torque -= self
+                    .state
+                    .velocity
+                    .signum()
+                    * fault_friction;
+            } else {
+                torque -= torque.clamp(
+                    -fault_friction,
+                    fault_friction,
+                );
+            }
+        }
+
         // Update state
         self.state
             .torque = torque;
@@ -655,6 +1085,42 @@ impl ActuatorModel for ReactionWheel {
                 .parameters
                 .inertia;
 
+        // Imbalance disturbances rotate with the rotor, the static imbalance force also
+        // produces a torque about the body origin
+        let mut imbalance_force_body = Vector3::zeros();
+        let mut imbalance_torque_body = Vector3::zeros();
+        if let Some(imbalance) = &self
+            .parameters
+            .imbalance
+        {
+            let (force_wheel, torque_wheel) = imbalance.calculate(
+                self.state
+                    .angle,
+                velocity,
+            );
+            let rotation = &connection
+                .transform
+                .rotation;
+            imbalance_force_body = rotation.transform(&force_wheel);
+            imbalance_torque_body = rotation.transform(&torque_wheel);
+            if let Some(misalignment) = &self
+                .parameters
+                .misalignment
+            {
+                imbalance_force_body = misalignment.transform(&imbalance_force_body);
+                imbalance_torque_body = misalignment.transform(&imbalance_torque_body);
+            }
+            imbalance_torque_body += connection
+                .transform
+                .translation
+                .vec()
+                .cross(&imbalance_force_body);
+        }
+        self.state
+            .imbalance_force_body = imbalance_force_body;
+        self.state
+            .imbalance_torque_body = imbalance_torque_body;
+
         // Update body
         let mut body = connection
             .body
@@ -666,14 +1132,17 @@ impl ActuatorModel for ReactionWheel {
         body.state
             .actuator_force_body += Force::from(Vector6::new(
             self.state
-                .torque_body[0],
+                .torque_body[0]
+                + imbalance_torque_body[0],
             self.state
-                .torque_body[1],
+                .torque_body[1]
+                + imbalance_torque_body[1],
             self.state
-                .torque_body[2],
-            0.0,
-            0.0,
-            0.0,
+                .torque_body[2]
+                + imbalance_torque_body[2],
+            imbalance_force_body[0],
+            imbalance_force_body[1],
+            imbalance_force_body[2],
         ));
         Ok(())
     }
@@ -712,6 +1181,26 @@ impl ActuatorModel for ReactionWheel {
         writer.float_buffer[10] = self
             .state
             .torque_body[2];
+        writer.float_buffer[11] = self
+            .state
+            .speed_command;
+        writer.float_buffer[12] = self
+            .state
+            .speed_measured;
+        writer.float_buffer[13] = self
+            .state
+            .angle;
+        writer.float_buffer[14] = self
+            .state
+            .bus_power;
+        for i in 0..3 {
+            writer.float_buffer[15 + i] = self
+                .state
+                .imbalance_force_body[i];
+            writer.float_buffer[18 + i] = self
+                .state
+                .imbalance_torque_body[i];
+        }
         writer
             .write_record()
             .unwrap();
@@ -730,25 +1219,96 @@ impl ActuatorModel for ReactionWheel {
             "torque(body)[x]",
             "torque(body)[y]",
             "torque(body)[z]",
+            "speed_command",
+            "speed_measured",
+            "angle",
+            "bus_power",
+            "imbalance_force(body)[x]",
+            "imbalance_force(body)[y]",
+            "imbalance_force(body)[z]",
+            "imbalance_torque(body)[x]",
+            "imbalance_torque(body)[y]",
+            "imbalance_torque(body)[z]",
         ]
     }
 
+    /// The state is the wheel speed, followed by the speed loop integral when there is a speed
+    /// controller and the rotor angle when there is an imbalance
     fn state_derivative(&self, derivative: &mut [f64]) {
         derivative[0] = self
             .state
             .acceleration;
+        let mut i = 1;
+        if self
+            .parameters
+            .speed_controller
+            .is_some()
+        {
+            derivative[i] = self
+                .state
+                .speed_integral_rate;
+            i += 1;
+        }
+        if self
+            .parameters
+            .imbalance
+            .is_some()
+        {
+            derivative[i] = self
+                .state
+                .velocity;
+        }
     }
 
     fn state_vector_init(&self) -> StateVector {
-        StateVector::new(vec![
+        let mut state = vec![
             self.state
                 .velocity,
-        ])
+        ];
+        if self
+            .parameters
+            .speed_controller
+            .is_some()
+        {
+            state.push(
+                self.state
+                    .speed_integral,
+            );
+        }
+        if self
+            .parameters
+            .imbalance
+            .is_some()
+        {
+            state.push(
+                self.state
+                    .angle,
+            );
+        }
+        StateVector::new(state)
     }
 
     fn state_vector_read(&mut self, state: &[f64]) {
         self.state
             .velocity = state[0];
+        let mut i = 1;
+        if self
+            .parameters
+            .speed_controller
+            .is_some()
+        {
+            self.state
+                .speed_integral = state[i];
+            i += 1;
+        }
+        if self
+            .parameters
+            .imbalance
+            .is_some()
+        {
+            self.state
+                .angle = state[i];
+        }
         self.state
             .momentum = self
             .state
@@ -758,9 +1318,30 @@ impl ActuatorModel for ReactionWheel {
                 .inertia;
     }
 
+    fn command_schema(&self) -> BufferSchema {
+        ReactionWheelCommand::schema()
+    }
+
     fn read_command(&mut self, cmd: &HardwareBuffer) -> Result<(), ActuatorErrors> {
         self.state
             .command = cmd.read::<ReactionWheelCommand>()?;
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::mem::offset_of;
+
+    #[test]
+    fn test_command_layout() {
+        ReactionWheelCommand::schema()
+            .check_layout::<ReactionWheelCommand>(&[
+                offset_of!(ReactionWheelCommand, value),
+                offset_of!(ReactionWheelCommand, command),
+                offset_of!(ReactionWheelCommand, _padding),
+            ])
+            .unwrap();
+    }
+}
@@ -1,6 +1,12 @@
-use crate::{BufferError, HardwareBuffer, actuator::ActuatorModel, body::BodyConnection};
+use crate::{
+    BufferError, HardwareBuffer,
+    actuator::ActuatorModel,
+    body::BodyConnection,
+    fault::ActuatorFaultState,
+    schema::{BufferSchema, FieldType},
+};
 use bytemuck::{Pod, Zeroable};
-use nadir_diffeq::saving::StateWriter;
+use nadir_diffeq::{saving::StateWriter, state::state_vector::StateVector};
 use nalgebra::{Vector3, Vector6};
 use rand::rngs::SmallRng;
 use rotations::{
@@ -14,40 +20,236 @@ use uncertainty::{UncertainValue, Uncertainty};
 
 use super::ActuatorErrors;
 
+/// Standard gravity (m/s^2) used to convert Isp to exhaust velocity
+const G0: f64 = 9.80665;
+
 #[derive(Debug, Error)]
 pub enum ThrusterErrors {
     #[error("{0}")]
     BufferError(#[from] BufferError),
+    #[error("blowdown requires the thruster isp to be set")]
+    BlowdownRequiresIsp,
     #[error("invalid command for thruster")]
     InvalidCommand,
     #[error("{0}")]
     Quaternion(#[from] QuaternionErrors),
-    #[error("thruster force must be greater than 0.0")]
+    #[error("delay must be greater than 0.0")]
     NegativeDelay,
+    #[error("minimum on time must be greater than 0.0")]
+    NegativeMinimumOnTime,
+    #[error("time constants must be greater than 0.0")]
+    NegativeTimeConstant,
+    #[error("blowdown gas volume, pressure and propellant density must be greater than 0.0")]
+    NonPositiveBlowdown,
+    #[error("thruster force must be greater than 0.0")]
+    NonPositiveForce,
+    #[error("isp must be greater than 0.0")]
+    NonPositiveIsp,
 }
 
-#[derive(Clone, Copy, Debug, Pod, PartialEq, Zeroable)]
+/// Command for a thruster
+/// OFF: close the valve
+/// ON: open the valve until commanded OFF
+/// ON_TIME: open the valve for `value` seconds, e.g. the output of a PWPF modulator for the
+/// current control cycle
+#[derive(Clone, Copy, Debug, Default, Pod, PartialEq, Zeroable)]
 #[repr(C)]
-pub struct ThrusterCommand(u8);
+pub struct ThrusterCommand {
+    pub value: f64,
+    pub command: u8,
+    _padding: [u8; 7],
+}
 
 impl ThrusterCommand {
-    const OFF: Self = Self(0);
-    const ON: Self = Self(1);
+    pub fn schema() -> BufferSchema {
+        BufferSchema::new("ThrusterCommand")
+            .with_field(
+                "value",
+                FieldType::F64,
+                "s for on_time",
+            )
+            .with_field("command", FieldType::U8, "")
+            .with_padding(7)
+            .with_constant(
+                "off",
+                FieldType::U8,
+                Self::OFF as u64,
+            )
+            .with_constant(
+                "on",
+                FieldType::U8,
+                Self::ON as u64,
+            )
+            .with_constant(
+                "on_time",
+                FieldType::U8,
+                Self::ON_TIME as u64,
+            )
+    }
+
+    pub const OFF: u8 = 0;
+    pub const ON: u8 = 1;
+    pub const ON_TIME: u8 = 2;
+
+    pub fn off() -> Self {
+        Self { value: 0.0, command: Self::OFF, _padding: [0; 7] }
+    }
+
+    pub fn on() -> Self {
+        Self { value: 0.0, command: Self::ON, _padding: [0; 7] }
+    }
+
+    pub fn on_time(on_time: f64) -> Self {
+        Self { value: on_time, command: Self::ON_TIME, _padding: [0; 7] }
+    }
+}
+
+/// Isothermal blowdown of a pressurant gas as propellant is expelled.
+/// Thrust scales linearly with tank pressure and isp scales with pressure^isp_exponent
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct BlowdownBuilder {
+    gas_volume: UncertainValue,         // m^3
+    initial_pressure: UncertainValue,   // Pa
+    isp_exponent: UncertainValue,       // isp ∝ (p/p0)^isp_exponent
+    propellant_density: UncertainValue, // kg/m^3
+}
+
+impl BlowdownBuilder {
+    pub fn new(
+        initial_pressure: f64,
+        gas_volume: f64,
+        propellant_density: f64,
+    ) -> Result<Self, ThrusterErrors> {
+        if initial_pressure <= 0.0 || gas_volume <= 0.0 || propellant_density <= 0.0 {
+            return Err(ThrusterErrors::NonPositiveBlowdown);
+        }
+        Ok(Self {
+            gas_volume: UncertainValue::new(gas_volume),
+            initial_pressure: UncertainValue::new(initial_pressure),
+            isp_exponent: UncertainValue::new(0.0),
+            propellant_density: UncertainValue::new(propellant_density),
+        })
+    }
+
+    pub fn with_isp_exponent(mut self, isp_exponent: f64) -> Self {
+        self.isp_exponent
+            .nominal = isp_exponent;
+        self
+    }
+
+    fn sample(&self, nominal: bool, rng: &mut SmallRng) -> Blowdown {
+        Blowdown {
+            gas_volume: self
+                .gas_volume
+                .sample(nominal, rng),
+            initial_pressure: self
+                .initial_pressure
+                .sample(nominal, rng),
+            isp_exponent: self
+                .isp_exponent
+                .sample(nominal, rng),
+            propellant_density: self
+                .propellant_density
+                .sample(nominal, rng),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Blowdown {
+    gas_volume: f64,
+    initial_pressure: f64,
+    isp_exponent: f64,
+    propellant_density: f64,
+}
+
+impl Blowdown {
+    fn pressure(&self, propellant_used: f64) -> f64 {
+        self.initial_pressure * self.gas_volume
+            / (self.gas_volume + propellant_used / self.propellant_density)
+    }
+}
+
+/// Linear plume impingement model, the force (N) and torque (Nm) on the body
+/// per N of thrust, both in the body frame
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct PlumeImpingementBuilder {
+    force: [UncertainValue; 3],
+    torque: [UncertainValue; 3],
+}
+
+impl PlumeImpingementBuilder {
+    pub fn new(force: Vector3<f64>, torque: Vector3<f64>) -> Self {
+        Self {
+            force: [
+                UncertainValue::new(force[0]),
+                UncertainValue::new(force[1]),
+                UncertainValue::new(force[2]),
+            ],
+            torque: [
+                UncertainValue::new(torque[0]),
+                UncertainValue::new(torque[1]),
+                UncertainValue::new(torque[2]),
+            ],
+        }
+    }
+
+    fn sample(&self, nominal: bool, rng: &mut SmallRng) -> PlumeImpingement {
+        PlumeImpingement {
+            force: Vector3::new(
+                self.force[0].sample(nominal, rng),
+                self.force[1].sample(nominal, rng),
+                self.force[2].sample(nominal, rng),
+            ),
+            torque: Vector3::new(
+                self.torque[0].sample(nominal, rng),
+                self.torque[1].sample(nominal, rng),
+                self.torque[2].sample(nominal, rng),
+            ),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct PlumeImpingement {
+    force: Vector3<f64>,
+    torque: Vector3<f64>,
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
 struct ThrusterParametersBuilder {
+    #[serde(default)]
+    blowdown: Option<BlowdownBuilder>,
     delay: Option<UncertainValue>, //sec
+    #[serde(default)]
+    isp: Option<UncertainValue>, // sec
+    #[serde(default)]
+    minimum_on_time: Option<UncertainValue>, // sec
     misalignment: Option<UnitQuaternionBuilder>,
     force: UncertainValue,
+    #[serde(default)]
+    plume_impingement: Option<PlumeImpingementBuilder>,
+    #[serde(default)]
+    rise_time_constant: Option<UncertainValue>, // sec
+    #[serde(default)]
+    tail_off_time_constant: Option<UncertainValue>, // sec
 }
 
 impl ThrusterParametersBuilder {
     pub fn new(force: f64) -> Result<Self, ThrusterErrors> {
+        if force <= 0.0 {
+            return Err(ThrusterErrors::NonPositiveForce);
+        }
         Ok(Self {
+            blowdown: None,
             delay: None,
+            isp: None,
+            minimum_on_time: None,
             misalignment: None,
             force: UncertainValue::new(force),
+            plume_impingement: None,
+            rise_time_constant: None,
+            tail_off_time_constant: None,
         })
     }
 
@@ -61,40 +263,180 @@ impl ThrusterParametersBuilder {
         } else {
             None
         };
+        if self
+            .blowdown
+            .is_some()
+            && self
+                .isp
+                .is_none()
+        {
+            return Err(ThrusterErrors::BlowdownRequiresIsp);
+        }
+        let mut sample_or_zero = |value: &Option<UncertainValue>| match value {
+            Some(value) => value.sample(nominal, rng),
+            None => 0.0,
+        };
+        let delay = sample_or_zero(&self.delay);
+        let minimum_on_time = sample_or_zero(&self.minimum_on_time);
+        let rise_time_constant = sample_or_zero(&self.rise_time_constant);
+        let tail_off_time_constant = sample_or_zero(&self.tail_off_time_constant);
+        let isp = self
+            .isp
+            .as_ref()
+            .map(|isp| isp.sample(nominal, rng));
+        let blowdown = self
+            .blowdown
+            .as_ref()
+            .map(|blowdown| blowdown.sample(nominal, rng));
+        let plume_impingement = self
+            .plume_impingement
+            .as_ref()
+            .map(|plume| plume.sample(nominal, rng));
         Ok(ThrusterParameters {
+            blowdown,
+            delay,
+            isp,
+            minimum_on_time,
             misalignment,
             force: self
                 .force
                 .sample(nominal, rng),
+            plume_impingement,
+            rise_time_constant,
+            tail_off_time_constant,
         })
     }
 }
 
 #[derive(Debug)]
 struct ThrusterParameters {
+    blowdown: Option<Blowdown>,
+    delay: f64,
+    isp: Option<f64>,
+    minimum_on_time: f64,
     misalignment: Option<UnitQuaternion>,
     force: f64,
+    plume_impingement: Option<PlumeImpingement>,
+    rise_time_constant: f64,
+    tail_off_time_constant: f64,
+}
+
+/// A single opening of the thruster valve, thrust follows a first order rise from the
+/// opening time and a first order tail off from the closing time.
+/// The valve only opens and closes when the solver reaches those times, so that a step ending
+/// on them is integrated entirely with the valve in its previous state.
+#[derive(Clone, Copy, Debug)]
+struct ThrusterPulse {
+    open: f64,
+    close: Option<f64>,
+    /// thrust fraction when the valve opened, nonzero if the previous pulse was still tailing off
+    fraction_open: f64,
+    opened: bool,
+    closed: bool,
+}
+
+impl ThrusterPulse {
+    fn new(open: f64, close: Option<f64>, fraction_open: f64) -> Self {
+        Self { open, close, fraction_open, opened: false, closed: false }
+    }
+
+    fn rise(&self, t: f64, rise_time_constant: f64) -> f64 {
+        if rise_time_constant > 0.0 {
+            1.0 - (1.0 - self.fraction_open)
+                * (-(t - self.open).max(0.0) / rise_time_constant).exp()
+        } else {
+            1.0
+        }
+    }
+
+    fn fraction(&self, t: f64, rise_time_constant: f64, tail_off_time_constant: f64) -> f64 {
+        if !self.opened {
+            return self.fraction_open;
+        }
+        match self.close {
+            Some(close) if self.closed => {
+                let fraction_close = self.rise(close, rise_time_constant);
+                if tail_off_time_constant > 0.0 {
+                    fraction_close * (-(t - close).max(0.0) / tail_off_time_constant).exp()
+                } else {
+                    0.0
+                }
+            }
+            _ => self.rise(t, rise_time_constant),
+        }
+    }
+
+    fn is_open(&self) -> bool {
+        self.opened && !self.closed
+    }
+
+    /// Opens and closes the valve if the sim time has reached those times
+    fn advance(&mut self, t: f64) {
+        if t >= self.open {
+            self.opened = true;
+        }
+        if self.opened
+            && self
+                .close
+                .is_some_and(|close| t >= close)
+        {
+            self.closed = true;
+        }
+    }
+
+    /// The next time the valve opens or closes
+    fn next_switch(&self) -> Option<f64> {
+        if !self.opened {
+            Some(self.open)
+        } else if !self.closed {
+            self.close
+        } else {
+            None
+        }
+    }
 }
 
 #[derive(Debug)]
 pub struct ThrusterState {
     pub command: ThrusterCommand,
-    force: f64,                // N
-    force_body: Vector3<f64>,  // N
+    /// Command override from an active stuck on/off fault
+    fault_override: Option<bool>,
+    force: f64,               // N
+    force_body: Vector3<f64>, // N
+    fraction: f64,            // thrust / nominal thrust
+    mass_flow: f64,           // kg/s
+    new_command: bool,        // command has been read but not yet applied
+    pressure: f64,            // Pa
+    propellant_used: f64,     // kg
+    pulse: Option<ThrusterPulse>,
+    previous_pulse: Option<ThrusterPulse>,
     torque_body: Vector3<f64>, //Nm
+    valve_open: bool,
 }
 
 impl ThrusterState {
     pub fn new() -> Self {
         Self {
-            command: ThrusterCommand::OFF,
+            command: ThrusterCommand::off(),
+            fault_override: None,
             force: 0.0,
             force_body: Vector3::zeros(),
+            fraction: 0.0,
+            mass_flow: 0.0,
+            new_command: false,
+            pressure: 0.0,
+            propellant_used: 0.0,
+            pulse: None,
+            previous_pulse: None,
             torque_body: Vector3::zeros(),
+            valve_open: false,
         }
     }
 }
 
+/// A thruster with a thrust axis along +X of the actuator frame.
+/// Supports ON/OFF and on time commanding, a minimum on time (minimum impulse bit),
+/// first order thrust rise and tail off, blowdown and plume impingement.
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct ThrusterBuilder {
     parameters: ThrusterParametersBuilder,
@@ -105,6 +447,16 @@ impl ThrusterBuilder {
         Ok(Self { parameters: ThrusterParametersBuilder::new(force)? })
     }
 
+    pub fn set_blowdown(&mut self, blowdown: BlowdownBuilder) {
+        self.parameters
+            .blowdown = Some(blowdown);
+    }
+
+    pub fn with_blowdown(mut self, blowdown: BlowdownBuilder) -> Self {
+        self.set_blowdown(blowdown);
+        self
+    }
+
     pub fn set_delay(&mut self, delay: f64) -> Result<(), ThrusterErrors> {
         if delay < 0.0 {
             return Err(ThrusterErrors::NegativeDelay);
@@ -137,6 +489,39 @@ impl ThrusterBuilder {
         Ok(self)
     }
 
+    /// Specific impulse (s), required for mass flow and blowdown
+    pub fn set_isp(&mut self, isp: f64) -> Result<(), ThrusterErrors> {
+        if isp <= 0.0 {
+            return Err(ThrusterErrors::NonPositiveIsp);
+        }
+        self.parameters
+            .isp = Some(UncertainValue::new(isp));
+        Ok(())
+    }
+
+    pub fn with_isp(mut self, isp: f64) -> Result<Self, ThrusterErrors> {
+        self.set_isp(isp)?;
+        Ok(self)
+    }
+
+    /// Shortest valve opening (s), shorter on time commands are extended to this value.
+    /// The minimum impulse bit is approximately force * minimum on time.
+    pub fn set_minimum_on_time(&mut self, minimum_on_time: f64) -> Result<(), ThrusterErrors> {
+        if minimum_on_time < 0.0 {
+            return Err(ThrusterErrors::NegativeMinimumOnTime);
+        }
+        self.parameters
+            .minimum_on_time = Some(UncertainValue::new(
+            minimum_on_time,
+        ));
+        Ok(())
+    }
+
+    pub fn with_minimum_on_time(mut self, minimum_on_time: f64) -> Result<Self, ThrusterErrors> {
+        self.set_minimum_on_time(minimum_on_time)?;
+        Ok(self)
+    }
+
     pub fn set_misalignment(&mut self, misalignment: UnitQuaternionBuilder) {
         self.parameters
             .misalignment = Some(misalignment);
@@ -147,6 +532,51 @@ impl ThrusterBuilder {
             .misalignment = Some(misalignment);
         self
     }
+
+    pub fn set_plume_impingement(&mut self, plume: PlumeImpingementBuilder) {
+        self.parameters
+            .plume_impingement = Some(plume);
+    }
+
+    pub fn with_plume_impingement(mut self, plume: PlumeImpingementBuilder) -> Self {
+        self.set_plume_impingement(plume);
+        self
+    }
+
+    pub fn set_rise_time_constant(&mut self, time_constant: f64) -> Result<(), ThrusterErrors> {
+        if time_constant < 0.0 {
+            return Err(ThrusterErrors::NegativeTimeConstant);
+        }
+        self.parameters
+            .rise_time_constant = Some(UncertainValue::new(
+            time_constant,
+        ));
+        Ok(())
+    }
+
+    pub fn with_rise_time_constant(mut self, time_constant: f64) -> Result<Self, ThrusterErrors> {
+        self.set_rise_time_constant(time_constant)?;
+        Ok(self)
+    }
+
+    pub fn set_tail_off_time_constant(&mut self, time_constant: f64) -> Result<(), ThrusterErrors> {
+        if time_constant < 0.0 {
+            return Err(ThrusterErrors::NegativeTimeConstant);
+        }
+        self.parameters
+            .tail_off_time_constant = Some(UncertainValue::new(
+            time_constant,
+        ));
+        Ok(())
+    }
+
+    pub fn with_tail_off_time_constant(
+        mut self,
+        time_constant: f64,
+    ) -> Result<Self, ThrusterErrors> {
+        self.set_tail_off_time_constant(time_constant)?;
+        Ok(self)
+    }
 }
 
 impl Uncertainty for ThrusterBuilder {
@@ -157,7 +587,10 @@ impl Uncertainty for ThrusterBuilder {
         let parameters = self
             .parameters
             .sample(nominal, rng)?;
-        let state = ThrusterState::new();
+        let mut state = ThrusterState::new();
+        if let Some(blowdown) = &parameters.blowdown {
+            state.pressure = blowdown.initial_pressure;
+        }
         Ok(Thruster { parameters, state })
     }
 }
@@ -168,24 +601,234 @@ pub struct Thruster {
     pub state: ThrusterState,
 }
 
-impl ActuatorModel for Thruster {
-    fn update(&mut self, connection: &BodyConnection) -> Result<(), ActuatorErrors> {
-        // Determine initial torque based on command type
-        let force = match self
+impl Thruster {
+    fn fraction(&self, t: f64) -> f64 {
+        self.pulse_fraction(
+            &self
+                .state
+                .pulse,
+            &self
+                .state
+                .previous_pulse,
+            t,
+        )
+    }
+
+    fn pulse_fraction(
+        &self,
+        pulse: &Option<ThrusterPulse>,
+        previous_pulse: &Option<ThrusterPulse>,
+        t: f64,
+    ) -> f64 {
+        let rise = self
+            .parameters
+            .rise_time_constant;
+        let tail_off = self
+            .parameters
+            .tail_off_time_constant;
+        match (pulse, previous_pulse) {
+            (Some(pulse), Some(previous)) if !pulse.opened => previous.fraction(t, rise, tail_off),
+            (Some(pulse), _) => pulse.fraction(t, rise, tail_off),
+            (None, _) => 0.0,
+        }
+    }
+
+    fn start_pulse(&mut self, open: f64, close: Option<f64>) {
+        // the current pulse may open or close before the new one opens
+        let mut pulse = self
             .state
-            .command
+            .pulse;
+        let mut previous_pulse = self
+            .state
+            .previous_pulse;
+        for pulse in [&mut pulse, &mut previous_pulse]
+            .into_iter()
+            .flatten()
         {
+            pulse.advance(open);
+        }
+        let fraction_open = self.pulse_fraction(&pulse, &previous_pulse, open);
+        self.state
+            .previous_pulse = self
+            .state
+            .pulse
+            .take();
+        self.state
+            .pulse = Some(ThrusterPulse::new(
+            open,
+            close,
+            fraction_open,
+        ));
+    }
+
+    /// Opens and closes the valves of the current and previous pulses up to time t
+    fn advance_pulses(&mut self, t: f64) {
+        for pulse in [
+            &mut self
+                .state
+                .pulse,
+            &mut self
+                .state
+                .previous_pulse,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            pulse.advance(t);
+        }
+    }
+
+    /// Converts a newly read command into a valve pulse, applied after the command delay
+    fn apply_command(&mut self, t: f64) -> Result<(), ThrusterErrors> {
+        let t_apply = t + self
+            .parameters
+            .delay;
+        let command = self
+            .state
+            .command;
+        match command.command {
+            ThrusterCommand::OFF => {
+                if let Some(pulse) = &mut self
+                    .state
+                    .pulse
+                    && pulse
+                        .close
+                        .is_none_or(|close| close > t_apply)
+                {
+                    pulse.close = Some(t_apply.max(pulse.open));
+                }
+            }
             ThrusterCommand::ON => {
-                self.parameters
-                    .force
+                let already_open = self
+                    .state
+                    .pulse
+                    .is_some_and(|pulse| {
+                        pulse
+                            .close
+                            .is_none()
+                    });
+                if !already_open {
+                    self.start_pulse(t_apply, None);
+                }
+            }
+            ThrusterCommand::ON_TIME => {
+                if command.value > 0.0 {
+                    let on_time = command
+                        .value
+                        .max(
+                            self.parameters
+                                .minimum_on_time,
+                        );
+                    self.start_pulse(
+                        t_apply,
+                        Some(t_apply + on_time),
+                    );
+                }
             }
-            ThrusterCommand::OFF => 0.0,
-            _ => return Err(ThrusterErrors::InvalidCommand.into()),
+            _ => return Err(ThrusterErrors::InvalidCommand),
+        }
+        Ok(())
+    }
+}
+
+impl ActuatorModel for Thruster {
+    fn apply_faults(&mut self, faults: &ActuatorFaultState) {
+        self.state
+            .fault_override = faults.thruster_stuck;
+    }
+
+    /// Valve opening and closing times, so that the solver steps to them rather than integrating
+    /// across the jump in thrust
+    fn next_discontinuity(&mut self, t: f64) -> Option<f64> {
+        // commands read by software at this time would be applied by the next call to update,
+        // apply them now so their valve times are known. An invalid command is left for update
+        // to report.
+        if self
+            .state
+            .new_command
+            && self
+                .apply_command(t)
+                .is_ok()
+        {
+            self.state
+                .new_command = false;
+        }
+        self.advance_pulses(t);
+        [
+            self.state
+                .pulse,
+            self.state
+                .previous_pulse,
+        ]
+        .iter()
+        .flatten()
+        .filter_map(|pulse| pulse.next_switch())
+        .reduce(f64::min)
+    }
+
+    fn update(&mut self, t: f64, connection: &BodyConnection) -> Result<(), ActuatorErrors> {
+        if self
+            .state
+            .new_command
+        {
+            self.state
+                .new_command = false;
+            self.apply_command(t)?;
+            // without a delay the valve switches immediately
+            self.advance_pulses(t);
+        }
+
+        // A stuck thruster ignores the commanded state
+        let (valve_open, fraction) = match self
+            .state
+            .fault_override
+        {
+            Some(true) => (true, 1.0),
+            Some(false) => (false, 0.0),
+            None => (
+                self.state
+                    .pulse
+                    .is_some_and(|pulse| pulse.is_open()),
+                self.fraction(t),
+            ),
         };
 
+        // Blowdown reduces thrust and isp as the tank pressure drops
+        let mut pressure_ratio = 1.0;
+        let mut isp = self
+            .parameters
+            .isp;
+        if let Some(blowdown) = &self
+            .parameters
+            .blowdown
+        {
+            let pressure = blowdown.pressure(
+                self.state
+                    .propellant_used,
+            );
+            pressure_ratio = pressure / blowdown.initial_pressure;
+            isp = isp.map(|isp| isp * pressure_ratio.powf(blowdown.isp_exponent));
+            self.state
+                .pressure = pressure;
+        }
+        let force = fraction
+            * pressure_ratio
+            * self
+                .parameters
+                .force;
+
         // Update state
+        self.state
+            .valve_open = valve_open;
+        self.state
+            .fraction = fraction;
         self.state
             .force = force;
+        self.state
+            .mass_flow = match isp {
+            Some(isp) => force / (isp * G0),
+            None => 0.0,
+        };
         // equal and opposite
         self.state
             .force_body = connection
@@ -206,24 +849,40 @@ impl ActuatorModel for Thruster {
             );
         }
 
-        // Update body
-        let mut body = connection
-            .body
-            .borrow_mut();
         let moment_arm = connection
             .transform
             .translation
             .vec();
-        let torque_body = moment_arm.cross(
+        self.state
+            .torque_body = moment_arm.cross(
             &self
                 .state
                 .force_body,
         );
+
+        // plume impingement on the body scales with thrust
+        if let Some(plume) = &self
+            .parameters
+            .plume_impingement
+        {
+            self.state
+                .force_body += plume.force * force;
+            self.state
+                .torque_body += plume.torque * force;
+        }
+
+        // Update body
+        let mut body = connection
+            .body
+            .borrow_mut();
         body.state
             .actuator_force_body += Force::from(Vector6::new(
-            torque_body[0],
-            torque_body[1],
-            torque_body[2],
+            self.state
+                .torque_body[0],
+            self.state
+                .torque_body[1],
+            self.state
+                .torque_body[2],
             self.state
                 .force_body[0],
             self.state
@@ -238,28 +897,47 @@ impl ActuatorModel for Thruster {
         writer.float_buffer[0] = self
             .state
             .command
-            .0 as f64;
+            .command as f64;
         writer.float_buffer[1] = self
             .state
-            .force;
+            .command
+            .value;
         writer.float_buffer[2] = self
             .state
-            .force_body[0];
+            .valve_open as u8 as f64;
         writer.float_buffer[3] = self
             .state
-            .force_body[1];
+            .fraction;
         writer.float_buffer[4] = self
             .state
-            .force_body[2];
+            .force;
         writer.float_buffer[5] = self
             .state
-            .torque_body[0];
+            .force_body[0];
         writer.float_buffer[6] = self
             .state
-            .torque_body[1];
+            .force_body[1];
         writer.float_buffer[7] = self
+            .state
+            .force_body[2];
+        writer.float_buffer[8] = self
+            .state
+            .torque_body[0];
+        writer.float_buffer[9] = self
+            .state
+            .torque_body[1];
+        writer.float_buffer[10] = self
             .state
             .torque_body[2];
+        writer.float_buffer[11] = self
+            .state
+            .mass_flow;
+        writer.float_buffer[12] = self
+            .state
+            .propellant_used;
+        writer.float_buffer[13] = self
+            .state
+            .pressure;
         writer
             .write_record()
             .unwrap();
@@ -268,6 +946,9 @@ impl ActuatorModel for Thruster {
     fn writer_headers(&self) -> &[&str] {
         &[
             "command",
+            "command_value",
+            "valve_open",
+            "thrust_fraction",
             "force(thruster)",
             "force(body)[x]",
             "force(body)[y]",
@@ -275,14 +956,76 @@ impl ActuatorModel for Thruster {
             "torque(body)[x]",
             "torque(body)[y]",
             "torque(body)[z]",
+            "mass_flow",
+            "propellant_used",
+            "pressure",
         ]
     }
 
-    fn state_vector_read(&mut self, _state: &[f64]) {}
+    fn state_derivative(&self, derivative: &mut [f64]) {
+        if self
+            .parameters
+            .isp
+            .is_some()
+        {
+            derivative[0] = self
+                .state
+                .mass_flow;
+        }
+    }
+
+    fn state_vector_init(&self) -> StateVector {
+        if self
+            .parameters
+            .isp
+            .is_some()
+        {
+            StateVector::new(vec![
+                self.state
+                    .propellant_used,
+            ])
+        } else {
+            StateVector::new(vec![])
+        }
+    }
+
+    fn state_vector_read(&mut self, state: &[f64]) {
+        if self
+            .parameters
+            .isp
+            .is_some()
+        {
+            self.state
+                .propellant_used = state[0];
+        }
+    }
+
+    fn command_schema(&self) -> BufferSchema {
+        ThrusterCommand::schema()
+    }
 
     fn read_command(&mut self, cmd: &HardwareBuffer) -> Result<(), ActuatorErrors> {
         self.state
             .command = cmd.read::<ThrusterCommand>()?;
+        self.state
+            .new_command = true;
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::mem::offset_of;
+
+    #[test]
+    fn test_command_layout() {
+        ThrusterCommand::schema()
+            .check_layout::<ThrusterCommand>(&[
+                offset_of!(ThrusterCommand, value),
+                offset_of!(ThrusterCommand, command),
+                offset_of!(ThrusterCommand, _padding),
+            ])
+            .unwrap();
+    }
+}
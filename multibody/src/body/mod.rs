@@ -31,7 +31,10 @@ use std::{
 };
 use thiserror::Error;
 use transforms::Transform;
-use uncertainty::Uncertainty;
+use uncertainty::{UncertainValue, Uncertainty};
+
+/// Magnetic fields are calculated in nT
+pub(crate) const NT_TO_TESLA: f64 = 1e-9;
 
 #[derive(Debug, Error)]
 pub enum BodyErrors {
@@ -45,6 +48,8 @@ pub enum BodyErrors {
     InnerJointExists(String),
     #[error("{0}")]
     Joint(#[from] JointErrors),
+    #[error("eddy current coefficient must be greater than or equal to 0 for body '{0}'")]
+    NegativeEddyCurrentCoefficient(String),
     #[error("no inner joint found for body '{0}'")]
     NoInnerJoint(String),
     #[error("no mass properties found for body '{0}'")]
@@ -58,12 +63,18 @@ pub enum BodyErrors {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BodyBuilder {
     pub actuators: Vec<Id>,
+    /// Eddy current coefficient (m^4/ohm) of the conductive structure
+    #[serde(default)]
+    pub eddy_current_coefficient: Option<UncertainValue>,
     pub id: Id,
     pub inner_joint: Option<Id>,
     pub mass_properties: Option<MassPropertiesBuilder>,
     pub mesh: Option<Mesh>,
     pub name: String,
     pub outer_joints: Vec<Id>, // id of joint in system.joints, joint contains the transform information
+    /// Residual magnetic dipole (Am^2) of the body in the body frame
+    #[serde(default)]
+    pub residual_dipole: Option<[UncertainValue; 3]>,
     pub sensors: Vec<Id>,
 }
 
@@ -74,12 +85,14 @@ impl BodyBuilder {
         }
         Ok(Self {
             actuators: Vec::new(),
+            eddy_current_coefficient: None,
             id,
             mesh: None,
             inner_joint: None,
             mass_properties: None,
             name: name.to_string(),
             outer_joints: Vec::new(),
+            residual_dipole: None,
             sensors: Vec::new(),
         })
     }
@@ -173,8 +186,26 @@ impl BodyBuilder {
             ));
         };
 
+        let residual_dipole = match &self.residual_dipole {
+            Some(dipole) => Vector3::new(
+                dipole[0].sample(nominal, rng),
+                dipole[1].sample(nominal, rng),
+                dipole[2].sample(nominal, rng),
+            ),
+            None => Vector3::zeros(),
+        };
+        let eddy_current_coefficient = self
+            .eddy_current_coefficient
+            .as_ref()
+            .map_or(0.0, |coefficient| {
+                coefficient.sample(nominal, rng)
+            })
+            .max(0.0);
+
         let body = Body {
+            eddy_current_coefficient,
             inner_joint: Rc::downgrade(&inner_joint),
+            magnetic_dipoles: Vec::new(),
             mass_properties,
             mesh: self
                 .mesh
@@ -183,6 +214,7 @@ impl BodyBuilder {
                 .name
                 .clone(),
             outer_joints: Vec::new(),
+            residual_dipole,
             state: BodyState::default(),
             writer_id: None,
         };
@@ -266,15 +298,42 @@ impl BodyBuilder {
     pub fn set_mass_properties(&mut self, mass_properties: MassPropertiesBuilder) {
         self.mass_properties = Some(mass_properties);
     }
+
+    /// Setter method for the eddy current coefficient (m^4/ohm), producing a damping
+    /// torque of k (w x B) x B
+    pub fn set_eddy_current_coefficient(
+        &mut self,
+        coefficient: UncertainValue,
+    ) -> Result<(), BodyErrors> {
+        if coefficient.nominal < 0.0 {
+            return Err(
+                BodyErrors::NegativeEddyCurrentCoefficient(
+                    self.name
+                        .clone(),
+                ),
+            );
+        }
+        self.eddy_current_coefficient = Some(coefficient);
+        Ok(())
+    }
+
+    /// Setter method for the residual magnetic dipole (Am^2) in the body frame
+    pub fn set_residual_dipole(&mut self, dipole: [UncertainValue; 3]) {
+        self.residual_dipole = Some(dipole);
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct Body {
+    pub eddy_current_coefficient: f64, // m^4/ohm
     pub inner_joint: Weak<RefCell<Joint>>,
+    /// Magnetic dipoles on the body that contaminate magnetometer measurements
+    pub magnetic_dipoles: Vec<MagneticDipole>,
     pub mass_properties: MassProperties,
     pub mesh: Option<Mesh>,
     pub name: String,
     pub outer_joints: Vec<Weak<RefCell<Joint>>>,
+    pub residual_dipole: Vector3<f64>, // Am^2
     pub state: BodyState,
     writer_id: Option<WriterId>,
 }
@@ -360,6 +419,31 @@ impl Body {
             .magnetic_field_body = q.transform(&b_vec);
     }
 
+    /// Disturbance torque from the residual dipole and eddy currents interacting with the magnetic field
+    pub fn calculate_magnetic_disturbance(&mut self) {
+        let b = self
+            .state
+            .magnetic_field_body
+            * NT_TO_TESLA;
+        let residual_torque = self
+            .residual_dipole
+            .cross(&b);
+        let eddy_torque = self.eddy_current_coefficient
+            * self
+                .state
+                .angular_rate_body
+                .cross(&b)
+                .cross(&b);
+        let torque = residual_torque + eddy_torque;
+
+        self.state
+            .magnetic_torque_body = torque;
+        self.state
+            .environments_force_body += Force::from(Vector6::new(
+            torque[0], torque[1], torque[2], 0.0, 0.0, 0.0,
+        ));
+    }
+
     pub fn calculate_external_force(&mut self) {
         // convert gravity to spatial force
         let gravity_force_body = Force::from(Vector6::new(
@@ -559,6 +643,9 @@ impl Body {
             "magnetic_field(body)[x]",
             "magnetic_field(body)[y]",
             "magnetic_field(body)[z]",
+            "magnetic_torque(body)[x]",
+            "magnetic_torque(body)[y]",
+            "magnetic_torque(body)[z]",
             "kinetic_energy",
             "potential_energy",
             "total_energy",
@@ -714,11 +801,20 @@ impl Body {
                     .magnetic_field_body[2];
                 writer.float_buffer[43] = self
                     .state
-                    .kinetic_energy;
+                    .magnetic_torque_body[0];
                 writer.float_buffer[44] = self
                     .state
-                    .potential_energy;
+                    .magnetic_torque_body[1];
                 writer.float_buffer[45] = self
+                    .state
+                    .magnetic_torque_body[2];
+                writer.float_buffer[46] = self
+                    .state
+                    .kinetic_energy;
+                writer.float_buffer[47] = self
+                    .state
+                    .potential_energy;
+                writer.float_buffer[48] = self
                     .state
                     .total_energy;
                 writer
@@ -729,6 +825,28 @@ impl Body {
     }
 }
 
+/// A magnetic dipole located on a body, both in the body frame
+#[derive(Debug, Clone, Copy)]
+pub struct MagneticDipole {
+    pub moment: Vector3<f64>,   // Am^2
+    pub position: Vector3<f64>, // m
+}
+
+impl MagneticDipole {
+    /// Magnetic field (nT) of the dipole at a position in the body frame
+    pub fn field_at(&self, position: &Vector3<f64>) -> Vector3<f64> {
+        // mu_0 / 4pi in T-m/A, converted to nT
+        const MU0_4PI_NT: f64 = 1e-7 * 1e9;
+        let r = position - self.position;
+        let r_mag = r.norm();
+        if r_mag < f64::EPSILON {
+            return Vector3::zeros();
+        }
+        let r_hat = r / r_mag;
+        MU0_4PI_NT * (3.0 * r_hat * r_hat.dot(&self.moment) - self.moment) / r_mag.powi(3)
+    }
+}
+
 #[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
 pub struct BodyState {
     pub acceleration_base: Vector3<f64>,
@@ -753,6 +871,7 @@ pub struct BodyState {
     pub linear_momentum_base: Vector3<f64>,
     pub magnetic_field_base: Vector3<f64>,
     pub magnetic_field_body: Vector3<f64>,
+    pub magnetic_torque_body: Vector3<f64>,
     pub position_base: Vector3<f64>,
     pub potential_energy: f64,
     pub total_energy: f64,
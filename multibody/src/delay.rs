@@ -1,7 +1,8 @@
 use rotations::prelude::{Quaternion, UnitQuaternion};
+use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub enum InterpolationMethod {
     Linear,
     CubicHermite,
@@ -0,0 +1,511 @@
+use crate::{
+    actuator::{ActuatorBuilder, ActuatorModelBuilders},
+    body::Body,
+    sensor::noise::{Noise, NoiseBuilder, NoiseErrors},
+};
+use nalgebra::Vector3;
+use rand::rngs::SmallRng;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use uncertainty::{UncertainValue, Uncertainty};
+
+#[derive(Debug, Error)]
+pub enum FaultErrors {
+    #[error("could not find actuator '{1}' for fault '{0}'")]
+    ActuatorNotFound(String, String),
+    #[error("fault '{0}' can not be applied to an actuator")]
+    InvalidActuatorFault(String),
+    #[error("fault '{0}' can not be applied to the model of actuator '{1}'")]
+    InvalidActuatorModelFault(String, String),
+    #[error("fault '{0}' can not be applied to a sensor")]
+    InvalidSensorFault(String),
+    #[error("fault name cannot be empty")]
+    NameEmpty,
+    #[error("fault check period must be greater than 0.0")]
+    NonPositiveCheckPeriod,
+    #[error("fault duration must be greater than 0.0")]
+    NonPositiveDuration,
+    #[error("{0}")]
+    Noise(#[from] NoiseErrors),
+    #[error("could not find sensor '{1}' for fault '{0}'")]
+    SensorNotFound(String, String),
+}
+
+/// The named sensor or actuator that a fault is applied to
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum FaultTarget {
+    Actuator(String),
+    Sensor(String),
+}
+
+/// A condition on the state of the body the faulted component is connected to
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum FaultCondition {
+    /// Magnitude of the body angular rate (rad/s) is above the value
+    AngularRateAbove(f64),
+    /// Magnitude of the body angular rate (rad/s) is below the value
+    AngularRateBelow(f64),
+    /// Magnitude of the body position (m) in the base frame is above the value
+    RadiusAbove(f64),
+    /// Magnitude of the body position (m) in the base frame is below the value
+    RadiusBelow(f64),
+}
+
+impl FaultCondition {
+    fn check(&self, body: &Body) -> bool {
+        match self {
+            FaultCondition::AngularRateAbove(value) => {
+                body.state
+                    .angular_rate_body
+                    .norm()
+                    > *value
+            }
+            FaultCondition::AngularRateBelow(value) => {
+                body.state
+                    .angular_rate_body
+                    .norm()
+                    < *value
+            }
+            FaultCondition::RadiusAbove(value) => {
+                body.state
+                    .position_base
+                    .norm()
+                    > *value
+            }
+            FaultCondition::RadiusBelow(value) => {
+                body.state
+                    .position_base
+                    .norm()
+                    < *value
+            }
+        }
+    }
+}
+
+/// Determines when a fault becomes active. Once triggered, a fault stays active
+/// for its duration, or for the remainder of the simulation if no duration is provided.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum FaultTriggerBuilder {
+    /// Fault activates at the sim time (s)
+    Time(UncertainValue),
+    /// Fault activates the first time the condition is met, checked on accepted steps
+    /// every fault check period of the system
+    Condition(FaultCondition),
+}
+
+#[derive(Clone, Debug)]
+enum FaultTrigger {
+    Time(f64),
+    Condition(FaultCondition),
+}
+
+/// The effect of the fault on the component
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum FaultModeBuilders {
+    /// Sensor output is held at its last value
+    SensorStuck,
+    /// Sensor telemetry is zeroed, including any validity flags
+    SensorDropout,
+    /// Constant offset added to each axis of the sensor measurement
+    SensorBiasJump([UncertainValue; 3]),
+    /// Additional noise added to each axis of the sensor measurement
+    SensorNoise(NoiseBuilder),
+    /// Actuator ignores new commands, holding the last command received
+    ActuatorCommandStuck,
+    /// Additional friction torque (Nm) opposing the reaction wheel speed
+    WheelFrictionSpike(UncertainValue),
+    /// Thruster fires regardless of command
+    ThrusterStuckOn,
+    /// Thruster does not fire regardless of command
+    ThrusterStuckOff,
+}
+
+impl FaultModeBuilders {
+    fn is_sensor_fault(&self) -> bool {
+        matches!(
+            self,
+            FaultModeBuilders::SensorStuck
+                | FaultModeBuilders::SensorDropout
+                | FaultModeBuilders::SensorBiasJump(_)
+                | FaultModeBuilders::SensorNoise(_)
+        )
+    }
+}
+
+#[derive(Debug)]
+pub enum FaultModes {
+    SensorStuck,
+    SensorDropout,
+    SensorBiasJump(Vector3<f64>),
+    SensorNoise([Noise; 3]),
+    ActuatorCommandStuck,
+    WheelFrictionSpike(f64),
+    ThrusterStuckOn,
+    ThrusterStuckOff,
+}
+
+/// A fault declared against a named sensor or actuator
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FaultBuilder {
+    pub name: String,
+    pub target: FaultTarget,
+    pub trigger: FaultTriggerBuilder,
+    pub duration: Option<UncertainValue>,
+    pub mode: FaultModeBuilders,
+}
+
+impl FaultBuilder {
+    pub fn new(
+        name: &str,
+        target: FaultTarget,
+        trigger: FaultTriggerBuilder,
+        mode: FaultModeBuilders,
+    ) -> Result<Self, FaultErrors> {
+        if name.is_empty() {
+            return Err(FaultErrors::NameEmpty);
+        }
+        let builder = Self {
+            name: name.to_string(),
+            target,
+            trigger,
+            duration: None,
+            mode,
+        };
+        builder.validate()?;
+        Ok(builder)
+    }
+
+    /// Fault at a sim time (s) against a named sensor
+    pub fn sensor_at(
+        name: &str,
+        sensor: &str,
+        time: f64,
+        mode: FaultModeBuilders,
+    ) -> Result<Self, FaultErrors> {
+        Self::new(
+            name,
+            FaultTarget::Sensor(sensor.to_string()),
+            FaultTriggerBuilder::Time(UncertainValue::new(time)),
+            mode,
+        )
+    }
+
+    /// Fault at a sim time (s) against a named actuator
+    pub fn actuator_at(
+        name: &str,
+        actuator: &str,
+        time: f64,
+        mode: FaultModeBuilders,
+    ) -> Result<Self, FaultErrors> {
+        Self::new(
+            name,
+            FaultTarget::Actuator(actuator.to_string()),
+            FaultTriggerBuilder::Time(UncertainValue::new(time)),
+            mode,
+        )
+    }
+
+    pub fn with_duration(mut self, duration: f64) -> Result<Self, FaultErrors> {
+        self.set_duration(duration)?;
+        Ok(self)
+    }
+
+    pub fn set_duration(&mut self, duration: f64) -> Result<(), FaultErrors> {
+        if duration <= 0.0 {
+            return Err(FaultErrors::NonPositiveDuration);
+        }
+        self.duration = Some(UncertainValue::new(duration));
+        Ok(())
+    }
+
+    /// Checks that the fault mode can be applied to the target type
+    pub fn validate(&self) -> Result<(), FaultErrors> {
+        match (
+            &self.target,
+            self.mode
+                .is_sensor_fault(),
+        ) {
+            (FaultTarget::Sensor(_), false) => Err(
+                FaultErrors::InvalidSensorFault(
+                    self.name
+                        .clone(),
+                ),
+            ),
+            (FaultTarget::Actuator(_), true) => Err(
+                FaultErrors::InvalidActuatorFault(
+                    self.name
+                        .clone(),
+                ),
+            ),
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks that a wheel or thruster fault mode targets an actuator of that model
+    pub fn validate_actuator(&self, actuator: &ActuatorBuilder) -> Result<(), FaultErrors> {
+        let compatible = match &self.mode {
+            FaultModeBuilders::WheelFrictionSpike(_) => matches!(
+                actuator.model,
+                ActuatorModelBuilders::ReactionWheel(_)
+            ),
+            FaultModeBuilders::ThrusterStuckOn | FaultModeBuilders::ThrusterStuckOff => {
+                matches!(
+                    actuator.model,
+                    ActuatorModelBuilders::Thruster(_)
+                )
+            }
+            _ => true,
+        };
+        if compatible {
+            Ok(())
+        } else {
+            Err(
+                FaultErrors::InvalidActuatorModelFault(
+                    self.name
+                        .clone(),
+                    actuator
+                        .name
+                        .clone(),
+                ),
+            )
+        }
+    }
+}
+
+impl Uncertainty for FaultBuilder {
+    type Error = FaultErrors;
+    type Output = Fault;
+    fn sample(&self, nominal: bool, rng: &mut SmallRng) -> Result<Self::Output, Self::Error> {
+        let trigger = match &self.trigger {
+            FaultTriggerBuilder::Time(time) => FaultTrigger::Time(time.sample(nominal, rng)),
+            FaultTriggerBuilder::Condition(condition) => FaultTrigger::Condition(condition.clone()),
+        };
+        let duration = self
+            .duration
+            .as_ref()
+            .map(|duration| duration.sample(nominal, rng));
+        let mode = match &self.mode {
+            FaultModeBuilders::SensorStuck => FaultModes::SensorStuck,
+            FaultModeBuilders::SensorDropout => FaultModes::SensorDropout,
+            FaultModeBuilders::SensorBiasJump(bias) => FaultModes::SensorBiasJump(Vector3::new(
+                bias[0].sample(nominal, rng),
+                bias[1].sample(nominal, rng),
+                bias[2].sample(nominal, rng),
+            )),
+            FaultModeBuilders::SensorNoise(noise) => FaultModes::SensorNoise([
+                noise.sample(nominal, rng)?,
+                noise.sample(nominal, rng)?,
+                noise.sample(nominal, rng)?,
+            ]),
+            FaultModeBuilders::ActuatorCommandStuck => FaultModes::ActuatorCommandStuck,
+            FaultModeBuilders::WheelFrictionSpike(friction) => {
+                FaultModes::WheelFrictionSpike(friction.sample(nominal, rng))
+            }
+            FaultModeBuilders::ThrusterStuckOn => FaultModes::ThrusterStuckOn,
+            FaultModeBuilders::ThrusterStuckOff => FaultModes::ThrusterStuckOff,
+        };
+        Ok(Fault {
+            name: self
+                .name
+                .clone(),
+            trigger,
+            duration,
+            mode,
+            start_time: None,
+            active: false,
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct Fault {
+    pub name: String,
+    trigger: FaultTrigger,
+    duration: Option<f64>,
+    pub mode: FaultModes,
+    /// Sim time the fault was triggered
+    pub start_time: Option<f64>,
+    pub active: bool,
+}
+
+impl Fault {
+    /// Latches the start time of a condition triggered fault if its condition is met.
+    /// Only called on accepted steps, so that trial states at intermediate stages of the
+    /// integrator can't trigger the fault.
+    pub fn check_condition(&mut self, t: f64, body: &Body) {
+        if self
+            .start_time
+            .is_some()
+        {
+            return;
+        }
+        if let FaultTrigger::Condition(condition) = &self.trigger
+            && condition.check(body)
+        {
+            self.start_time = Some(t);
+        }
+    }
+
+    /// Whether the fault is triggered by a condition on the body state
+    pub fn is_conditional(&self) -> bool {
+        matches!(
+            self.trigger,
+            FaultTrigger::Condition(_)
+        )
+    }
+
+    /// The next time after `t` that the fault turns on or off, if it is known. Condition
+    /// triggered faults only have a known end time once their condition has been met.
+    pub fn next_transition(&self, t: f64) -> Option<f64> {
+        let start = match (self.start_time, &self.trigger) {
+            (Some(start), _) => start,
+            (None, FaultTrigger::Time(time)) => *time,
+            (None, FaultTrigger::Condition(_)) => return None,
+        };
+        if start > t {
+            return Some(start);
+        }
+        self.duration
+            .map(|duration| start + duration)
+            .filter(|end| *end > t)
+    }
+
+    /// Evaluates the trigger against the sim time, returning whether the fault is active.
+    /// Condition triggers are latched separately by check_condition.
+    pub fn update(&mut self, t: f64) -> bool {
+        if self
+            .start_time
+            .is_none()
+            && let FaultTrigger::Time(time) = &self.trigger
+            && t >= *time
+        {
+            self.start_time = Some(*time);
+        }
+        self.active = match self.start_time {
+            Some(start) => {
+                t >= start
+                    && match self.duration {
+                        Some(duration) => t < start + duration,
+                        None => true,
+                    }
+            }
+            None => false,
+        };
+        self.active
+    }
+}
+
+/// The combined effect of all active faults on an actuator model
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ActuatorFaultState {
+    pub command_stuck: bool,
+    /// Additional friction torque (Nm) opposing wheel speed
+    pub wheel_friction: f64,
+    /// Overrides the thruster command, Some(true) for stuck on, Some(false) for stuck off
+    pub thruster_stuck: Option<bool>,
+}
+
+impl ActuatorFaultState {
+    pub fn from_faults(faults: &[Fault]) -> Self {
+        let mut state = Self::default();
+        for fault in faults
+            .iter()
+            .filter(|fault| fault.active)
+        {
+            match &fault.mode {
+                FaultModes::ActuatorCommandStuck => state.command_stuck = true,
+                FaultModes::WheelFrictionSpike(friction) => state.wheel_friction += friction,
+                FaultModes::ThrusterStuckOn => state.thruster_stuck = Some(true),
+                FaultModes::ThrusterStuckOff => state.thruster_stuck = Some(false),
+                _ => {}
+            }
+        }
+        state
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::actuator::{reaction_wheel::ReactionWheelBuilder, thruster::ThrusterBuilder};
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_time_window_transitions() {
+        let mut fault = FaultBuilder::actuator_at(
+            "stuck",
+            "wheel",
+            10.0,
+            FaultModeBuilders::ActuatorCommandStuck,
+        )
+        .unwrap()
+        .with_duration(5.0)
+        .unwrap()
+        .sample(
+            true,
+            &mut SmallRng::seed_from_u64(0),
+        )
+        .unwrap();
+
+        assert_eq!(
+            fault.next_transition(0.0),
+            Some(10.0)
+        );
+        assert!(!fault.update(9.0));
+        assert!(fault.update(10.0));
+        assert_eq!(
+            fault.next_transition(10.0),
+            Some(15.0)
+        );
+        assert!(!fault.update(15.0));
+        assert_eq!(
+            fault.next_transition(15.0),
+            None
+        );
+    }
+
+    #[test]
+    fn test_actuator_model_mismatch() {
+        let wheel = ActuatorBuilder::new(
+            "wheel",
+            ActuatorModelBuilders::ReactionWheel(ReactionWheelBuilder::new(0.01, 0.1).unwrap()),
+        );
+        let thruster = ActuatorBuilder::new(
+            "thruster",
+            ActuatorModelBuilders::Thruster(ThrusterBuilder::new(1.0).unwrap()),
+        );
+        let friction = FaultBuilder::actuator_at(
+            "friction",
+            "thruster",
+            0.0,
+            FaultModeBuilders::WheelFrictionSpike(UncertainValue::new(0.01)),
+        )
+        .unwrap();
+        let stuck_on = FaultBuilder::actuator_at(
+            "stuck_on",
+            "wheel",
+            0.0,
+            FaultModeBuilders::ThrusterStuckOn,
+        )
+        .unwrap();
+
+        assert!(
+            friction
+                .validate_actuator(&wheel)
+                .is_ok()
+        );
+        assert!(matches!(
+            friction.validate_actuator(&thruster),
+            Err(FaultErrors::InvalidActuatorModelFault(..))
+        ));
+        assert!(
+            stuck_on
+                .validate_actuator(&thruster)
+                .is_ok()
+        );
+        assert!(matches!(
+            stuck_on.validate_actuator(&wheel),
+            Err(FaultErrors::InvalidActuatorModelFault(..))
+        ));
+    }
+}
@@ -558,11 +558,12 @@ impl JointModel for Floating {
             self.state
                 .v[2],
         ];
-        StateVector::new(state)
+        // mark the quaternion so Lie group solvers integrate it on the unit sphere
+        StateVector::new(state).with_quaternion(0)
     }
 
     fn state_vector_read(&mut self, state: &[f64]) {
-        // need to normalize the integrated quaternion
+        // need to normalize the integrated quaternion, unless it was integrated by a Lie group solver
         let q = Quaternion::new(
             state[0], state[1], state[2], state[3],
         )
@@ -212,6 +212,41 @@ pub enum JointModels {
     Revolute(Revolute),
 }
 
+impl JointModels {
+    /// Position and rate of a single degree of freedom joint, None for joints with multiple degrees of freedom
+    pub fn single_dof_state(&self) -> Option<(f64, f64)> {
+        match self {
+            JointModels::Floating(_) => None,
+            JointModels::Prismatic(model) => Some((
+                model
+                    .state
+                    .position,
+                model
+                    .state
+                    .velocity,
+            )),
+            JointModels::Revolute(model) => Some((
+                model
+                    .state
+                    .angle,
+                model
+                    .state
+                    .angular_rate,
+            )),
+        }
+    }
+
+    /// Adds an actuator generalized force across a single degree of freedom joint.
+    /// The joint force is reset by calculate_tau, so this must be called after each update_joints
+    pub fn add_actuator_tau(&mut self, tau: f64) {
+        match self {
+            JointModels::Floating(_) => {}
+            JointModels::Prismatic(model) => model.add_actuator_tau(tau),
+            JointModels::Revolute(model) => model.add_actuator_tau(tau),
+        }
+    }
+}
+
 impl JointModel for JointModels {
     fn calculate_joint_inertia(
         &mut self,
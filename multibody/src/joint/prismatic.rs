@@ -510,6 +510,12 @@ impl Prismatic {
     fn new(parameters: PrismaticParameters, state: PrismaticState) -> Self {
         Self { parameters, state, cache: PrismaticCache::default() }
     }
+
+    /// Adds an actuator force (N) across the joint, must be called after calculate_tau
+    pub fn add_actuator_tau(&mut self, tau: f64) {
+        self.cache
+            .tau += tau;
+    }
 }
 
 impl JointModel for Prismatic {
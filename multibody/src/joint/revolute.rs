@@ -600,6 +600,12 @@ impl Revolute {
     pub fn new(parameters: RevoluteParameters, state: RevoluteState) -> Self {
         Self { parameters, state, ..Default::default() }
     }
+
+    /// Adds an actuator torque (Nm) across the joint, must be called after calculate_tau
+    pub fn add_actuator_tau(&mut self, tau: f64) {
+        self.cache
+            .tau += tau;
+    }
 }
 
 impl JointModel for Revolute {
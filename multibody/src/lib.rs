@@ -3,8 +3,10 @@ pub mod algorithms;
 pub mod base;
 pub mod body;
 pub mod delay;
+pub mod fault;
 pub mod joint;
 pub mod mechanism;
+pub mod schema;
 pub mod sensor;
 pub mod software;
 pub mod system;
@@ -14,6 +16,7 @@ use base::BaseErrors;
 use body::BodyErrors;
 use bytemuck::{Pod, Zeroable, bytes_of, checked::from_bytes};
 use celestial::CelestialErrors;
+use fault::FaultErrors;
 
 use joint::JointErrors;
 use sensor::SensorErrors;
@@ -50,6 +53,8 @@ pub enum MultibodyErrors {
     #[error("invalid connection")]
     InvalidConnection,
     #[error("{0}")]
+    FaultErrors(#[from] FaultErrors),
+    #[error("{0}")]
     JointErrors(#[from] JointErrors),
     #[error("joint '{0}' must have an inner body")]
     JointMissingInnerBody(String),
@@ -160,6 +165,12 @@ impl HardwareBuffer {
         ))
     }
 
+    /// Maximum number of bytes the buffer can hold
+    pub fn capacity(&self) -> usize {
+        self.data
+            .len()
+    }
+
     /// Return the used slice of the buffer
     pub fn as_bytes(&self) -> &[u8] {
         &self.data[..self.size]
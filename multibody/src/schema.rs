@@ -0,0 +1,650 @@
+use crate::HardwareBuffer;
+use bytemuck::Pod;
+use serde::{Deserialize, Serialize};
+use std::{fmt::Write, path::Path};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum SchemaErrors {
+    #[error("schema '{0}' has conflicting definitions")]
+    ConflictingDefinitions(String),
+    #[error("schema '{0}' field '{1}' does not match")]
+    FieldMismatch(String, String),
+    #[error("schema '{0}' has {1} fields but {2} were expected")]
+    FieldCount(String, usize, usize),
+    #[error("{0}")]
+    Io(#[from] std::io::Error),
+    #[error("schema '{0}' field '{1}' is at offset {2} but the struct has it at {3}")]
+    OffsetMismatch(String, String, usize, usize),
+    #[error("expected {0} schemas but {1} were provided")]
+    SchemaCount(usize, usize),
+    #[error("schema '{0}' is {1} bytes but the buffer holds {2}")]
+    SizeMismatch(String, usize, usize),
+    #[error("schema '{0}' does not have a field '{1}'")]
+    UnknownField(String, String),
+}
+
+/// Primitive types that can appear in a HardwareBuffer
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FieldType {
+    U8,
+    I8,
+    U16,
+    I16,
+    U32,
+    I32,
+    U64,
+    I64,
+    F32,
+    F64,
+}
+
+impl FieldType {
+    /// Size in bytes, which is also the alignment for repr(C) structs
+    pub fn size(&self) -> usize {
+        match self {
+            FieldType::U8 | FieldType::I8 => 1,
+            FieldType::U16 | FieldType::I16 => 2,
+            FieldType::U32 | FieldType::I32 | FieldType::F32 => 4,
+            FieldType::U64 | FieldType::I64 | FieldType::F64 => 8,
+        }
+    }
+
+    pub fn c_type(&self) -> &'static str {
+        match self {
+            FieldType::U8 => "uint8_t",
+            FieldType::I8 => "int8_t",
+            FieldType::U16 => "uint16_t",
+            FieldType::I16 => "int16_t",
+            FieldType::U32 => "uint32_t",
+            FieldType::I32 => "int32_t",
+            FieldType::U64 => "uint64_t",
+            FieldType::I64 => "int64_t",
+            FieldType::F32 => "float",
+            FieldType::F64 => "double",
+        }
+    }
+
+    pub fn rust_type(&self) -> &'static str {
+        match self {
+            FieldType::U8 => "u8",
+            FieldType::I8 => "i8",
+            FieldType::U16 => "u16",
+            FieldType::I16 => "i16",
+            FieldType::U32 => "u32",
+            FieldType::I32 => "i32",
+            FieldType::U64 => "u64",
+            FieldType::I64 => "i64",
+            FieldType::F32 => "f32",
+            FieldType::F64 => "f64",
+        }
+    }
+
+    /// Decodes a single value from native endian bytes
+    fn decode(&self, bytes: &[u8]) -> f64 {
+        match self {
+            FieldType::U8 => bytes[0] as f64,
+            FieldType::I8 => bytes[0] as i8 as f64,
+            FieldType::U16 => u16::from_ne_bytes([bytes[0], bytes[1]]) as f64,
+            FieldType::I16 => i16::from_ne_bytes([bytes[0], bytes[1]]) as f64,
+            FieldType::U32 => u32::from_ne_bytes(
+                bytes[..4]
+                    .try_into()
+                    .unwrap(),
+            ) as f64,
+            FieldType::I32 => i32::from_ne_bytes(
+                bytes[..4]
+                    .try_into()
+                    .unwrap(),
+            ) as f64,
+            FieldType::U64 => u64::from_ne_bytes(
+                bytes[..8]
+                    .try_into()
+                    .unwrap(),
+            ) as f64,
+            FieldType::I64 => i64::from_ne_bytes(
+                bytes[..8]
+                    .try_into()
+                    .unwrap(),
+            ) as f64,
+            FieldType::F32 => f32::from_ne_bytes(
+                bytes[..4]
+                    .try_into()
+                    .unwrap(),
+            ) as f64,
+            FieldType::F64 => f64::from_ne_bytes(
+                bytes[..8]
+                    .try_into()
+                    .unwrap(),
+            ),
+        }
+    }
+}
+
+/// A named field in a buffer, scalars have a count of 1
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SchemaField {
+    pub name: String,
+    pub field_type: FieldType,
+    pub count: usize,
+    /// Byte offset from the start of the buffer
+    pub offset: usize,
+    pub units: String,
+}
+
+/// A named integer constant that belongs with the layout, like a command mode,
+/// with the type of the field it is written to
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SchemaConstant {
+    pub name: String,
+    #[serde(default = "default_constant_type")]
+    pub field_type: FieldType,
+    pub value: u64,
+}
+
+fn default_constant_type() -> FieldType {
+    FieldType::U8
+}
+
+/// Describes the layout of a repr(C) struct written to a HardwareBuffer.
+/// Fields are appended in declaration order and offsets follow the C layout rules,
+/// so a schema built alongside the struct can be checked against it with check_layout.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BufferSchema {
+    pub name: String,
+    pub fields: Vec<SchemaField>,
+    pub constants: Vec<SchemaConstant>,
+    /// Total size in bytes, including trailing padding
+    pub size: usize,
+    alignment: usize,
+}
+
+impl BufferSchema {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            fields: Vec::new(),
+            constants: Vec::new(),
+            size: 0,
+            alignment: 1,
+        }
+    }
+
+    pub fn with_field(self, name: &str, field_type: FieldType, units: &str) -> Self {
+        self.with_array(name, field_type, 1, units)
+    }
+
+    pub fn with_array(
+        mut self,
+        name: &str,
+        field_type: FieldType,
+        count: usize,
+        units: &str,
+    ) -> Self {
+        let align = field_type.size();
+        // end of the last field, before trailing padding
+        let end = self
+            .fields
+            .last()
+            .map_or(0, |field| {
+                field.offset
+                    + field
+                        .field_type
+                        .size()
+                        * field.count
+            });
+        let offset = end.next_multiple_of(align);
+        self.alignment = self
+            .alignment
+            .max(align);
+        self.size = (offset + align * count).next_multiple_of(self.alignment);
+        self.fields
+            .push(SchemaField {
+                name: name.to_string(),
+                field_type,
+                count,
+                offset,
+                units: units.to_string(),
+            });
+        self
+    }
+
+    /// Explicit padding bytes, named so generated structs match the Rust definition
+    pub fn with_padding(self, bytes: usize) -> Self {
+        self.with_array(
+            "_padding",
+            FieldType::U8,
+            bytes,
+            "",
+        )
+    }
+
+    pub fn with_constant(mut self, name: &str, field_type: FieldType, value: u64) -> Self {
+        self.constants
+            .push(SchemaConstant { name: name.to_string(), field_type, value });
+        self
+    }
+
+    pub fn field(&self, name: &str) -> Option<&SchemaField> {
+        self.fields
+            .iter()
+            .find(|field| field.name == name)
+    }
+
+    /// Checks that a struct has the same size as the schema
+    pub fn check_size<T: Pod>(&self) -> Result<(), SchemaErrors> {
+        let size = size_of::<T>();
+        if size != self.size {
+            return Err(SchemaErrors::SizeMismatch(
+                self.name
+                    .clone(),
+                self.size,
+                size,
+            ));
+        }
+        Ok(())
+    }
+
+    /// Checks that a struct has the same size as the schema and its fields the same offsets, with the
+    /// offsets of every field of the struct in declaration order, padding included, for example from
+    /// `std::mem::offset_of!`. Field names are not compared, but reordered fields of different sizes
+    /// are caught, which check_size alone doesn't.
+    pub fn check_layout<T: Pod>(&self, offsets: &[usize]) -> Result<(), SchemaErrors> {
+        self.check_size::<T>()?;
+        if self
+            .fields
+            .len()
+            != offsets.len()
+        {
+            return Err(SchemaErrors::FieldCount(
+                self.name
+                    .clone(),
+                self.fields
+                    .len(),
+                offsets.len(),
+            ));
+        }
+        for (field, &offset) in self
+            .fields
+            .iter()
+            .zip(offsets)
+        {
+            if field.offset != offset {
+                return Err(SchemaErrors::OffsetMismatch(
+                    self.name
+                        .clone(),
+                    field
+                        .name
+                        .clone(),
+                    field.offset,
+                    offset,
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that another schema, for example one written by the flight software, has the same layout.
+    /// Names, types, counts and offsets must match. Units are not compared.
+    pub fn check(&self, expected: &BufferSchema) -> Result<(), SchemaErrors> {
+        if self.size != expected.size {
+            return Err(SchemaErrors::SizeMismatch(
+                self.name
+                    .clone(),
+                expected.size,
+                self.size,
+            ));
+        }
+        if self
+            .fields
+            .len()
+            != expected
+                .fields
+                .len()
+        {
+            return Err(SchemaErrors::FieldCount(
+                self.name
+                    .clone(),
+                self.fields
+                    .len(),
+                expected
+                    .fields
+                    .len(),
+            ));
+        }
+        for (field, other) in self
+            .fields
+            .iter()
+            .zip(&expected.fields)
+        {
+            if field.name != other.name
+                || field.field_type != other.field_type
+                || field.count != other.count
+                || field.offset != other.offset
+            {
+                return Err(SchemaErrors::FieldMismatch(
+                    self.name
+                        .clone(),
+                    other
+                        .name
+                        .clone(),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads a field out of a buffer by name, converting each element to f64
+    pub fn read_field(
+        &self,
+        buffer: &HardwareBuffer,
+        name: &str,
+    ) -> Result<Vec<f64>, SchemaErrors> {
+        let bytes = buffer.as_bytes();
+        if bytes.len() != self.size {
+            return Err(SchemaErrors::SizeMismatch(
+                self.name
+                    .clone(),
+                self.size,
+                bytes.len(),
+            ));
+        }
+        let field = self
+            .field(name)
+            .ok_or_else(|| {
+                SchemaErrors::UnknownField(
+                    self.name
+                        .clone(),
+                    name.to_string(),
+                )
+            })?;
+        let size = field
+            .field_type
+            .size();
+        Ok((0..field.count)
+            .map(|i| {
+                let start = field.offset + i * size;
+                field
+                    .field_type
+                    .decode(&bytes[start..start + size])
+            })
+            .collect())
+    }
+
+    /// The struct definition and constants as C
+    pub fn to_c(&self) -> String {
+        let mut s = String::new();
+        let prefix = constant_prefix(&self.name);
+        for constant in &self.constants {
+            writeln!(
+                s,
+                "#define {}_{} {}",
+                prefix,
+                constant
+                    .name
+                    .to_uppercase(),
+                constant.value
+            )
+            .unwrap();
+        }
+        writeln!(s, "typedef struct {{").unwrap();
+        for field in &self.fields {
+            let units = units_comment(field);
+            if field.count == 1 {
+                writeln!(
+                    s,
+                    "    {} {};{}",
+                    field
+                        .field_type
+                        .c_type(),
+                    field.name,
+                    units
+                )
+                .unwrap();
+            } else {
+                writeln!(
+                    s,
+                    "    {} {}[{}];{}",
+                    field
+                        .field_type
+                        .c_type(),
+                    field.name,
+                    field.count,
+                    units
+                )
+                .unwrap();
+            }
+        }
+        writeln!(s, "}} {};", self.name).unwrap();
+        writeln!(
+            s,
+            "_Static_assert(sizeof({}) == {}, \"{} layout mismatch\");",
+            self.name, self.size, self.name
+        )
+        .unwrap();
+        s
+    }
+
+    /// The struct definition and constants as Rust, deriving bytemuck Pod
+    pub fn to_rust(&self) -> String {
+        let mut s = String::new();
+        writeln!(
+            s,
+            "#[derive(Clone, Copy, Debug, Default, Pod, Zeroable)]"
+        )
+        .unwrap();
+        writeln!(s, "#[repr(C)]").unwrap();
+        writeln!(
+            s,
+            "pub struct {} {{",
+            self.name
+        )
+        .unwrap();
+        for field in &self.fields {
+            let units = units_comment(field);
+            if field.count == 1 {
+                writeln!(
+                    s,
+                    "    pub {}: {},{}",
+                    field.name,
+                    field
+                        .field_type
+                        .rust_type(),
+                    units
+                )
+                .unwrap();
+            } else {
+                writeln!(
+                    s,
+                    "    pub {}: [{}; {}],{}",
+                    field.name,
+                    field
+                        .field_type
+                        .rust_type(),
+                    field.count,
+                    units
+                )
+                .unwrap();
+            }
+        }
+        writeln!(s, "}}").unwrap();
+        if !self
+            .constants
+            .is_empty()
+        {
+            writeln!(s).unwrap();
+            writeln!(s, "impl {} {{", self.name).unwrap();
+            for constant in &self.constants {
+                writeln!(
+                    s,
+                    "    pub const {}: {} = {};",
+                    constant
+                        .name
+                        .to_uppercase(),
+                    constant
+                        .field_type
+                        .rust_type(),
+                    constant.value
+                )
+                .unwrap();
+            }
+            writeln!(s, "}}").unwrap();
+        }
+        writeln!(
+            s,
+            "const _: () = assert!(size_of::<{}>() == {});",
+            self.name, self.size
+        )
+        .unwrap();
+        s
+    }
+}
+
+/// Removes duplicate schemas by name, returning an error if two schemas share a name but not a layout
+pub fn unique_schemas(schemas: &[BufferSchema]) -> Result<Vec<BufferSchema>, SchemaErrors> {
+    let mut unique: Vec<BufferSchema> = Vec::new();
+    for schema in schemas {
+        match unique
+            .iter()
+            .find(|s| s.name == schema.name)
+        {
+            Some(existing) => {
+                if existing != schema {
+                    return Err(
+                        SchemaErrors::ConflictingDefinitions(
+                            schema
+                                .name
+                                .clone(),
+                        ),
+                    );
+                }
+            }
+            None => unique.push(schema.clone()),
+        }
+    }
+    Ok(unique)
+}
+
+/// A C header defining every schema
+pub fn c_header(schemas: &[BufferSchema]) -> Result<String, SchemaErrors> {
+    let mut s = String::new();
+    writeln!(
+        s,
+        "// Generated by nadir from the sim telemetry schemas, do not edit"
+    )
+    .unwrap();
+    writeln!(s, "#ifndef NADIR_TELEMETRY_H").unwrap();
+    writeln!(s, "#define NADIR_TELEMETRY_H").unwrap();
+    writeln!(s).unwrap();
+    writeln!(s, "#include <stdint.h>").unwrap();
+    for schema in unique_schemas(schemas)? {
+        writeln!(s).unwrap();
+        s.push_str(&schema.to_c());
+    }
+    writeln!(s).unwrap();
+    writeln!(
+        s,
+        "#endif // NADIR_TELEMETRY_H"
+    )
+    .unwrap();
+    Ok(s)
+}
+
+/// A Rust module defining every schema
+pub fn rust_module(schemas: &[BufferSchema]) -> Result<String, SchemaErrors> {
+    let mut s = String::new();
+    writeln!(
+        s,
+        "// Generated by nadir from the sim telemetry schemas, do not edit"
+    )
+    .unwrap();
+    writeln!(
+        s,
+        "use bytemuck::{{Pod, Zeroable}};"
+    )
+    .unwrap();
+    for schema in unique_schemas(schemas)? {
+        writeln!(s).unwrap();
+        s.push_str(&schema.to_rust());
+    }
+    Ok(s)
+}
+
+pub fn write_c_header(schemas: &[BufferSchema], path: &Path) -> Result<(), SchemaErrors> {
+    std::fs::write(path, c_header(schemas)?)?;
+    Ok(())
+}
+
+pub fn write_rust_module(schemas: &[BufferSchema], path: &Path) -> Result<(), SchemaErrors> {
+    std::fs::write(path, rust_module(schemas)?)?;
+    Ok(())
+}
+
+fn units_comment(field: &SchemaField) -> String {
+    if field
+        .units
+        .is_empty()
+    {
+        String::new()
+    } else {
+        format!(" // {}", field.units)
+    }
+}
+
+/// GpsTelemetry -> GPS_TELEMETRY
+fn constant_prefix(name: &str) -> String {
+    let mut prefix = String::new();
+    for (i, c) in name
+        .chars()
+        .enumerate()
+    {
+        if c.is_uppercase() && i > 0 {
+            prefix.push('_');
+        }
+        prefix.push(c.to_ascii_uppercase());
+    }
+    prefix
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        actuator::reaction_wheel::ReactionWheelCommand,
+        sensor::{SensorModel, gps::GpsBuilder, star_tracker::StarTrackerTelemetry},
+    };
+    use rand::{SeedableRng, rngs::SmallRng};
+    use uncertainty::Uncertainty;
+
+    fn schemas() -> Vec<BufferSchema> {
+        let gps = GpsBuilder::new()
+            .sample(
+                true,
+                &mut SmallRng::seed_from_u64(0),
+            )
+            .unwrap();
+        vec![
+            gps.telemetry_schema(),
+            StarTrackerTelemetry::schema(),
+            ReactionWheelCommand::schema(),
+            // duplicates are only written once
+            ReactionWheelCommand::schema(),
+        ]
+    }
+
+    #[test]
+    fn test_c_header_golden() {
+        assert_eq!(
+            c_header(&schemas()).unwrap(),
+            include_str!("../resources/telemetry_golden.h")
+        );
+    }
+
+    #[test]
+    fn test_rust_module_golden() {
+        assert_eq!(
+            rust_module(&schemas()).unwrap(),
+            include_str!("../resources/telemetry_golden.rs")
+        );
+    }
+}
@@ -1,23 +1,52 @@
 use crate::{
     HardwareBuffer,
     body::BodyConnection,
-    delay::DelayedValue,
+    delay::InterpolationMethod,
+    schema::{BufferSchema, FieldType},
     sensor::{
         SensorModel,
         noise::{Noise, NoiseBuilder},
+        pipeline::{ErrorPipeline, ErrorPipelineBuilder, ErrorPipelineErrors},
     },
 };
 
+use aerospace::orbit::KeplerianElements;
 use bytemuck::{Pod, Zeroable};
+use celestial::CelestialBodies;
 use nadir_diffeq::saving::StateWriter;
 use nalgebra::Vector3;
 use serde::{Deserialize, Serialize};
+use std::f64::consts::PI;
 use thiserror::Error;
-use time::Time;
+use time::{Time, TimeSystem};
 use uncertainty::{Normal, UncertainValue, Uncertainty, UncertaintyErrors};
 
 use super::{SensorErrors, noise::NoiseErrors};
 
+/// Speed of light in vacuum (m/s), used to convert receiver clock bias to range and for light time
+const SPEED_OF_LIGHT: f64 = 299_792_458.0;
+/// Iterations of the pseudorange light time correction
+const LIGHT_TIME_ITERATIONS: usize = 2;
+/// Maximum number of pseudorange channels reported by the receiver
+pub const GPS_MAX_CHANNELS: usize = 12;
+/// Minimum number of visible satellites required for a navigation solution
+const GPS_MIN_SATELLITES: usize = 4;
+
+const PSEUDORANGE_HEADERS: [&str; GPS_MAX_CHANNELS] = [
+    "pseudorange[0]",
+    "pseudorange[1]",
+    "pseudorange[2]",
+    "pseudorange[3]",
+    "pseudorange[4]",
+    "pseudorange[5]",
+    "pseudorange[6]",
+    "pseudorange[7]",
+    "pseudorange[8]",
+    "pseudorange[9]",
+    "pseudorange[10]",
+    "pseudorange[11]",
+];
+
 #[derive(Debug, Error)]
 pub enum GpsErrors {
     #[error("gps name cannot be empty")]
@@ -25,14 +54,110 @@ pub enum GpsErrors {
     #[error("{0}")]
     Noise(#[from] NoiseErrors),
     #[error("{0}")]
+    Pipeline(#[from] ErrorPipelineErrors),
+    #[error("gps pps period must be greater than 0.0")]
+    PpsPeriodNotPositive,
+    #[error("{0}")]
     Uncertainty(#[from] UncertaintyErrors),
 }
 
+fn default_epoch() -> Time {
+    Time::from_sec_j2k(0.0, TimeSystem::GPS)
+}
+
+/// A constellation of navigation satellites propagated with two-body Keplerian motion.
+/// Satellite positions are assumed to share the base frame of the receiver, which should
+/// be Earth centered inertial.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct GpsConstellation {
+    /// Grazing altitude (m) below which a line of sight is considered blocked by the Earth
+    pub mask_altitude: f64,
+    pub satellites: Vec<KeplerianElements>,
+}
+
+impl GpsConstellation {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The nominal 24 satellite Walker 55°:24/6/1 GPS constellation, with orbital elements
+    /// defined at the provided epoch
+    pub fn nominal(epoch: Time) -> Self {
+        let semimajor_axis = 26_559_700.0;
+        let inclination = 55.0 * PI / 180.0;
+        let mut satellites = Vec::with_capacity(24);
+        for plane in 0..6 {
+            let raan = plane as f64 * 60.0 * PI / 180.0;
+            for slot in 0..4 {
+                let anomaly = (slot as f64 * 90.0 + plane as f64 * 15.0) * PI / 180.0;
+                satellites.push(KeplerianElements::new(
+                    semimajor_axis,
+                    0.0,
+                    inclination,
+                    raan,
+                    0.0,
+                    anomaly,
+                    epoch,
+                    CelestialBodies::Earth,
+                ));
+            }
+        }
+        Self { mask_altitude: 0.0, satellites }
+    }
+
+    pub fn with_mask_altitude(mut self, altitude: f64) -> Self {
+        self.mask_altitude = altitude;
+        self
+    }
+
+    pub fn set_mask_altitude(&mut self, altitude: f64) {
+        self.mask_altitude = altitude;
+    }
+
+    pub fn with_satellite(mut self, satellite: KeplerianElements) -> Self {
+        self.satellites
+            .push(satellite);
+        self
+    }
+
+    pub fn add_satellite(&mut self, satellite: KeplerianElements) {
+        self.satellites
+            .push(satellite);
+    }
+}
+
+/// Returns true if the line of sight between the receiver and satellite clears the Earth
+/// plus the mask altitude
+fn line_of_sight(receiver: &Vector3<f64>, satellite: &Vector3<f64>, radius: f64) -> bool {
+    let d = satellite - receiver;
+    let dd = d.dot(&d);
+    if dd == 0.0 {
+        return receiver.norm() > radius;
+    }
+    let tau = (-receiver.dot(&d) / dd).clamp(0.0, 1.0);
+    (receiver + tau * d).norm() > radius
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 struct GpsParametersBuilder {
-    delay: Option<UncertainValue>,
-    position_noise: Option<[NoiseBuilder; 3]>,
-    velocity_noise: Option<[NoiseBuilder; 3]>,
+    #[serde(default)]
+    position_errors: ErrorPipelineBuilder,
+    #[serde(default)]
+    velocity_errors: ErrorPipelineBuilder,
+    #[serde(default)]
+    clock_bias: Option<UncertainValue>,
+    #[serde(default)]
+    clock_drift: Option<UncertainValue>,
+    #[serde(default = "default_epoch")]
+    epoch: Time,
+    #[serde(default)]
+    pps_period: Option<f64>,
+    #[serde(default)]
+    max_altitude: Option<f64>,
+    #[serde(default)]
+    constellation: Option<GpsConstellation>,
+    #[serde(default)]
+    pseudorange_noise: Option<NoiseBuilder>,
 }
 
 impl Uncertainty for GpsParametersBuilder {
@@ -43,68 +168,94 @@ impl Uncertainty for GpsParametersBuilder {
         nominal: bool,
         rng: &mut rand::prelude::SmallRng,
     ) -> Result<Self::Output, Self::Error> {
-        let delay = match &self.delay {
-            Some(delay) => {
-                let delay = delay.sample(nominal, rng);
-                Some([
-                    DelayedValue::new(delay),
-                    DelayedValue::new(delay),
-                    DelayedValue::new(delay),
-                    DelayedValue::new(delay),
-                    DelayedValue::new(delay),
-                    DelayedValue::new(delay),
-                ])
-            }
-            None => None,
+        let position_errors = self
+            .position_errors
+            .sample(nominal, rng)?;
+        let velocity_errors = self
+            .velocity_errors
+            .sample(nominal, rng)?;
+        let clock_bias = match &self.clock_bias {
+            Some(bias) => bias.sample(nominal, rng),
+            None => 0.0,
         };
-        let position_noise = match &self.position_noise {
-            Some(position_noise) => Some([
-                position_noise[0].sample(nominal, rng)?,
-                position_noise[1].sample(nominal, rng)?,
-                position_noise[2].sample(nominal, rng)?,
-            ]),
-            None => None,
+        let clock_drift = match &self.clock_drift {
+            Some(drift) => drift.sample(nominal, rng),
+            None => 0.0,
         };
-        let velocity_noise = match &self.velocity_noise {
-            Some(velocity_noise) => Some([
-                velocity_noise[0].sample(nominal, rng)?,
-                velocity_noise[1].sample(nominal, rng)?,
-                velocity_noise[2].sample(nominal, rng)?,
-            ]),
+        let pseudorange_noise = match &self.pseudorange_noise {
+            Some(noise) => Some(noise.sample(nominal, rng)?),
             None => None,
         };
-        Ok(GpsParameters { delay, position_noise, velocity_noise })
+        Ok(GpsParameters {
+            position_errors,
+            velocity_errors,
+            clock_bias,
+            clock_drift,
+            epoch: self
+                .epoch
+                .to_system(TimeSystem::GPS),
+            pps_period: self.pps_period,
+            max_altitude: self.max_altitude,
+            constellation: self
+                .constellation
+                .clone(),
+            pseudorange_noise,
+        })
     }
 }
 #[derive(Debug)]
 struct GpsParameters {
-    delay: Option<[DelayedValue; 6]>,
-    position_noise: Option<[Noise; 3]>,
-    velocity_noise: Option<[Noise; 3]>,
+    position_errors: ErrorPipeline,
+    velocity_errors: ErrorPipeline,
+    clock_bias: f64,
+    clock_drift: f64,
+    epoch: Time,
+    pps_period: Option<f64>,
+    max_altitude: Option<f64>,
+    constellation: Option<GpsConstellation>,
+    pseudorange_noise: Option<Noise>,
 }
 
 #[derive(Debug)]
 pub struct GpsState {
+    /// Receiver time tag of the current measurement in TimeSystem::GPS
     pub time: Time,
     pub position: Vector3<f64>,
     pub velocity: Vector3<f64>,
-    position_noise: Option<Vector3<f64>>,
-    velocity_noise: Option<Vector3<f64>>,
+    /// Receiver clock bias (s)
+    pub clock_bias: f64,
+    pub valid: bool,
+    pub num_visible: usize,
+    pub prn: [u8; GPS_MAX_CHANNELS],
+    /// Pseudoranges (m) to the satellites in prn, light time corrected geometric range plus
+    /// receiver clock bias and noise
+    pub pseudorange: [f64; GPS_MAX_CHANNELS],
+    position_error: Vector3<f64>,
+    velocity_error: Vector3<f64>,
+    last_epoch: Option<i64>,
 }
 
 impl Default for GpsState {
     fn default() -> Self {
         Self {
-            time: Time::from_sec_j2k(0.0, time::TimeSystem::GPS),
+            time: default_epoch(),
             position: Vector3::zeros(),
             velocity: Vector3::zeros(),
-            position_noise: None,
-            velocity_noise: None,
+            clock_bias: 0.0,
+            valid: false,
+            num_visible: 0,
+            prn: [0; GPS_MAX_CHANNELS],
+            pseudorange: [0.0; GPS_MAX_CHANNELS],
+            position_error: Vector3::zeros(),
+            velocity_error: Vector3::zeros(),
+            last_epoch: None,
         }
     }
 }
 
-/// A simple GPS with gaussian white noise & constant delay
+/// A GNSS receiver with position/velocity error pipelines, a bias/drift receiver clock,
+/// PPS epoch quantization, altitude and Earth blockage outages, and optional
+/// pseudoranges to a simulated constellation
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct GpsBuilder {
     parameters: GpsParametersBuilder,
@@ -114,114 +265,265 @@ impl GpsBuilder {
     pub fn new() -> Self {
         Self {
             parameters: GpsParametersBuilder {
-                delay: None,
-                position_noise: None,
-                velocity_noise: None,
+                position_errors: ErrorPipelineBuilder::new(),
+                velocity_errors: ErrorPipelineBuilder::new(),
+                clock_bias: None,
+                clock_drift: None,
+                epoch: default_epoch(),
+                pps_period: None,
+                max_altitude: None,
+                constellation: None,
+                pseudorange_noise: None,
             },
         }
     }
 
+    /// Replaces the error pipeline applied to the position solution
+    pub fn with_position_errors(mut self, errors: ErrorPipelineBuilder) -> Self {
+        self.parameters
+            .position_errors = errors;
+        self
+    }
+
+    pub fn set_position_errors(&mut self, errors: ErrorPipelineBuilder) {
+        self.parameters
+            .position_errors = errors;
+    }
+
+    /// Replaces the error pipeline applied to the velocity solution
+    pub fn with_velocity_errors(mut self, errors: ErrorPipelineBuilder) -> Self {
+        self.parameters
+            .velocity_errors = errors;
+        self
+    }
+
+    pub fn set_velocity_errors(&mut self, errors: ErrorPipelineBuilder) {
+        self.parameters
+            .velocity_errors = errors;
+    }
+
+    /// Sets the delay of both the position and velocity solutions
     pub fn with_delay(mut self, delay: f64) -> Self {
-        if let Some(selfdelay) = &mut self
+        self.parameters
+            .position_errors
+            .set_delay(delay);
+        self.parameters
+            .velocity_errors
+            .set_delay(delay);
+        self
+    }
+
+    /// Sets the delay of both solutions, returning an error immediately if it is negative
+    pub fn try_with_delay(mut self, delay: f64) -> Result<Self, GpsErrors> {
+        self.parameters
+            .position_errors
+            .try_set_delay(delay)?;
+        self.parameters
+            .velocity_errors
+            .try_set_delay(delay)?;
+        Ok(self)
+    }
+
+    pub fn with_delay_interpolation(mut self, method: InterpolationMethod) -> Self {
+        self.parameters
+            .position_errors
+            .set_delay_interpolation(method);
+        self.parameters
+            .velocity_errors
+            .set_delay_interpolation(method);
+        self
+    }
+
+    pub fn with_uncertain_delay_normal(mut self, mean: f64, std: f64) -> Result<Self, GpsErrors> {
+        self.parameters
+            .position_errors
+            .set_uncertain_delay_normal(mean, std)?;
+        self.parameters
+            .velocity_errors
+            .set_uncertain_delay_normal(mean, std)?;
+        Ok(self)
+    }
+
+    /// Receiver clock bias (s) at the start of the simulation
+    pub fn with_clock_bias(mut self, bias: f64) -> Self {
+        self.set_clock_bias(bias);
+        self
+    }
+
+    pub fn set_clock_bias(&mut self, bias: f64) {
+        if let Some(selfbias) = &mut self
             .parameters
-            .delay
+            .clock_bias
         {
-            selfdelay.nominal = delay
+            selfbias.nominal = bias
         } else {
             self.parameters
-                .delay = Some(UncertainValue::new(delay));
+                .clock_bias = Some(UncertainValue::new(bias));
         }
+    }
+
+    pub fn with_uncertain_clock_bias_normal(
+        mut self,
+        mean: f64,
+        std: f64,
+    ) -> Result<Self, GpsErrors> {
+        let dist = Normal::new(mean, std)?;
+        if let Some(bias) = &mut self
+            .parameters
+            .clock_bias
+        {
+            bias.set_distribution(dist.into())?;
+        } else {
+            self.parameters
+                .clock_bias = Some(UncertainValue::new(mean).with_distribution(dist.into())?);
+        }
+        Ok(self)
+    }
+
+    /// Receiver clock drift (s/s)
+    pub fn with_clock_drift(mut self, drift: f64) -> Self {
+        self.set_clock_drift(drift);
         self
     }
 
-    pub fn with_uncertain_delay_normal(mut self, mean: f64, std: f64) -> Result<Self, GpsErrors> {
+    pub fn set_clock_drift(&mut self, drift: f64) {
+        if let Some(selfdrift) = &mut self
+            .parameters
+            .clock_drift
+        {
+            selfdrift.nominal = drift
+        } else {
+            self.parameters
+                .clock_drift = Some(UncertainValue::new(drift));
+        }
+    }
+
+    pub fn with_uncertain_clock_drift_normal(
+        mut self,
+        mean: f64,
+        std: f64,
+    ) -> Result<Self, GpsErrors> {
         let dist = Normal::new(mean, std)?;
-        if let Some(delay) = &mut self
+        if let Some(drift) = &mut self
             .parameters
-            .delay
+            .clock_drift
         {
-            delay.set_distribution(dist.into())?;
+            drift.set_distribution(dist.into())?;
         } else {
             self.parameters
-                .delay = Some(UncertainValue::new(mean).with_distribution(dist.into())?);
+                .clock_drift = Some(UncertainValue::new(mean).with_distribution(dist.into())?);
         }
         Ok(self)
     }
 
-    pub fn with_noise_position_normal(mut self, mean: f64, std: f64) -> Self {
-        let noise1 = NoiseBuilder::new_normal(mean, std);
-        let noise2 = NoiseBuilder::new_normal(mean, std);
-        let noise3 = NoiseBuilder::new_normal(mean, std);
-        let noise = [noise1, noise2, noise3];
+    /// Epoch corresponding to simulation time 0.0, used for the receiver time tag
+    /// and for propagating the constellation
+    pub fn with_epoch(mut self, epoch: Time) -> Self {
         self.parameters
-            .position_noise = Some(noise);
+            .epoch = epoch;
+        self
+    }
+
+    pub fn set_epoch(&mut self, epoch: Time) {
+        self.parameters
+            .epoch = epoch;
+    }
+
+    /// Quantizes measurements to the receiver's PPS, holding the last measurement between
+    /// epochs of the receiver clock
+    pub fn with_pps_period(mut self, period: f64) -> Result<Self, GpsErrors> {
+        self.set_pps_period(period)?;
+        Ok(self)
+    }
+
+    pub fn set_pps_period(&mut self, period: f64) -> Result<(), GpsErrors> {
+        if period <= 0.0 {
+            return Err(GpsErrors::PpsPeriodNotPositive);
+        }
+        self.parameters
+            .pps_period = Some(period);
+        Ok(())
+    }
+
+    /// Altitude (m) above the Earth's radius at which the receiver loses its solution
+    pub fn with_max_altitude(mut self, altitude: f64) -> Self {
+        self.parameters
+            .max_altitude = Some(altitude);
+        self
+    }
+
+    pub fn set_max_altitude(&mut self, altitude: f64) {
+        self.parameters
+            .max_altitude = Some(altitude);
+    }
+
+    /// Enables pseudorange output and Earth blockage of the constellation's satellites.
+    /// The solution is invalid when fewer than 4 satellites are visible.
+    pub fn with_constellation(mut self, constellation: GpsConstellation) -> Self {
+        self.parameters
+            .constellation = Some(constellation);
+        self
+    }
+
+    pub fn set_constellation(&mut self, constellation: GpsConstellation) {
+        self.parameters
+            .constellation = Some(constellation);
+    }
+
+    pub fn with_noise_pseudorange_normal(mut self, mean: f64, std: f64) -> Self {
+        self.set_noise_pseudorange_normal(mean, std);
+        self
+    }
+
+    pub fn set_noise_pseudorange_normal(&mut self, mean: f64, std: f64) {
+        self.parameters
+            .pseudorange_noise = Some(NoiseBuilder::new_normal(
+            mean, std,
+        ));
+    }
+
+    pub fn with_noise_position_normal(mut self, mean: f64, std: f64) -> Self {
+        self.set_noise_position_normal(mean, std);
         self
     }
 
     pub fn set_noise_position_normal(&mut self, mean: f64, std: f64) {
-        let noise1 = NoiseBuilder::new_normal(mean, std);
-        let noise2 = NoiseBuilder::new_normal(mean, std);
-        let noise3 = NoiseBuilder::new_normal(mean, std);
-        let noise = [noise1, noise2, noise3];
         self.parameters
-            .position_noise = Some(noise);
+            .position_errors
+            .set_noise_normal(mean, std);
     }
 
     pub fn with_noise_position_uniform(mut self, low: f64, high: f64) -> Self {
-        let noise1 = NoiseBuilder::new_uniform(low, high);
-        let noise2 = NoiseBuilder::new_normal(low, high);
-        let noise3 = NoiseBuilder::new_normal(low, high);
-        let noise = [noise1, noise2, noise3];
-        self.parameters
-            .position_noise = Some(noise);
+        self.set_noise_position_uniform(low, high);
         self
     }
 
     pub fn set_noise_position_uniform(&mut self, low: f64, high: f64) {
-        let noise1 = NoiseBuilder::new_uniform(low, high);
-        let noise2 = NoiseBuilder::new_normal(low, high);
-        let noise3 = NoiseBuilder::new_normal(low, high);
-        let noise = [noise1, noise2, noise3];
         self.parameters
-            .position_noise = Some(noise);
+            .position_errors
+            .set_noise_uniform(low, high);
     }
 
     pub fn with_noise_velocity_normal(mut self, mean: f64, std: f64) -> Self {
-        let noise1 = NoiseBuilder::new_normal(mean, std);
-        let noise2 = NoiseBuilder::new_normal(mean, std);
-        let noise3 = NoiseBuilder::new_normal(mean, std);
-        let noise = [noise1, noise2, noise3];
-        self.parameters
-            .velocity_noise = Some(noise);
+        self.set_noise_velocity_normal(mean, std);
         self
     }
 
     pub fn set_noise_velocity_normal(&mut self, mean: f64, std: f64) {
-        let noise1 = NoiseBuilder::new_normal(mean, std);
-        let noise2 = NoiseBuilder::new_normal(mean, std);
-        let noise3 = NoiseBuilder::new_normal(mean, std);
-        let noise = [noise1, noise2, noise3];
         self.parameters
-            .velocity_noise = Some(noise);
+            .velocity_errors
+            .set_noise_normal(mean, std);
     }
 
     pub fn with_noise_velocity_uniform(mut self, low: f64, high: f64) -> Self {
-        let noise1 = NoiseBuilder::new_uniform(low, high);
-        let noise2 = NoiseBuilder::new_normal(low, high);
-        let noise3 = NoiseBuilder::new_normal(low, high);
-        let noise = [noise1, noise2, noise3];
-        self.parameters
-            .velocity_noise = Some(noise);
+        self.set_noise_velocity_uniform(low, high);
         self
     }
 
     pub fn set_noise_velocity_uniform(&mut self, low: f64, high: f64) {
-        let noise1 = NoiseBuilder::new_uniform(low, high);
-        let noise2 = NoiseBuilder::new_normal(low, high);
-        let noise3 = NoiseBuilder::new_normal(low, high);
-        let noise = [noise1, noise2, noise3];
         self.parameters
-            .velocity_noise = Some(noise);
+            .velocity_errors
+            .set_noise_uniform(low, high);
     }
 }
 
@@ -233,153 +535,383 @@ impl Uncertainty for GpsBuilder {
         nominal: bool,
         rng: &mut rand::prelude::SmallRng,
     ) -> Result<Self::Output, Self::Error> {
+        let parameters = self
+            .parameters
+            .sample(nominal, rng)?;
+
+        let mut headers = vec![
+            "time(gps)",
+            "position[x]",
+            "position[y]",
+            "position[z]",
+            "velocity[x]",
+            "velocity[y]",
+            "velocity[z]",
+            "clock_bias",
+            "valid",
+        ];
+        if !parameters
+            .position_errors
+            .is_empty()
+        {
+            headers.extend_from_slice(&[
+                "position_error[x]",
+                "position_error[y]",
+                "position_error[z]",
+            ]);
+        }
+        if !parameters
+            .velocity_errors
+            .is_empty()
+        {
+            headers.extend_from_slice(&[
+                "velocity_error[x]",
+                "velocity_error[y]",
+                "velocity_error[z]",
+            ]);
+        }
+        if parameters
+            .constellation
+            .is_some()
+        {
+            headers.push("num_visible");
+            headers.extend_from_slice(&PSEUDORANGE_HEADERS);
+        }
+
         Ok(Gps {
-            parameters: self
-                .parameters
-                .sample(nominal, rng)?,
+            parameters,
             state: GpsState::default(),
             telemetry: GpsTelemetry::default(),
+            headers,
         })
     }
 }
 
-/// A simple GPS with gaussian white noise & constant delay
+/// A GNSS receiver with position/velocity error pipelines, a bias/drift receiver clock,
+/// PPS epoch quantization, altitude and Earth blockage outages, and optional
+/// pseudoranges to a simulated constellation
 #[derive(Debug)]
 pub struct Gps {
     parameters: GpsParameters,
     pub state: GpsState,
     telemetry: GpsTelemetry,
+    headers: Vec<&'static str>,
 }
 
-impl SensorModel for Gps {
-    fn update(&mut self, t: f64, connection: &BodyConnection) {
-        let body = connection
-            .body
-            .borrow();
+impl Gps {
+    /// Computes pseudoranges to all visible satellites at the true measurement time,
+    /// returning the number of visible satellites.
+    /// Satellite positions are corrected for light time, taken at the time the signal was
+    /// transmitted rather than received. Both positions are inertial, so there is no Sagnac
+    /// correction, and atmospheric delays are not modeled.
+    fn update_pseudoranges(&mut self, t: f64, position: &Vector3<f64>) -> usize {
+        let constellation = match &self
+            .parameters
+            .constellation
+        {
+            Some(constellation) => constellation,
+            None => return 0,
+        };
+        let epoch = self
+            .parameters
+            .epoch
+            + t;
+        let radius = CelestialBodies::Earth.get_radius() + constellation.mask_altitude;
+        let clock_range = SPEED_OF_LIGHT
+            * self
+                .state
+                .clock_bias;
 
-        let true_position = body
-            .state
-            .position_base;
-        let true_velocity = body
-            .state
-            .velocity_base;
+        self.state
+            .prn = [0; GPS_MAX_CHANNELS];
+        self.state
+            .pseudorange = [0.0; GPS_MAX_CHANNELS];
+
+        let mut num_visible = 0;
+        for (i, satellite) in constellation
+            .satellites
+            .iter()
+            .enumerate()
+        {
+            let satellite_position_at = |epoch| {
+                satellite
+                    .keplers_problem(epoch)
+                    .map(|orbit| {
+                        orbit
+                            .get_rv()
+                            .0
+                    })
+            };
+            let mut satellite_position = match satellite_position_at(epoch) {
+                Ok(position) => position,
+                Err(_) => continue,
+            };
+            // the signal left the satellite one light time before it was received
+            for _ in 0..LIGHT_TIME_ITERATIONS {
+                let light_time = (satellite_position - position).norm() / SPEED_OF_LIGHT;
+                satellite_position = match satellite_position_at(epoch - light_time) {
+                    Ok(position) => position,
+                    Err(_) => break,
+                };
+            }
+            if !line_of_sight(
+                position,
+                &satellite_position,
+                radius,
+            ) {
+                continue;
+            }
+            if num_visible < GPS_MAX_CHANNELS {
+                let noise = match &mut self
+                    .parameters
+                    .pseudorange_noise
+                {
+                    Some(noise) => noise.sample(),
+                    None => 0.0,
+                };
+                self.state
+                    .prn[num_visible] = (i + 1) as u8;
+                self.state
+                    .pseudorange[num_visible] =
+                    (satellite_position - position).norm() + clock_range + noise;
+            }
+            num_visible += 1;
+        }
+        num_visible
+    }
+
+    /// Updates the measurement at time `t` from the true inertial position and velocity of
+    /// the receiver
+    fn measure(&mut self, t: f64, true_position: Vector3<f64>, true_velocity: Vector3<f64>) {
+        // errors are applied every update so that delay history is continuous between epochs
+        let position = self
+            .parameters
+            .position_errors
+            .apply(t, true_position);
+        let velocity = self
+            .parameters
+            .velocity_errors
+            .apply(t, true_velocity);
 
-        let (position, velocity) = if let Some(delay) = &mut self
+        // receiver clock
+        let clock_bias = self
             .parameters
-            .delay
+            .clock_bias
+            + self
+                .parameters
+                .clock_drift
+                * t;
+        let receiver_time = t + clock_bias;
+
+        // quantize the measurement to the receiver's pps, holding the previous measurement
+        // until the receiver clock reaches the next epoch
+        let (measurement_time, age) = if let Some(period) = self
+            .parameters
+            .pps_period
         {
-            let mut delayed_position = Vector3::zeros();
-            let mut delayed_velocity = Vector3::zeros();
-            for i in 0..3 {
-                delay[i].update(t, true_position[i]);
-                delayed_position[i] = delay[i].get_delayed_reading(t);
-                delay[i + 3].update(t, true_velocity[i]);
-                delayed_velocity[i] = delay[i + 3].get_delayed_reading(t);
+            let epoch = (receiver_time / period).floor() as i64;
+            if self
+                .state
+                .last_epoch
+                == Some(epoch)
+            {
+                return;
             }
+            self.state
+                .last_epoch = Some(epoch);
+            let measurement_time = epoch as f64 * period;
             (
-                delayed_position,
-                delayed_velocity,
+                measurement_time,
+                receiver_time - measurement_time,
             )
         } else {
-            (true_position, true_velocity)
+            (receiver_time, 0.0)
         };
 
-        if let Some(noise_model) = &mut self
+        // propagate the measurement back to the true time of the receiver epoch
+        let true_position = true_position - true_velocity * age;
+        let true_time = t - age;
+
+        self.state
+            .clock_bias = clock_bias;
+        self.state
+            .time = self
             .parameters
-            .position_noise
-        {
-            let noise1 = noise_model[0].sample();
-            let noise2 = noise_model[1].sample();
-            let noise3 = noise_model[2].sample();
-            let noise = Vector3::new(noise1, noise2, noise3);
-            self.state
-                .position_noise = Some(noise);
-            self.state
-                .position = position + noise;
-        } else {
-            self.state
-                .position = position;
-        }
+            .epoch
+            + measurement_time;
+        self.state
+            .position = position - velocity * age;
+        self.state
+            .velocity = velocity;
+        self.state
+            .position_error = self
+            .parameters
+            .position_errors
+            .error;
+        self.state
+            .velocity_error = self
+            .parameters
+            .velocity_errors
+            .error;
 
-        if let Some(noise_model) = &mut self
+        // outages
+        let altitude = true_position.norm() - CelestialBodies::Earth.get_radius();
+        let mut valid = match self
             .parameters
-            .velocity_noise
+            .max_altitude
         {
-            let noise1 = noise_model[0].sample();
-            let noise2 = noise_model[1].sample();
-            let noise3 = noise_model[2].sample();
-            let noise = Vector3::new(noise1, noise2, noise3);
-            self.state
-                .velocity_noise = Some(noise);
-            self.state
-                .velocity = velocity + noise;
-        } else {
+            Some(max_altitude) => altitude <= max_altitude,
+            None => true,
+        };
+        if self
+            .parameters
+            .constellation
+            .is_some()
+        {
+            let num_visible = self.update_pseudoranges(true_time, &true_position);
             self.state
-                .velocity = velocity;
+                .num_visible = num_visible;
+            valid &= num_visible >= GPS_MIN_SATELLITES;
         }
+        self.state
+            .valid = valid;
 
         // update telemetry
         self.telemetry
-            .position = self
+            .time = self
             .state
-            .position
-            .into();
+            .time
+            .get_seconds_j2k();
+        if valid {
+            self.telemetry
+                .position = self
+                .state
+                .position
+                .into();
+            self.telemetry
+                .velocity = self
+                .state
+                .velocity
+                .into();
+        } else {
+            self.telemetry
+                .position = [0.0; 3];
+            self.telemetry
+                .velocity = [0.0; 3];
+        }
         self.telemetry
-            .velocity = self
+            .pseudorange = self
             .state
-            .velocity
-            .into();
+            .pseudorange;
         self.telemetry
-            .valid = 1u8;
+            .prn = self
+            .state
+            .prn;
+        self.telemetry
+            .num_pseudoranges = self
+            .state
+            .num_visible
+            .min(GPS_MAX_CHANNELS) as u8;
+        self.telemetry
+            .valid = valid as u8;
+    }
+}
+
+impl SensorModel for Gps {
+    fn update(&mut self, t: f64, connection: &BodyConnection) {
+        let body = connection
+            .body
+            .borrow();
+        self.measure(
+            t,
+            body.state
+                .position_base,
+            body.state
+                .velocity_base,
+        );
     }
 
     fn writer_save_fn(&self, writer: &mut StateWriter) {
         writer.float_buffer[0] = self
             .state
-            .position[0];
+            .time
+            .get_seconds_j2k();
         writer.float_buffer[1] = self
             .state
-            .position[1];
+            .position[0];
         writer.float_buffer[2] = self
             .state
-            .position[2];
+            .position[1];
         writer.float_buffer[3] = self
             .state
-            .velocity[0];
+            .position[2];
         writer.float_buffer[4] = self
             .state
-            .velocity[1];
+            .velocity[0];
         writer.float_buffer[5] = self
+            .state
+            .velocity[1];
+        writer.float_buffer[6] = self
             .state
             .velocity[2];
+        writer.float_buffer[7] = self
+            .state
+            .clock_bias;
+        writer.float_buffer[8] = if self
+            .state
+            .valid
+        {
+            1.0
+        } else {
+            0.0
+        };
 
-        match (
-            &self
+        let mut i = 9;
+        if !self
+            .parameters
+            .position_errors
+            .is_empty()
+        {
+            writer.float_buffer[i..i + 3].copy_from_slice(
+                self.state
+                    .position_error
+                    .as_slice(),
+            );
+            i += 3;
+        }
+        if !self
+            .parameters
+            .velocity_errors
+            .is_empty()
+        {
+            writer.float_buffer[i..i + 3].copy_from_slice(
+                self.state
+                    .velocity_error
+                    .as_slice(),
+            );
+            i += 3;
+        }
+        if self
+            .parameters
+            .constellation
+            .is_some()
+        {
+            writer.float_buffer[i] = self
                 .state
-                .position_noise,
-            &self
+                .num_visible as f64;
+            i += 1;
+            let n = self
                 .state
-                .velocity_noise,
-        ) {
-            (Some(position_noise), Some(velocity_noise)) => {
-                writer.float_buffer[6] = position_noise[0];
-                writer.float_buffer[7] = position_noise[1];
-                writer.float_buffer[8] = position_noise[2];
-                writer.float_buffer[9] = velocity_noise[0];
-                writer.float_buffer[10] = velocity_noise[1];
-                writer.float_buffer[11] = velocity_noise[2];
-            }
-            (Some(position_noise), None) => {
-                writer.float_buffer[6] = position_noise[0];
-                writer.float_buffer[7] = position_noise[1];
-                writer.float_buffer[8] = position_noise[2];
-            }
-            (None, Some(velocity_noise)) => {
-                writer.float_buffer[6] = velocity_noise[0];
-                writer.float_buffer[7] = velocity_noise[1];
-                writer.float_buffer[8] = velocity_noise[2];
+                .num_visible
+                .min(GPS_MAX_CHANNELS);
+            for channel in 0..GPS_MAX_CHANNELS {
+                writer.float_buffer[i + channel] = if channel < n {
+                    self.state
+                        .pseudorange[channel]
+                } else {
+                    f64::NAN
+                };
             }
-            _ => {}
         }
         writer
             .write_record()
@@ -387,73 +919,252 @@ impl SensorModel for Gps {
     }
 
     fn writer_headers(&self) -> &[&str] {
-        match (
-            &self
-                .parameters
-                .position_noise,
-            &self
-                .parameters
-                .velocity_noise,
-        ) {
-            (Some(_), Some(_)) => &[
-                "position[x]",
-                "position[y]",
-                "position[z]",
-                "velocity[x]",
-                "velocity[y]",
-                "velocity[z]",
-                "position_noise[x]",
-                "position_noise[y]",
-                "position_noise[z]",
-                "velocity_noise[x]",
-                "velocity_noise[y]",
-                "velocity_noise[z]",
-            ],
-            (Some(_), None) => &[
-                "position[x]",
-                "position[y]",
-                "position[z]",
-                "velocity[x]",
-                "velocity[y]",
-                "velocity[z]",
-                "position_noise[x]",
-                "position_noise[y]",
-                "position_noise[z]",
-            ],
-            (None, Some(_)) => &[
-                "position[x]",
-                "position[y]",
-                "position[z]",
-                "velocity[x]",
-                "velocity[y]",
-                "velocity[z]",
-                "velocity_noise[x]",
-                "velocity_noise[y]",
-                "velocity_noise[z]",
-            ],
-            (None, None) => &[
-                "position[x]",
-                "position[y]",
-                "position[z]",
-                "velocity[x]",
-                "velocity[y]",
-                "velocity[z]",
-            ],
-        }
+        &self.headers
+    }
+
+    fn telemetry_schema(&self) -> BufferSchema {
+        GpsTelemetry::schema()
     }
 
     fn write_buffer(&self, buffer: &mut HardwareBuffer) -> Result<(), SensorErrors> {
         buffer.write(&self.telemetry);
         Ok(())
     }
+
+    /// Fault offsets are applied to the position solution (m)
+    fn apply_fault_offset(&mut self, offset: &Vector3<f64>) {
+        self.state
+            .position += offset;
+        if self
+            .state
+            .valid
+        {
+            self.telemetry
+                .position = self
+                .state
+                .position
+                .into();
+        }
+    }
 }
 
 #[derive(Debug, Default, Copy, Clone, Pod, Zeroable)]
 #[repr(C)]
 struct GpsTelemetry {
+    /// receiver time tag, seconds since J2000 in TimeSystem::GPS
     time: f64,
     position: [f64; 3],
     velocity: [f64; 3],
+    pseudorange: [f64; GPS_MAX_CHANNELS],
+    prn: [u8; GPS_MAX_CHANNELS],
+    num_pseudoranges: u8,
     valid: u8,
-    _padding: [u8; 7],
+    _padding: [u8; 2],
+}
+
+impl GpsTelemetry {
+    fn schema() -> BufferSchema {
+        BufferSchema::new("GpsTelemetry")
+            .with_field(
+                "time",
+                FieldType::F64,
+                "s since J2000, GPS time",
+            )
+            .with_array(
+                "position",
+                FieldType::F64,
+                3,
+                "m",
+            )
+            .with_array(
+                "velocity",
+                FieldType::F64,
+                3,
+                "m/s",
+            )
+            .with_array(
+                "pseudorange",
+                FieldType::F64,
+                GPS_MAX_CHANNELS,
+                "m",
+            )
+            .with_array(
+                "prn",
+                FieldType::U8,
+                GPS_MAX_CHANNELS,
+                "",
+            )
+            .with_field(
+                "num_pseudoranges",
+                FieldType::U8,
+                "",
+            )
+            .with_field("valid", FieldType::U8, "")
+            .with_padding(2)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{SeedableRng, rngs::SmallRng};
+    use std::mem::offset_of;
+
+    const BIAS: f64 = 1e-4;
+    const DRIFT: f64 = 1e-6;
+
+    fn receiver(constellation: Option<GpsConstellation>) -> Gps {
+        let mut builder = GpsBuilder::new()
+            .with_clock_bias(BIAS)
+            .with_clock_drift(DRIFT)
+            .with_pps_period(1.0)
+            .unwrap();
+        if let Some(constellation) = constellation {
+            builder = builder.with_constellation(constellation);
+        }
+        builder
+            .sample(
+                true,
+                &mut SmallRng::seed_from_u64(0),
+            )
+            .unwrap()
+    }
+
+    #[test]
+    fn test_pps_quantization_and_clock() {
+        let mut gps = receiver(None);
+        let position = Vector3::new(7e6, 0.0, 0.0);
+        let velocity = Vector3::new(0.0, 7.5e3, 0.0);
+
+        // receiver time 0.5 + bias is in the first epoch
+        gps.measure(0.5, position, velocity);
+        let age = 0.5 + BIAS + DRIFT * 0.5;
+        assert_eq!(
+            gps.state
+                .time,
+            gps.parameters
+                .epoch
+        );
+        assert!(
+            (gps.state
+                .clock_bias
+                - (BIAS + DRIFT * 0.5))
+                .abs()
+                < 1e-15
+        );
+        assert!(
+            (gps.state
+                .position
+                - (position - velocity * age))
+                .norm()
+                < 1e-6
+        );
+
+        // held until the receiver clock reaches the next epoch
+        gps.measure(0.9, position * 2.0, velocity);
+        assert!(
+            (gps.state
+                .position
+                - (position - velocity * age))
+                .norm()
+                < 1e-6
+        );
+
+        // the receiver clock runs ahead, so the next epoch is reached before t = 1
+        let t = 1.0 - BIAS / 2.0;
+        gps.measure(t, position, velocity);
+        let age = t + BIAS + DRIFT * t - 1.0;
+        assert!(age > 0.0);
+        assert!(
+            (gps.state
+                .time
+                - gps
+                    .parameters
+                    .epoch
+                - 1.0)
+                .abs()
+                < 1e-9
+        );
+        assert!(
+            (gps.state
+                .position
+                - (position - velocity * age))
+                .norm()
+                < 1e-6
+        );
+    }
+
+    #[test]
+    fn test_pseudorange_channels() {
+        let epoch = default_epoch();
+        let constellation = GpsConstellation::nominal(epoch);
+        let mut gps = receiver(Some(constellation.clone()));
+        // far enough out that more satellites are visible than there are channels
+        let position = Vector3::new(6.4e7, 0.0, 0.0);
+        let t = 10.0 - BIAS;
+        gps.measure(t, position, Vector3::zeros());
+
+        let num_visible = gps
+            .state
+            .num_visible;
+        assert!(num_visible > GPS_MAX_CHANNELS);
+        assert!(
+            gps.state
+                .valid
+        );
+        assert_eq!(
+            gps.telemetry
+                .num_pseudoranges as usize,
+            GPS_MAX_CHANNELS
+        );
+
+        // channels are filled in order of prn with light time corrected ranges plus clock bias
+        let true_time = t - (t + BIAS + DRIFT * t - 10.0);
+        let clock_range = SPEED_OF_LIGHT * (BIAS + DRIFT * t);
+        let mut last_prn = 0;
+        for channel in 0..GPS_MAX_CHANNELS {
+            let prn = gps
+                .state
+                .prn[channel];
+            assert!(prn > last_prn);
+            last_prn = prn;
+
+            let satellite = &constellation.satellites[prn as usize - 1];
+            let mut transmit_time = epoch + true_time;
+            let mut range = 0.0;
+            for _ in 0..5 {
+                let satellite_position = satellite
+                    .keplers_problem(transmit_time)
+                    .unwrap()
+                    .get_rv()
+                    .0;
+                range = (satellite_position - position).norm();
+                transmit_time = epoch + true_time - range / SPEED_OF_LIGHT;
+            }
+            assert!(
+                (gps.state
+                    .pseudorange[channel]
+                    - range
+                    - clock_range)
+                    .abs()
+                    < 0.1
+            );
+        }
+    }
+
+    #[test]
+    fn test_telemetry_layout() {
+        GpsTelemetry::schema()
+            .check_layout::<GpsTelemetry>(&[
+                offset_of!(GpsTelemetry, time),
+                offset_of!(GpsTelemetry, position),
+                offset_of!(GpsTelemetry, velocity),
+                offset_of!(GpsTelemetry, pseudorange),
+                offset_of!(GpsTelemetry, prn),
+                offset_of!(GpsTelemetry, num_pseudoranges),
+                offset_of!(GpsTelemetry, valid),
+                offset_of!(GpsTelemetry, _padding),
+            ])
+            .unwrap();
+    }
 }
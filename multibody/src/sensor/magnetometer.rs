@@ -1,8 +1,12 @@
 use crate::{
     HardwareBuffer,
     body::BodyConnection,
-    delay::DelayedValue,
-    sensor::{SensorModel, noise::Noise},
+    delay::InterpolationMethod,
+    schema::{BufferSchema, FieldType},
+    sensor::{
+        SensorModel,
+        pipeline::{ErrorPipeline, ErrorPipelineBuilder, ErrorPipelineErrors},
+    },
 };
 use bytemuck::{Pod, Zeroable};
 use nadir_diffeq::saving::StateWriter;
@@ -10,25 +14,24 @@ use nalgebra::Vector3;
 use rotations::RotationTrait;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
-use uncertainty::{Normal, UncertainValue, Uncertainty, UncertaintyErrors};
+use uncertainty::{Uncertainty, UncertaintyErrors};
 
-use super::{
-    SensorErrors,
-    noise::{NoiseBuilder, NoiseErrors},
-};
+use super::{SensorErrors, noise::NoiseErrors};
 
 #[derive(Debug, Error)]
 pub enum MagnetometerErrors {
     #[error("{0}")]
     Noise(#[from] NoiseErrors),
     #[error("{0}")]
+    Pipeline(#[from] ErrorPipelineErrors),
+    #[error("{0}")]
     Uncertainty(#[from] UncertaintyErrors),
 }
 
 #[derive(Clone, Debug, Default, Serialize, Deserialize)]
 struct MagnetometerParametersBuilder {
-    delay: Option<UncertainValue>,
-    noise: Option<[NoiseBuilder; 3]>,
+    #[serde(default)]
+    errors: ErrorPipelineBuilder,
 }
 
 impl Uncertainty for MagnetometerParametersBuilder {
@@ -39,36 +42,20 @@ impl Uncertainty for MagnetometerParametersBuilder {
         nominal: bool,
         rng: &mut rand::prelude::SmallRng,
     ) -> Result<Self::Output, Self::Error> {
-        let delay = match &self.delay {
-            Some(delay) => {
-                let delay = delay.sample(nominal, rng);
-                Some([DelayedValue::new(delay), DelayedValue::new(delay), DelayedValue::new(delay)])
-            }
-            None => None,
-        };
-        let noise = match &self.noise {
-            Some(noise) => Some([
-                noise[0].sample(nominal, rng)?,
-                noise[1].sample(nominal, rng)?,
-                noise[2].sample(nominal, rng)?,
-            ]),
-            None => None,
-        };
-        Ok(MagnetometerParameters { delay, noise })
+        let errors = self
+            .errors
+            .sample(nominal, rng)?;
+        Ok(MagnetometerParameters { errors })
     }
 }
 
 #[derive(Debug)]
 struct MagnetometerParameters {
-    delay: Option<[DelayedValue; 3]>,
-    noise: Option<[Noise; 3]>,
+    errors: ErrorPipeline,
 }
 
-/// A simple rate sensor with gaussian white noise & constant delay
-/// The sensor frame is right hand rotation about X
-/// The transform should put the X axis of the sensor
-/// about the desired rotation axis in the body frame
-/// You can use Rotation::AlignedAxes to simplify the logic
+/// A 3 axis magnetometer with a configurable error pipeline (bias, scale factor, noise, delay, etc.)
+/// The measurement is the body's magnetic field expressed in the sensor frame
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct MagnetometerBuilder {
     parameters: MagnetometerParametersBuilder,
@@ -79,16 +66,37 @@ impl MagnetometerBuilder {
         Self { parameters: MagnetometerParametersBuilder::default() }
     }
 
+    /// Replaces the error pipeline, see ErrorPipelineBuilder for the available stages
+    pub fn with_errors(mut self, errors: ErrorPipelineBuilder) -> Self {
+        self.parameters
+            .errors = errors;
+        self
+    }
+
+    pub fn set_errors(&mut self, errors: ErrorPipelineBuilder) {
+        self.parameters
+            .errors = errors;
+    }
+
     pub fn with_delay(mut self, delay: f64) -> Self {
-        if let Some(selfdelay) = &mut self
-            .parameters
-            .delay
-        {
-            selfdelay.nominal = delay
-        } else {
-            self.parameters
-                .delay = Some(UncertainValue::new(delay));
-        }
+        self.parameters
+            .errors
+            .set_delay(delay);
+        self
+    }
+
+    /// Sets the delay, returning an error immediately if it is negative
+    pub fn try_with_delay(mut self, delay: f64) -> Result<Self, MagnetometerErrors> {
+        self.parameters
+            .errors
+            .try_set_delay(delay)?;
+        Ok(self)
+    }
+
+    pub fn with_delay_interpolation(mut self, method: InterpolationMethod) -> Self {
+        self.parameters
+            .errors
+            .set_delay_interpolation(method);
         self
     }
 
@@ -97,55 +105,32 @@ impl MagnetometerBuilder {
         mean: f64,
         std: f64,
     ) -> Result<Self, MagnetometerErrors> {
-        let dist = Normal::new(mean, std)?;
-        if let Some(delay) = &mut self
-            .parameters
-            .delay
-        {
-            delay.set_distribution(dist.into())?;
-        } else {
-            self.parameters
-                .delay = Some(UncertainValue::new(mean).with_distribution(dist.into())?);
-        }
+        self.parameters
+            .errors
+            .set_uncertain_delay_normal(mean, std)?;
         Ok(self)
     }
 
     pub fn with_noise_normal(mut self, mean: f64, std: f64) -> Self {
-        let noise1 = NoiseBuilder::new_normal(mean, std);
-        let noise2 = NoiseBuilder::new_normal(mean, std);
-        let noise3 = NoiseBuilder::new_normal(mean, std);
-        let noise = [noise1, noise2, noise3];
-        self.parameters
-            .noise = Some(noise);
+        self.set_noise_normal(mean, std);
         self
     }
 
     pub fn set_noise_normal(&mut self, mean: f64, std: f64) {
-        let noise1 = NoiseBuilder::new_normal(mean, std);
-        let noise2 = NoiseBuilder::new_normal(mean, std);
-        let noise3 = NoiseBuilder::new_normal(mean, std);
-        let noise = [noise1, noise2, noise3];
         self.parameters
-            .noise = Some(noise);
+            .errors
+            .set_noise_normal(mean, std);
     }
 
     pub fn with_noise_uniform(mut self, low: f64, high: f64) -> Self {
-        let noise1 = NoiseBuilder::new_uniform(low, high);
-        let noise2 = NoiseBuilder::new_normal(low, high);
-        let noise3 = NoiseBuilder::new_normal(low, high);
-        let noise = [noise1, noise2, noise3];
-        self.parameters
-            .noise = Some(noise);
+        self.set_noise_uniform(low, high);
         self
     }
 
     pub fn set_noise_uniform(&mut self, low: f64, high: f64) {
-        let noise1 = NoiseBuilder::new_uniform(low, high);
-        let noise2 = NoiseBuilder::new_normal(low, high);
-        let noise3 = NoiseBuilder::new_normal(low, high);
-        let noise = [noise1, noise2, noise3];
         self.parameters
-            .noise = Some(noise);
+            .errors
+            .set_noise_uniform(low, high);
     }
 }
 
@@ -169,15 +154,12 @@ impl Uncertainty for MagnetometerBuilder {
 
 #[derive(Debug, Default)]
 pub struct MagnetometerState {
-    noise: Option<Vector3<f64>>,
+    error: Option<Vector3<f64>>,
     pub measurement: Vector3<f64>,
 }
 
-/// A simple rate sensor with gaussian white noise & constant delay
-/// The sensor frame is right hand rotation about X
-/// The transform should put the X axis of the sensor
-/// about the desired rotation axis in the body frame
-/// You can use Rotation::AlignedAxes to simplify the logic
+/// A 3 axis magnetometer with a configurable error pipeline (bias, scale factor, noise, delay, etc.)
+/// The measurement is the body's magnetic field expressed in the sensor frame
 #[derive(Debug)]
 pub struct Magnetometer {
     parameters: MagnetometerParameters,
@@ -185,8 +167,6 @@ pub struct Magnetometer {
     telemetry: MagnetometerTelemetry,
 }
 
-impl Magnetometer {}
-
 impl SensorModel for Magnetometer {
     fn update(&mut self, t: f64, connection: &BodyConnection) {
         let transform = &connection.transform;
@@ -194,48 +174,30 @@ impl SensorModel for Magnetometer {
             .body
             .borrow();
 
-        let sensor_b = transform
-            .rotation
-            .transform(
-                &body
-                    .state
+        // include the field of any dipoles on the body, such as magnetic torquers
+        let sensor_position = transform
+            .translation
+            .vec();
+        let field_body = body
+            .magnetic_dipoles
+            .iter()
+            .fold(
+                body.state
                     .magnetic_field_body,
+                |field, dipole| field + dipole.field_at(&sensor_position),
             );
+        let sensor_b = transform
+            .rotation
+            .transform(&field_body);
 
-        let sensor_b = if let Some(delay) = &mut self
-            .parameters
-            .delay
-        {
-            let new_b = transform
-                .rotation
-                .transform(&sensor_b);
-            let mut delayed_b = Vector3::zeros();
-            for i in 0..3 {
-                delay[i].update(t, new_b[i]);
-                delayed_b[i] = delay[i].get_delayed_reading(t);
-            }
-            delayed_b
-        } else {
-            transform
-                .rotation
-                .transform(&sensor_b)
-        };
-
-        if let Some(noise_model) = &mut self
+        let errors = &mut self
             .parameters
-            .noise
-        {
-            let noise1 = noise_model[0].sample();
-            let noise2 = noise_model[1].sample();
-            let noise3 = noise_model[2].sample();
-            let noise = Vector3::new(noise1, noise2, noise3);
+            .errors;
+        self.state
+            .measurement = errors.apply(t, sensor_b);
+        if !errors.is_empty() {
             self.state
-                .noise = Some(noise);
-            self.state
-                .measurement = sensor_b + noise;
-        } else {
-            self.state
-                .measurement = sensor_b
+                .error = Some(errors.error);
         }
 
         // update telemetry
@@ -247,32 +209,22 @@ impl SensorModel for Magnetometer {
     }
 
     fn writer_save_fn(&self, writer: &mut StateWriter) {
-        if let Some(noise) = &self
+        writer.float_buffer[0] = self
+            .state
+            .measurement[0];
+        writer.float_buffer[1] = self
             .state
-            .noise
+            .measurement[1];
+        writer.float_buffer[2] = self
+            .state
+            .measurement[2];
+        if let Some(error) = &self
+            .state
+            .error
         {
-            writer.float_buffer[0] = self
-                .state
-                .measurement[0];
-            writer.float_buffer[1] = self
-                .state
-                .measurement[1];
-            writer.float_buffer[2] = self
-                .state
-                .measurement[2];
-            writer.float_buffer[3] = noise[0];
-            writer.float_buffer[4] = noise[1];
-            writer.float_buffer[5] = noise[2];
-        } else {
-            writer.float_buffer[0] = self
-                .state
-                .measurement[0];
-            writer.float_buffer[1] = self
-                .state
-                .measurement[1];
-            writer.float_buffer[2] = self
-                .state
-                .measurement[2];
+            writer.float_buffer[3] = error[0];
+            writer.float_buffer[4] = error[1];
+            writer.float_buffer[5] = error[2];
         }
         writer
             .write_record()
@@ -280,27 +232,42 @@ impl SensorModel for Magnetometer {
     }
 
     fn writer_headers(&self) -> &[&str] {
-        if let Some(_) = &self
+        if self
             .parameters
-            .noise
+            .errors
+            .is_empty()
         {
+            &["measurement[x]", "measurement[y]", "measurement[z]"]
+        } else {
             &[
                 "measurement[x]",
                 "measurement[y]",
                 "measurement[z]",
-                "noise[x]",
-                "noise[y]",
-                "noise[z]",
+                "error[x]",
+                "error[y]",
+                "error[z]",
             ]
-        } else {
-            &["measurement[x]", "measurement[y]", "measurement[z]"]
         }
     }
 
+    fn telemetry_schema(&self) -> BufferSchema {
+        MagnetometerTelemetry::schema()
+    }
+
     fn write_buffer(&self, buffer: &mut HardwareBuffer) -> Result<(), SensorErrors> {
         buffer.write(&self.telemetry);
         Ok(())
     }
+
+    fn apply_fault_offset(&mut self, offset: &Vector3<f64>) {
+        self.state
+            .measurement += offset;
+        self.telemetry
+            .measurement = self
+            .state
+            .measurement
+            .into();
+    }
 }
 
 #[derive(Debug, Default, Copy, Clone, Pod, Zeroable)]
@@ -308,3 +275,30 @@ impl SensorModel for Magnetometer {
 pub struct MagnetometerTelemetry {
     measurement: [f64; 3],
 }
+
+impl MagnetometerTelemetry {
+    pub fn schema() -> BufferSchema {
+        BufferSchema::new("MagnetometerTelemetry").with_array(
+            "measurement",
+            FieldType::F64,
+            3,
+            "nT",
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::mem::offset_of;
+
+    #[test]
+    fn test_telemetry_layout() {
+        MagnetometerTelemetry::schema()
+            .check_layout::<MagnetometerTelemetry>(&[offset_of!(
+                MagnetometerTelemetry,
+                measurement
+            )])
+            .unwrap();
+    }
+}
@@ -2,12 +2,15 @@ pub mod noise;
 use crate::{
     HardwareBuffer,
     body::{BodyConnection, BodyConnectionBuilder},
+    fault::{Fault, FaultModes},
+    schema::BufferSchema,
     system::Id,
 };
 
 use gps::{Gps, GpsBuilder, GpsErrors};
 use magnetometer::{Magnetometer, MagnetometerBuilder, MagnetometerErrors};
 use nadir_diffeq::saving::{StateWriter, StateWriterBuilder, WriterId, WriterManager};
+use nalgebra::Vector3;
 use rand::rngs::SmallRng;
 use rate_gyro::{RateGyro, RateGyroBuilder, RateGyroErrors};
 use serde::{Deserialize, Serialize};
@@ -19,6 +22,7 @@ use uncertainty::{Uncertainty, UncertaintyErrors};
 
 pub mod gps;
 pub mod magnetometer;
+pub mod pipeline;
 pub mod rate_gyro;
 pub mod star_tracker;
 
@@ -45,6 +49,10 @@ pub trait SensorModel {
     fn writer_headers(&self) -> &[&str];
     fn writer_save_fn(&self, writer: &mut StateWriter);
     fn write_buffer(&self, buffer: &mut HardwareBuffer) -> Result<(), SensorErrors>;
+    /// Layout of the telemetry written by write_buffer
+    fn telemetry_schema(&self) -> BufferSchema;
+    /// Adds an offset from active faults to the measurement and telemetry, in the sensor frame
+    fn apply_fault_offset(&mut self, offset: &Vector3<f64>);
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -84,6 +92,7 @@ impl SensorBuilder {
             connection,
             writer_id: None,
             telemetry_buffer: HardwareBuffer::new(),
+            faults: Vec::new(),
         })
     }
 }
@@ -94,18 +103,77 @@ pub struct Sensor {
     pub model: SensorModels,
     pub connection: BodyConnection,
     pub telemetry_buffer: HardwareBuffer,
+    /// Faults injected into this sensor, attached by the system at build time
+    pub faults: Vec<Fault>,
     writer_id: Option<WriterId>,
 }
 
 impl Sensor {
     pub fn update(&mut self, t: f64) -> Result<(), SensorErrors> {
+        let mut stuck = false;
+        let mut dropout = false;
+        let mut offset = Vector3::zeros();
+        if !self
+            .faults
+            .is_empty()
+        {
+            for fault in &mut self.faults {
+                if !fault.update(t) {
+                    continue;
+                }
+                match &mut fault.mode {
+                    FaultModes::SensorStuck => stuck = true,
+                    FaultModes::SensorDropout => dropout = true,
+                    FaultModes::SensorBiasJump(bias) => offset += *bias,
+                    FaultModes::SensorNoise(noise) => {
+                        offset += Vector3::new(
+                            noise[0].sample(),
+                            noise[1].sample(),
+                            noise[2].sample(),
+                        )
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        // a stuck sensor holds both its state and its last telemetry
+        if stuck {
+            return Ok(());
+        }
+
         self.model
             .update(t, &self.connection);
+        if offset != Vector3::zeros() {
+            self.model
+                .apply_fault_offset(&offset);
+        }
         self.model
             .write_buffer(&mut self.telemetry_buffer)?;
+        if dropout {
+            self.telemetry_buffer
+                .as_bytes_mut()
+                .fill(0);
+        }
         Ok(())
     }
 
+    /// Latches any condition triggered faults whose condition is met, called on accepted steps
+    pub fn check_fault_conditions(&mut self, t: f64) {
+        let body = self
+            .connection
+            .body
+            .borrow();
+        for fault in &mut self.faults {
+            fault.check_condition(t, &body);
+        }
+    }
+
+    pub fn telemetry_schema(&self) -> BufferSchema {
+        self.model
+            .telemetry_schema()
+    }
+
     pub fn writer_init_fn(&mut self, manager: &mut WriterManager) {
         let rel_path = PathBuf::new()
             .join("sensors")
@@ -216,6 +284,15 @@ impl SensorModel for SensorModels {
         }
     }
 
+    fn telemetry_schema(&self) -> BufferSchema {
+        match self {
+            SensorModels::Gps(sensor) => sensor.telemetry_schema(),
+            SensorModels::Magnetometer(sensor) => sensor.telemetry_schema(),
+            SensorModels::RateGyro(sensor) => sensor.telemetry_schema(),
+            SensorModels::StarTracker(sensor) => sensor.telemetry_schema(),
+        }
+    }
+
     fn write_buffer(&self, buffer: &mut HardwareBuffer) -> Result<(), SensorErrors> {
         match self {
             SensorModels::Gps(sensor) => sensor.write_buffer(buffer),
@@ -224,4 +301,13 @@ impl SensorModel for SensorModels {
             SensorModels::StarTracker(sensor) => sensor.write_buffer(buffer),
         }
     }
+
+    fn apply_fault_offset(&mut self, offset: &Vector3<f64>) {
+        match self {
+            SensorModels::Gps(sensor) => sensor.apply_fault_offset(offset),
+            SensorModels::Magnetometer(sensor) => sensor.apply_fault_offset(offset),
+            SensorModels::RateGyro(sensor) => sensor.apply_fault_offset(offset),
+            SensorModels::StarTracker(sensor) => sensor.apply_fault_offset(offset),
+        }
+    }
 }
@@ -0,0 +1,577 @@
+use crate::{
+    delay::{DelayedValue, InterpolationMethod},
+    sensor::noise::{Noise, NoiseBuilder, NoiseErrors},
+};
+use nalgebra::{Matrix3, Vector3};
+use rand::rngs::SmallRng;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use uncertainty::{Normal, UncertainValue, Uncertainty, UncertaintyErrors};
+
+#[derive(Debug, Error)]
+pub enum ErrorPipelineErrors {
+    #[error("sensor delay must be greater than or equal to 0.0")]
+    NegativeDelay,
+    #[error("{0}")]
+    Noise(#[from] NoiseErrors),
+    #[error("quantization lsb must be greater than 0.0")]
+    QuantizationNotPositive,
+    #[error("bias random walk must be greater than or equal to 0.0")]
+    RandomWalkNegative,
+    #[error("saturation lower limit must be less than the upper limit")]
+    SaturationLimits,
+    #[error("{0}")]
+    Uncertainty(#[from] UncertaintyErrors),
+}
+
+/// A single error stage of a vector sensor's error pipeline.
+/// Each parameter is an UncertainValue so it can be dispersed independently for monte carlo.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum ErrorStageBuilder {
+    /// Constant bias per axis with an optional random walk (units/sqrt(s))
+    Bias {
+        bias: [UncertainValue; 3],
+        random_walk: Option<UncertainValue>,
+    },
+    /// Relative scale factor error per axis, output = (1 + scale_factor) * input
+    ScaleFactor([UncertainValue; 3]),
+    /// Off diagonal cross-coupling terms [xy, xz, yx, yz, zx, zy], output = (I + C) * input
+    CrossCoupling([UncertainValue; 6]),
+    /// Additive white noise per axis
+    Noise([NoiseBuilder; 3]),
+    /// Rounds the output to the nearest multiple of the least significant bit
+    Quantization(UncertainValue),
+    /// Clamps the output to the lower and upper limits
+    Saturation {
+        lower: UncertainValue,
+        upper: UncertainValue,
+    },
+    /// Transport delay of the input, interpolated from the stored history
+    Delay {
+        delay: UncertainValue,
+        interpolation: InterpolationMethod,
+    },
+}
+
+impl ErrorStageBuilder {
+    fn same_kind(&self, other: &ErrorStageBuilder) -> bool {
+        std::mem::discriminant(self) == std::mem::discriminant(other)
+    }
+
+    /// Position of the stage in the pipeline, so the output doesn't depend on the order the
+    /// stages were set in. The true input is delayed, then the deterministic sensor errors are
+    /// applied, then the noise, and finally the output is digitized.
+    fn rank(&self) -> usize {
+        match self {
+            ErrorStageBuilder::Delay { .. } => 0,
+            ErrorStageBuilder::ScaleFactor(_) => 1,
+            ErrorStageBuilder::CrossCoupling(_) => 2,
+            ErrorStageBuilder::Bias { .. } => 3,
+            ErrorStageBuilder::Noise(_) => 4,
+            ErrorStageBuilder::Quantization(_) => 5,
+            ErrorStageBuilder::Saturation { .. } => 6,
+        }
+    }
+}
+
+impl Uncertainty for ErrorStageBuilder {
+    type Error = ErrorPipelineErrors;
+    type Output = ErrorStage;
+    fn sample(&self, nominal: bool, rng: &mut SmallRng) -> Result<Self::Output, Self::Error> {
+        let stage = match self {
+            ErrorStageBuilder::Bias { bias, random_walk } => {
+                let bias = Vector3::new(
+                    bias[0].sample(nominal, rng),
+                    bias[1].sample(nominal, rng),
+                    bias[2].sample(nominal, rng),
+                );
+                let random_walk = match random_walk {
+                    Some(random_walk) => {
+                        let sigma = random_walk
+                            .sample(nominal, rng)
+                            .abs();
+                        Some([
+                            NoiseBuilder::new_normal(0.0, sigma).sample(nominal, rng)?,
+                            NoiseBuilder::new_normal(0.0, sigma).sample(nominal, rng)?,
+                            NoiseBuilder::new_normal(0.0, sigma).sample(nominal, rng)?,
+                        ])
+                    }
+                    None => None,
+                };
+                ErrorStage::Bias { bias, random_walk, last_time: None }
+            }
+            ErrorStageBuilder::ScaleFactor(scale_factor) => ErrorStage::ScaleFactor(Vector3::new(
+                1.0 + scale_factor[0].sample(nominal, rng),
+                1.0 + scale_factor[1].sample(nominal, rng),
+                1.0 + scale_factor[2].sample(nominal, rng),
+            )),
+            ErrorStageBuilder::CrossCoupling(c) => {
+                let xy = c[0].sample(nominal, rng);
+                let xz = c[1].sample(nominal, rng);
+                let yx = c[2].sample(nominal, rng);
+                let yz = c[3].sample(nominal, rng);
+                let zx = c[4].sample(nominal, rng);
+                let zy = c[5].sample(nominal, rng);
+                ErrorStage::CrossCoupling(Matrix3::new(
+                    1.0, xy, xz, yx, 1.0, yz, zx, zy, 1.0,
+                ))
+            }
+            ErrorStageBuilder::Noise(noise) => ErrorStage::Noise([
+                noise[0].sample(nominal, rng)?,
+                noise[1].sample(nominal, rng)?,
+                noise[2].sample(nominal, rng)?,
+            ]),
+            ErrorStageBuilder::Quantization(lsb) => ErrorStage::Quantization(
+                lsb.sample(nominal, rng)
+                    .abs(),
+            ),
+            ErrorStageBuilder::Saturation { lower, upper } => {
+                let lower = lower.sample(nominal, rng);
+                let upper = upper.sample(nominal, rng);
+                if lower > upper {
+                    return Err(ErrorPipelineErrors::SaturationLimits);
+                }
+                ErrorStage::Saturation { lower, upper }
+            }
+            ErrorStageBuilder::Delay { delay, interpolation } => {
+                if delay.nominal < 0.0 {
+                    return Err(ErrorPipelineErrors::NegativeDelay);
+                }
+                let delay = delay
+                    .sample(nominal, rng)
+                    .max(0.0);
+                ErrorStage::Delay([
+                    DelayedValue::new(delay).with_interpolation(*interpolation),
+                    DelayedValue::new(delay).with_interpolation(*interpolation),
+                    DelayedValue::new(delay).with_interpolation(*interpolation),
+                ])
+            }
+        };
+        Ok(stage)
+    }
+}
+
+#[derive(Debug)]
+pub enum ErrorStage {
+    Bias {
+        bias: Vector3<f64>,
+        random_walk: Option<[Noise; 3]>,
+        last_time: Option<f64>,
+    },
+    ScaleFactor(Vector3<f64>),
+    CrossCoupling(Matrix3<f64>),
+    Noise([Noise; 3]),
+    Quantization(f64),
+    Saturation {
+        lower: f64,
+        upper: f64,
+    },
+    Delay([DelayedValue; 3]),
+}
+
+impl ErrorStage {
+    fn apply(&mut self, t: f64, value: Vector3<f64>) -> Vector3<f64> {
+        match self {
+            ErrorStage::Bias { bias, random_walk, last_time } => {
+                if let Some(random_walk) = random_walk {
+                    // only walk the bias forward in time, so repeated calls at the same
+                    // time (or intermediate solver stages) don't accumulate
+                    if let Some(last) = last_time {
+                        if t > *last {
+                            let sqrt_dt = (t - *last).sqrt();
+                            for i in 0..3 {
+                                bias[i] += random_walk[i].sample() * sqrt_dt;
+                            }
+                            *last_time = Some(t);
+                        }
+                    } else {
+                        *last_time = Some(t);
+                    }
+                }
+                value + *bias
+            }
+            ErrorStage::ScaleFactor(scale_factor) => value.component_mul(scale_factor),
+            ErrorStage::CrossCoupling(matrix) => *matrix * value,
+            ErrorStage::Noise(noise) => {
+                value
+                    + Vector3::new(
+                        noise[0].sample(),
+                        noise[1].sample(),
+                        noise[2].sample(),
+                    )
+            }
+            ErrorStage::Quantization(lsb) => {
+                if *lsb > 0.0 {
+                    value.map(|v| (v / *lsb).round() * *lsb)
+                } else {
+                    value
+                }
+            }
+            ErrorStage::Saturation { lower, upper } => value.map(|v| v.clamp(*lower, *upper)),
+            ErrorStage::Delay(delay) => {
+                let mut delayed = Vector3::zeros();
+                for i in 0..3 {
+                    delay[i].update(t, value[i]);
+                    delayed[i] = delay[i].get_delayed_reading(t);
+                }
+                delayed
+            }
+        }
+    }
+}
+
+/// A composable set of error stages for a 3 axis vector sensor.
+/// Stages are applied in a fixed order regardless of the order they were set in:
+/// delay, scale factor, cross-coupling, bias, noise, quantization, saturation.
+/// Setting a stage that already exists replaces it.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ErrorPipelineBuilder {
+    pub stages: Vec<ErrorStageBuilder>,
+}
+
+impl ErrorPipelineBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.stages
+            .is_empty()
+    }
+
+    pub fn with_stage(mut self, stage: ErrorStageBuilder) -> Self {
+        self.set_stage(stage);
+        self
+    }
+
+    /// Replaces a stage of the same kind if one exists, otherwise inserts the stage in order
+    pub fn set_stage(&mut self, stage: ErrorStageBuilder) {
+        if let Some(existing) = self
+            .stages
+            .iter_mut()
+            .find(|existing| existing.same_kind(&stage))
+        {
+            *existing = stage;
+        } else {
+            let index = self
+                .stages
+                .partition_point(|existing| existing.rank() <= stage.rank());
+            self.stages
+                .insert(index, stage);
+        }
+    }
+
+    fn stage_mut<F>(&mut self, f: F) -> Option<&mut ErrorStageBuilder>
+    where
+        F: Fn(&ErrorStageBuilder) -> bool,
+    {
+        self.stages
+            .iter_mut()
+            .find(|stage| f(stage))
+    }
+
+    pub fn with_bias(mut self, bias: Vector3<f64>) -> Self {
+        self.set_bias(bias);
+        self
+    }
+
+    pub fn set_bias(&mut self, bias: Vector3<f64>) {
+        let new_bias = [
+            UncertainValue::new(bias[0]),
+            UncertainValue::new(bias[1]),
+            UncertainValue::new(bias[2]),
+        ];
+        if let Some(ErrorStageBuilder::Bias { bias, .. }) = self.stage_mut(|stage| {
+            matches!(
+                stage,
+                ErrorStageBuilder::Bias { .. }
+            )
+        }) {
+            *bias = new_bias;
+        } else {
+            self.set_stage(ErrorStageBuilder::Bias { bias: new_bias, random_walk: None });
+        }
+    }
+
+    /// Disperses the bias of every axis with a normal distribution
+    pub fn with_uncertain_bias_normal(
+        mut self,
+        mean: f64,
+        std: f64,
+    ) -> Result<Self, ErrorPipelineErrors> {
+        let dist = Normal::new(mean, std)?;
+        let new_bias = [
+            UncertainValue::new(mean).with_distribution(
+                dist.clone()
+                    .into(),
+            )?,
+            UncertainValue::new(mean).with_distribution(
+                dist.clone()
+                    .into(),
+            )?,
+            UncertainValue::new(mean).with_distribution(dist.into())?,
+        ];
+        if let Some(ErrorStageBuilder::Bias { bias, .. }) = self.stage_mut(|stage| {
+            matches!(
+                stage,
+                ErrorStageBuilder::Bias { .. }
+            )
+        }) {
+            *bias = new_bias;
+        } else {
+            self.set_stage(ErrorStageBuilder::Bias { bias: new_bias, random_walk: None });
+        }
+        Ok(self)
+    }
+
+    /// Random walk of the bias in units/sqrt(s)
+    pub fn with_bias_random_walk(mut self, sigma: f64) -> Result<Self, ErrorPipelineErrors> {
+        self.set_bias_random_walk(sigma)?;
+        Ok(self)
+    }
+
+    pub fn set_bias_random_walk(&mut self, sigma: f64) -> Result<(), ErrorPipelineErrors> {
+        if sigma < 0.0 {
+            return Err(ErrorPipelineErrors::RandomWalkNegative);
+        }
+        if let Some(ErrorStageBuilder::Bias { random_walk, .. }) = self.stage_mut(|stage| {
+            matches!(
+                stage,
+                ErrorStageBuilder::Bias { .. }
+            )
+        }) {
+            *random_walk = Some(UncertainValue::new(sigma));
+        } else {
+            self.set_stage(ErrorStageBuilder::Bias {
+                bias: Default::default(),
+                random_walk: Some(UncertainValue::new(sigma)),
+            });
+        }
+        Ok(())
+    }
+
+    /// Relative scale factor error per axis
+    pub fn with_scale_factor(mut self, scale_factor: Vector3<f64>) -> Self {
+        self.set_scale_factor(scale_factor);
+        self
+    }
+
+    pub fn set_scale_factor(&mut self, scale_factor: Vector3<f64>) {
+        self.set_stage(
+            ErrorStageBuilder::ScaleFactor([
+                UncertainValue::new(scale_factor[0]),
+                UncertainValue::new(scale_factor[1]),
+                UncertainValue::new(scale_factor[2]),
+            ]),
+        );
+    }
+
+    /// Cross-coupling matrix, only the off diagonal terms are used
+    pub fn with_cross_coupling(mut self, matrix: Matrix3<f64>) -> Self {
+        self.set_cross_coupling(matrix);
+        self
+    }
+
+    pub fn set_cross_coupling(&mut self, matrix: Matrix3<f64>) {
+        self.set_stage(
+            ErrorStageBuilder::CrossCoupling([
+                UncertainValue::new(matrix[(0, 1)]),
+                UncertainValue::new(matrix[(0, 2)]),
+                UncertainValue::new(matrix[(1, 0)]),
+                UncertainValue::new(matrix[(1, 2)]),
+                UncertainValue::new(matrix[(2, 0)]),
+                UncertainValue::new(matrix[(2, 1)]),
+            ]),
+        );
+    }
+
+    pub fn with_noise_normal(mut self, mean: f64, std: f64) -> Self {
+        self.set_noise_normal(mean, std);
+        self
+    }
+
+    pub fn set_noise_normal(&mut self, mean: f64, std: f64) {
+        self.set_stage(ErrorStageBuilder::Noise([
+            NoiseBuilder::new_normal(mean, std),
+            NoiseBuilder::new_normal(mean, std),
+            NoiseBuilder::new_normal(mean, std),
+        ]));
+    }
+
+    pub fn with_noise_uniform(mut self, low: f64, high: f64) -> Self {
+        self.set_noise_uniform(low, high);
+        self
+    }
+
+    pub fn set_noise_uniform(&mut self, low: f64, high: f64) {
+        self.set_stage(ErrorStageBuilder::Noise([
+            NoiseBuilder::new_uniform(low, high),
+            NoiseBuilder::new_uniform(low, high),
+            NoiseBuilder::new_uniform(low, high),
+        ]));
+    }
+
+    pub fn with_quantization(mut self, lsb: f64) -> Result<Self, ErrorPipelineErrors> {
+        self.set_quantization(lsb)?;
+        Ok(self)
+    }
+
+    pub fn set_quantization(&mut self, lsb: f64) -> Result<(), ErrorPipelineErrors> {
+        if lsb <= 0.0 {
+            return Err(ErrorPipelineErrors::QuantizationNotPositive);
+        }
+        self.set_stage(ErrorStageBuilder::Quantization(UncertainValue::new(lsb)));
+        Ok(())
+    }
+
+    pub fn with_saturation(mut self, lower: f64, upper: f64) -> Result<Self, ErrorPipelineErrors> {
+        self.set_saturation(lower, upper)?;
+        Ok(self)
+    }
+
+    pub fn set_saturation(&mut self, lower: f64, upper: f64) -> Result<(), ErrorPipelineErrors> {
+        if lower >= upper {
+            return Err(ErrorPipelineErrors::SaturationLimits);
+        }
+        self.set_stage(
+            ErrorStageBuilder::Saturation {
+                lower: UncertainValue::new(lower),
+                upper: UncertainValue::new(upper),
+            },
+        );
+        Ok(())
+    }
+
+    /// Sets the delay, a negative delay is reported when the pipeline is sampled
+    pub fn with_delay(mut self, delay: f64) -> Self {
+        self.set_delay(delay);
+        self
+    }
+
+    /// Sets the delay, returning an error immediately if it is negative
+    pub fn try_with_delay(mut self, delay: f64) -> Result<Self, ErrorPipelineErrors> {
+        self.try_set_delay(delay)?;
+        Ok(self)
+    }
+
+    pub fn try_set_delay(&mut self, delay: f64) -> Result<(), ErrorPipelineErrors> {
+        if delay < 0.0 {
+            return Err(ErrorPipelineErrors::NegativeDelay);
+        }
+        self.set_delay(delay);
+        Ok(())
+    }
+
+    /// Sets the delay, keeping the interpolation method if a delay stage already exists
+    pub fn set_delay(&mut self, delay: f64) {
+        if let Some(ErrorStageBuilder::Delay { delay: selfdelay, .. }) = self.stage_mut(|stage| {
+            matches!(
+                stage,
+                ErrorStageBuilder::Delay { .. }
+            )
+        }) {
+            selfdelay.nominal = delay;
+        } else {
+            self.set_stage(ErrorStageBuilder::Delay {
+                delay: UncertainValue::new(delay),
+                interpolation: InterpolationMethod::Linear,
+            });
+        }
+    }
+
+    pub fn with_uncertain_delay_normal(
+        mut self,
+        mean: f64,
+        std: f64,
+    ) -> Result<Self, ErrorPipelineErrors> {
+        self.set_uncertain_delay_normal(mean, std)?;
+        Ok(self)
+    }
+
+    pub fn set_uncertain_delay_normal(
+        &mut self,
+        mean: f64,
+        std: f64,
+    ) -> Result<(), ErrorPipelineErrors> {
+        let dist = Normal::new(mean, std)?;
+        if let Some(ErrorStageBuilder::Delay { delay, .. }) = self.stage_mut(|stage| {
+            matches!(
+                stage,
+                ErrorStageBuilder::Delay { .. }
+            )
+        }) {
+            delay.set_distribution(dist.into())?;
+        } else {
+            self.set_stage(ErrorStageBuilder::Delay {
+                delay: UncertainValue::new(mean).with_distribution(dist.into())?,
+                interpolation: InterpolationMethod::Linear,
+            });
+        }
+        Ok(())
+    }
+
+    pub fn with_delay_interpolation(mut self, method: InterpolationMethod) -> Self {
+        self.set_delay_interpolation(method);
+        self
+    }
+
+    /// Sets the interpolation method of the delay stage, adding a zero delay stage if needed
+    pub fn set_delay_interpolation(&mut self, method: InterpolationMethod) {
+        if let Some(ErrorStageBuilder::Delay { interpolation, .. }) = self.stage_mut(|stage| {
+            matches!(
+                stage,
+                ErrorStageBuilder::Delay { .. }
+            )
+        }) {
+            *interpolation = method;
+        } else {
+            self.set_stage(ErrorStageBuilder::Delay {
+                delay: UncertainValue::new(0.0),
+                interpolation: method,
+            });
+        }
+    }
+}
+
+impl Uncertainty for ErrorPipelineBuilder {
+    type Error = ErrorPipelineErrors;
+    type Output = ErrorPipeline;
+    fn sample(&self, nominal: bool, rng: &mut SmallRng) -> Result<Self::Output, Self::Error> {
+        // stages is public and may be deserialized in any order, so sort a copy by rank
+        let mut builders: Vec<&ErrorStageBuilder> = self
+            .stages
+            .iter()
+            .collect();
+        builders.sort_by_key(|stage| stage.rank());
+        let mut stages = Vec::with_capacity(builders.len());
+        for stage in builders {
+            stages.push(stage.sample(nominal, rng)?);
+        }
+        Ok(ErrorPipeline { stages, error: Vector3::zeros() })
+    }
+}
+
+/// A composable set of error stages for a 3 axis vector sensor, in the order they are applied
+#[derive(Debug, Default)]
+pub struct ErrorPipeline {
+    pub stages: Vec<ErrorStage>,
+    /// total error of the last output, output - input
+    pub error: Vector3<f64>,
+}
+
+impl ErrorPipeline {
+    pub fn is_empty(&self) -> bool {
+        self.stages
+            .is_empty()
+    }
+
+    /// Passes the true value through each stage in order, returning the measured value
+    pub fn apply(&mut self, t: f64, value: Vector3<f64>) -> Vector3<f64> {
+        let mut output = value;
+        for stage in &mut self.stages {
+            output = stage.apply(t, output);
+        }
+        self.error = output - value;
+        output
+    }
+}
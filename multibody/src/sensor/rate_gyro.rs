@@ -1,8 +1,12 @@
 use crate::{
     HardwareBuffer,
     body::BodyConnection,
-    delay::DelayedValue,
-    sensor::{SensorModel, noise::Noise},
+    delay::InterpolationMethod,
+    schema::{BufferSchema, FieldType},
+    sensor::{
+        SensorModel,
+        pipeline::{ErrorPipeline, ErrorPipelineBuilder, ErrorPipelineErrors},
+    },
 };
 use bytemuck::{Pod, Zeroable};
 use nadir_diffeq::saving::StateWriter;
@@ -11,25 +15,24 @@ use rotations::RotationTrait;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
-use uncertainty::{Normal, UncertainValue, Uncertainty, UncertaintyErrors};
+use uncertainty::{Uncertainty, UncertaintyErrors};
 
-use super::{
-    SensorErrors,
-    noise::{NoiseBuilder, NoiseErrors},
-};
+use super::{SensorErrors, noise::NoiseErrors};
 
 #[derive(Debug, Error)]
 pub enum RateGyroErrors {
     #[error("{0}")]
     Noise(#[from] NoiseErrors),
     #[error("{0}")]
+    Pipeline(#[from] ErrorPipelineErrors),
+    #[error("{0}")]
     Uncertainty(#[from] UncertaintyErrors),
 }
 
 #[derive(Clone, Debug, Default, Serialize, Deserialize)]
 struct RateGyroParametersBuilder {
-    delay: Option<UncertainValue>,
-    noise: Option<[NoiseBuilder; 3]>,
+    #[serde(default)]
+    errors: ErrorPipelineBuilder,
 }
 
 impl Uncertainty for RateGyroParametersBuilder {
@@ -40,39 +43,24 @@ impl Uncertainty for RateGyroParametersBuilder {
         nominal: bool,
         rng: &mut rand::prelude::SmallRng,
     ) -> Result<Self::Output, Self::Error> {
-        let delay = match &self.delay {
-            Some(delay) => Some([
-                DelayedValue::new(delay.sample(nominal, rng)),
-                DelayedValue::new(delay.sample(nominal, rng)),
-                DelayedValue::new(delay.sample(nominal, rng)),
-            ]),
-            None => None,
-        };
-        let noise = match &self.noise {
-            Some(noise) => Some([
-                noise[0].sample(nominal, rng)?,
-                noise[1].sample(nominal, rng)?,
-                noise[2].sample(nominal, rng)?,
-            ]),
-            None => None,
-        };
-
-        Ok(RateGyroParameters { delay, noise })
+        let errors = self
+            .errors
+            .sample(nominal, rng)?;
+        Ok(RateGyroParameters { errors })
     }
 }
 #[derive(Debug)]
 struct RateGyroParameters {
-    delay: Option<[DelayedValue; 3]>,
-    noise: Option<[Noise; 3]>,
+    errors: ErrorPipeline,
 }
 
 #[derive(Debug, Default)]
 pub struct RateGyroState {
-    noise: Option<Vector3<f64>>,
+    error: Option<Vector3<f64>>,
     pub measurement: Vector3<f64>,
 }
 
-/// A simple rate sensor with gaussian white noise & constant delay
+/// A rate sensor with a configurable error pipeline (bias, scale factor, noise, delay, etc.)
 /// The sensor frame is right hand rotation about X
 /// The transform should put the X axis of the sensor
 /// about the desired rotation axis in the body frame
@@ -87,16 +75,37 @@ impl RateGyroBuilder {
         RateGyroBuilder { parameters: RateGyroParametersBuilder::default() }
     }
 
+    /// Replaces the error pipeline, see ErrorPipelineBuilder for the available stages
+    pub fn with_errors(mut self, errors: ErrorPipelineBuilder) -> Self {
+        self.parameters
+            .errors = errors;
+        self
+    }
+
+    pub fn set_errors(&mut self, errors: ErrorPipelineBuilder) {
+        self.parameters
+            .errors = errors;
+    }
+
     pub fn with_delay(mut self, delay: f64) -> Self {
-        if let Some(selfdelay) = &mut self
-            .parameters
-            .delay
-        {
-            selfdelay.nominal = delay
-        } else {
-            self.parameters
-                .delay = Some(UncertainValue::new(delay));
-        }
+        self.parameters
+            .errors
+            .set_delay(delay);
+        self
+    }
+
+    /// Sets the delay, returning an error immediately if it is negative
+    pub fn try_with_delay(mut self, delay: f64) -> Result<Self, RateGyroErrors> {
+        self.parameters
+            .errors
+            .try_set_delay(delay)?;
+        Ok(self)
+    }
+
+    pub fn with_delay_interpolation(mut self, method: InterpolationMethod) -> Self {
+        self.parameters
+            .errors
+            .set_delay_interpolation(method);
         self
     }
 
@@ -105,55 +114,32 @@ impl RateGyroBuilder {
         mean: f64,
         std: f64,
     ) -> Result<Self, RateGyroErrors> {
-        let dist = Normal::new(mean, std)?;
-        if let Some(delay) = &mut self
-            .parameters
-            .delay
-        {
-            delay.set_distribution(dist.into())?;
-        } else {
-            self.parameters
-                .delay = Some(UncertainValue::new(mean).with_distribution(dist.into())?);
-        }
+        self.parameters
+            .errors
+            .set_uncertain_delay_normal(mean, std)?;
         Ok(self)
     }
 
     pub fn with_noise_normal(mut self, mean: f64, std: f64) -> Self {
-        let noise1 = NoiseBuilder::new_normal(mean, std);
-        let noise2 = NoiseBuilder::new_normal(mean, std);
-        let noise3 = NoiseBuilder::new_normal(mean, std);
-        let noise = [noise1, noise2, noise3];
-        self.parameters
-            .noise = Some(noise);
+        self.set_noise_normal(mean, std);
         self
     }
 
     pub fn set_noise_normal(&mut self, mean: f64, std: f64) {
-        let noise1 = NoiseBuilder::new_normal(mean, std);
-        let noise2 = NoiseBuilder::new_normal(mean, std);
-        let noise3 = NoiseBuilder::new_normal(mean, std);
-        let noise = [noise1, noise2, noise3];
         self.parameters
-            .noise = Some(noise);
+            .errors
+            .set_noise_normal(mean, std);
     }
 
     pub fn with_noise_uniform(mut self, low: f64, high: f64) -> Self {
-        let noise1 = NoiseBuilder::new_uniform(low, high);
-        let noise2 = NoiseBuilder::new_uniform(low, high);
-        let noise3 = NoiseBuilder::new_uniform(low, high);
-        let noise = [noise1, noise2, noise3];
-        self.parameters
-            .noise = Some(noise);
+        self.set_noise_uniform(low, high);
         self
     }
 
     pub fn set_noise_uniform(&mut self, low: f64, high: f64) {
-        let noise1 = NoiseBuilder::new_uniform(low, high);
-        let noise2 = NoiseBuilder::new_uniform(low, high);
-        let noise3 = NoiseBuilder::new_uniform(low, high);
-        let noise = [noise1, noise2, noise3];
         self.parameters
-            .noise = Some(noise);
+            .errors
+            .set_noise_uniform(low, high);
     }
 }
 
@@ -175,7 +161,7 @@ impl Uncertainty for RateGyroBuilder {
     }
 }
 
-/// A simple rate sensor with gaussian white noise & constant delay
+/// A rate sensor with a configurable error pipeline (bias, scale factor, noise, delay, etc.)
 /// The sensor frame is right hand rotation about X
 /// The transform should put the X axis of the sensor
 /// about the desired rotation axis in the body frame
@@ -193,42 +179,22 @@ impl SensorModel for RateGyro {
             .body
             .borrow();
         let transform = &connection.transform;
-        let body_rate = body
-            .state
-            .angular_rate_body;
-        let sensor_rate = if let Some(delay) = &mut self
-            .parameters
-            .delay
-        {
-            let new_rate = transform
-                .rotation
-                .transform(&body_rate);
-            let mut delayed_rate = Vector3::zeros();
-            for i in 0..3 {
-                delay[i].update(t, new_rate[i]);
-                delayed_rate[i] = delay[i].get_delayed_reading(t);
-            }
-            delayed_rate
-        } else {
-            transform
-                .rotation
-                .transform(&body_rate)
-        };
-        if let Some(noise_model) = &mut self
+        let sensor_rate = transform
+            .rotation
+            .transform(
+                &body
+                    .state
+                    .angular_rate_body,
+            );
+
+        let errors = &mut self
             .parameters
-            .noise
-        {
-            let noise1 = noise_model[0].sample();
-            let noise2 = noise_model[1].sample();
-            let noise3 = noise_model[2].sample();
-            let noise = Vector3::new(noise1, noise2, noise3);
-            self.state
-                .noise = Some(noise);
-            self.state
-                .measurement = sensor_rate + noise;
-        } else {
+            .errors;
+        self.state
+            .measurement = errors.apply(t, sensor_rate);
+        if !errors.is_empty() {
             self.state
-                .measurement = sensor_rate;
+                .error = Some(errors.error);
         }
 
         //update telemetry
@@ -251,13 +217,13 @@ impl SensorModel for RateGyro {
         writer.float_buffer[2] = self
             .state
             .measurement[2];
-        if let Some(noise) = &self
+        if let Some(error) = &self
             .state
-            .noise
+            .error
         {
-            writer.float_buffer[3] = noise[0];
-            writer.float_buffer[4] = noise[1];
-            writer.float_buffer[5] = noise[2];
+            writer.float_buffer[3] = error[0];
+            writer.float_buffer[4] = error[1];
+            writer.float_buffer[5] = error[2];
         }
         writer
             .write_record()
@@ -265,27 +231,42 @@ impl SensorModel for RateGyro {
     }
 
     fn writer_headers(&self) -> &[&str] {
-        if let Some(_) = &self
+        if self
             .parameters
-            .noise
+            .errors
+            .is_empty()
         {
+            &["measurement[x]", "measurement[y]", "measurement[z]"]
+        } else {
             &[
                 "measurement[x]",
                 "measurement[y]",
                 "measurement[z]",
-                "noise[x]",
-                "noise[y]",
-                "noise[z]",
+                "error[x]",
+                "error[y]",
+                "error[z]",
             ]
-        } else {
-            &["measurement[x]", "measurement[y]", "measurement[z]"]
         }
     }
 
+    fn telemetry_schema(&self) -> BufferSchema {
+        RateGyroTelemetry::schema()
+    }
+
     fn write_buffer(&self, buffer: &mut HardwareBuffer) -> Result<(), SensorErrors> {
         buffer.write(&self.telemetry);
         Ok(())
     }
+
+    fn apply_fault_offset(&mut self, offset: &Vector3<f64>) {
+        self.state
+            .measurement += offset;
+        self.telemetry
+            .measurement = self
+            .state
+            .measurement
+            .into();
+    }
 }
 
 #[derive(Debug, Default, Copy, Clone, Pod, Zeroable)]
@@ -295,3 +276,34 @@ pub struct RateGyroTelemetry {
     valid: u8,
     _padding: [u8; 7],
 }
+
+impl RateGyroTelemetry {
+    pub fn schema() -> BufferSchema {
+        BufferSchema::new("RateGyroTelemetry")
+            .with_array(
+                "measurement",
+                FieldType::F64,
+                3,
+                "rad/s",
+            )
+            .with_field("valid", FieldType::U8, "")
+            .with_padding(7)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::mem::offset_of;
+
+    #[test]
+    fn test_telemetry_layout() {
+        RateGyroTelemetry::schema()
+            .check_layout::<RateGyroTelemetry>(&[
+                offset_of!(RateGyroTelemetry, measurement),
+                offset_of!(RateGyroTelemetry, valid),
+                offset_of!(RateGyroTelemetry, _padding),
+            ])
+            .unwrap();
+    }
+}
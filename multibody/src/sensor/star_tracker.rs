@@ -6,11 +6,16 @@ use crate::{
     HardwareBuffer,
     body::BodyConnection,
     delay::DelayedQuaternion,
+    schema::{BufferSchema, FieldType},
     sensor::{SensorModel, noise::QuaternionNoise},
 };
 use bytemuck::{Pod, Zeroable};
 use nadir_diffeq::saving::StateWriter;
-use rotations::prelude::{QuaternionErrors, UnitQuaternion, UnitQuaternionBuilder};
+use nalgebra::Vector3;
+use rotations::{
+    axis_angle::AxisAngle,
+    prelude::{QuaternionErrors, UnitQuaternion, UnitQuaternionBuilder},
+};
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 use uncertainty::{UncertainValue, Uncertainty, UncertaintyErrors};
@@ -267,10 +272,35 @@ impl SensorModel for StarTracker {
         }
     }
 
+    fn telemetry_schema(&self) -> BufferSchema {
+        StarTrackerTelemetry::schema()
+    }
+
     fn write_buffer(&self, buffer: &mut HardwareBuffer) -> Result<(), SensorErrors> {
         buffer.write(&self.telemetry);
         Ok(())
     }
+
+    /// Fault offsets are applied as a small angle rotation (rad) in the sensor frame
+    fn apply_fault_offset(&mut self, offset: &Vector3<f64>) {
+        let angle = offset.norm();
+        if angle == 0.0 {
+            return;
+        }
+        // unwrap should be safe since angle is nonzero
+        let axis_angle = AxisAngle::new(angle, offset / angle).unwrap();
+        self.state
+            .measurement = UnitQuaternion::from(&axis_angle)
+            * self
+                .state
+                .measurement;
+        let q = &self
+            .state
+            .measurement
+            .0;
+        self.telemetry
+            .q = [q.x, q.y, q.z, q.w];
+    }
 }
 
 #[derive(Debug, Default, Copy, Clone, Pod, Zeroable)]
@@ -280,3 +310,34 @@ pub struct StarTrackerTelemetry {
     valid: u8,
     _padding: [u8; 7],
 }
+
+impl StarTrackerTelemetry {
+    pub fn schema() -> BufferSchema {
+        BufferSchema::new("StarTrackerTelemetry")
+            .with_array(
+                "q",
+                FieldType::F64,
+                4,
+                "[x, y, z, w]",
+            )
+            .with_field("valid", FieldType::U8, "")
+            .with_padding(7)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::mem::offset_of;
+
+    #[test]
+    fn test_telemetry_layout() {
+        StarTrackerTelemetry::schema()
+            .check_layout::<StarTrackerTelemetry>(&[
+                offset_of!(StarTrackerTelemetry, q),
+                offset_of!(StarTrackerTelemetry, valid),
+                offset_of!(StarTrackerTelemetry, _padding),
+            ])
+            .unwrap();
+    }
+}
@@ -0,0 +1,521 @@
+//! Flight software run in a separate process, exchanging buffers with the sim over a unix domain socket.
+//!
+//! The protocol is lockstep: the sim sends a message and blocks until the software answers.
+//! All integers are little endian.
+//!
+//! sim -> software
+//! - INIT: `[1u8][len: u32][ron encoded (sensor schemas, actuator schemas)]`
+//! - STEP: `[2u8][sensor count: u32][actuator count: u32]` followed by each sensor buffer as `[len: u32][bytes]`
+//! - SHUTDOWN: `[3u8]`
+//!
+//! software -> sim
+//! - OK: `[4u8][actuator count: u32]` followed by each actuator buffer as `[len: u32][bytes]`,
+//!   the count is 0 in response to INIT
+//! - ERROR: `[5u8][len: u32][utf8 message]`
+//!
+//! Rust flight software can implement [`FlightSoftware`] and call [`serve`] from its main function.
+
+use super::{FlightSoftware, ProcessSoftware, SoftwareErrors};
+use crate::{HardwareBuffer, schema::BufferSchema};
+use std::{
+    env,
+    io::{ErrorKind, Read, Write},
+    os::unix::net::{UnixListener, UnixStream},
+    path::{Path, PathBuf},
+    process::{Child, Command},
+    sync::atomic::{AtomicUsize, Ordering},
+    thread::sleep,
+    time::{Duration, Instant},
+};
+
+/// Environment variable holding the socket path when the sim spawns the software
+pub const SOCKET_ENV: &str = "NADIR_SOFTWARE_SOCKET";
+
+const MSG_INIT: u8 = 1;
+const MSG_STEP: u8 = 2;
+const MSG_SHUTDOWN: u8 = 3;
+const MSG_OK: u8 = 4;
+const MSG_ERROR: u8 = 5;
+
+/// Makes socket paths unique when several sims run in the same process, like in monte carlo
+static SOCKET_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+/// The sim side of the connection
+#[derive(Debug)]
+pub(super) struct ProcessRuntime {
+    name: String,
+    stream: UnixStream,
+    child: Option<Child>,
+    socket_path: PathBuf,
+}
+
+impl ProcessRuntime {
+    pub(super) fn new(software: &ProcessSoftware) -> Result<Self, SoftwareErrors> {
+        let socket_path = match &software.socket_path {
+            Some(path) => PathBuf::from(path),
+            None => env::temp_dir().join(format!(
+                "nadir_{}_{}_{}.sock",
+                std::process::id(),
+                software.name,
+                SOCKET_COUNT.fetch_add(1, Ordering::Relaxed)
+            )),
+        };
+        // a stale socket from a previous run would make bind fail
+        if socket_path.exists() {
+            std::fs::remove_file(&socket_path).map_err(|e| process_error(&software.name, e))?;
+        }
+        let listener =
+            UnixListener::bind(&socket_path).map_err(|e| process_error(&software.name, e))?;
+
+        let mut child = match &software.executable {
+            Some(executable) => Some(
+                Command::new(executable)
+                    .args(&software.args)
+                    .env(SOCKET_ENV, &socket_path)
+                    .spawn()
+                    .map_err(|e| process_error(&software.name, e))?,
+            ),
+            None => None,
+        };
+
+        let stream = accept(
+            &software.name,
+            &listener,
+            &mut child,
+            software.timeout,
+        );
+        // the listener is no longer needed once connected, only one process per socket
+        drop(listener);
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(e) => {
+                if let Some(child) = &mut child {
+                    let _ = child.kill();
+                    let _ = child.wait();
+                }
+                let _ = std::fs::remove_file(&socket_path);
+                return Err(e);
+            }
+        };
+        let timeout = Some(Duration::from_secs_f64(
+            software.timeout,
+        ));
+        stream
+            .set_read_timeout(timeout)
+            .map_err(|e| process_error(&software.name, e))?;
+        stream
+            .set_write_timeout(timeout)
+            .map_err(|e| process_error(&software.name, e))?;
+
+        Ok(Self {
+            name: software
+                .name
+                .clone(),
+            stream,
+            child,
+            socket_path,
+        })
+    }
+
+    /// Sends the schemas so the software can check them, see FlightSoftware::check_schemas
+    pub(super) fn check_schemas(
+        &mut self,
+        sensors: &[BufferSchema],
+        actuators: &[BufferSchema],
+    ) -> Result<(), SoftwareErrors> {
+        let schemas = ron::to_string(&(sensors, actuators)).map_err(|e| {
+            SoftwareErrors::ProcessError(
+                self.name
+                    .clone(),
+                e.to_string(),
+            )
+        })?;
+        let mut message = vec![MSG_INIT];
+        write_bytes(
+            &mut message,
+            schemas.as_bytes(),
+        );
+        self.send(&message)?;
+        self.receive(&mut [])
+    }
+
+    pub(super) fn step(
+        &mut self,
+        sensors: &[HardwareBuffer],
+        actuators: &mut [HardwareBuffer],
+    ) -> Result<(), SoftwareErrors> {
+        let mut message = vec![MSG_STEP];
+        message.extend_from_slice(&(sensors.len() as u32).to_le_bytes());
+        message.extend_from_slice(&(actuators.len() as u32).to_le_bytes());
+        for buffer in sensors {
+            write_bytes(
+                &mut message,
+                buffer.as_bytes(),
+            );
+        }
+        self.send(&message)?;
+        self.receive(actuators)
+    }
+
+    fn send(&mut self, message: &[u8]) -> Result<(), SoftwareErrors> {
+        self.stream
+            .write_all(message)
+            .map_err(|e| self.connection_error(e))
+    }
+
+    /// Reads the response to the last message, copying any buffers into actuators
+    fn receive(&mut self, actuators: &mut [HardwareBuffer]) -> Result<(), SoftwareErrors> {
+        let mut stream = &self.stream;
+        let result = (|| -> std::io::Result<Result<(), SoftwareErrors>> {
+            match read_u8(&mut stream)? {
+                MSG_OK => {
+                    let count = read_u32(&mut stream)? as usize;
+                    if count != actuators.len() {
+                        return Ok(Err(
+                            SoftwareErrors::ProcessError(
+                                self.name
+                                    .clone(),
+                                format!(
+                                    "expected {} actuator buffers but received {}",
+                                    actuators.len(),
+                                    count
+                                ),
+                            ),
+                        ));
+                    }
+                    for buffer in actuators.iter_mut() {
+                        let bytes = read_bytes(&mut stream)?;
+                        if bytes.len() > buffer.capacity() {
+                            return Ok(Err(
+                                SoftwareErrors::ProcessError(
+                                    self.name
+                                        .clone(),
+                                    format!(
+                                        "received a {} byte actuator buffer",
+                                        bytes.len()
+                                    ),
+                                ),
+                            ));
+                        }
+                        buffer.write_bytes(&bytes);
+                    }
+                    Ok(Ok(()))
+                }
+                MSG_ERROR => {
+                    let bytes = read_bytes(&mut stream)?;
+                    Ok(Err(
+                        SoftwareErrors::FlightSoftwareError(
+                            String::from_utf8_lossy(&bytes).to_string(),
+                        ),
+                    ))
+                }
+                other => Ok(Err(
+                    SoftwareErrors::ProcessError(
+                        self.name
+                            .clone(),
+                        format!(
+                            "unexpected message type {}",
+                            other
+                        ),
+                    ),
+                )),
+            }
+        })();
+        result.map_err(|e| self.connection_error(e))?
+    }
+
+    /// A broken connection usually means the process died, so report its exit status if it has one
+    fn connection_error(&mut self, e: std::io::Error) -> SoftwareErrors {
+        if let Some(child) = &mut self.child {
+            // give the process a moment to finish exiting after closing its socket
+            let deadline = Instant::now() + Duration::from_millis(100);
+            while Instant::now() < deadline {
+                if let Ok(Some(status)) = child.try_wait() {
+                    return SoftwareErrors::ProcessExited(
+                        self.name
+                            .clone(),
+                        status.to_string(),
+                    );
+                }
+                sleep(Duration::from_millis(5));
+            }
+        }
+        SoftwareErrors::ProcessError(
+            self.name
+                .clone(),
+            e.to_string(),
+        )
+    }
+}
+
+impl Drop for ProcessRuntime {
+    fn drop(&mut self) {
+        let _ = self
+            .stream
+            .write_all(&[MSG_SHUTDOWN]);
+        if let Some(child) = &mut self.child {
+            let deadline = Instant::now() + Duration::from_secs(1);
+            while Instant::now() < deadline {
+                if let Ok(Some(_)) = child.try_wait() {
+                    break;
+                }
+                sleep(Duration::from_millis(5));
+            }
+            if let Ok(None) = child.try_wait() {
+                let _ = child.kill();
+                let _ = child.wait();
+            }
+        }
+        let _ = std::fs::remove_file(&self.socket_path);
+    }
+}
+
+/// Waits for the software to connect, failing early if a spawned process exits first
+fn accept(
+    name: &str,
+    listener: &UnixListener,
+    child: &mut Option<Child>,
+    timeout: f64,
+) -> Result<UnixStream, SoftwareErrors> {
+    listener
+        .set_nonblocking(true)
+        .map_err(|e| process_error(name, e))?;
+    // software started by hand, for example under a debugger, gets as long as it needs
+    let deadline = child
+        .as_ref()
+        .map(|_| Instant::now() + Duration::from_secs_f64(timeout));
+    loop {
+        match listener.accept() {
+            Ok((stream, _)) => {
+                stream
+                    .set_nonblocking(false)
+                    .map_err(|e| process_error(name, e))?;
+                return Ok(stream);
+            }
+            Err(e) if e.kind() == ErrorKind::WouldBlock => {}
+            Err(e) => return Err(process_error(name, e)),
+        }
+        if let Some(child) = child
+            && let Ok(Some(status)) = child.try_wait()
+        {
+            return Err(SoftwareErrors::ProcessExited(
+                name.to_string(),
+                status.to_string(),
+            ));
+        }
+        if let Some(deadline) = deadline
+            && Instant::now() > deadline
+        {
+            return Err(SoftwareErrors::ProcessError(
+                name.to_string(),
+                "timed out waiting for the process to connect".to_string(),
+            ));
+        }
+        sleep(Duration::from_millis(5));
+    }
+}
+
+/// Runs flight software as the process side of the connection, until the sim shuts it down.
+/// The socket path is read from NADIR_SOFTWARE_SOCKET, which the sim sets when it spawns the process.
+pub fn serve<S: FlightSoftware>(software: S) -> Result<(), SoftwareErrors> {
+    let path = env::var(SOCKET_ENV).map_err(|_| {
+        SoftwareErrors::ProcessError(
+            "serve".to_string(),
+            format!("{} is not set", SOCKET_ENV),
+        )
+    })?;
+    serve_at(software, path)
+}
+
+/// Like serve, but connects to a given socket, for running the software by hand against a waiting sim
+pub fn serve_at<S: FlightSoftware, P: AsRef<Path>>(
+    mut software: S,
+    path: P,
+) -> Result<(), SoftwareErrors> {
+    let io_error = |e: std::io::Error| {
+        SoftwareErrors::ProcessError(
+            "serve".to_string(),
+            e.to_string(),
+        )
+    };
+    let mut stream = UnixStream::connect(path).map_err(io_error)?;
+
+    loop {
+        let message = match read_u8(&mut stream) {
+            Ok(message) => message,
+            // the sim closed the connection without a shutdown, e.g. it was stopped
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(()),
+            Err(e) => return Err(io_error(e)),
+        };
+
+        let result = match message {
+            MSG_INIT => {
+                let bytes = read_bytes(&mut stream).map_err(io_error)?;
+                match ron::from_str::<(
+                    Vec<BufferSchema>,
+                    Vec<BufferSchema>,
+                )>(&String::from_utf8_lossy(
+                    &bytes,
+                )) {
+                    Ok((sensors, actuators)) => software
+                        .check_schemas(&sensors, &actuators)
+                        .map(|_| Vec::new())
+                        .map_err(|e| e.to_string()),
+                    Err(e) => Err(e.to_string()),
+                }
+            }
+            MSG_STEP => {
+                let sensor_count = read_u32(&mut stream).map_err(io_error)? as usize;
+                let actuator_count = read_u32(&mut stream).map_err(io_error)? as usize;
+                let mut sensors = vec![HardwareBuffer::new(); sensor_count];
+                for buffer in &mut sensors {
+                    let bytes = read_bytes(&mut stream).map_err(io_error)?;
+                    if bytes.len() > buffer.capacity() {
+                        return Err(io_error(std::io::Error::new(
+                            ErrorKind::InvalidData,
+                            format!(
+                                "received a {} byte sensor buffer",
+                                bytes.len()
+                            ),
+                        )));
+                    }
+                    buffer.write_bytes(&bytes);
+                }
+                let mut actuators = vec![HardwareBuffer::new(); actuator_count];
+                software
+                    .step(&sensors, &mut actuators)
+                    .map(|_| actuators)
+                    .map_err(|e| e.to_string())
+            }
+            MSG_SHUTDOWN => return Ok(()),
+            other => Err(format!(
+                "unexpected message type {}",
+                other
+            )),
+        };
+
+        let mut response = Vec::new();
+        match result {
+            Ok(actuators) => {
+                response.push(MSG_OK);
+                response.extend_from_slice(&(actuators.len() as u32).to_le_bytes());
+                for buffer in &actuators {
+                    write_bytes(
+                        &mut response,
+                        buffer.as_bytes(),
+                    );
+                }
+            }
+            Err(message) => {
+                response.push(MSG_ERROR);
+                write_bytes(
+                    &mut response,
+                    message.as_bytes(),
+                );
+            }
+        }
+        stream
+            .write_all(&response)
+            .map_err(io_error)?;
+    }
+}
+
+fn process_error(name: &str, e: std::io::Error) -> SoftwareErrors {
+    SoftwareErrors::ProcessError(
+        name.to_string(),
+        e.to_string(),
+    )
+}
+
+fn write_bytes(message: &mut Vec<u8>, bytes: &[u8]) {
+    message.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+    message.extend_from_slice(bytes);
+}
+
+fn read_u8(stream: &mut impl Read) -> std::io::Result<u8> {
+    let mut byte = [0u8; 1];
+    stream.read_exact(&mut byte)?;
+    Ok(byte[0])
+}
+
+fn read_u32(stream: &mut impl Read) -> std::io::Result<u32> {
+    let mut bytes = [0u8; 4];
+    stream.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_bytes(stream: &mut impl Read) -> std::io::Result<Vec<u8>> {
+    let len = read_u32(stream)? as usize;
+    let mut bytes = vec![0u8; len];
+    stream.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn test_process_exits_before_connecting() {
+        let software = ProcessSoftware::new("fsw", "/bin/false").with_timeout(5.0);
+        match ProcessRuntime::new(&software) {
+            Err(SoftwareErrors::ProcessExited(name, _)) => assert_eq!(name, "fsw"),
+            other => panic!(
+                "expected ProcessExited, got {:?}",
+                other
+            ),
+        }
+    }
+
+    #[test]
+    fn test_software_closes_socket() {
+        let socket_path = env::temp_dir().join(format!(
+            "nadir_test_{}_closes.sock",
+            std::process::id()
+        ));
+        let software = ProcessSoftware::external("fsw", &socket_path).with_timeout(5.0);
+
+        // answers INIT, then drops the connection in the middle of the first step
+        let client_path = socket_path.clone();
+        let client = thread::spawn(move || {
+            let mut stream = loop {
+                if let Ok(stream) = UnixStream::connect(&client_path) {
+                    break stream;
+                }
+                sleep(Duration::from_millis(5));
+            };
+            assert_eq!(
+                read_u8(&mut stream).unwrap(),
+                MSG_INIT
+            );
+            read_bytes(&mut stream).unwrap();
+            let mut response = vec![MSG_OK];
+            response.extend_from_slice(&0u32.to_le_bytes());
+            stream
+                .write_all(&response)
+                .unwrap();
+            assert_eq!(
+                read_u8(&mut stream).unwrap(),
+                MSG_STEP
+            );
+        });
+
+        let mut runtime = ProcessRuntime::new(&software).unwrap();
+        runtime
+            .check_schemas(&[], &[])
+            .unwrap();
+
+        let mut actuators = vec![HardwareBuffer::new()];
+        match runtime.step(&[], &mut actuators) {
+            Err(SoftwareErrors::ProcessError(name, _)) => assert_eq!(name, "fsw"),
+            other => panic!(
+                "expected ProcessError, got {:?}",
+                other
+            ),
+        }
+        client
+            .join()
+            .unwrap();
+    }
+}
@@ -1,14 +1,26 @@
 use crate::{
     HardwareBuffer,
     actuator::{Actuator, ActuatorErrors},
+    schema::{BufferSchema, SchemaErrors},
     sensor::Sensor,
 };
 use libloading::{Library, Symbol};
 use nadir_result::ResultManager;
 use serde::{Deserialize, Serialize};
-use std::{ffi::c_void, path::Path};
+use std::{
+    collections::VecDeque,
+    error::Error,
+    ffi::c_void,
+    fmt::{Debug, Formatter},
+    path::Path,
+    sync::Arc,
+};
 use thiserror::Error;
 
+#[cfg(unix)]
+pub mod ipc;
+pub mod replay;
+
 #[derive(Debug, Error)]
 pub enum SoftwareErrors {
     #[error("{0}")]
@@ -21,10 +33,109 @@ pub enum SoftwareErrors {
     InitializationError(i32),
     #[error("Software execution failed with code: {0}")]
     ExecutionError(i32),
+    #[error("Flight software error: {0}")]
+    FlightSoftwareError(String),
     #[error("Software step returned null state pointer")]
     NullStatePointer,
     #[error("Invalid pointer passed to software")]
     InvalidPointer,
+    #[error("Software process '{0}' failed: {1}")]
+    ProcessError(String, String),
+    #[error("Software process '{0}' exited: {1}")]
+    ProcessExited(String, String),
+    #[error("Telemetry log error: {0}")]
+    TelemetryLog(String),
+    #[error("Software index {0} does not match a sensor or actuator in the system")]
+    InvalidIndex(usize),
+    #[error("{0}")]
+    Schema(#[from] SchemaErrors),
+    #[error("Software rate must be positive, got {0}")]
+    InvalidRate(f64),
+    #[error("Software phase must be non-negative, got {0}")]
+    InvalidPhase(f64),
+    #[error("Software latency must be non-negative and less than one period, got {0}")]
+    InvalidLatency(f64),
+}
+
+/// Times within this tolerance of a scheduled step or command are treated as due,
+/// since the solver lands on event times only to within floating point error
+const SCHEDULE_TOLERANCE: f64 = 1e-9;
+
+/// When and how often the software runs, and how long its commands take to reach the actuators
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct SoftwareSchedule {
+    /// Rate the software is stepped at (Hz)
+    pub rate: f64,
+    /// Time of the first step (sec)
+    pub phase: f64,
+    /// Compute latency, the delay between reading sensors and applying commands (sec)
+    pub latency: f64,
+}
+
+impl Default for SoftwareSchedule {
+    fn default() -> Self {
+        Self { rate: 10.0, phase: 0.0, latency: 0.0 }
+    }
+}
+
+impl SoftwareSchedule {
+    pub fn new(rate: f64) -> Result<Self, SoftwareErrors> {
+        let mut schedule = Self::default();
+        schedule.set_rate(rate)?;
+        Ok(schedule)
+    }
+
+    pub fn period(&self) -> f64 {
+        1.0 / self.rate
+    }
+
+    pub fn set_rate(&mut self, rate: f64) -> Result<(), SoftwareErrors> {
+        if !(rate > 0.0 && rate.is_finite()) {
+            return Err(SoftwareErrors::InvalidRate(
+                rate,
+            ));
+        }
+        self.rate = rate;
+        Ok(())
+    }
+
+    pub fn with_phase(mut self, phase: f64) -> Result<Self, SoftwareErrors> {
+        self.set_phase(phase)?;
+        Ok(self)
+    }
+
+    pub fn set_phase(&mut self, phase: f64) -> Result<(), SoftwareErrors> {
+        if !(phase >= 0.0 && phase.is_finite()) {
+            return Err(SoftwareErrors::InvalidPhase(
+                phase,
+            ));
+        }
+        self.phase = phase;
+        Ok(())
+    }
+
+    pub fn with_latency(mut self, latency: f64) -> Result<Self, SoftwareErrors> {
+        self.set_latency(latency)?;
+        Ok(self)
+    }
+
+    /// Latency is limited to one period so at most one set of commands is in flight
+    pub fn set_latency(&mut self, latency: f64) -> Result<(), SoftwareErrors> {
+        if !(latency >= 0.0 && latency < self.period()) {
+            return Err(SoftwareErrors::InvalidLatency(latency));
+        }
+        self.latency = latency;
+        Ok(())
+    }
+
+    /// Checks values that may have come from a config file rather than the setters
+    fn validate(&self) -> Result<(), SoftwareErrors> {
+        let mut schedule = Self::default();
+        schedule.set_rate(self.rate)?;
+        schedule.set_phase(self.phase)?;
+        schedule.set_latency(self.latency)?;
+        Ok(())
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -33,6 +144,8 @@ pub struct Software {
     pub lib_path: String,
     pub sensor_indices: Vec<usize>,
     pub actuator_indices: Vec<usize>,
+    #[serde(default)]
+    pub schedule: SoftwareSchedule,
 }
 
 impl Software {
@@ -45,6 +158,181 @@ impl Software {
                 .to_string(),
             sensor_indices: Vec::new(),
             actuator_indices: Vec::new(),
+            schedule: SoftwareSchedule::default(),
+        }
+    }
+
+    pub fn with_sensor_indices(mut self, indices: Vec<usize>) -> Self {
+        self.sensor_indices = indices;
+        self
+    }
+
+    pub fn with_actuator_indices(mut self, indices: Vec<usize>) -> Self {
+        self.actuator_indices = indices;
+        self
+    }
+
+    pub fn with_schedule(mut self, schedule: SoftwareSchedule) -> Self {
+        self.schedule = schedule;
+        self
+    }
+
+    pub fn set_schedule(&mut self, schedule: SoftwareSchedule) {
+        self.schedule = schedule;
+    }
+}
+
+fn default_process_timeout() -> f64 {
+    10.0
+}
+
+/// Flight software run as a separate process, exchanging buffers with the sim over a unix
+/// domain socket in lockstep. A crash in the software is reported as an error rather than
+/// taking down the sim, and the software is never loaded into the sim's address space.
+/// See the ipc module for the protocol.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProcessSoftware {
+    pub name: String,
+    /// Executable to spawn, or None to wait for a process started separately, e.g. under a debugger
+    pub executable: Option<String>,
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// Socket to listen on, a unique path in the temp directory is used if None
+    #[serde(default)]
+    pub socket_path: Option<String>,
+    pub sensor_indices: Vec<usize>,
+    pub actuator_indices: Vec<usize>,
+    #[serde(default)]
+    pub schedule: SoftwareSchedule,
+    /// Time to wait for a spawned process to connect, and for each response (sec)
+    #[serde(default = "default_process_timeout")]
+    pub timeout: f64,
+}
+
+impl ProcessSoftware {
+    pub fn new<P: AsRef<Path>>(name: &str, executable: P) -> Self {
+        Self {
+            name: name.to_string(),
+            executable: Some(
+                executable
+                    .as_ref()
+                    .to_string_lossy()
+                    .to_string(),
+            ),
+            args: Vec::new(),
+            socket_path: None,
+            sensor_indices: Vec::new(),
+            actuator_indices: Vec::new(),
+            schedule: SoftwareSchedule::default(),
+            timeout: default_process_timeout(),
+        }
+    }
+
+    /// Waits on socket_path for software started by hand rather than spawning it.
+    /// Building the system blocks until the software connects, with no timeout.
+    pub fn external<P: AsRef<Path>>(name: &str, socket_path: P) -> Self {
+        Self {
+            name: name.to_string(),
+            executable: None,
+            args: Vec::new(),
+            socket_path: Some(
+                socket_path
+                    .as_ref()
+                    .to_string_lossy()
+                    .to_string(),
+            ),
+            sensor_indices: Vec::new(),
+            actuator_indices: Vec::new(),
+            schedule: SoftwareSchedule::default(),
+            timeout: default_process_timeout(),
+        }
+    }
+
+    pub fn with_args(mut self, args: Vec<String>) -> Self {
+        self.args = args;
+        self
+    }
+
+    pub fn with_sensor_indices(mut self, indices: Vec<usize>) -> Self {
+        self.sensor_indices = indices;
+        self
+    }
+
+    pub fn with_actuator_indices(mut self, indices: Vec<usize>) -> Self {
+        self.actuator_indices = indices;
+        self
+    }
+
+    pub fn with_schedule(mut self, schedule: SoftwareSchedule) -> Self {
+        self.schedule = schedule;
+        self
+    }
+
+    pub fn set_schedule(&mut self, schedule: SoftwareSchedule) {
+        self.schedule = schedule;
+    }
+
+    pub fn with_timeout(mut self, timeout: f64) -> Self {
+        self.timeout = timeout;
+        self
+    }
+}
+
+/// Flight software compiled with the sim and run in process, an alternative to the C dylib interface
+pub trait FlightSoftware {
+    /// Runs one step of the software, reading sensor telemetry and writing actuator commands.
+    /// Buffers are ordered by the sensor and actuator indices the software was registered with.
+    fn step(
+        &mut self,
+        sensors: &[HardwareBuffer],
+        actuators: &mut [HardwareBuffer],
+    ) -> Result<(), Box<dyn Error>>;
+
+    /// Called once when the system is built with the layouts of the buffers passed to step,
+    /// so the software can check them against the structs it reads and writes
+    fn check_schemas(
+        &mut self,
+        _sensors: &[BufferSchema],
+        _actuators: &[BufferSchema],
+    ) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
+
+    fn initialize_results(&mut self, _results: &mut ResultManager) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
+
+    fn write_results(&self, _results: &mut ResultManager) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
+}
+
+type FlightSoftwareFactory = Arc<dyn Fn() -> Box<dyn FlightSoftware> + Send + Sync>;
+
+/// Rust flight software registered directly on the system builder.
+/// A new instance is created from the factory each time a system is built,
+/// so monte carlo runs do not share software state.
+#[derive(Clone)]
+pub struct NativeSoftware {
+    pub name: String,
+    factory: FlightSoftwareFactory,
+    pub sensor_indices: Vec<usize>,
+    pub actuator_indices: Vec<usize>,
+    pub schedule: SoftwareSchedule,
+}
+
+impl NativeSoftware {
+    pub fn new<F, S>(name: &str, factory: F) -> Self
+    where
+        F: Fn() -> S + Send + Sync + 'static,
+        S: FlightSoftware + 'static,
+    {
+        Self {
+            name: name.to_string(),
+            factory: Arc::new(move || Box::new(factory())),
+            sensor_indices: Vec::new(),
+            actuator_indices: Vec::new(),
+            schedule: SoftwareSchedule::default(),
         }
     }
 
@@ -57,6 +345,32 @@ impl Software {
         self.actuator_indices = indices;
         self
     }
+
+    pub fn with_schedule(mut self, schedule: SoftwareSchedule) -> Self {
+        self.schedule = schedule;
+        self
+    }
+
+    pub fn set_schedule(&mut self, schedule: SoftwareSchedule) {
+        self.schedule = schedule;
+    }
+}
+
+impl Debug for NativeSoftware {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("NativeSoftware")
+            .field("name", &self.name)
+            .field(
+                "sensor_indices",
+                &self.sensor_indices,
+            )
+            .field(
+                "actuator_indices",
+                &self.actuator_indices,
+            )
+            .field("schedule", &self.schedule)
+            .finish()
+    }
 }
 
 // Enhanced FFI function types with proper state handling and error codes
@@ -72,26 +386,19 @@ pub type StepFnC = unsafe extern "C" fn(
 ) -> i32;
 pub type CleanupFnC = unsafe extern "C" fn(software_state: *mut std::ffi::c_void);
 
+/// Software loaded from a dynamic library through the C interface
 #[derive(Debug)]
-pub struct SoftwareSim {
+struct DylibSoftware {
     step_fn: StepFnC,
     init_results_fn: InitResultsFn,
     write_results_fn: WriteResultsFn,
     cleanup_fn: CleanupFnC,
     software_state: *mut std::ffi::c_void,
-    sensor_indices: Vec<usize>,
-    actuator_indices: Vec<usize>,
-    sensor_telemetry_cache: Vec<HardwareBuffer>,
-    actuator_command_cache: Vec<HardwareBuffer>,
     _lib: Library, // to keep it alive
 }
 
-impl SoftwareSim {
-    pub fn new<P: AsRef<Path>>(
-        lib_path: P,
-        sensor_indices: Vec<usize>,
-        actuator_indices: Vec<usize>,
-    ) -> Result<Self, SoftwareErrors> {
+impl DylibSoftware {
+    fn new<P: AsRef<Path>>(lib_path: P) -> Result<Self, SoftwareErrors> {
         // Load the dynamic library
         let lib = unsafe {
             Library::new(lib_path.as_ref()).map_err(|e| {
@@ -181,28 +488,20 @@ impl SoftwareSim {
             return Err(SoftwareErrors::NullStatePointer);
         }
 
-        // Prepare caches
-        let sensor_telemetry_cache = vec![HardwareBuffer::new(); sensor_indices.len()];
-        let actuator_command_cache = vec![HardwareBuffer::new(); actuator_indices.len()];
-
         Ok(Self {
             step_fn: *step_fn,
             init_results_fn: *init_results_fn,
             write_results_fn: *write_results_fn,
             cleanup_fn: *cleanup_fn,
             software_state,
-            sensor_indices,
-            actuator_indices,
-            sensor_telemetry_cache,
-            actuator_command_cache,
             _lib: lib,
         })
     }
 
-    pub fn step(
+    fn step(
         &mut self,
-        sensors: &[Sensor],
-        actuators: &mut [Actuator],
+        sensors: &[HardwareBuffer],
+        actuators: &mut [HardwareBuffer],
     ) -> Result<(), SoftwareErrors> {
         // Check for valid state
         if self
@@ -212,33 +511,14 @@ impl SoftwareSim {
             return Err(SoftwareErrors::NullStatePointer);
         }
 
-        // Copy sensor telemetry to cache
-        for (cache_idx, &sensor_idx) in self
-            .sensor_indices
-            .iter()
-            .enumerate()
-        {
-            if sensor_idx < sensors.len() {
-                self.sensor_telemetry_cache[cache_idx].write_bytes(
-                    sensors[sensor_idx]
-                        .telemetry_buffer
-                        .as_bytes(),
-                );
-            }
-        }
-
         // Run software step with persistent state
         let result = unsafe {
             (self.step_fn)(
                 self.software_state,
-                self.sensor_telemetry_cache
-                    .as_ptr(),
-                self.sensor_telemetry_cache
-                    .len(),
-                self.actuator_command_cache
-                    .as_mut_ptr(),
-                self.actuator_command_cache
-                    .len(),
+                sensors.as_ptr(),
+                sensors.len(),
+                actuators.as_mut_ptr(),
+                actuators.len(),
             )
         };
 
@@ -246,22 +526,10 @@ impl SoftwareSim {
         if result != 0 {
             return Err(SoftwareErrors::ExecutionError(result));
         }
-
-        // Read actuator commands
-        for (cache_idx, &actuator_idx) in self
-            .actuator_indices
-            .iter()
-            .enumerate()
-        {
-            if actuator_idx < actuators.len() {
-                actuators[actuator_idx].read_command(&self.actuator_command_cache[cache_idx])?;
-            }
-        }
-
         Ok(())
     }
 
-    pub fn initialize_results(&self, results: &mut ResultManager) -> Result<(), SoftwareErrors> {
+    fn initialize_results(&self, results: &mut ResultManager) -> Result<(), SoftwareErrors> {
         // Ensure the software state is valid
         if self
             .software_state
@@ -288,7 +556,7 @@ impl SoftwareSim {
         }
     }
 
-    pub fn write_results(&self, results: &mut ResultManager) -> Result<(), SoftwareErrors> {
+    fn write_results(&self, results: &mut ResultManager) -> Result<(), SoftwareErrors> {
         // Ensure the software state is valid
         if self
             .software_state
@@ -316,8 +584,8 @@ impl SoftwareSim {
     }
 }
 
-// Properly clean up resources when SoftwareSim is dropped
-impl Drop for SoftwareSim {
+// Properly clean up resources when the software is dropped
+impl Drop for DylibSoftware {
     fn drop(&mut self) {
         if !self
             .software_state
@@ -329,6 +597,332 @@ impl Drop for SoftwareSim {
     }
 }
 
+enum SoftwareRuntime {
+    Dylib(DylibSoftware),
+    Native(Box<dyn FlightSoftware>),
+    #[cfg(unix)]
+    Process(ipc::ProcessRuntime),
+}
+
+impl Debug for SoftwareRuntime {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SoftwareRuntime::Dylib(software) => f
+                .debug_tuple("Dylib")
+                .field(software)
+                .finish(),
+            SoftwareRuntime::Native(_) => f.write_str("Native"),
+            #[cfg(unix)]
+            SoftwareRuntime::Process(software) => f
+                .debug_tuple("Process")
+                .field(software)
+                .finish(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct SoftwareSim {
+    runtime: SoftwareRuntime,
+    sensor_indices: Vec<usize>,
+    actuator_indices: Vec<usize>,
+    sensor_telemetry_cache: Vec<HardwareBuffer>,
+    actuator_command_cache: Vec<HardwareBuffer>,
+    schedule: SoftwareSchedule,
+    next_step_time: f64,
+    /// Commands computed but not yet applied, with the time they reach the actuators
+    pending_commands: VecDeque<(f64, Vec<HardwareBuffer>)>,
+    sensor_schemas: Vec<BufferSchema>,
+    actuator_schemas: Vec<BufferSchema>,
+}
+
+impl SoftwareSim {
+    pub fn new<P: AsRef<Path>>(
+        lib_path: P,
+        sensor_indices: Vec<usize>,
+        actuator_indices: Vec<usize>,
+    ) -> Result<Self, SoftwareErrors> {
+        let software = DylibSoftware::new(lib_path)?;
+        Ok(Self::from_runtime(
+            SoftwareRuntime::Dylib(software),
+            sensor_indices,
+            actuator_indices,
+        ))
+    }
+
+    /// Creates the sim from Rust flight software run in process
+    pub fn from_native(
+        software: Box<dyn FlightSoftware>,
+        sensor_indices: Vec<usize>,
+        actuator_indices: Vec<usize>,
+    ) -> Self {
+        Self::from_runtime(
+            SoftwareRuntime::Native(software),
+            sensor_indices,
+            actuator_indices,
+        )
+    }
+
+    fn from_runtime(
+        runtime: SoftwareRuntime,
+        sensor_indices: Vec<usize>,
+        actuator_indices: Vec<usize>,
+    ) -> Self {
+        // Prepare caches
+        let sensor_telemetry_cache = vec![HardwareBuffer::new(); sensor_indices.len()];
+        let actuator_command_cache = vec![HardwareBuffer::new(); actuator_indices.len()];
+
+        Self {
+            runtime,
+            sensor_indices,
+            actuator_indices,
+            sensor_telemetry_cache,
+            actuator_command_cache,
+            schedule: SoftwareSchedule::default(),
+            next_step_time: 0.0,
+            pending_commands: VecDeque::new(),
+            sensor_schemas: Vec::new(),
+            actuator_schemas: Vec::new(),
+        }
+    }
+
+    pub fn with_schedule(mut self, schedule: SoftwareSchedule) -> Result<Self, SoftwareErrors> {
+        schedule.validate()?;
+        self.next_step_time = schedule.phase;
+        self.schedule = schedule;
+        Ok(self)
+    }
+
+    pub fn schedule(&self) -> &SoftwareSchedule {
+        &self.schedule
+    }
+
+    /// Collects the telemetry and command layouts for this software's sensors and actuators,
+    /// in the order the buffers are passed to step, and gives them to native software to check
+    pub fn set_schemas(
+        &mut self,
+        sensors: &[Sensor],
+        actuators: &[Actuator],
+    ) -> Result<(), SoftwareErrors> {
+        self.sensor_schemas = self
+            .sensor_indices
+            .iter()
+            .map(|&i| {
+                sensors
+                    .get(i)
+                    .map(|sensor| sensor.telemetry_schema())
+                    .ok_or(SoftwareErrors::InvalidIndex(
+                        i,
+                    ))
+            })
+            .collect::<Result<_, _>>()?;
+        self.actuator_schemas = self
+            .actuator_indices
+            .iter()
+            .map(|&i| {
+                actuators
+                    .get(i)
+                    .map(|actuator| actuator.command_schema())
+                    .ok_or(SoftwareErrors::InvalidIndex(
+                        i,
+                    ))
+            })
+            .collect::<Result<_, _>>()?;
+
+        match &mut self.runtime {
+            SoftwareRuntime::Dylib(_) => {}
+            SoftwareRuntime::Native(software) => software
+                .check_schemas(
+                    &self.sensor_schemas,
+                    &self.actuator_schemas,
+                )
+                .map_err(|e| SoftwareErrors::FlightSoftwareError(e.to_string()))?,
+            #[cfg(unix)]
+            SoftwareRuntime::Process(software) => software.check_schemas(
+                &self.sensor_schemas,
+                &self.actuator_schemas,
+            )?,
+        }
+        Ok(())
+    }
+
+    pub fn sensor_schemas(&self) -> &[BufferSchema] {
+        &self.sensor_schemas
+    }
+
+    pub fn actuator_schemas(&self) -> &[BufferSchema] {
+        &self.actuator_schemas
+    }
+
+    /// Steps the software if it is due at time t and applies any delayed commands that have arrived.
+    /// Commands are applied immediately when the schedule has no latency.
+    /// Returns true if the software stepped.
+    pub fn update(
+        &mut self,
+        t: f64,
+        sensors: &[Sensor],
+        actuators: &mut [Actuator],
+    ) -> Result<bool, SoftwareErrors> {
+        let stepped = t >= self.next_step_time - SCHEDULE_TOLERANCE;
+        if stepped {
+            self.run(sensors)?;
+            self.next_step_time = t + self
+                .schedule
+                .period();
+
+            if self
+                .schedule
+                .latency
+                > 0.0
+            {
+                self.pending_commands
+                    .push_back((
+                        t + self
+                            .schedule
+                            .latency,
+                        self.actuator_command_cache
+                            .clone(),
+                    ));
+            } else {
+                self.apply_commands(actuators)?;
+            }
+        }
+
+        while let Some((apply_time, _)) = self
+            .pending_commands
+            .front()
+        {
+            if t < apply_time - SCHEDULE_TOLERANCE {
+                break;
+            }
+            if let Some((_, commands)) = self
+                .pending_commands
+                .pop_front()
+            {
+                self.actuator_command_cache = commands;
+                self.apply_commands(actuators)?;
+            }
+        }
+
+        Ok(stepped)
+    }
+
+    /// Steps the software and applies its commands immediately, ignoring the schedule
+    pub fn step(
+        &mut self,
+        sensors: &[Sensor],
+        actuators: &mut [Actuator],
+    ) -> Result<(), SoftwareErrors> {
+        self.run(sensors)?;
+        self.apply_commands(actuators)
+    }
+
+    /// Steps the software with sensor buffers already in the order of the sensor indices,
+    /// ignoring the schedule, and returns the commands in the order of the actuator indices
+    pub fn step_buffers(
+        &mut self,
+        sensors: &[HardwareBuffer],
+    ) -> Result<&[HardwareBuffer], SoftwareErrors> {
+        for (cache, buffer) in self
+            .sensor_telemetry_cache
+            .iter_mut()
+            .zip(sensors)
+        {
+            cache.write_bytes(buffer.as_bytes());
+        }
+        self.execute()?;
+        Ok(&self.actuator_command_cache)
+    }
+
+    /// The latest commands, in the order of the actuator indices
+    pub fn commands(&self) -> &[HardwareBuffer] {
+        &self.actuator_command_cache
+    }
+
+    /// Copies sensor telemetry in and runs one step, leaving the commands in the cache
+    fn run(&mut self, sensors: &[Sensor]) -> Result<(), SoftwareErrors> {
+        // Copy sensor telemetry to cache
+        for (cache_idx, &sensor_idx) in self
+            .sensor_indices
+            .iter()
+            .enumerate()
+        {
+            if sensor_idx < sensors.len() {
+                self.sensor_telemetry_cache[cache_idx].write_bytes(
+                    sensors[sensor_idx]
+                        .telemetry_buffer
+                        .as_bytes(),
+                );
+            }
+        }
+
+        self.execute()
+    }
+
+    fn execute(&mut self) -> Result<(), SoftwareErrors> {
+        match &mut self.runtime {
+            SoftwareRuntime::Dylib(software) => software.step(
+                &self.sensor_telemetry_cache,
+                &mut self.actuator_command_cache,
+            )?,
+            SoftwareRuntime::Native(software) => software
+                .step(
+                    &self.sensor_telemetry_cache,
+                    &mut self.actuator_command_cache,
+                )
+                .map_err(|e| SoftwareErrors::FlightSoftwareError(e.to_string()))?,
+            #[cfg(unix)]
+            SoftwareRuntime::Process(software) => software.step(
+                &self.sensor_telemetry_cache,
+                &mut self.actuator_command_cache,
+            )?,
+        }
+
+        Ok(())
+    }
+
+    fn apply_commands(&self, actuators: &mut [Actuator]) -> Result<(), SoftwareErrors> {
+        // Read actuator commands
+        for (cache_idx, &actuator_idx) in self
+            .actuator_indices
+            .iter()
+            .enumerate()
+        {
+            if actuator_idx < actuators.len() {
+                actuators[actuator_idx].read_command(&self.actuator_command_cache[cache_idx])?;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn initialize_results(
+        &mut self,
+        results: &mut ResultManager,
+    ) -> Result<(), SoftwareErrors> {
+        match &mut self.runtime {
+            SoftwareRuntime::Dylib(software) => software.initialize_results(results),
+            SoftwareRuntime::Native(software) => software
+                .initialize_results(results)
+                .map_err(|e| SoftwareErrors::FlightSoftwareError(e.to_string())),
+            // the process writes its own results
+            #[cfg(unix)]
+            SoftwareRuntime::Process(_) => Ok(()),
+        }
+    }
+
+    pub fn write_results(&self, results: &mut ResultManager) -> Result<(), SoftwareErrors> {
+        match &self.runtime {
+            SoftwareRuntime::Dylib(software) => software.write_results(results),
+            SoftwareRuntime::Native(software) => software
+                .write_results(results)
+                .map_err(|e| SoftwareErrors::FlightSoftwareError(e.to_string())),
+            #[cfg(unix)]
+            SoftwareRuntime::Process(_) => Ok(()),
+        }
+    }
+}
+
 impl TryFrom<&Software> for SoftwareSim {
     type Error = SoftwareErrors;
 
@@ -339,6 +933,49 @@ impl TryFrom<&Software> for SoftwareSim {
                 .clone(),
             soft.actuator_indices
                 .clone(),
+        )?
+        .with_schedule(soft.schedule)
+    }
+}
+
+impl TryFrom<&NativeSoftware> for SoftwareSim {
+    type Error = SoftwareErrors;
+
+    fn try_from(soft: &NativeSoftware) -> Result<Self, Self::Error> {
+        SoftwareSim::from_native(
+            (soft.factory)(),
+            soft.sensor_indices
+                .clone(),
+            soft.actuator_indices
+                .clone(),
         )
+        .with_schedule(soft.schedule)
+    }
+}
+
+impl TryFrom<&ProcessSoftware> for SoftwareSim {
+    type Error = SoftwareErrors;
+
+    #[cfg(unix)]
+    fn try_from(soft: &ProcessSoftware) -> Result<Self, Self::Error> {
+        SoftwareSim::from_runtime(
+            SoftwareRuntime::Process(ipc::ProcessRuntime::new(
+                soft,
+            )?),
+            soft.sensor_indices
+                .clone(),
+            soft.actuator_indices
+                .clone(),
+        )
+        .with_schedule(soft.schedule)
+    }
+
+    #[cfg(not(unix))]
+    fn try_from(soft: &ProcessSoftware) -> Result<Self, Self::Error> {
+        Err(SoftwareErrors::ProcessError(
+            soft.name
+                .clone(),
+            "process software requires unix domain sockets".to_string(),
+        ))
     }
 }
@@ -0,0 +1,389 @@
+//! Recording of the buffers exchanged with flight software, and replay of those recordings
+//! into a SoftwareSim without the dynamics, for regression testing software changes
+//! against archived sim runs.
+
+use super::{SoftwareErrors, SoftwareSim};
+use crate::{HardwareBuffer, actuator::Actuator, schema::BufferSchema, sensor::Sensor};
+use bincode::config::standard;
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, BufWriter, Read, Write},
+    path::Path,
+};
+
+/// Identifies a telemetry log file
+const LOG_MAGIC: &[u8; 8] = b"NADIRTLM";
+const LOG_VERSION: u32 = 1;
+
+/// Everything one software saw and commanded in one step
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TelemetryFrame {
+    /// Sim time of the step (sec)
+    pub t: f64,
+    /// Index of the software in sys.software
+    pub software: usize,
+    /// Telemetry buffer of every sensor in the system, by sensor index
+    pub sensors: Vec<Vec<u8>>,
+    /// Commands the software produced, as (actuator index, buffer)
+    pub commands: Vec<(usize, Vec<u8>)>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct LogHeader {
+    version: u32,
+    sensor_schemas: Vec<BufferSchema>,
+    actuator_schemas: Vec<BufferSchema>,
+}
+
+/// A recorded run, with the schemas of every sensor and actuator so the log describes itself
+#[derive(Clone, Debug, Default)]
+pub struct TelemetryLog {
+    pub sensor_schemas: Vec<BufferSchema>,
+    pub actuator_schemas: Vec<BufferSchema>,
+    pub frames: Vec<TelemetryFrame>,
+}
+
+impl TelemetryLog {
+    /// Writes the log as a binary file: the magic bytes, a header, then one frame after another
+    pub fn write(&self, path: &Path) -> Result<(), SoftwareErrors> {
+        let mut writer = BufWriter::new(File::create(path).map_err(log_error)?);
+        writer
+            .write_all(LOG_MAGIC)
+            .map_err(log_error)?;
+        let header = LogHeader {
+            version: LOG_VERSION,
+            sensor_schemas: self
+                .sensor_schemas
+                .clone(),
+            actuator_schemas: self
+                .actuator_schemas
+                .clone(),
+        };
+        bincode::serde::encode_into_std_write(
+            &header,
+            &mut writer,
+            standard(),
+        )
+        .map_err(log_error)?;
+        for frame in &self.frames {
+            bincode::serde::encode_into_std_write(frame, &mut writer, standard())
+                .map_err(log_error)?;
+        }
+        writer
+            .flush()
+            .map_err(log_error)?;
+        Ok(())
+    }
+
+    pub fn read(path: &Path) -> Result<Self, SoftwareErrors> {
+        let mut reader = BufReader::new(File::open(path).map_err(log_error)?);
+        let mut magic = [0u8; 8];
+        reader
+            .read_exact(&mut magic)
+            .map_err(log_error)?;
+        if &magic != LOG_MAGIC {
+            return Err(SoftwareErrors::TelemetryLog(
+                format!(
+                    "{} is not a telemetry log",
+                    path.display()
+                ),
+            ));
+        }
+        let header: LogHeader =
+            bincode::serde::decode_from_std_read(&mut reader, standard()).map_err(log_error)?;
+        if header.version != LOG_VERSION {
+            return Err(SoftwareErrors::TelemetryLog(
+                format!(
+                    "unsupported log version {}",
+                    header.version
+                ),
+            ));
+        }
+
+        let mut frames = Vec::new();
+        loop {
+            // frames run to the end of the file, so check for it before decoding the next one
+            if reader
+                .fill_buf()
+                .map_err(log_error)?
+                .is_empty()
+            {
+                break;
+            }
+            frames.push(
+                bincode::serde::decode_from_std_read(&mut reader, standard()).map_err(log_error)?,
+            );
+        }
+
+        Ok(Self {
+            sensor_schemas: header.sensor_schemas,
+            actuator_schemas: header.actuator_schemas,
+            frames,
+        })
+    }
+
+    /// Frames recorded for one software, in time order
+    pub fn frames_for(&self, software: usize) -> impl Iterator<Item = &TelemetryFrame> {
+        self.frames
+            .iter()
+            .filter(move |frame| frame.software == software)
+    }
+}
+
+/// Collects a frame for every software step during a sim
+#[derive(Debug, Default)]
+pub struct TelemetryRecorder {
+    log: TelemetryLog,
+}
+
+impl TelemetryRecorder {
+    pub fn new(sensors: &[Sensor], actuators: &[Actuator]) -> Self {
+        Self {
+            log: TelemetryLog {
+                sensor_schemas: sensors
+                    .iter()
+                    .map(|sensor| sensor.telemetry_schema())
+                    .collect(),
+                actuator_schemas: actuators
+                    .iter()
+                    .map(|actuator| actuator.command_schema())
+                    .collect(),
+                frames: Vec::new(),
+            },
+        }
+    }
+
+    /// Records the sensor telemetry the software read at time t and the commands it produced
+    pub fn record(&mut self, t: f64, index: usize, software: &SoftwareSim, sensors: &[Sensor]) {
+        self.log
+            .frames
+            .push(TelemetryFrame {
+                t,
+                software: index,
+                sensors: sensors
+                    .iter()
+                    .map(|sensor| {
+                        sensor
+                            .telemetry_buffer
+                            .as_bytes()
+                            .to_vec()
+                    })
+                    .collect(),
+                commands: software
+                    .actuator_indices
+                    .iter()
+                    .zip(software.commands())
+                    .map(|(&i, buffer)| {
+                        (
+                            i,
+                            buffer
+                                .as_bytes()
+                                .to_vec(),
+                        )
+                    })
+                    .collect(),
+            });
+    }
+
+    pub fn log(&self) -> &TelemetryLog {
+        &self.log
+    }
+
+    pub fn write(&self, path: &Path) -> Result<(), SoftwareErrors> {
+        self.log
+            .write(path)
+    }
+}
+
+/// A replayed command that differs from the recording
+#[derive(Clone, Debug)]
+pub struct ReplayMismatch {
+    pub t: f64,
+    pub actuator: usize,
+    /// The first field that differs, or None when compared byte for byte
+    pub field: Option<String>,
+    /// Values of the field, or the raw bytes when compared byte for byte
+    pub recorded: Vec<f64>,
+    pub replayed: Vec<f64>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ReplayReport {
+    /// Number of recorded steps replayed
+    pub steps: usize,
+    pub mismatches: Vec<ReplayMismatch>,
+}
+
+impl ReplayReport {
+    pub fn passed(&self) -> bool {
+        self.mismatches
+            .is_empty()
+    }
+}
+
+/// Feeds recorded sensor telemetry into software and compares the commands with the recording.
+/// Commands are compared byte for byte unless a tolerance is set, in which case the actuator
+/// schemas in the log are used to compare each field numerically.
+#[derive(Debug)]
+pub struct ReplayDriver {
+    log: TelemetryLog,
+    tolerance: Option<f64>,
+}
+
+impl ReplayDriver {
+    pub fn new(log: TelemetryLog) -> Self {
+        Self { log, tolerance: None }
+    }
+
+    pub fn from_file(path: &Path) -> Result<Self, SoftwareErrors> {
+        Ok(Self::new(TelemetryLog::read(
+            path,
+        )?))
+    }
+
+    pub fn with_tolerance(mut self, tolerance: f64) -> Self {
+        self.tolerance = Some(tolerance);
+        self
+    }
+
+    pub fn log(&self) -> &TelemetryLog {
+        &self.log
+    }
+
+    /// Replays the frames recorded for software `index` in sys.software into `software`, which should
+    /// be configured with the same sensor and actuator indices as the recorded software
+    pub fn run(
+        &self,
+        index: usize,
+        software: &mut SoftwareSim,
+    ) -> Result<ReplayReport, SoftwareErrors> {
+        let mut report = ReplayReport::default();
+        let mut sensors = vec![
+            HardwareBuffer::new();
+            software
+                .sensor_indices
+                .len()
+        ];
+
+        for frame in self
+            .log
+            .frames_for(index)
+        {
+            for (buffer, &i) in sensors
+                .iter_mut()
+                .zip(&software.sensor_indices)
+            {
+                let bytes = frame
+                    .sensors
+                    .get(i)
+                    .ok_or(SoftwareErrors::InvalidIndex(
+                        i,
+                    ))?;
+                buffer.write_bytes(bytes);
+            }
+            software.step_buffers(&sensors)?;
+            let commands = software.commands();
+
+            for (actuator, recorded) in &frame.commands {
+                let position = software
+                    .actuator_indices
+                    .iter()
+                    .position(|i| i == actuator)
+                    .ok_or(SoftwareErrors::InvalidIndex(
+                        *actuator,
+                    ))?;
+                if let Some(mismatch) = self.compare(
+                    frame.t,
+                    *actuator,
+                    recorded,
+                    &commands[position],
+                ) {
+                    report
+                        .mismatches
+                        .push(mismatch);
+                }
+            }
+            report.steps += 1;
+        }
+        Ok(report)
+    }
+
+    fn compare(
+        &self,
+        t: f64,
+        actuator: usize,
+        recorded: &[u8],
+        replayed: &HardwareBuffer,
+    ) -> Option<ReplayMismatch> {
+        let schema = self
+            .log
+            .actuator_schemas
+            .get(actuator);
+        if let (Some(tolerance), Some(schema)) = (self.tolerance, schema) {
+            let mut recorded_buffer = HardwareBuffer::new();
+            recorded_buffer.write_bytes(recorded);
+            for field in &schema.fields {
+                // padding carries no information
+                if field
+                    .name
+                    .starts_with('_')
+                {
+                    continue;
+                }
+                let (Ok(a), Ok(b)) = (
+                    schema.read_field(&recorded_buffer, &field.name),
+                    schema.read_field(replayed, &field.name),
+                ) else {
+                    break;
+                };
+                if a.iter()
+                    .zip(&b)
+                    .any(|(a, b)| (a - b).abs() > tolerance)
+                {
+                    return Some(ReplayMismatch {
+                        t,
+                        actuator,
+                        field: Some(
+                            field
+                                .name
+                                .clone(),
+                        ),
+                        recorded: a,
+                        replayed: b,
+                    });
+                }
+            }
+            // fall through to the byte comparison if the buffers did not match the schema
+            if recorded.len() == schema.size
+                && replayed
+                    .as_bytes()
+                    .len()
+                    == schema.size
+            {
+                return None;
+            }
+        }
+        if recorded != replayed.as_bytes() {
+            return Some(ReplayMismatch {
+                t,
+                actuator,
+                field: None,
+                recorded: recorded
+                    .iter()
+                    .map(|&b| b as f64)
+                    .collect(),
+                replayed: replayed
+                    .as_bytes()
+                    .iter()
+                    .map(|&b| b as f64)
+                    .collect(),
+            });
+        }
+        None
+    }
+}
+
+fn log_error<E: std::fmt::Display>(e: E) -> SoftwareErrors {
+    SoftwareErrors::TelemetryLog(e.to_string())
+}
@@ -1,22 +1,29 @@
 use crate::{
     MultibodyErrors,
-    actuator::{Actuator, ActuatorBuilder},
+    actuator::{Actuator, ActuatorBuilder, ActuatorModels},
     algorithms::MultibodyAlgorithm,
     base::{Base, BaseBuilder, BaseRef, BaseSystems, BaseSystemsBuilder},
     body::{BodyBuilder, BodyConnection, BodyRef},
+    fault::{FaultBuilder, FaultErrors, FaultTarget},
     joint::{JointBuilder, JointConnection, JointModel, JointModelBuilders, JointRef},
+    schema::{self, BufferSchema, SchemaErrors},
     sensor::{Sensor, SensorBuilder},
-    software::{Software, SoftwareSim},
+    software::{
+        NativeSoftware, ProcessSoftware, Software, SoftwareErrors, SoftwareSim,
+        replay::TelemetryRecorder,
+    },
 };
 
 use core::fmt;
 use gravity::{Gravity, constant::ConstantGravity, newtonian::NewtonianGravity};
 use nadir_diffeq::{
+    events::PeriodicEvent,
     model::{OdeModel, StateFromModelMut},
     saving::{StateWriterBuilder, WriterId, WriterManager},
     state::state_vector::StateVector,
 };
 
+use nalgebra::Matrix3;
 use rand::{Rng, SeedableRng, rngs::SmallRng};
 use ron::{
     from_str,
@@ -37,14 +44,33 @@ use std::{
 use thiserror::Error;
 use uncertainty::Uncertainty;
 
+fn default_fault_check_period() -> f64 {
+    1.0
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MultibodySystemBuilder {
     pub actuators: Vec<ActuatorBuilder>,
     pub algorithm: MultibodyAlgorithm,
     pub base: BaseBuilder,
     pub bodies: HashMap<Id, BodyBuilder>,
+    #[serde(default)]
+    pub faults: Vec<FaultBuilder>,
+    /// Period (s) at which condition triggered faults are checked, defaults to 1 s. This is the
+    /// latency with which a fault detects its condition.
+    #[serde(default = "default_fault_check_period")]
+    pub fault_check_period: f64,
     pub identifier: Identifier,
     pub joints: HashMap<Id, JointBuilder>,
+    /// Rust flight software run in process, not serialized since it is compiled with the sim
+    #[serde(skip)]
+    pub native_software: Vec<NativeSoftware>,
+    /// Flight software run in separate processes
+    #[serde(default)]
+    pub process_software: Vec<ProcessSoftware>,
+    /// Records the buffers exchanged with software to software/telemetry.bin in the results folder
+    #[serde(default)]
+    pub record_telemetry: bool,
     seed: u64,
     pub sensors: Vec<SensorBuilder>,
     pub software: Vec<Software>,
@@ -61,6 +87,22 @@ impl MultibodySystemBuilder {
             .insert(body.id, body);
     }
 
+    /// Adds a fault to be injected into a named sensor or actuator during the sim
+    pub fn add_fault(&mut self, fault: FaultBuilder) {
+        self.faults
+            .push(fault);
+    }
+
+    /// Sets the period (s) at which condition triggered faults are checked, 1 s by default.
+    /// Conditions are only checked on accepted steps, so a fault can trigger up to one period late.
+    pub fn set_fault_check_period(&mut self, period: f64) -> Result<(), MultibodyErrors> {
+        if period <= 0.0 {
+            return Err(FaultErrors::NonPositiveCheckPeriod.into());
+        }
+        self.fault_check_period = period;
+        Ok(())
+    }
+
     pub fn add_joint(&mut self, joint: JointBuilder) {
         self.joints
             .insert(joint.id, joint);
@@ -71,6 +113,23 @@ impl MultibodySystemBuilder {
             .push(sensor);
     }
 
+    /// Adds Rust flight software, stepped after any dylib software in sys.software
+    pub fn add_native_software(&mut self, software: NativeSoftware) {
+        self.native_software
+            .push(software);
+    }
+
+    /// Adds flight software run in a separate process, stepped after any native software
+    pub fn add_process_software(&mut self, software: ProcessSoftware) {
+        self.process_software
+            .push(software);
+    }
+
+    /// Records every software step for replay, see software::replay
+    pub fn set_record_telemetry(&mut self, record: bool) {
+        self.record_telemetry = record;
+    }
+
     pub fn add_software(&mut self, software: Software) {
         self.software
             .push(software);
@@ -149,8 +208,13 @@ impl MultibodySystemBuilder {
             algorithm: MultibodyAlgorithm::ArticulatedBody, // for now, default to this
             base: BaseBuilder::new(id.next()),
             bodies: HashMap::new(),
+            faults: Vec::new(),
+            fault_check_period: default_fault_check_period(),
             identifier: id,
             joints: HashMap::new(),
+            native_software: Vec::new(),
+            process_software: Vec::new(),
+            record_telemetry: false,
             seed,
             sensors: Vec::new(),
             software: Vec::new(),
@@ -495,13 +559,74 @@ impl MultibodySystemBuilder {
             joint.inner_joint = inner_joint;
         }
 
+        // attach faults to their sensors and actuators
+        // sensors and actuators are created in traversal order, so look them up by name
+        for fault_builder in &self.faults {
+            let fault = fault_builder.sample(nominal, &mut sys_rng)?;
+            match &fault_builder.target {
+                FaultTarget::Sensor(name) => sensors
+                    .iter_mut()
+                    .find(|sensor| &sensor.name == name)
+                    .expect("validation should catch this")
+                    .faults
+                    .push(fault),
+                FaultTarget::Actuator(name) => actuators
+                    .iter_mut()
+                    .find(|actuator| &actuator.name == name)
+                    .expect("validation should catch this")
+                    .faults
+                    .push(fault),
+            }
+        }
+
         // create software
         for sw in &self.software {
             software.push(SoftwareSim::try_from(sw)?);
         }
+        for sw in &self.native_software {
+            software.push(SoftwareSim::try_from(sw)?);
+        }
+        for sw in &self.process_software {
+            software.push(SoftwareSim::try_from(sw)?);
+        }
+        for sw in &mut software {
+            sw.set_schemas(&sensors, &actuators)?;
+        }
+
+        let recorder = self
+            .record_telemetry
+            .then(|| TelemetryRecorder::new(&sensors, &actuators));
+
+        // group the control moment gyros by the body they are mounted on
+        let mut cmg_groups: HashMap<Id, Vec<usize>> = HashMap::new();
+        for (i, actuator) in actuators
+            .iter()
+            .enumerate()
+        {
+            if let ActuatorModels::ControlMomentGyro(_) = &actuator.model {
+                let body_id = self
+                    .actuators
+                    .iter()
+                    .find(|builder| builder.name == actuator.name)
+                    .and_then(|builder| {
+                        builder
+                            .connection
+                            .as_ref()
+                    })
+                    .expect("validation should catch this")
+                    .body_id;
+                cmg_groups
+                    .entry(body_id)
+                    .or_default()
+                    .push(i);
+            }
+        }
 
         let sys = MultibodySystem {
             actuators,
+            cmg_groups: cmg_groups
+                .into_values()
+                .collect(),
             algorithm: self.algorithm,
             base: Rc::new(RefCell::new(Base::from(
                 &self.base,
@@ -510,6 +635,10 @@ impl MultibodySystemBuilder {
             joints,
             sensors,
             software,
+            software_error: None,
+            recorder,
+            fault_check_period: self.fault_check_period,
+            fault_id: None,
             sim_time_id: None,
         };
 
@@ -670,6 +799,49 @@ impl MultibodySystemBuilder {
                 );
             }
         }
+
+        // check that every fault targets an existing component with a compatible mode
+        if self.fault_check_period <= 0.0 {
+            return Err(FaultErrors::NonPositiveCheckPeriod.into());
+        }
+        for fault in &self.faults {
+            fault.validate()?;
+            match &fault.target {
+                FaultTarget::Sensor(name) => {
+                    if !self
+                        .sensors
+                        .iter()
+                        .any(|sensor| &sensor.name == name)
+                    {
+                        return Err(FaultErrors::SensorNotFound(
+                            fault
+                                .name
+                                .clone(),
+                            name.clone(),
+                        )
+                        .into());
+                    }
+                }
+                FaultTarget::Actuator(name) => {
+                    match self
+                        .actuators
+                        .iter()
+                        .find(|actuator| &actuator.name == name)
+                    {
+                        Some(actuator) => fault.validate_actuator(actuator)?,
+                        None => {
+                            return Err(FaultErrors::ActuatorNotFound(
+                                fault
+                                    .name
+                                    .clone(),
+                                name.clone(),
+                            )
+                            .into());
+                        }
+                    }
+                }
+            }
+        }
         Ok(())
     }
 }
@@ -689,16 +861,50 @@ impl Uncertainty for MultibodySystemBuilder {
 #[derive(Debug)]
 pub struct MultibodySystem {
     pub actuators: Vec<Actuator>,
+    /// Indices into actuators of the control moment gyros on each body, which share a
+    /// singularity measure
+    cmg_groups: Vec<Vec<usize>>,
     pub algorithm: MultibodyAlgorithm,
     pub base: BaseRef,
     pub bodies: Vec<BodyRef>,
     pub joints: Vec<JointRef>,
     pub sensors: Vec<Sensor>,
     pub software: Vec<SoftwareSim>,
+    /// Set when software fails in an event, returned as an error from the next call to f
+    software_error: Option<SoftwareErrors>,
+    pub recorder: Option<TelemetryRecorder>,
+    /// Period (s) of the event that checks condition triggered faults
+    fault_check_period: f64,
+    pub fault_id: Option<WriterId>,
     pub sim_time_id: Option<WriterId>,
 }
 
 impl MultibodySystem {
+    /// Telemetry layouts of every sensor followed by command layouts of every actuator
+    pub fn schemas(&self) -> Vec<BufferSchema> {
+        let mut schemas: Vec<BufferSchema> = self
+            .sensors
+            .iter()
+            .map(|sensor| sensor.telemetry_schema())
+            .collect();
+        schemas.extend(
+            self.actuators
+                .iter()
+                .map(|actuator| actuator.command_schema()),
+        );
+        schemas
+    }
+
+    /// Writes the telemetry and command structs as a C header for flight software
+    pub fn export_c_header(&self, path: &Path) -> Result<(), SchemaErrors> {
+        schema::write_c_header(&self.schemas(), path)
+    }
+
+    /// Writes the telemetry and command structs as a Rust module for flight software
+    pub fn export_rust_module(&self, path: &Path) -> Result<(), SchemaErrors> {
+        schema::write_rust_module(&self.schemas(), path)
+    }
+
     pub fn init_fn(model: &mut Self, _state: &StateVector, manager: &mut WriterManager) {
         // sim_time
         model.sim_time_id = Some(
@@ -732,6 +938,36 @@ impl MultibodySystem {
             actuator.writer_init_fn(manager);
         }
 
+        // faults, one active flag per fault
+        let fault_names: Vec<&str> = model
+            .sensors
+            .iter()
+            .flat_map(|sensor| &sensor.faults)
+            .chain(
+                model
+                    .actuators
+                    .iter()
+                    .flat_map(|actuator| &actuator.faults),
+            )
+            .map(|fault| {
+                fault
+                    .name
+                    .as_str()
+            })
+            .collect();
+        if !fault_names.is_empty() {
+            model.fault_id = Some(
+                manager.add_writer(
+                    StateWriterBuilder::new(
+                        fault_names.len(),
+                        "faults.csv".into(),
+                    )
+                    .with_headers(&fault_names)
+                    .unwrap(),
+                ),
+            );
+        }
+
         // celestial
         match &mut model
             .base
@@ -745,16 +981,15 @@ impl MultibodySystem {
 
     pub fn save_fn(&self, _state: &StateVector, t: f64, manager: &mut WriterManager) {
         // sim time
-        if let Some(id) = &self.sim_time_id {
-            if let Some(writer) = manager
+        if let Some(id) = &self.sim_time_id
+            && let Some(writer) = manager
                 .writers
                 .get_mut(id)
-            {
-                writer.float_buffer[0] = t;
-                writer
-                    .write_record()
-                    .unwrap();
-            }
+        {
+            writer.float_buffer[0] = t;
+            writer
+                .write_record()
+                .unwrap();
         }
 
         // bodies
@@ -780,6 +1015,29 @@ impl MultibodySystem {
             actuator.writer_save_fn(manager);
         }
 
+        // faults
+        if let Some(id) = &self.fault_id
+            && let Some(writer) = manager
+                .writers
+                .get_mut(id)
+        {
+            let faults = self
+                .sensors
+                .iter()
+                .flat_map(|sensor| &sensor.faults)
+                .chain(
+                    self.actuators
+                        .iter()
+                        .flat_map(|actuator| &actuator.faults),
+                );
+            for (i, fault) in faults.enumerate() {
+                writer.float_buffer[i] = fault.active as u8 as f64;
+            }
+            writer
+                .write_record()
+                .unwrap();
+        }
+
         match &self
             .base
             .borrow()
@@ -803,13 +1061,50 @@ impl MultibodySystem {
         }
     }
 
-    fn update_actuators(&mut self) -> Result<(), MultibodyErrors> {
+    fn update_actuators(&mut self, t: f64) -> Result<(), MultibodyErrors> {
+        // dipoles are reported again by the actuators, magnetometers have already read the
+        // previous values, consistent with the magnetic field being updated after the sensors
+        for body in &self.bodies {
+            body.borrow_mut()
+                .magnetic_dipoles
+                .clear();
+        }
         self.actuators
             .iter_mut()
-            .try_for_each(|actuator| actuator.update())?;
+            .try_for_each(|actuator| actuator.update(t))?;
+        self.update_cmg_singularity();
         Ok(())
     }
 
+    /// The singularity metric of a CMG array depends on every CMG on the same body,
+    /// so it is calculated once all CMGs have been updated
+    fn update_cmg_singularity(&mut self) {
+        for group in &self.cmg_groups {
+            let c_ct = group
+                .iter()
+                .flat_map(
+                    |&i| match &self.actuators[i].model {
+                        ActuatorModels::ControlMomentGyro(cmg) => cmg.gimbal_torque_axes(),
+                        _ => &[],
+                    },
+                )
+                .fold(
+                    Matrix3::zeros(),
+                    |acc, axis| acc + axis * axis.transpose(),
+                );
+            let singularity = c_ct
+                .determinant()
+                .max(0.0)
+                .sqrt();
+            for &i in group {
+                if let ActuatorModels::ControlMomentGyro(cmg) = &mut self.actuators[i].model {
+                    cmg.state
+                        .singularity = singularity;
+                }
+            }
+        }
+    }
+
     fn update_body_acceleration(&mut self) {
         for body in &self.bodies {
             body.borrow_mut()
@@ -865,6 +1160,8 @@ impl MultibodySystem {
                 BaseSystems::Celestial(celestial) => body.calculate_gravity_celestial(celestial),
             }
 
+            body.calculate_magnetic_disturbance();
+
             // calculate total external forces for the outer body
             body.calculate_external_force();
 
@@ -917,41 +1214,104 @@ impl MultibodySystem {
         }
     }
 
-    pub fn post_sim_fn(&self, manager: &Option<WriterManager>) {
-        // also need to write the meshes for animation
-        if let Some(manager) = &manager {
-            for body in &self.bodies {
-                let body = body.borrow_mut();
-
-                if let Some(mesh) = &body.mesh {
-                    if let Some(root_dir) = &manager.root_dir {
-                        let bodies_folder = root_dir.join("bodies");
-                        let mesh_file_path = bodies_folder.join(
-                            body.name
-                                .clone()
-                                + ".mesh",
-                        );
-                        let mut mesh_file = File::create(mesh_file_path).unwrap();
-                        let ron_string = to_string_pretty(mesh, PrettyConfig::default()).unwrap();
-                        mesh_file
-                            .write_all(ron_string.as_bytes())
-                            .unwrap();
+    /// Steps any software due at time t and applies delayed commands that have arrived.
+    /// Scheduled automatically through OdeModel::periodic_events.
+    /// Failures, like a crashed software process, stop the sim with an error rather than a panic.
+    pub fn software_fn(model: &mut Self, _state: &mut StateVector, t: f64) {
+        if model
+            .software_error
+            .is_some()
+        {
+            return;
+        }
+        for (i, software) in model
+            .software
+            .iter_mut()
+            .enumerate()
+        {
+            match software.update(
+                t,
+                &model.sensors,
+                &mut model.actuators,
+            ) {
+                Ok(stepped) => {
+                    if stepped && let Some(recorder) = &mut model.recorder {
+                        recorder.record(t, i, software, &model.sensors);
                     }
                 }
+                Err(e) => {
+                    model.software_error = Some(e);
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Latches condition triggered faults, run as a periodic event so that only accepted
+    /// states are checked rather than the trial states at each stage of the integrator
+    pub fn fault_check_fn(model: &mut Self, _state: &mut StateVector, t: f64) {
+        for sensor in &mut model.sensors {
+            sensor.check_fault_conditions(t);
+        }
+        for actuator in &mut model.actuators {
+            actuator.check_fault_conditions(t);
+        }
+    }
+
+    /// Writes the software telemetry recording and the body meshes for animation to the
+    /// results folder, run as a postsim event
+    pub fn post_sim_fn(&self, manager: &Option<WriterManager>) -> Result<(), Box<dyn Error>> {
+        let Some(root_dir) = manager
+            .as_ref()
+            .and_then(|manager| {
+                manager
+                    .root_dir
+                    .as_ref()
+            })
+        else {
+            return Ok(());
+        };
+
+        if let Some(recorder) = &self.recorder {
+            let software_folder = root_dir.join("software");
+            std::fs::create_dir_all(&software_folder)?;
+            recorder.write(&software_folder.join("telemetry.bin"))?;
+        }
+
+        // also need to write the meshes for animation
+        for body in &self.bodies {
+            let body = body.borrow();
+            if let Some(mesh) = &body.mesh {
+                let bodies_folder = root_dir.join("bodies");
+                let mesh_file_path = bodies_folder.join(
+                    body.name
+                        .clone()
+                        + ".mesh",
+                );
+                let mut mesh_file = File::create(mesh_file_path)?;
+                let ron_string = to_string_pretty(mesh, PrettyConfig::default())?;
+                mesh_file.write_all(ron_string.as_bytes())?;
             }
         }
+        Ok(())
     }
 }
 
 impl OdeModel for MultibodySystem {
     type State = StateVector;
     fn f(&mut self, t: f64, x: &StateVector, dx: &mut StateVector) -> Result<(), Box<dyn Error>> {
+        if let Some(e) = self
+            .software_error
+            .take()
+        {
+            return Err(e.into());
+        }
         self.update_state(x); // write the integrated states back in to the joints actuators and sensors
         self.update_base(t); // update epoch based celestial states based on new time
         self.update_joints(); // update joint state based quantities like transforms
         self.update_body_states(); // need to update the body position for gravity calcs prior to update_forces
         self.update_sensors(t)?;
-        self.update_actuators()?; // update the actuators before updating forces on the bodies
+        self.update_actuators(t)?; // update the actuators before updating forces on the bodies
         self.update_environments(); //update the environmental forces before updating forcces on the bodies
         self.update_forces(); // update body forces
 
@@ -1052,6 +1412,57 @@ impl OdeModel for MultibodySystem {
 
         Ok(())
     }
+
+    /// Thruster valves open and close, and actuator faults start and end, at times that generally
+    /// fall between steps
+    fn next_discontinuity(&mut self, t: f64) -> Option<f64> {
+        self.actuators
+            .iter_mut()
+            .filter_map(|actuator| actuator.next_discontinuity(t))
+            .reduce(f64::min)
+    }
+
+    /// One event per software step time, plus one at each delayed command time when there is latency.
+    /// All of them call software_fn, which decides which software is actually due.
+    /// Condition triggered faults are checked by their own event when there are any, every fault
+    /// check period set with MultibodySystemBuilder::set_fault_check_period, which is the latency
+    /// with which they trigger.
+    fn periodic_events(&self) -> Vec<PeriodicEvent<Self, StateVector>> {
+        let mut events = Vec::new();
+        let conditional_faults = self
+            .sensors
+            .iter()
+            .flat_map(|sensor| &sensor.faults)
+            .chain(
+                self.actuators
+                    .iter()
+                    .flat_map(|actuator| &actuator.faults),
+            )
+            .any(|fault| fault.is_conditional());
+        if conditional_faults {
+            events.push(PeriodicEvent::new(
+                self.fault_check_period,
+                0.0,
+                Self::fault_check_fn,
+            ));
+        }
+        for software in &self.software {
+            let schedule = software.schedule();
+            events.push(PeriodicEvent::new(
+                schedule.period(),
+                schedule.phase,
+                Self::software_fn,
+            ));
+            if schedule.latency > 0.0 {
+                events.push(PeriodicEvent::new(
+                    schedule.period(),
+                    schedule.phase + schedule.latency,
+                    Self::software_fn,
+                ));
+            }
+        }
+        events
+    }
 }
 
 impl StateFromModelMut for MultibodySystem {
@@ -0,0 +1,225 @@
+use std::error::Error;
+
+use nadir_diffeq::{
+    OdeProblem,
+    events::ContinuousEvent,
+    model::OdeModel,
+    solution::{DenseOutput, Solution},
+    solvers::{ImplicitMethods, MultiStepMethods, OdeSolver, RungeKuttaMethods, SolverMethods},
+    state::state_array::StateArray,
+    stepping::AdaptiveStepControl,
+};
+
+/// Harmonic oscillator with unit frequency, x = cos(t) for x(0) = 1, v(0) = 0
+#[derive(Debug, Clone)]
+struct Oscillator;
+
+impl OdeModel for Oscillator {
+    type State = StateArray<2>;
+
+    fn f(
+        &mut self,
+        _t: f64,
+        y: &StateArray<2>,
+        dy: &mut StateArray<2>,
+    ) -> Result<(), Box<dyn Error>> {
+        dy[0] = y[1];
+        dy[1] = -y[0];
+        Ok(())
+    }
+}
+
+/// Ball falling under unit gravity, bouncing off the floor with a coefficient of restitution
+#[derive(Debug, Clone)]
+struct Ball;
+
+impl OdeModel for Ball {
+    type State = StateArray<2>;
+
+    fn f(
+        &mut self,
+        _t: f64,
+        y: &StateArray<2>,
+        dy: &mut StateArray<2>,
+    ) -> Result<(), Box<dyn Error>> {
+        dy[0] = y[1];
+        dy[1] = -1.0;
+        Ok(())
+    }
+}
+
+const RESTITUTION: f64 = 0.8;
+
+fn solve<Model: OdeModel<State = StateArray<2>>>(
+    method: SolverMethods,
+    problem: OdeProblem<Model, StateArray<2>>,
+    x0: StateArray<2>,
+    tspan: (f64, f64),
+) -> Result<
+    (
+        Solution<StateArray<2>>,
+        DenseOutput<StateArray<2>>,
+    ),
+    Box<dyn Error>,
+> {
+    let mut solution = OdeSolver::new(method)
+        .with_dense_output(true)
+        .solve_adaptive(
+            problem,
+            x0,
+            tspan,
+            AdaptiveStepControl::default()
+                .with_abs_tol(1e-10)
+                .with_rel_tol(1e-10),
+        )?;
+    let dense_output = solution
+        .dense_output
+        .take()
+        .ok_or("expected dense output")?;
+    Ok((solution, dense_output))
+}
+
+fn difference(a: &StateArray<2>, b: &StateArray<2>) -> f64 {
+    (a[0] - b[0])
+        .abs()
+        .max((a[1] - b[1]).abs())
+}
+
+/// Checks the dense output of the oscillator at the end of each step, between them against the exact
+/// solution, and after a serialize/deserialize round trip.
+fn oscillator(method: SolverMethods) -> Result<(), Box<dyn Error>> {
+    let tspan = (0.0, 20.0);
+    let (solution, dense_output) = solve(
+        method,
+        OdeProblem::new(Oscillator),
+        StateArray::new([1.0, 0.0]),
+        tspan,
+    )?;
+    let result = solution
+        .result
+        .ok_or("expected the result to be saved to memory")?;
+
+    // at() evaluates the step starting at a saved time, so check the end of each step just before
+    let mut step_error = 0.0_f64;
+    for i in 1..result
+        .t
+        .len()
+    {
+        let t = result.t[i] - 1e-12 * (result.t[i] - result.t[i - 1]);
+        step_error = step_error.max(difference(
+            &dense_output.at(t)?,
+            &result.y[i],
+        ));
+    }
+
+    let ts: Vec<f64> = (0..=2000)
+        .map(|i| tspan.0 + (tspan.1 - tspan.0) * i as f64 / 2000.0)
+        .collect();
+    let samples = dense_output.sample(&ts)?;
+    let exact_error = ts
+        .iter()
+        .zip(&samples)
+        .map(|(t, x)| {
+            difference(
+                x,
+                &StateArray::new([t.cos(), -t.sin()]),
+            )
+        })
+        .fold(0.0, f64::max);
+
+    let serialized = ron::to_string(&dense_output)?;
+    let deserialized: DenseOutput<StateArray<2>> = ron::from_str(&serialized)?;
+    let round_trip_error = ts
+        .iter()
+        .zip(&deserialized.sample(&ts)?)
+        .zip(&samples)
+        .map(|((_, a), b)| difference(a, b))
+        .fold(0.0, f64::max);
+
+    println!(
+        "{} steps, error {:.3e} at the end of the steps, {:.3e} against the exact solution, {:.3e} after a round trip",
+        solution
+            .stats
+            .accepted_steps,
+        step_error,
+        exact_error,
+        round_trip_error
+    );
+    Ok(())
+}
+
+/// Checks that the dense output of the bouncing ball gives the state after each bounce at the
+/// bounce times, and the state before the bounce just before them.
+fn bouncing_ball(method: SolverMethods) -> Result<(), Box<dyn Error>> {
+    let problem = OdeProblem::new(Ball).with_continuous_event(ContinuousEvent::new(
+        |x: &StateArray<2>, _t| x[0],
+        |_model: &mut Ball, x: &mut StateArray<2>, _t| {
+            x[1] *= -RESTITUTION;
+        },
+    ));
+    let (solution, dense_output) = solve(
+        method,
+        problem,
+        StateArray::new([1.0, 0.0]),
+        (0.0, 5.0),
+    )?;
+    let result = solution
+        .result
+        .ok_or("expected the result to be saved to memory")?;
+
+    // the result holds the states before and after the action at each event time
+    let mut bounces = 0;
+    let mut error = 0.0_f64;
+    for i in 1..result
+        .t
+        .len()
+    {
+        if result.t[i] != result.t[i - 1] {
+            continue;
+        }
+        let t = result.t[i];
+        bounces += 1;
+        error = error.max(difference(
+            &dense_output.at(t)?,
+            &result.y[i],
+        ));
+        // the velocity just before the bounce is still downward
+        let before = dense_output.at(t - 1e-9)?;
+        if before[1] >= 0.0 {
+            return Err(
+                format!("expected a downward velocity before the bounce at t = {t}").into(),
+            );
+        }
+    }
+    println!(
+        "{} bounces, error {:.3e} after the bounce at the bounce times",
+        bounces, error
+    );
+    Ok(())
+}
+
+/// Evaluates the dense output of Tsit5, Radau IIA and Adams-Bashforth-Moulton at the solver's
+/// steps, between them, at continuous events, and after a serialize/deserialize round trip.
+fn main() -> Result<(), Box<dyn Error>> {
+    let methods: [(&str, SolverMethods); 3] = [
+        (
+            "Tsit5",
+            RungeKuttaMethods::Tsit5.into(),
+        ),
+        (
+            "Radau IIA 5",
+            ImplicitMethods::RadauIIA5.into(),
+        ),
+        (
+            "Adams-Bashforth-Moulton",
+            MultiStepMethods::AdamsBashforthMoulton.into(),
+        ),
+    ];
+    for (name, method) in methods {
+        print!("{name:<24} oscillator: ");
+        oscillator(method)?;
+        print!("{name:<24} bouncing ball: ");
+        bouncing_ball(method)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,117 @@
+use std::error::Error;
+
+use gravity::{
+    GravityModel,
+    egm::{EgmGravity, EgmModel},
+};
+use nadir_diffeq::{
+    OdeProblem,
+    model::OdeModel,
+    solvers::{MultiStepMethods, OdeSolver, RungeKuttaMethods, SolverMethods},
+    state::state_array::StateArray,
+    stepping::AdaptiveStepControl,
+};
+use nalgebra::Vector3;
+
+/// Low Earth orbit in a 20x20 EGM96 gravity field. Earth rotation is ignored, so the field is
+/// fixed in the frame the orbit is integrated in.
+#[derive(Debug, Clone)]
+struct EgmOrbit {
+    gravity: EgmGravity,
+}
+
+impl OdeModel for EgmOrbit {
+    type State = StateArray<6>;
+
+    fn f(
+        &mut self,
+        _t: f64,
+        y: &StateArray<6>,
+        dy: &mut StateArray<6>,
+    ) -> Result<(), Box<dyn Error>> {
+        let acceleration = self
+            .gravity
+            .calculate(&Vector3::new(
+                y[0], y[1], y[2],
+            ))?;
+        for i in 0..3 {
+            dy[i] = y[i + 3];
+            dy[i + 3] = acceleration[i];
+        }
+        Ok(())
+    }
+}
+
+/// Ten orbits of about 97 minutes each
+const TSPAN: (f64, f64) = (0.0, 58000.0);
+
+fn solve(
+    method: SolverMethods,
+    rel_tol: f64,
+) -> Result<(StateArray<6>, usize, usize), Box<dyn Error>> {
+    let model = EgmOrbit {
+        gravity: EgmGravity::new(EgmModel::Egm96, 20, 20)?.with_newtonian(),
+    };
+    // slightly eccentric and inclined
+    let x0 = StateArray::new([7e6, 0.0, 0.0, 0.0, 6500.0, 3900.0]);
+    let solution = OdeSolver::new(method).solve_adaptive(
+        OdeProblem::new(model),
+        x0,
+        TSPAN,
+        AdaptiveStepControl::default()
+            .with_abs_tol(1e-6)
+            .with_rel_tol(rel_tol),
+    )?;
+    let result = solution
+        .result
+        .ok_or("expected the result to be saved to memory")?;
+    let x = *result
+        .y
+        .last()
+        .ok_or("empty result")?;
+    Ok((
+        x,
+        solution
+            .stats
+            .accepted_steps,
+        solution
+            .stats
+            .function_evaluations,
+    ))
+}
+
+/// Compares Adams-Bashforth-Moulton against Tsit5 and Verner9 on an orbit with a high degree
+/// gravity field, where each evaluation of f is expensive. The multistep method needs two
+/// evaluations per step at any order, so it reaches the same accuracy with far fewer evaluations.
+fn main() -> Result<(), Box<dyn Error>> {
+    let (reference, _, _) = solve(
+        RungeKuttaMethods::Verner9.into(),
+        1e-14,
+    )?;
+
+    for (name, method) in [
+        (
+            "Adams-Bashforth-Moulton",
+            MultiStepMethods::AdamsBashforthMoulton.into(),
+        ),
+        (
+            "Tsit5",
+            RungeKuttaMethods::Tsit5.into(),
+        ),
+        (
+            "Verner9",
+            RungeKuttaMethods::Verner9.into(),
+        ),
+    ] {
+        let (x, steps, evaluations) = solve(method, 1e-10)?;
+        let error = ((x[0] - reference[0]).powi(2)
+            + (x[1] - reference[1]).powi(2)
+            + (x[2] - reference[2]).powi(2))
+        .sqrt();
+        println!(
+            "{:<24} position error {:.3e} m, {} steps, {} function evaluations",
+            name, error, steps, evaluations
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,178 @@
+use std::error::Error;
+
+use nadir_diffeq::{
+    OdeProblem,
+    model::{OdeModel, SecondOrderModel},
+    solvers::{OdeSolver, RungeKuttaMethods, SecondOrderMethods, SolverMethods},
+    state::{second_order::SecondOrderState, state_array::StateArray},
+    stepping::AdaptiveStepControl,
+};
+
+/// Two body orbit, in units where mu = 1
+#[derive(Debug, Clone)]
+struct TwoBody;
+
+impl OdeModel for TwoBody {
+    type State = SecondOrderState<StateArray<3>>;
+
+    fn f(
+        &mut self,
+        t: f64,
+        state: &Self::State,
+        derivative: &mut Self::State,
+    ) -> Result<(), Box<dyn Error>> {
+        self.second_order_f(t, state, derivative)
+    }
+}
+
+impl SecondOrderModel for TwoBody {
+    type Position = StateArray<3>;
+
+    fn acceleration(
+        &mut self,
+        _t: f64,
+        position: &StateArray<3>,
+        acceleration: &mut StateArray<3>,
+    ) -> Result<(), Box<dyn Error>> {
+        let r3 = norm(position).powi(3);
+        for i in 0..3 {
+            acceleration[i] = -position[i] / r3;
+        }
+        Ok(())
+    }
+}
+
+/// Eccentricity of the test orbit, which starts at periapsis with a semi-major axis of 1
+const E: f64 = 0.5;
+const ORBITS: f64 = 1000.0;
+
+fn norm(x: &StateArray<3>) -> f64 {
+    (x[0] * x[0] + x[1] * x[1] + x[2] * x[2]).sqrt()
+}
+
+fn energy(x: &SecondOrderState<StateArray<3>>) -> f64 {
+    0.5 * norm(&x.velocity).powi(2) - 1.0 / norm(&x.position)
+}
+
+fn initial_state() -> SecondOrderState<StateArray<3>> {
+    SecondOrderState::new(
+        StateArray::new([1.0 - E, 0.0, 0.0]),
+        StateArray::new([0.0, ((1.0 + E) / (1.0 - E)).sqrt(), 0.0]),
+    )
+}
+
+/// Prints the largest relative energy error over the first and the last 10 orbits
+fn energy_drift(method: SolverMethods, dt: f64) -> Result<(), Box<dyn Error>> {
+    let period = 2.0 * std::f64::consts::PI;
+    let x0 = initial_state();
+    let e0 = energy(&x0);
+    let solution = OdeSolver::new(method).solve_second_order_fixed(
+        OdeProblem::new(TwoBody),
+        x0,
+        (0.0, ORBITS * period),
+        dt,
+    )?;
+    let result = solution
+        .result
+        .ok_or("expected the result to be saved to memory")?;
+
+    let mut first = 0.0_f64;
+    let mut last = 0.0_f64;
+    for (t, x) in result
+        .t
+        .iter()
+        .zip(&result.y)
+    {
+        let error = ((energy(x) - e0) / e0).abs();
+        if *t <= 10.0 * period {
+            first = first.max(error);
+        }
+        if *t >= (ORBITS - 10.0) * period {
+            last = last.max(error);
+        }
+    }
+    println!(
+        "energy error {:.3e} over the first 10 orbits, {:.3e} over the last 10, {} function evaluations",
+        first,
+        last,
+        solution
+            .stats
+            .function_evaluations
+    );
+    Ok(())
+}
+
+/// Prints the energy error after 10 orbits with adaptive steps
+fn adaptive(method: SolverMethods) -> Result<(), Box<dyn Error>> {
+    let x0 = initial_state();
+    let e0 = energy(&x0);
+    let solution = OdeSolver::new(method).solve_second_order_adaptive(
+        OdeProblem::new(TwoBody),
+        x0,
+        (
+            0.0,
+            10.0 * 2.0 * std::f64::consts::PI,
+        ),
+        AdaptiveStepControl::default()
+            .with_abs_tol(1e-12)
+            .with_rel_tol(1e-12),
+    )?;
+    let result = solution
+        .result
+        .ok_or("expected the result to be saved to memory")?;
+    let x = result
+        .y
+        .last()
+        .ok_or("empty result")?;
+    println!(
+        "energy error {:.3e}, {} steps, {} function evaluations",
+        ((energy(x) - e0) / e0).abs(),
+        solution
+            .stats
+            .accepted_steps,
+        solution
+            .stats
+            .function_evaluations
+    );
+    Ok(())
+}
+
+/// Compares the long term energy error of the symplectic methods against Runge-Kutta on an
+/// eccentric orbit. The symplectic errors stay bounded while the Runge-Kutta error grows.
+/// Then compares the adaptive Runge-Kutta-Nyström pairs against the Runge-Kutta pairs they
+/// are derived from.
+fn main() -> Result<(), Box<dyn Error>> {
+    let dt = 2.0 * std::f64::consts::PI / 500.0;
+    print!("Velocity Verlet: ");
+    energy_drift(
+        SecondOrderMethods::VelocityVerlet.into(),
+        dt,
+    )?;
+    print!("Yoshida 4:       ");
+    energy_drift(
+        SecondOrderMethods::Yoshida4.into(),
+        dt,
+    )?;
+    print!("Yoshida 8:       ");
+    energy_drift(
+        SecondOrderMethods::Yoshida8.into(),
+        dt,
+    )?;
+    print!("Runge-Kutta 4:   ");
+    energy_drift(
+        RungeKuttaMethods::Rk4.into(),
+        dt,
+    )?;
+
+    print!("Adaptive Nystrom 4: ");
+    adaptive(SecondOrderMethods::Nystrom4.into())?;
+    print!("Adaptive Nystrom 6: ");
+    adaptive(SecondOrderMethods::Nystrom6.into())?;
+    print!("Adaptive Verner 6:  ");
+    adaptive(RungeKuttaMethods::Verner6.into())?;
+    print!("Adaptive Nystrom 9: ");
+    adaptive(SecondOrderMethods::Nystrom9.into())?;
+    print!("Adaptive Verner 9:  ");
+    adaptive(RungeKuttaMethods::Verner9.into())?;
+    Ok(())
+}
@@ -0,0 +1,154 @@
+use std::error::Error;
+
+use nadir_diffeq::{
+    OdeProblem,
+    events::PeriodicEvent,
+    model::{OdeModel, SecondOrderModel},
+    solvers::{OdeSolver, RungeKuttaMethods, SecondOrderMethods, SolverMethods},
+    state::{second_order::SecondOrderState, state_array::StateArray},
+};
+
+/// Two body orbit, in units where mu = 1
+#[derive(Debug, Clone)]
+struct TwoBody;
+
+impl OdeModel for TwoBody {
+    type State = SecondOrderState<StateArray<3>>;
+
+    fn f(
+        &mut self,
+        t: f64,
+        state: &Self::State,
+        derivative: &mut Self::State,
+    ) -> Result<(), Box<dyn Error>> {
+        self.second_order_f(t, state, derivative)
+    }
+}
+
+impl SecondOrderModel for TwoBody {
+    type Position = StateArray<3>;
+
+    fn acceleration(
+        &mut self,
+        _t: f64,
+        position: &StateArray<3>,
+        acceleration: &mut StateArray<3>,
+    ) -> Result<(), Box<dyn Error>> {
+        let r = (position[0] * position[0] + position[1] * position[1] + position[2] * position[2])
+            .sqrt();
+        let r3 = r * r * r;
+        for i in 0..3 {
+            acceleration[i] = -position[i] / r3;
+        }
+        Ok(())
+    }
+}
+
+/// Semi-major axis and eccentricity of the test orbit, starting at periapsis
+const A: f64 = 1.0;
+const E: f64 = 0.3;
+
+/// Position and velocity at time t from Kepler's equation
+fn kepler(t: f64) -> ([f64; 3], [f64; 3]) {
+    let n = (1.0 / (A * A * A)).sqrt();
+    let mean_anomaly = n * t;
+    let mut eccentric_anomaly = mean_anomaly;
+    for _ in 0..50 {
+        eccentric_anomaly -= (eccentric_anomaly - E * eccentric_anomaly.sin() - mean_anomaly)
+            / (1.0 - E * eccentric_anomaly.cos());
+    }
+    let (sin, cos) = eccentric_anomaly.sin_cos();
+    let b = A * (1.0 - E * E).sqrt();
+    let rate = n / (1.0 - E * cos);
+    (
+        [A * (cos - E), b * sin, 0.0],
+        [-A * sin * rate, b * cos * rate, 0.0],
+    )
+}
+
+fn solve(
+    method: SolverMethods,
+    tspan: (f64, f64),
+    dt: f64,
+    event_period: Option<f64>,
+) -> Result<(), Box<dyn Error>> {
+    let (r0, v0) = kepler(0.0);
+    let x0 = SecondOrderState::new(
+        StateArray::new(r0),
+        StateArray::new(v0),
+    );
+    let mut problem = OdeProblem::new(TwoBody);
+    if let Some(period) = event_period {
+        // does nothing, but the solver can't know that the model wasn't changed
+        problem = problem.with_periodic_event(PeriodicEvent::new(
+            period,
+            0.0,
+            |_, _, _| {},
+        ));
+    }
+    let solver = OdeSolver::new(method);
+    let solution = solver.solve_second_order_fixed(problem, x0, tspan, dt)?;
+
+    let result = solution
+        .result
+        .ok_or("expected the result to be saved to memory")?;
+    let t = *result
+        .t
+        .last()
+        .ok_or("empty result")?;
+    let x = result
+        .y
+        .last()
+        .ok_or("empty result")?;
+    let (r, _) = kepler(t);
+    let error = ((x.position[0] - r[0]).powi(2)
+        + (x.position[1] - r[1]).powi(2)
+        + (x.position[2] - r[2]).powi(2))
+    .sqrt();
+    println!(
+        "position error {:.3e}, {} function evaluations",
+        error,
+        solution
+            .stats
+            .function_evaluations
+    );
+    Ok(())
+}
+
+/// Compares Gauss-Jackson against the analytic Kepler solution and against Yoshida8 and RK4
+/// at the same step size, and shows the cost of restarting Gauss-Jackson at every event.
+fn main() -> Result<(), Box<dyn Error>> {
+    let period = 2.0 * std::f64::consts::PI * (A * A * A).sqrt();
+    let tspan = (0.0, 10.0 * period);
+    let dt = period / 500.0;
+
+    print!("Gauss-Jackson 8: ");
+    solve(
+        SecondOrderMethods::GaussJackson8.into(),
+        tspan,
+        dt,
+        None,
+    )?;
+    print!("Yoshida 8:       ");
+    solve(
+        SecondOrderMethods::Yoshida8.into(),
+        tspan,
+        dt,
+        None,
+    )?;
+    print!("Runge-Kutta 4:   ");
+    solve(
+        RungeKuttaMethods::Rk4.into(),
+        tspan,
+        dt,
+        None,
+    )?;
+    print!("Gauss-Jackson 8 with an event every 10 steps: ");
+    solve(
+        SecondOrderMethods::GaussJackson8.into(),
+        tspan,
+        dt,
+        Some(10.0 * dt),
+    )?;
+    Ok(())
+}
@@ -87,6 +87,7 @@ fn main() -> Result<(), Box<dyn Error>> {
             (0.0, 100.0),
             AdaptiveStepControl::default(),
         )?
+        .result
         .unwrap();
 
     let mut f = Figure::new();
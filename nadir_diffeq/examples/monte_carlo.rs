@@ -68,20 +68,19 @@ fn main() -> Result<(), Box<dyn Error>> {
         UncertainValue::new(0.0),
     ]);
 
-    let results = solver.solve_adaptive(
+    let solutions = solver.solve_adaptive(
         problem,
         x0,
         (0.0, 10.0),
         AdaptiveStepControl::default().with_max_dt(0.1),
     )?;
 
-    if let Some(results) = results {
-        let theme = PlotThemes::Dark.palette();
-        let mut f = Figure::new();
-        let a = f.get_axes(0)?;
+    let theme = PlotThemes::Dark.palette();
+    let mut f = Figure::new();
+    let a = f.get_axes(0)?;
 
-        for i in 0..results.len() {
-            let result = &results[i];
+    for solution in &solutions {
+        if let Some(result) = &solution.result {
             let y: Vec<f64> = result
                 .y
                 .iter()
@@ -95,8 +94,8 @@ fn main() -> Result<(), Box<dyn Error>> {
                     .with_legend(false),
             )
         }
-        QuickPlot::plot(f)?;
     }
+    QuickPlot::plot(f)?;
 
     Ok(())
 }
@@ -52,6 +52,7 @@ fn main() -> Result<(), Box<dyn Error>> {
                 .with_abs_tol(1e-6)
                 .with_rel_tol(1e-6),
         )?
+        .result
         .unwrap();
 
     let mut f = Figure::new();
@@ -0,0 +1,132 @@
+use std::error::Error;
+
+use nadir_diffeq::{
+    OdeProblem,
+    model::OdeModel,
+    solvers::{ImplicitMethods, OdeSolver, RungeKuttaMethods, SolverMethods},
+    state::state_array::StateArray,
+    stepping::AdaptiveStepControl,
+};
+
+/// Robertson's chemical kinetics problem, a standard stiff test case whose rate constants span
+/// nine orders of magnitude.
+#[derive(Debug, Clone)]
+struct Robertson;
+
+impl OdeModel for Robertson {
+    type State = StateArray<3>;
+
+    fn f(
+        &mut self,
+        _t: f64,
+        y: &StateArray<3>,
+        dy: &mut StateArray<3>,
+    ) -> Result<(), Box<dyn Error>> {
+        dy[0] = -0.04 * y[0] + 1e4 * y[1] * y[2];
+        dy[1] = 0.04 * y[0] - 1e4 * y[1] * y[2] - 3e7 * y[1] * y[1];
+        dy[2] = 3e7 * y[1] * y[1];
+        Ok(())
+    }
+}
+
+/// Van der Pol oscillator with mu = 1000, which is stiff everywhere except the fast transitions.
+#[derive(Debug, Clone)]
+struct VanDerPol {
+    mu: f64,
+}
+
+impl OdeModel for VanDerPol {
+    type State = StateArray<2>;
+
+    fn f(
+        &mut self,
+        _t: f64,
+        y: &StateArray<2>,
+        dy: &mut StateArray<2>,
+    ) -> Result<(), Box<dyn Error>> {
+        dy[0] = y[1];
+        dy[1] = self.mu * (1.0 - y[0] * y[0]) * y[1] - y[0];
+        Ok(())
+    }
+
+    fn jacobian(
+        &mut self,
+        _t: f64,
+        y: &StateArray<2>,
+        jacobian: &mut [f64],
+    ) -> Result<bool, Box<dyn Error>> {
+        jacobian[0] = 0.0;
+        jacobian[1] = 1.0;
+        jacobian[2] = -2.0 * self.mu * y[0] * y[1] - 1.0;
+        jacobian[3] = self.mu * (1.0 - y[0] * y[0]);
+        Ok(true)
+    }
+}
+
+/// Reference solution of the Robertson problem at t = 40 (Hairer & Wanner)
+const ROBERTSON_40: [f64; 3] = [0.7158270687193306, 9.185534764557338e-6, 0.2841637457458413];
+
+fn robertson(method: SolverMethods) -> Result<(), Box<dyn Error>> {
+    let solver = OdeSolver::new(method);
+    let solution = solver.solve_adaptive(
+        OdeProblem::new(Robertson),
+        StateArray::new([1.0, 0.0, 0.0]),
+        (0.0, 40.0),
+        AdaptiveStepControl::default()
+            .with_abs_tol(1e-10)
+            .with_rel_tol(1e-8),
+    )?;
+    let result = solution
+        .result
+        .ok_or("expected the result to be saved to memory")?;
+    let y = result
+        .y
+        .last()
+        .ok_or("empty result")?;
+    let error = (0..3)
+        .map(|i| ((y[i] - ROBERTSON_40[i]) / ROBERTSON_40[i]).abs())
+        .fold(0.0, f64::max);
+    println!(
+        "max relative error {:.3e}, {} steps, {} function evaluations",
+        error,
+        solution
+            .stats
+            .accepted_steps,
+        solution
+            .stats
+            .function_evaluations
+    );
+    Ok(())
+}
+
+/// Solves the Robertson problem with Radau IIA and Tsit5, and the stiff Van der Pol oscillator
+/// with Radau IIA using the model's analytic Jacobian.
+fn main() -> Result<(), Box<dyn Error>> {
+    print!("Robertson, Radau IIA 5: ");
+    robertson(ImplicitMethods::RadauIIA5.into())?;
+    print!("Robertson, Tsit5:       ");
+    robertson(RungeKuttaMethods::Tsit5.into())?;
+
+    let solver = OdeSolver::new(ImplicitMethods::RadauIIA5.into());
+    let solution = solver.solve_adaptive(
+        OdeProblem::new(VanDerPol { mu: 1000.0 }),
+        StateArray::new([2.0, 0.0]),
+        (0.0, 3000.0),
+        AdaptiveStepControl::default()
+            .with_abs_tol(1e-6)
+            .with_rel_tol(1e-6),
+    )?;
+    println!(
+        "Van der Pol mu = 1000, Radau IIA 5: {} steps, {} rejected, {} function evaluations",
+        solution
+            .stats
+            .accepted_steps,
+        solution
+            .stats
+            .rejected_steps,
+        solution
+            .stats
+            .function_evaluations
+    );
+    Ok(())
+}
@@ -0,0 +1,115 @@
+use std::error::Error;
+
+use nadir_diffeq::{
+    OdeProblem,
+    model::OdeModel,
+    solution::Solution,
+    solvers::{LieGroupMethods, OdeSolver, RungeKuttaMethods, SolverMethods},
+    state::state_vector::StateVector,
+    stepping::AdaptiveStepControl,
+};
+
+/// Torque free rigid body with principal moments of inertia, with the state
+/// [qx, qy, qz, qw, wx, wy, wz] of the body to inertial quaternion and the body rates
+#[derive(Debug, Clone)]
+struct RigidBody {
+    inertia: [f64; 3],
+}
+
+impl OdeModel for RigidBody {
+    type State = StateVector;
+
+    fn f(&mut self, _t: f64, x: &StateVector, dx: &mut StateVector) -> Result<(), Box<dyn Error>> {
+        let (q, w) = (&x[0..4], &x[4..7]);
+        // q' = q ⊗ (w, 0) / 2
+        dx[0] = 0.5 * (q[3] * w[0] + q[1] * w[2] - q[2] * w[1]);
+        dx[1] = 0.5 * (q[3] * w[1] + q[2] * w[0] - q[0] * w[2]);
+        dx[2] = 0.5 * (q[3] * w[2] + q[0] * w[1] - q[1] * w[0]);
+        dx[3] = -0.5 * (q[0] * w[0] + q[1] * w[1] + q[2] * w[2]);
+        // Euler's equations
+        let [i1, i2, i3] = self.inertia;
+        dx[4] = (i2 - i3) * w[1] * w[2] / i1;
+        dx[5] = (i3 - i1) * w[2] * w[0] / i2;
+        dx[6] = (i1 - i2) * w[0] * w[1] / i3;
+        Ok(())
+    }
+}
+
+const TSPAN: (f64, f64) = (0.0, 600.0);
+
+fn initial_state() -> StateVector {
+    // spinning at about 5 rev/s about the major axis, with some nutation
+    StateVector::new(vec![
+        0.0, 0.0, 0.0, 1.0, 0.5, 0.3, 30.0,
+    ])
+    .with_quaternion(0)
+}
+
+/// Prints the largest departure of the quaternion norm from one and the function evaluations
+fn print_drift(solution: Solution<StateVector>) -> Result<(), Box<dyn Error>> {
+    let evaluations = solution
+        .stats
+        .function_evaluations;
+    let result = solution
+        .result
+        .ok_or("expected the result to be saved to memory")?;
+    let drift = result
+        .y
+        .iter()
+        .map(|x| (x[0] * x[0] + x[1] * x[1] + x[2] * x[2] + x[3] * x[3]).sqrt() - 1.0)
+        .fold(0.0, |max: f64, drift| {
+            max.max(drift.abs())
+        });
+    println!(
+        "quaternion norm drift {:.3e}, {} function evaluations",
+        drift, evaluations
+    );
+    Ok(())
+}
+
+fn solve_fixed(method: SolverMethods, dt: f64) -> Result<(), Box<dyn Error>> {
+    let model = RigidBody { inertia: [1.0, 2.0, 3.0] };
+    let solution = OdeSolver::new(method).solve_lie_group_fixed(
+        OdeProblem::new(model),
+        initial_state(),
+        TSPAN,
+        dt,
+    )?;
+    print_drift(solution)
+}
+
+fn solve_adaptive(method: SolverMethods) -> Result<(), Box<dyn Error>> {
+    let model = RigidBody { inertia: [1.0, 2.0, 3.0] };
+    let solution = OdeSolver::new(method).solve_lie_group_adaptive(
+        OdeProblem::new(model),
+        initial_state(),
+        TSPAN,
+        AdaptiveStepControl::default()
+            .with_abs_tol(1e-6)
+            .with_rel_tol(1e-6),
+    )?;
+    print_drift(solution)
+}
+
+/// Propagates a rigid body spinning at a high rate and compares the drift of the attitude
+/// quaternion from the unit sphere between the Runge-Kutta-Munthe-Kaas methods and the plain
+/// Runge-Kutta methods they are built on. The Lie group methods only update the quaternion through
+/// the exponential map, so it stays on the unit sphere to roundoff, while the plain methods drift.
+fn main() -> Result<(), Box<dyn Error>> {
+    let dt = 0.005;
+    print!("Runge-Kutta 4, fixed step:        ");
+    solve_fixed(
+        RungeKuttaMethods::Rk4.into(),
+        dt,
+    )?;
+    print!("Munthe-Kaas RK4, fixed step:      ");
+    solve_fixed(
+        LieGroupMethods::RungeKuttaMuntheKaas(RungeKuttaMethods::Rk4).into(),
+        dt,
+    )?;
+    print!("Tsit5, adaptive step:             ");
+    solve_adaptive(RungeKuttaMethods::Tsit5.into())?;
+    print!("Munthe-Kaas Tsit5, adaptive step: ");
+    solve_adaptive(LieGroupMethods::RungeKuttaMuntheKaas(RungeKuttaMethods::Tsit5).into())?;
+    Ok(())
+}
@@ -1,12 +1,21 @@
-use std::{error::Error, f64::INFINITY};
+use serde::{Deserialize, Serialize};
+use std::{
+    error::Error,
+    f64::INFINITY,
+    mem::take,
+    sync::{Arc, Mutex, PoisonError},
+    time::{Duration, Instant},
+};
 
 use crate::{OdeModel, saving::WriterManager, state::OdeState};
 
 /// Manages time-based events during ODE integration.
 ///
-/// Supports both:
+/// Supports:
 /// - **Periodic events** that occur at fixed intervals.
-/// - **Continuous events** that trigger when a condition crosses zero.
+/// - **Discrete events** that occur once at a scheduled time.
+/// - **Continuous events** that trigger when a condition crosses zero, optionally in one direction
+///   or stopping the integration, and are recorded in the event log.
 pub struct EventManager<Model, State>
 where
     Model: OdeModel<State = State>,
@@ -16,6 +25,8 @@ where
     pub continuous_events: Vec<ContinuousEvent<Model, State>>,
     /// List of periodic events occurring at fixed time intervals.
     pub periodic_events: Vec<PeriodicEvent<Model, State>>,
+    /// List of one-shot events occurring at scheduled times.
+    pub discrete_events: Vec<DiscreteEvent<Model, State>>,
     /// List of save events occurring at some frequency.
     pub save_events: Vec<SaveEvent<Model, State>>,
     /// List of events to run at the beginning of the sim.
@@ -24,24 +35,40 @@ where
     pub postsim_events: Vec<PostSimEvent<Model>>,
     /// Next scheduled periodic event time and its indices.
     next_periodic: NextEvent,
-    /// Placeholder for future discrete events (not yet implemented).
+    /// Next scheduled discrete event time and its indices.
     next_discrete: NextEvent,
+    /// Next discontinuity in f reported by the model.
+    next_discontinuity: f64,
+    /// Continuous events that crossed zero in the current step.
+    crossings: Vec<Crossing>,
+    /// Continuous events located in the current step, to be performed at the same time.
+    pending_continuous: Vec<(usize, EventDirection)>,
+    /// Record of the continuous events that occurred, in order.
+    log: Vec<EventRecord>,
+    /// The terminal continuous event that stopped the integration, if any.
+    termination: Option<EventRecord>,
+    /// Time spent processing events.
+    elapsed: Duration,
 }
 
-// Manual implementation so that Model and State  are not required to be Clone
+// Manual implementation so that Model and State  are not required to be Clone.
+// The clone starts a fresh run, without the event log, termination or timing of the original.
 impl<Model, State> Clone for EventManager<Model, State>
 where
     Model: OdeModel<State = State>,
     State: OdeState,
 {
     fn clone(&self) -> Self {
-        Self {
+        let mut events = Self {
             continuous_events: self
                 .continuous_events
                 .clone(),
             periodic_events: self
                 .periodic_events
                 .clone(),
+            discrete_events: self
+                .discrete_events
+                .clone(),
             save_events: self
                 .save_events
                 .clone(),
@@ -57,7 +84,15 @@ where
             next_discrete: self
                 .next_discrete
                 .clone(),
-        }
+            next_discontinuity: INFINITY,
+            crossings: Vec::new(),
+            pending_continuous: Vec::new(),
+            log: Vec::new(),
+            termination: None,
+            elapsed: Duration::ZERO,
+        };
+        events.reset();
+        events
     }
 }
 
@@ -71,16 +106,34 @@ where
         Self {
             continuous_events: Vec::new(),
             periodic_events: Vec::new(),
+            discrete_events: Vec::new(),
             save_events: Vec::new(),
             presim_events: Vec::new(),
             postsim_events: Vec::new(),
             next_periodic: NextEvent { next_time: INFINITY, index: Vec::new() },
             next_discrete: NextEvent { next_time: INFINITY, index: Vec::new() },
+            next_discontinuity: INFINITY,
+            crossings: Vec::new(),
+            pending_continuous: Vec::new(),
+            log: Vec::new(),
+            termination: None,
+            elapsed: Duration::ZERO,
         }
     }
 
     /// Add a new continuous event that is evaluated every step.
-    pub fn add_continuous(&mut self, event: ContinuousEvent<Model, State>) {
+    /// Unnamed events are named after their index, e.g. `continuous_event_0`.
+    pub fn add_continuous(&mut self, mut event: ContinuousEvent<Model, State>) {
+        if event
+            .name
+            .is_empty()
+        {
+            event.name = format!(
+                "continuous_event_{}",
+                self.continuous_events
+                    .len()
+            );
+        }
         self.continuous_events
             .push(event);
     }
@@ -92,6 +145,13 @@ where
         self.find_next_periodic();
     }
 
+    /// Add a discrete event and update the internal schedule.
+    pub fn add_discrete(&mut self, event: DiscreteEvent<Model, State>) {
+        self.discrete_events
+            .push(event);
+        self.find_next_discrete();
+    }
+
     /// Add a new continuous event that is evaluated every step.
     pub fn add_save(&mut self, event: SaveEvent<Model, State>) {
         self.save_events
@@ -110,7 +170,8 @@ where
             .push(event);
     }
 
-    /// Returns the time of the next scheduled event (periodic or discrete).
+    /// Returns the time of the next scheduled event (periodic or discrete), or of the next
+    /// discontinuity reported by the model if that is sooner.
     pub fn next_time(&self) -> f64 {
         self.next_periodic
             .next_time
@@ -118,6 +179,7 @@ where
                 self.next_discrete
                     .next_time,
             )
+            .min(self.next_discontinuity)
     }
 
     /// Updates internal record of which periodic events are next.
@@ -167,6 +229,407 @@ where
             .next_time = min_time;
     }
 
+    /// Updates internal record of which pending discrete events are next.
+    fn find_next_discrete(&mut self) {
+        self.next_discrete
+            .next_time = INFINITY;
+        self.next_discrete
+            .index
+            .clear();
+
+        for (i, event) in self
+            .discrete_events
+            .iter()
+            .enumerate()
+            .filter(|(_, event)| !event.fired)
+        {
+            let time_diff = event.time
+                - self
+                    .next_discrete
+                    .next_time;
+
+            if time_diff.abs() < 1e-12 {
+                self.next_discrete
+                    .index
+                    .push(i);
+            } else if time_diff < 0.0 {
+                self.next_discrete
+                    .next_time = event.time;
+                self.next_discrete
+                    .index
+                    .clear();
+                self.next_discrete
+                    .index
+                    .push(i);
+            }
+        }
+    }
+
+    /// Record of the continuous events that occurred, in order.
+    pub fn log(&self) -> &[EventRecord] {
+        &self.log
+    }
+
+    /// Takes the record of the continuous events that occurred, leaving it empty.
+    pub fn take_log(&mut self) -> Vec<EventRecord> {
+        take(&mut self.log)
+    }
+
+    /// The terminal continuous event that stopped the integration, if any.
+    pub fn termination(&self) -> Option<&EventRecord> {
+        self.termination
+            .as_ref()
+    }
+
+    /// Whether a terminal continuous event has stopped the integration.
+    pub fn terminated(&self) -> bool {
+        self.termination
+            .is_some()
+    }
+
+    /// Time spent processing events, including locating continuous events and saving each step.
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    /// Clears the state of a previous run, so the manager can be reused for another solve:
+    /// the event log, termination, timing, the last condition values of the continuous events,
+    /// which discrete events have fired, and the periodic events' schedules back to their start times.
+    pub fn reset(&mut self) {
+        for event in &mut self.continuous_events {
+            event.first_pass = true;
+        }
+        for event in &mut self.periodic_events {
+            event.next_time = event.start_time;
+        }
+        for event in &mut self.discrete_events {
+            event.fired = false;
+        }
+        self.find_next_periodic();
+        self.find_next_discrete();
+        self.next_discontinuity = INFINITY;
+        self.crossings
+            .clear();
+        self.pending_continuous
+            .clear();
+        self.log
+            .clear();
+        self.termination = None;
+        self.elapsed = Duration::ZERO;
+    }
+
+    /// Runs the presim events with the initial state `x0` at time `t`.
+    pub fn process_presim_events(
+        &mut self,
+        model: &mut Model,
+        x0: &State,
+        t: f64,
+        writer_manager: &Option<WriterManager>,
+    ) -> Result<(), Box<dyn Error>> {
+        let start = Instant::now();
+        for event in &mut self.presim_events {
+            event
+                .f
+                .call(model, x0, t, writer_manager)?;
+        }
+        self.next_discontinuity = model
+            .next_discontinuity(t)
+            .unwrap_or(INFINITY);
+        self.elapsed += start.elapsed();
+        Ok(())
+    }
+
+    /// Runs the postsim events.
+    pub fn process_postsim_events(
+        &mut self,
+        model: &mut Model,
+        writer_manager: &Option<WriterManager>,
+    ) -> Result<(), Box<dyn Error>> {
+        let start = Instant::now();
+        for event in &mut self.postsim_events {
+            event
+                .f
+                .call(model, writer_manager)?;
+        }
+        self.elapsed += start.elapsed();
+        Ok(())
+    }
+
+    /// Checks whether any continuous event condition may have crossed zero in its direction over a step
+    /// from `(t0, x0)` to `(t1, x1)`. If so, `locate_continuous_events` must be called to find when.
+    pub fn detect_continuous_events(&mut self, t0: f64, x0: &State, t1: f64, x1: &State) -> bool {
+        let start = Instant::now();
+        self.crossings
+            .clear();
+        for (i, event) in self
+            .continuous_events
+            .iter_mut()
+            .enumerate()
+        {
+            if event.first_pass {
+                // need to calculate original value
+                event.last_check = event
+                    .condition
+                    .call(x0, t0);
+                event.first_pass = false;
+            }
+
+            let fa = event.last_check;
+            let fb = event
+                .condition
+                .call(x1, t1);
+            event.last_check = fb;
+
+            let direction = if fa < 0.0 && fb >= 0.0 {
+                EventDirection::Rising
+            } else if fa > 0.0 && fb <= 0.0 {
+                EventDirection::Falling
+            } else if fa == 0.0 && fb != 0.0 {
+                // starting on the surface, such as right after the event occurred, it only crossed
+                // if it left the surface to the other side, which is checked when locating
+                EventDirection::Either
+            } else {
+                continue;
+            };
+            if event
+                .direction
+                .matches(direction)
+            {
+                self.crossings
+                    .push(Crossing { index: i, t0, fa, t1, fb, direction, time: None });
+            }
+        }
+        self.elapsed += start.elapsed();
+        !self
+            .crossings
+            .is_empty()
+    }
+
+    /// Locates the crossings found by `detect_continuous_events` with Brent's method, where `g(condition, t)`
+    /// evaluates the condition on the solver's interpolant at `t` within the step.
+    ///
+    /// Returns the time of the earliest crossing, after which `perform_continuous_events` must be called
+    /// with the state at that time. Events crossing within their tolerance of the same time are performed
+    /// together, in the order they were added. Later crossings in the step are found again in the following
+    /// steps, once the solver restarts from the earliest.
+    pub fn locate_continuous_events<G>(&mut self, mut g: G) -> Result<Option<f64>, Box<dyn Error>>
+    where
+        G: FnMut(&mut ConditionFn<State>, f64) -> Result<f64, Box<dyn Error>>,
+    {
+        let start = Instant::now();
+        let mut event_time = INFINITY;
+        for crossing in &mut self.crossings {
+            let event = &mut self.continuous_events[crossing.index];
+
+            if crossing.fa == 0.0 {
+                // check which side the condition left the surface to, once it is clear of the event
+                // tolerance. Assuming it changes at about its average rate over the step, that takes
+                // tol / rate, doubled for margin.
+                let rate = crossing
+                    .fb
+                    .abs()
+                    / (crossing.t1 - crossing.t0);
+                let t0 = (crossing.t0 + 2.0 * event.tol / rate).min(crossing.t1);
+                let fa = g(&mut event.condition, t0)?;
+                crossing.direction = if fa < 0.0 && crossing.fb > 0.0 {
+                    EventDirection::Rising
+                } else if fa > 0.0 && crossing.fb < 0.0 {
+                    EventDirection::Falling
+                } else {
+                    continue;
+                };
+                if !event
+                    .direction
+                    .matches(crossing.direction)
+                {
+                    continue;
+                }
+                crossing.t0 = t0;
+                crossing.fa = fa;
+            }
+
+            let time = if crossing.fb == 0.0 {
+                crossing.t1
+            } else {
+                find_crossing(
+                    crossing.t0,
+                    crossing.fa,
+                    crossing.t1,
+                    crossing.fb,
+                    event.tol,
+                    |t| g(&mut event.condition, t),
+                )?
+            };
+            crossing.time = Some(time);
+            event_time = event_time.min(time);
+        }
+
+        if event_time == INFINITY {
+            self.elapsed += start.elapsed();
+            return Ok(None);
+        }
+
+        self.pending_continuous
+            .clear();
+        for crossing in &self.crossings {
+            let Some(time) = crossing.time else {
+                continue;
+            };
+            let event = &mut self.continuous_events[crossing.index];
+            if time == event_time
+                || g(
+                    &mut event.condition,
+                    event_time,
+                )?
+                .abs()
+                    <= event.tol
+            {
+                self.pending_continuous
+                    .push((
+                        crossing.index,
+                        crossing.direction,
+                    ));
+            }
+        }
+
+        self.elapsed += start.elapsed();
+        Ok(Some(event_time))
+    }
+
+    /// Performs the continuous events found by `locate_continuous_events` at time `t`, with `state` interpolated
+    /// to that time, and records them in the log. A terminal event stops the integration after the step.
+    pub fn perform_continuous_events(&mut self, model: &mut Model, state: &mut State, t: f64) {
+        let start = Instant::now();
+        let pending = take(&mut self.pending_continuous);
+
+        for &(i, direction) in &pending {
+            let event = &mut self.continuous_events[i];
+            event
+                .action
+                .call(model, state, t);
+
+            let record = EventRecord {
+                t,
+                name: event
+                    .name
+                    .clone(),
+                direction,
+            };
+            if event.terminal
+                && self
+                    .termination
+                    .is_none()
+            {
+                self.termination = Some(record.clone());
+            }
+            self.log
+                .push(record);
+        }
+
+        // the step ends here now, so restart every condition from the state after the actions.
+        // events that just occurred and are still within tolerance are treated as being on the surface,
+        // so they need to leave it and cross back before they trigger again
+        for (i, event) in self
+            .continuous_events
+            .iter_mut()
+            .enumerate()
+        {
+            let value = event
+                .condition
+                .call(state, t);
+            let occurred = pending
+                .iter()
+                .any(|(j, _)| *j == i);
+            event.last_check = if occurred && value.abs() <= event.tol {
+                0.0
+            } else {
+                value
+            };
+        }
+
+        self.pending_continuous = pending;
+        self.pending_continuous
+            .clear();
+        self.elapsed += start.elapsed();
+    }
+
+    /// Runs the save events that save every step with the `state` at time `t`.
+    pub fn process_save_events(
+        &mut self,
+        model: &Model,
+        state: &State,
+        t: f64,
+        manager: &mut WriterManager,
+    ) {
+        let start = Instant::now();
+        for event in &mut self.save_events {
+            if event
+                .options
+                .every_step
+            {
+                event
+                    .save_fn
+                    .call(model, state, t, manager);
+            }
+        }
+        self.elapsed += start.elapsed();
+    }
+
+    /// Executes the discrete and then the periodic events scheduled to occur at or before time `t`,
+    /// so one-shot commands and faults are applied before periodic software runs at the same time.
+    /// Then asks the model for its next discontinuity, which may have been scheduled by the events.
+    ///
+    /// Returns `true` if any event was triggered or a discontinuity of the model was reached.
+    pub fn process_scheduled_events(
+        &mut self,
+        model: &mut Model,
+        state: &mut State,
+        t: f64,
+    ) -> bool {
+        let start = Instant::now();
+        let discrete_event_occurred = self.process_discrete_events(model, state, t);
+        let periodic_event_occurred = self.process_periodic_events(model, state, t);
+        let discontinuity_reached = t >= self.next_discontinuity;
+        self.next_discontinuity = model
+            .next_discontinuity(t)
+            .unwrap_or(INFINITY);
+        self.elapsed += start.elapsed();
+        discrete_event_occurred || periodic_event_occurred || discontinuity_reached
+    }
+
+    /// Executes any pending discrete events that are scheduled to occur at or before time `t`.
+    ///
+    /// Returns `true` if any event was triggered.
+    pub fn process_discrete_events(
+        &mut self,
+        model: &mut Model,
+        state: &mut State,
+        t: f64,
+    ) -> bool {
+        let mut discrete_event_occurred = false;
+
+        // events scheduled before t in between steps of a solver that can't land on them still fire
+        while t
+            >= self
+                .next_discrete
+                .next_time
+        {
+            discrete_event_occurred = true;
+
+            for i in &self
+                .next_discrete
+                .index
+            {
+                let event = &mut self.discrete_events[*i];
+                event.perform_event(model, state, t);
+            }
+
+            self.find_next_discrete();
+        }
+
+        discrete_event_occurred
+    }
+
     /// Executes any periodic events that are scheduled to occur at or before time `t`.
     ///
     /// Returns `true` if any event was triggered.
@@ -202,6 +665,267 @@ where
     }
 }
 
+/// A callback run by an event, either a plain function pointer or a closure
+/// that captures configuration such as a target angle, a maneuver table, or a channel sender.
+///
+/// Function pointers are called directly. Closures are boxed and kept alongside a factory
+/// that rebuilds them from the values originally captured, so each clone of an event
+/// (e.g. for every Monte Carlo run) starts from a fresh copy of the closure.
+pub enum EventFn<P, C: ?Sized> {
+    /// A plain function pointer.
+    Pointer(P),
+    /// A boxed closure.
+    Closure(EventClosure<C>),
+}
+
+impl<P: Copy, C: ?Sized> Clone for EventFn<P, C> {
+    fn clone(&self) -> Self {
+        match self {
+            Self::Pointer(f) => Self::Pointer(*f), // Function pointers implement Copy/Clone
+            Self::Closure(f) => Self::Closure(f.clone()),
+        }
+    }
+}
+
+/// A boxed closure along with the factory used to clone it.
+pub struct EventClosure<C: ?Sized> {
+    /// The closure that is called. Only ever accessed through `&mut self`,
+    /// the mutex just makes the event `Sync` without requiring the closure to be.
+    f: Mutex<Box<C>>,
+    /// Creates a new box from the closure as it was originally captured.
+    factory: Arc<dyn Fn() -> Box<C> + Send + Sync>,
+}
+
+impl<C: ?Sized + 'static> EventClosure<C> {
+    /// Creates a new `EventClosure` from `f`, where `boxed` converts it to the boxed closure type.
+    fn new<F>(f: F, boxed: fn(F) -> Box<C>) -> Self
+    where
+        F: Clone + Send + 'static,
+    {
+        let first = boxed(f.clone());
+        let prototype = Mutex::new(f);
+        let factory = move || {
+            boxed(
+                prototype
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .clone(),
+            )
+        };
+        Self { f: Mutex::new(first), factory: Arc::new(factory) }
+    }
+}
+
+impl<C: ?Sized> EventClosure<C> {
+    /// Mutable access to the closure.
+    fn get_mut(&mut self) -> &mut C {
+        self.f
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl<C: ?Sized> Clone for EventClosure<C> {
+    fn clone(&self) -> Self {
+        Self {
+            f: Mutex::new((self.factory)()),
+            factory: self
+                .factory
+                .clone(),
+        }
+    }
+}
+
+/// Action for periodic, discrete and continuous events, which can modify the model and state.
+pub type ActionFn<Model, State> =
+    EventFn<fn(&mut Model, &mut State, f64), dyn FnMut(&mut Model, &mut State, f64) + Send>;
+
+impl<Model, State> ActionFn<Model, State> {
+    /// Wraps a closure, which is cloned for each copy of the event.
+    pub fn closure<F>(f: F) -> Self
+    where
+        Model: 'static,
+        State: 'static,
+        F: FnMut(&mut Model, &mut State, f64) + Clone + Send + 'static,
+    {
+        Self::Closure(EventClosure::new(f, |f| {
+            Box::new(f)
+        }))
+    }
+
+    /// Calls the action.
+    pub fn call(&mut self, model: &mut Model, state: &mut State, t: f64) {
+        match self {
+            Self::Pointer(f) => f(model, state, t),
+            Self::Closure(f) => (f.get_mut())(model, state, t),
+        }
+    }
+}
+
+/// Condition for continuous events, whose zero-crossing triggers the event.
+pub type ConditionFn<State> = EventFn<fn(&State, f64) -> f64, dyn FnMut(&State, f64) -> f64 + Send>;
+
+impl<State> ConditionFn<State> {
+    /// Wraps a closure, which is cloned for each copy of the event.
+    pub fn closure<F>(f: F) -> Self
+    where
+        State: 'static,
+        F: FnMut(&State, f64) -> f64 + Clone + Send + 'static,
+    {
+        Self::Closure(EventClosure::new(f, |f| {
+            Box::new(f)
+        }))
+    }
+
+    /// Evaluates the condition.
+    pub fn call(&mut self, state: &State, t: f64) -> f64 {
+        match self {
+            Self::Pointer(f) => f(state, t),
+            Self::Closure(f) => (f.get_mut())(state, t),
+        }
+    }
+}
+
+/// Initialization for save events, which sets up the writers.
+pub type SaveInitFn<Model, State> = EventFn<
+    fn(&mut Model, &State, &mut WriterManager),
+    dyn FnMut(&mut Model, &State, &mut WriterManager) + Send,
+>;
+
+impl<Model, State> SaveInitFn<Model, State> {
+    /// Wraps a closure, which is cloned for each copy of the event.
+    pub fn closure<F>(f: F) -> Self
+    where
+        Model: 'static,
+        State: 'static,
+        F: FnMut(&mut Model, &State, &mut WriterManager) + Clone + Send + 'static,
+    {
+        Self::Closure(EventClosure::new(f, |f| {
+            Box::new(f)
+        }))
+    }
+
+    /// Calls the initialization.
+    pub fn call(&mut self, model: &mut Model, state: &State, manager: &mut WriterManager) {
+        match self {
+            Self::Pointer(f) => f(model, state, manager),
+            Self::Closure(f) => (f.get_mut())(model, state, manager),
+        }
+    }
+}
+
+/// Save function for save events, which writes the model and state at time `t`.
+pub type SaveFn<Model, State> = EventFn<
+    fn(&Model, &State, f64, &mut WriterManager),
+    dyn FnMut(&Model, &State, f64, &mut WriterManager) + Send,
+>;
+
+impl<Model, State> SaveFn<Model, State> {
+    /// Wraps a closure, which is cloned for each copy of the event.
+    pub fn closure<F>(f: F) -> Self
+    where
+        Model: 'static,
+        State: 'static,
+        F: FnMut(&Model, &State, f64, &mut WriterManager) + Clone + Send + 'static,
+    {
+        Self::Closure(EventClosure::new(f, |f| {
+            Box::new(f)
+        }))
+    }
+
+    /// Calls the save function.
+    pub fn call(&mut self, model: &Model, state: &State, t: f64, manager: &mut WriterManager) {
+        match self {
+            Self::Pointer(f) => f(model, state, t, manager),
+            Self::Closure(f) => (f.get_mut())(model, state, t, manager),
+        }
+    }
+}
+
+/// Function for presim events, run once before the start of the simulation.
+pub type PreSimFn<Model, State> = EventFn<
+    fn(&mut Model, &State, f64, &Option<WriterManager>) -> Result<(), Box<dyn Error>>,
+    dyn FnMut(&mut Model, &State, f64, &Option<WriterManager>) -> Result<(), Box<dyn Error>> + Send,
+>;
+
+impl<Model, State> PreSimFn<Model, State> {
+    /// Wraps a closure, which is cloned for each copy of the event.
+    pub fn closure<F>(f: F) -> Self
+    where
+        Model: 'static,
+        State: 'static,
+        F: FnMut(&mut Model, &State, f64, &Option<WriterManager>) -> Result<(), Box<dyn Error>>
+            + Clone
+            + Send
+            + 'static,
+    {
+        Self::Closure(EventClosure::new(f, |f| {
+            Box::new(f)
+        }))
+    }
+
+    /// Calls the presim function.
+    pub fn call(
+        &mut self,
+        model: &mut Model,
+        state: &State,
+        t: f64,
+        manager: &Option<WriterManager>,
+    ) -> Result<(), Box<dyn Error>> {
+        match self {
+            Self::Pointer(f) => f(model, state, t, manager),
+            Self::Closure(f) => (f.get_mut())(model, state, t, manager),
+        }
+    }
+}
+
+/// Function for postsim events, run once at the end of the simulation.
+pub type PostSimFn<Model> = EventFn<
+    fn(&Model, &Option<WriterManager>) -> Result<(), Box<dyn Error>>,
+    dyn FnMut(&Model, &Option<WriterManager>) -> Result<(), Box<dyn Error>> + Send,
+>;
+
+impl<Model> PostSimFn<Model> {
+    /// Wraps a closure, which is cloned for each copy of the event.
+    pub fn closure<F>(f: F) -> Self
+    where
+        Model: 'static,
+        F: FnMut(&Model, &Option<WriterManager>) -> Result<(), Box<dyn Error>>
+            + Clone
+            + Send
+            + 'static,
+    {
+        Self::Closure(EventClosure::new(f, |f| {
+            Box::new(f)
+        }))
+    }
+
+    /// Calls the postsim function.
+    pub fn call(
+        &mut self,
+        model: &Model,
+        manager: &Option<WriterManager>,
+    ) -> Result<(), Box<dyn Error>> {
+        match self {
+            Self::Pointer(f) => f(model, manager),
+            Self::Closure(f) => (f.get_mut())(model, manager),
+        }
+    }
+}
+
+/// Internal struct for a continuous event condition that may have crossed zero between `t0` and `t1`,
+/// with `time` set once the crossing is located.
+#[derive(Clone)]
+struct Crossing {
+    index: usize,
+    t0: f64,
+    fa: f64,
+    t1: f64,
+    fb: f64,
+    direction: EventDirection,
+    time: Option<f64>,
+}
+
 /// Internal struct for tracking the next event time and indices of tied events.
 #[derive(Clone)]
 struct NextEvent {
@@ -219,8 +943,10 @@ where
     pub period: f64,
     /// Time at which the event is next scheduled to run.
     pub next_time: f64,
+    /// Time of the first trigger, which `EventManager::reset` schedules the event at again.
+    start_time: f64,
     /// The function to call when the event is triggered.
-    f: fn(&mut Model, &mut State, f64),
+    f: ActionFn<Model, State>,
 }
 
 impl<Model, State> PeriodicEvent<Model, State>
@@ -235,12 +961,38 @@ where
     /// * `start_time` - Initial trigger time.
     /// * `f` - Function to call at each trigger.
     pub fn new(period: f64, start_time: f64, f: fn(&mut Model, &mut State, f64)) -> Self {
-        Self { period, next_time: start_time, f }
+        Self {
+            period,
+            next_time: start_time,
+            start_time,
+            f: EventFn::Pointer(f),
+        }
+    }
+
+    /// Creates a new `PeriodicEvent` from a closure, which can capture configuration.
+    ///
+    /// # Arguments
+    /// * `period` - Time interval between executions.
+    /// * `start_time` - Initial trigger time.
+    /// * `f` - Closure to call at each trigger.
+    pub fn from_closure<F>(period: f64, start_time: f64, f: F) -> Self
+    where
+        Model: 'static,
+        State: 'static,
+        F: FnMut(&mut Model, &mut State, f64) + Clone + Send + 'static,
+    {
+        Self {
+            period,
+            next_time: start_time,
+            start_time,
+            f: ActionFn::closure(f),
+        }
     }
 
     /// Triggers the event and schedules the next one based on its period.
     pub fn perform_event(&mut self, model: &mut Model, state: &mut State, t: f64) {
-        (self.f)(model, state, t);
+        self.f
+            .call(model, state, t);
         self.next_time = t + self.period;
     }
 }
@@ -254,7 +1006,82 @@ where
         Self {
             period: self.period,
             next_time: self.next_time,
-            f: self.f, // Function pointers implement Copy/Clone
+            start_time: self.start_time,
+            f: self
+                .f
+                .clone(),
+        }
+    }
+}
+
+/// Represents a user-defined action to perform once at a scheduled time,
+/// such as a maneuver, a deployment command, or a fault.
+pub struct DiscreteEvent<Model, State>
+where
+    Model: OdeModel<State = State>,
+    State: OdeState,
+{
+    /// Time at which the event is scheduled to run.
+    pub time: f64,
+    /// Whether the event has already run.
+    fired: bool,
+    /// The function to call when the event is triggered.
+    f: ActionFn<Model, State>,
+}
+
+impl<Model, State> DiscreteEvent<Model, State>
+where
+    Model: OdeModel<State = State>,
+    State: OdeState,
+{
+    /// Creates a new `DiscreteEvent`.
+    ///
+    /// # Arguments
+    /// * `time` - Time to trigger the event.
+    /// * `f` - Function to call when triggered.
+    pub fn new(time: f64, f: fn(&mut Model, &mut State, f64)) -> Self {
+        Self { time, fired: false, f: EventFn::Pointer(f) }
+    }
+
+    /// Creates a new `DiscreteEvent` from a closure, which can capture configuration.
+    ///
+    /// # Arguments
+    /// * `time` - Time to trigger the event.
+    /// * `f` - Closure to call when triggered.
+    pub fn from_closure<F>(time: f64, f: F) -> Self
+    where
+        Model: 'static,
+        State: 'static,
+        F: FnMut(&mut Model, &mut State, f64) + Clone + Send + 'static,
+    {
+        Self { time, fired: false, f: ActionFn::closure(f) }
+    }
+
+    /// Whether the event has already run.
+    pub fn fired(&self) -> bool {
+        self.fired
+    }
+
+    /// Triggers the event and marks it as fired so it does not run again.
+    pub fn perform_event(&mut self, model: &mut Model, state: &mut State, t: f64) {
+        self.f
+            .call(model, state, t);
+        self.fired = true;
+    }
+}
+
+impl<Model, State> Clone for DiscreteEvent<Model, State>
+where
+    Model: OdeModel<State = State>,
+    State: OdeState,
+{
+    fn clone(&self) -> Self {
+        Self {
+            time: self.time,
+            fired: self.fired,
+            f: self
+                .f
+                .clone(),
         }
     }
 }
@@ -272,11 +1099,17 @@ where
     /// Flag to indicate if this is the first check (for setup).
     pub first_pass: bool,
     /// A function representing the condition. Should return a signed value.
-    pub condition: fn(&State, f64) -> f64,
+    pub condition: ConditionFn<State>,
     /// Action to perform when the zero-crossing is detected.
-    pub action: fn(&mut Model, &mut State, f64),
+    pub action: ActionFn<Model, State>,
     /// Tolerance used to detect zero-crossing.
     pub tol: f64,
+    /// Name of the event in the event log.
+    pub name: String,
+    /// Which zero-crossings trigger the event.
+    pub direction: EventDirection,
+    /// Whether the event stops the integration.
+    pub terminal: bool,
 }
 
 impl<Model, State> ContinuousEvent<Model, State>
@@ -293,9 +1126,36 @@ where
         Self {
             last_check: 1.0,
             first_pass: true,
-            condition,
-            action,
+            condition: EventFn::Pointer(condition),
+            action: EventFn::Pointer(action),
+            tol: 1e-6,
+            name: String::new(),
+            direction: EventDirection::Either,
+            terminal: false,
+        }
+    }
+
+    /// Creates a new continuous event from closures, which can capture configuration.
+    ///
+    /// # Arguments
+    /// * `condition` - A closure returning a value whose zero-crossing triggers the event.
+    /// * `action` - The action to take when the event is triggered.
+    pub fn from_closures<C, A>(condition: C, action: A) -> Self
+    where
+        Model: 'static,
+        State: 'static,
+        C: FnMut(&State, f64) -> f64 + Clone + Send + 'static,
+        A: FnMut(&mut Model, &mut State, f64) + Clone + Send + 'static,
+    {
+        Self {
+            last_check: 1.0,
+            first_pass: true,
+            condition: ConditionFn::closure(condition),
+            action: ActionFn::closure(action),
             tol: 1e-6,
+            name: String::new(),
+            direction: EventDirection::Either,
+            terminal: false,
         }
     }
 
@@ -304,6 +1164,24 @@ where
         self.tol = tol;
         self
     }
+
+    /// Sets the name of the event in the event log.
+    pub fn with_name(mut self, name: &str) -> Self {
+        self.name = name.to_string();
+        self
+    }
+
+    /// Sets which zero-crossings trigger the event, e.g. `Falling` to stop at impact but not at liftoff.
+    pub fn with_direction(mut self, direction: EventDirection) -> Self {
+        self.direction = direction;
+        self
+    }
+
+    /// Sets whether the event stops the integration once its action has been performed.
+    pub fn with_terminal(mut self, terminal: bool) -> Self {
+        self.terminal = terminal;
+        self
+    }
 }
 
 impl<Model, State> Clone for ContinuousEvent<Model, State>
@@ -315,13 +1193,140 @@ where
         Self {
             last_check: self.last_check,
             first_pass: self.first_pass,
-            condition: self.condition, // Function pointer implements Copy/Clone
-            action: self.action,       // Function pointer implements Copy/Clone
+            condition: self
+                .condition
+                .clone(),
+            action: self
+                .action
+                .clone(),
             tol: self.tol,
+            name: self
+                .name
+                .clone(),
+            direction: self.direction,
+            terminal: self.terminal,
         }
     }
 }
 
+/// Direction of a zero-crossing of a continuous event condition.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EventDirection {
+    /// The condition goes from negative to positive.
+    Rising,
+    /// The condition goes from positive to negative.
+    Falling,
+    /// Either direction, only used to filter events.
+    Either,
+}
+
+impl EventDirection {
+    /// Whether a crossing in `direction` passes this filter.
+    fn matches(self, direction: EventDirection) -> bool {
+        self == EventDirection::Either || self == direction
+    }
+}
+
+/// A continuous event occurrence recorded in the event log.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct EventRecord {
+    /// Time of the event.
+    pub t: f64,
+    /// Name of the event.
+    pub name: String,
+    /// Direction of the zero-crossing.
+    pub direction: EventDirection,
+}
+
+/// Finds the time in `[a, b]` where `g` crosses zero with Brent's method, given `fa` and `fb` of opposite signs.
+///
+/// Stops once `|g| <= tol` or the bracket can't shrink further, returning the best estimate.
+fn find_crossing<G>(
+    mut a: f64,
+    mut fa: f64,
+    mut b: f64,
+    mut fb: f64,
+    tol: f64,
+    mut g: G,
+) -> Result<f64, Box<dyn Error>>
+where
+    G: FnMut(f64) -> Result<f64, Box<dyn Error>>,
+{
+    const MAX_ITERS: usize = 100;
+
+    let mut c = b;
+    let mut fc = fb;
+    let mut d = b - a;
+    let mut e = d;
+
+    for _ in 0..MAX_ITERS {
+        if fb * fc > 0.0 {
+            // keep the root bracketed between b and c
+            c = a;
+            fc = fa;
+            d = b - a;
+            e = d;
+        }
+        if fc.abs() < fb.abs() {
+            a = b;
+            b = c;
+            c = a;
+            fa = fb;
+            fb = fc;
+            fc = fa;
+        }
+
+        let tol_t = 2.0 * f64::EPSILON * b.abs();
+        let mid = 0.5 * (c - b);
+        if fb.abs() <= tol || mid.abs() <= tol_t {
+            return Ok(b);
+        }
+
+        if e.abs() >= tol_t && fa.abs() > fb.abs() {
+            // inverse quadratic interpolation, or the secant method with only two points
+            let s = fb / fa;
+            let (mut p, mut q) = if a == c {
+                (2.0 * mid * s, 1.0 - s)
+            } else {
+                let q = fa / fc;
+                let r = fb / fc;
+                (
+                    s * (2.0 * mid * q * (q - r) - (b - a) * (r - 1.0)),
+                    (q - 1.0) * (r - 1.0) * (s - 1.0),
+                )
+            };
+            if p > 0.0 {
+                q = -q;
+            }
+            p = p.abs();
+
+            if 2.0 * p < (3.0 * mid * q - (tol_t * q).abs()).min((e * q).abs()) {
+                e = d;
+                d = p / q;
+            } else {
+                // bisection
+                d = mid;
+                e = d;
+            }
+        } else {
+            // bisection
+            d = mid;
+            e = d;
+        }
+
+        a = b;
+        fa = fb;
+        b += if d.abs() > tol_t {
+            d
+        } else {
+            tol_t.copysign(mid)
+        };
+        fb = g(b)?;
+    }
+
+    Ok(b)
+}
+
 #[derive(Clone)]
 pub struct SaveEventOptions {
     pub every_step: bool,
@@ -338,8 +1343,8 @@ impl Default for SaveEventOptions {
 /// SaveEvents will interpolate in between adaptive steps, even when set to periodic
 pub struct SaveEvent<Model, State> {
     pub options: SaveEventOptions,
-    pub init_fn: fn(&mut Model, &State, &mut WriterManager),
-    pub save_fn: fn(&Model, &State, f64, &mut WriterManager),
+    pub init_fn: SaveInitFn<Model, State>,
+    pub save_fn: SaveFn<Model, State>,
 }
 
 impl<Model, State> SaveEvent<Model, State> {
@@ -347,7 +1352,26 @@ impl<Model, State> SaveEvent<Model, State> {
         init_fn: fn(&mut Model, &State, &mut WriterManager),
         save_fn: fn(&Model, &State, f64, &mut WriterManager),
     ) -> Self {
-        Self { options: SaveEventOptions::default(), init_fn, save_fn }
+        Self {
+            options: SaveEventOptions::default(),
+            init_fn: EventFn::Pointer(init_fn),
+            save_fn: EventFn::Pointer(save_fn),
+        }
+    }
+
+    /// Creates a new `SaveEvent` from closures, which can capture configuration.
+    pub fn from_closures<I, S>(init_fn: I, save_fn: S) -> Self
+    where
+        Model: 'static,
+        State: 'static,
+        I: FnMut(&mut Model, &State, &mut WriterManager) + Clone + Send + 'static,
+        S: FnMut(&Model, &State, f64, &mut WriterManager) + Clone + Send + 'static,
+    {
+        Self {
+            options: SaveEventOptions::default(),
+            init_fn: SaveInitFn::closure(init_fn),
+            save_fn: SaveFn::closure(save_fn),
+        }
     }
 
     pub fn with_options(mut self, options: SaveEventOptions) -> Self {
@@ -362,46 +1386,147 @@ impl<Model, State> Clone for SaveEvent<Model, State> {
             options: self
                 .options
                 .clone(),
-            init_fn: self.init_fn, // Function pointer implements Copy/Clone
-            save_fn: self.save_fn, // Function pointer implements Copy/Clone
+            init_fn: self
+                .init_fn
+                .clone(),
+            save_fn: self
+                .save_fn
+                .clone(),
         }
     }
 }
 
 pub struct PreSimEvent<Model, State> {
-    pub f: fn(&mut Model, &State, f64, &Option<WriterManager>) -> Result<(), Box<dyn Error>>,
+    pub f: PreSimFn<Model, State>,
 }
 
 impl<Model, State> PreSimEvent<Model, State> {
     pub fn new(
         f: fn(&mut Model, &State, f64, &Option<WriterManager>) -> Result<(), Box<dyn Error>>,
     ) -> Self {
-        Self { f }
+        Self { f: EventFn::Pointer(f) }
+    }
+
+    /// Creates a new `PreSimEvent` from a closure, which can capture configuration.
+    pub fn from_closure<F>(f: F) -> Self
+    where
+        Model: 'static,
+        State: 'static,
+        F: FnMut(&mut Model, &State, f64, &Option<WriterManager>) -> Result<(), Box<dyn Error>>
+            + Clone
+            + Send
+            + 'static,
+    {
+        Self { f: PreSimFn::closure(f) }
     }
 }
 
 impl<Model, State> Clone for PreSimEvent<Model, State> {
     fn clone(&self) -> Self {
         Self {
-            f: self.f, // Function pointer implements Copy/Clone
+            f: self
+                .f
+                .clone(),
         }
     }
 }
 
 pub struct PostSimEvent<Model> {
-    pub f: fn(&Model, &Option<WriterManager>),
+    pub f: PostSimFn<Model>,
 }
 
 impl<Model> PostSimEvent<Model> {
-    pub fn new(f: fn(&Model, &Option<WriterManager>)) -> Self {
-        Self { f }
+    pub fn new(f: fn(&Model, &Option<WriterManager>) -> Result<(), Box<dyn Error>>) -> Self {
+        Self { f: EventFn::Pointer(f) }
+    }
+
+    /// Creates a new `PostSimEvent` from a closure, which can capture configuration.
+    pub fn from_closure<F>(f: F) -> Self
+    where
+        Model: 'static,
+        F: FnMut(&Model, &Option<WriterManager>) -> Result<(), Box<dyn Error>>
+            + Clone
+            + Send
+            + 'static,
+    {
+        Self { f: PostSimFn::closure(f) }
     }
 }
 
 impl<Model> Clone for PostSimEvent<Model> {
     fn clone(&self) -> Self {
         Self {
-            f: self.f, // Function pointer implements Copy/Clone
+            f: self
+                .f
+                .clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::state_array::StateArray;
+
+    #[derive(Debug, Default)]
+    struct Counter {
+        periodic: usize,
+        discrete: usize,
+    }
+
+    impl OdeModel for Counter {
+        type State = StateArray<1>;
+        fn f(
+            &mut self,
+            _t: f64,
+            _x: &StateArray<1>,
+            _dx: &mut StateArray<1>,
+        ) -> Result<(), Box<dyn Error>> {
+            Ok(())
+        }
+    }
+
+    /// Runs the scheduled events at each time, as a solver would at the end of each step
+    fn run(events: &mut EventManager<Counter, StateArray<1>>, model: &mut Counter, times: &[f64]) {
+        let mut x = StateArray::new([0.0]);
+        for t in times {
+            events.process_scheduled_events(model, &mut x, *t);
+        }
+    }
+
+    #[test]
+    fn test_reset_schedule() {
+        let mut events = EventManager::new();
+        events.add_periodic(PeriodicEvent::new(
+            1.0,
+            0.5,
+            |model: &mut Counter, _, _| model.periodic += 1,
+        ));
+        events.add_discrete(DiscreteEvent::new(
+            1.0,
+            |model: &mut Counter, _, _| model.discrete += 1,
+        ));
+        let times = [0.5, 1.0, 1.5, 2.5];
+
+        let mut model = Counter::default();
+        run(
+            &mut events,
+            &mut model,
+            &times,
+        );
+        assert_eq!(model.periodic, 3);
+        assert_eq!(model.discrete, 1);
+        assert_eq!(events.next_time(), 3.5);
+
+        // a copy of a manager that has run starts from the beginning, like the original after reset
+        let mut copy = events.clone();
+        events.reset();
+        for events in [&mut events, &mut copy] {
+            assert_eq!(events.next_time(), 0.5);
+            let mut model = Counter::default();
+            run(events, &mut model, &times);
+            assert_eq!(model.periodic, 3);
+            assert_eq!(model.discrete, 1);
         }
     }
 }
@@ -0,0 +1,691 @@
+//! Gauss-Jackson 8th order fixed step integrator for second order systems.
+//!
+//! This is the summed form of the multistep method commonly used for operational orbit propagation.
+//! The position and velocity are recovered from running second and first sums of the
+//! accelerations, plus a correction from the accelerations at the last nine steps. Because only
+//! sums are carried between steps, roundoff does not accumulate the way it does in the ordinate
+//! form.
+//!
+//! The method is started by integrating eight steps ahead with Yoshida's 8th order method, then
+//! iterating the Gauss-Jackson corrector over those points until they are self-consistent. The
+//! startup only steps forward from the initial time, so the model is never evaluated before the
+//! epoch. Periodic events that fire may change the state or the model, so they restart the method,
+//! and events that fire every few steps will erase its efficiency. A restart costs about 200
+//! acceleration evaluations (eight Yoshida8 steps plus the corrector passes), while a regular
+//! step costs 2, so events should be at least a hundred steps apart. The number of restarts is
+//! reported in `SolverStats::restarts`.
+
+use std::{collections::VecDeque, error::Error};
+
+use indicatif::ProgressBar;
+
+use crate::{
+    events::EventManager,
+    model::SecondOrderModel,
+    saving::{MemoryResult, WriterManager},
+    second_order::{SplittingCoefficients, Symplectic, solve_fixed_loop},
+    state::{OdeState, second_order::SecondOrderState},
+    stats::SolverStats,
+    stepping::FixedStepControl,
+};
+
+/// Number of accelerations in the stencil
+const POINTS: usize = 9;
+/// Bernoulli numbers B_2, B_4, ..., B_10
+const BERNOULLI: [f64; 5] = [1.0 / 6.0, -1.0 / 30.0, 1.0 / 42.0, -1.0 / 30.0, 5.0 / 66.0];
+/// Number of corrector passes over the startup points
+const STARTUP_ITERATIONS: usize = 10;
+
+/// Gauss-Jackson position (b) and velocity (c) coefficients for the stencil of accelerations at
+/// steps 0..8, evaluated at step x.
+///
+/// With r = h^2 (S + sum_k b_k a_k) and v = h (s + sum_k c_k a_k), where s and S are the first and
+/// second sums, b is the operator D^-2 - 1/(4 sinh^2(D/2)) and c is D^-1 - 1/2 - 1/(e^D - 1) in
+/// terms of the derivative D, which are applied to the interpolating polynomial of the stencil.
+fn coefficients(x: f64) -> ([f64; POINTS], [f64; POINTS]) {
+    let mut b = [0.0; POINTS];
+    let mut c = [0.0; POINTS];
+    for k in 0..POINTS {
+        let derivatives = lagrange_derivatives(k, x);
+        let mut factorial = 1.0;
+        for (j, bernoulli) in BERNOULLI
+            .iter()
+            .enumerate()
+        {
+            // the (2j)! of B_2j for j counting from 1
+            let n = 2 * (j + 1);
+            factorial *= ((n - 1) * n) as f64;
+            b[k] += (n - 1) as f64 * bernoulli / factorial * derivatives[n - 2];
+            if n - 1 < POINTS {
+                c[k] -= bernoulli / factorial * derivatives[n - 1];
+            }
+        }
+    }
+    (b, c)
+}
+
+/// Value and derivatives at x of the Lagrange basis polynomial for stencil point k.
+/// The polynomial is built about the center of the stencil to limit roundoff.
+fn lagrange_derivatives(k: usize, x: f64) -> [f64; POINTS] {
+    let center = (POINTS / 2) as f64;
+    // monomial coefficients in (tau - center), lowest degree first
+    let mut polynomial = [0.0; POINTS];
+    polynomial[0] = 1.0;
+    for m in (0..POINTS).filter(|m| *m != k) {
+        let node = m as f64 - center;
+        let scale = 1.0 / (k as f64 - m as f64);
+        for i in (0..POINTS).rev() {
+            let lower = if i > 0 {
+                polynomial[i - 1]
+            } else {
+                0.0
+            };
+            polynomial[i] = (lower - node * polynomial[i]) * scale;
+        }
+    }
+
+    let x = x - center;
+    let mut derivatives = [0.0; POINTS];
+    for (order, derivative) in derivatives
+        .iter_mut()
+        .enumerate()
+    {
+        // sum_i p_i i!/(i - order)! x^(i - order), by Horner's method
+        for i in (order..POINTS).rev() {
+            let falling: f64 = ((i - order + 1)..=i)
+                .map(|f| f as f64)
+                .product();
+            *derivative = *derivative * x + polynomial[i] * falling;
+        }
+    }
+    derivatives
+}
+
+/// A Gauss-Jackson 8th order fixed step solver for second order models.
+#[derive(Debug)]
+pub struct GaussJackson<Position: OdeState> {
+    /// Step size the sums and accelerations were built for
+    h: f64,
+    /// Accelerations at the last 9 steps, oldest first
+    accelerations: VecDeque<Position>,
+    /// First sum of the accelerations
+    s1: Position,
+    /// Second sum of the accelerations
+    s2: Position,
+    /// Startup states not yet returned by `step`
+    startup: VecDeque<SecondOrderState<Position>>,
+    /// Coefficients at each startup point
+    startup_b: [[f64; POINTS]; POINTS],
+    startup_c: [[f64; POINTS]; POINTS],
+    /// Predictor position coefficients, extrapolating one step past the stencil
+    predictor_b: [f64; POINTS],
+    /// Used to start the method and for steps shorter than h
+    starter: Symplectic<Position>,
+    /// Set after a short step, since the stored accelerations are no longer evenly spaced
+    restart_pending: bool,
+    scratch: Position,
+    stats: SolverStats,
+}
+
+impl<Position: OdeState> Default for GaussJackson<Position> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Position: OdeState> GaussJackson<Position> {
+    pub fn new() -> Self {
+        let mut startup_b = [[0.0; POINTS]; POINTS];
+        let mut startup_c = [[0.0; POINTS]; POINTS];
+        for n in 0..POINTS {
+            (startup_b[n], startup_c[n]) = coefficients(n as f64);
+        }
+
+        // The acceleration only depends on position, so only the predicted position is needed
+        let (predictor_b, _) = coefficients(POINTS as f64);
+
+        Self {
+            h: 0.0,
+            accelerations: VecDeque::with_capacity(POINTS),
+            s1: Position::default(),
+            s2: Position::default(),
+            startup: VecDeque::with_capacity(POINTS),
+            startup_b,
+            startup_c,
+            predictor_b,
+            starter: Symplectic::new(SplittingCoefficients::yoshida8()),
+            restart_pending: true,
+            scratch: Position::default(),
+            stats: SolverStats::default(),
+        }
+    }
+
+    /// Solves the system using fixed step size control, handling periodic events.
+    pub fn solve_fixed<Model: SecondOrderModel<Position = Position>>(
+        &mut self,
+        model: &mut Model,
+        x0: &SecondOrderState<Position>,
+        tspan: (f64, f64),
+        controller: &mut FixedStepControl,
+        events: &mut EventManager<Model, SecondOrderState<Position>>,
+        result: &mut Option<MemoryResult<SecondOrderState<Position>>>,
+        writer_manager: &mut Option<WriterManager>,
+        progress_bar: &mut ProgressBar,
+    ) -> Result<SolverStats, Box<dyn Error>> {
+        self.stats = SolverStats::default();
+        self.starter
+            .stats = SolverStats::default();
+        self.h = controller.dt;
+        self.restart_pending = true;
+        self.scratch
+            .clone_from(&x0.position);
+        let mut stats = solve_fixed_loop(
+            model,
+            x0,
+            tspan,
+            controller,
+            events,
+            result,
+            writer_manager,
+            progress_bar,
+            |model, t, dt, x, restart| self.step(model, t, dt, x, restart),
+        )?;
+        stats.function_evaluations += self
+            .stats
+            .function_evaluations
+            + self
+                .starter
+                .stats
+                .function_evaluations;
+        stats.restarts = self
+            .stats
+            .restarts;
+        Ok(stats)
+    }
+
+    /// Advances x from time t by dt. Steps of the nominal size continue the Gauss-Jackson
+    /// recursion, while shorter steps, such as landing on an event or the final time,
+    /// are taken with the starter and restart the method afterwards.
+    fn step<Model: SecondOrderModel<Position = Position>>(
+        &mut self,
+        model: &mut Model,
+        t: f64,
+        dt: f64,
+        x: &mut SecondOrderState<Position>,
+        restart: bool,
+    ) -> Result<(), Box<dyn Error>> {
+        if (dt - self.h).abs() > 1e-12 * self.h {
+            self.starter
+                .restart(x);
+            self.starter
+                .step(model, t, dt, x)?;
+            self.restart_pending = true;
+            return Ok(());
+        }
+
+        if restart || self.restart_pending {
+            self.start(model, t, x)?;
+            self.restart_pending = false;
+        }
+
+        if let Some(state) = self
+            .startup
+            .pop_front()
+        {
+            x.clone_from(&state);
+            return Ok(());
+        }
+
+        self.predict_correct(model, t, x)
+    }
+
+    /// Builds the sums and acceleration history from x at time t, leaving the next 8 states
+    /// in the startup queue.
+    fn start<Model: SecondOrderModel<Position = Position>>(
+        &mut self,
+        model: &mut Model,
+        t: f64,
+        x: &SecondOrderState<Position>,
+    ) -> Result<(), Box<dyn Error>> {
+        let h = self.h;
+        self.stats
+            .restarts += 1;
+
+        // initial guess from the starter
+        let mut states = Vec::with_capacity(POINTS);
+        states.push(x.clone());
+        self.starter
+            .restart(x);
+        for n in 1..POINTS {
+            let mut state = states[n - 1].clone();
+            self.starter
+                .step(
+                    model,
+                    t + (n - 1) as f64 * h,
+                    h,
+                    &mut state,
+                )?;
+            states.push(state);
+        }
+
+        let mut accelerations = Vec::with_capacity(POINTS);
+        for (n, state) in states
+            .iter()
+            .enumerate()
+        {
+            let mut acceleration = x
+                .position
+                .clone();
+            model.acceleration(
+                t + n as f64 * h,
+                &state.position,
+                &mut acceleration,
+            )?;
+            self.stats
+                .function_evaluations += 1;
+            accelerations.push(acceleration);
+        }
+
+        let mut s1 = x
+            .position
+            .clone();
+        let mut s2 = x
+            .position
+            .clone();
+        for _ in 0..STARTUP_ITERATIONS {
+            // sums at the initial point: s = v0/h - sum_k c_k a_k, S = r0/h^2 - sum_k b_k a_k
+            s1.clone_from(&x.velocity);
+            s1 *= 1.0 / h;
+            Self::accumulate(
+                &mut s1,
+                &accelerations,
+                &self.startup_c[0],
+                -1.0,
+                &mut self.scratch,
+            );
+            s2.clone_from(&x.position);
+            s2 *= 1.0 / (h * h);
+            Self::accumulate(
+                &mut s2,
+                &accelerations,
+                &self.startup_b[0],
+                -1.0,
+                &mut self.scratch,
+            );
+
+            for n in 1..POINTS {
+                Self::advance_second_sum(
+                    &mut s2,
+                    &s1,
+                    &accelerations[n - 1],
+                    &mut self.scratch,
+                );
+                Self::advance_first_sum(
+                    &mut s1,
+                    &accelerations[n - 1],
+                    &accelerations[n],
+                    &mut self.scratch,
+                );
+
+                let state = &mut states[n];
+                state
+                    .position
+                    .clone_from(&s2);
+                Self::accumulate(
+                    &mut state.position,
+                    &accelerations,
+                    &self.startup_b[n],
+                    1.0,
+                    &mut self.scratch,
+                );
+                state.position *= h * h;
+                state
+                    .velocity
+                    .clone_from(&s1);
+                Self::accumulate(
+                    &mut state.velocity,
+                    &accelerations,
+                    &self.startup_c[n],
+                    1.0,
+                    &mut self.scratch,
+                );
+                state.velocity *= h;
+            }
+
+            for n in 1..POINTS {
+                model.acceleration(
+                    t + n as f64 * h,
+                    &states[n].position,
+                    &mut accelerations[n],
+                )?;
+                self.stats
+                    .function_evaluations += 1;
+            }
+        }
+
+        // the sums at the last startup point, consistent with the final accelerations
+        s1.clone_from(&x.velocity);
+        s1 *= 1.0 / h;
+        Self::accumulate(
+            &mut s1,
+            &accelerations,
+            &self.startup_c[0],
+            -1.0,
+            &mut self.scratch,
+        );
+        s2.clone_from(&x.position);
+        s2 *= 1.0 / (h * h);
+        Self::accumulate(
+            &mut s2,
+            &accelerations,
+            &self.startup_b[0],
+            -1.0,
+            &mut self.scratch,
+        );
+        for n in 1..POINTS {
+            Self::advance_second_sum(
+                &mut s2,
+                &s1,
+                &accelerations[n - 1],
+                &mut self.scratch,
+            );
+            Self::advance_first_sum(
+                &mut s1,
+                &accelerations[n - 1],
+                &accelerations[n],
+                &mut self.scratch,
+            );
+        }
+        self.s1 = s1;
+        self.s2 = s2;
+
+        self.accelerations = accelerations.into();
+        self.startup = states
+            .into_iter()
+            .skip(1)
+            .collect();
+        Ok(())
+    }
+
+    /// Takes one predict-evaluate-correct-evaluate step from time t, writing the new state to x.
+    fn predict_correct<Model: SecondOrderModel<Position = Position>>(
+        &mut self,
+        model: &mut Model,
+        t: f64,
+        x: &mut SecondOrderState<Position>,
+    ) -> Result<(), Box<dyn Error>> {
+        let h = self.h;
+        let mut accelerations = std::mem::take(&mut self.accelerations);
+
+        // the second sum only needs the accelerations up to the current step
+        let mut s2 = std::mem::take(&mut self.s2);
+        Self::advance_second_sum(
+            &mut s2,
+            &self.s1,
+            &accelerations[POINTS - 1],
+            &mut self.scratch,
+        );
+
+        // predict
+        x.position
+            .clone_from(&s2);
+        Self::accumulate(
+            &mut x.position,
+            accelerations.make_contiguous(),
+            &self.predictor_b,
+            1.0,
+            &mut self.scratch,
+        );
+        x.position *= h * h;
+
+        // evaluate, reusing the oldest acceleration's storage
+        let mut new = accelerations
+            .pop_front()
+            .expect("Gauss-Jackson acceleration history is full after startup");
+        model.acceleration(t + h, &x.position, &mut new)?;
+        self.stats
+            .function_evaluations += 1;
+        accelerations.push_back(new);
+
+        // correct the position
+        let corrector_b = self.startup_b[POINTS - 1];
+        let corrector_c = self.startup_c[POINTS - 1];
+        x.position
+            .clone_from(&s2);
+        Self::accumulate(
+            &mut x.position,
+            accelerations.make_contiguous(),
+            &corrector_b,
+            1.0,
+            &mut self.scratch,
+        );
+        x.position *= h * h;
+
+        // evaluate at the corrected position, then correct the velocity with it
+        model.acceleration(
+            t + h,
+            &x.position,
+            &mut accelerations[POINTS - 1],
+        )?;
+        self.stats
+            .function_evaluations += 1;
+        let mut s1 = std::mem::take(&mut self.s1);
+        Self::advance_first_sum(
+            &mut s1,
+            &accelerations[POINTS - 2],
+            &accelerations[POINTS - 1],
+            &mut self.scratch,
+        );
+        x.velocity
+            .clone_from(&s1);
+        Self::accumulate(
+            &mut x.velocity,
+            accelerations.make_contiguous(),
+            &corrector_c,
+            1.0,
+            &mut self.scratch,
+        );
+        x.velocity *= h;
+
+        self.s1 = s1;
+        self.s2 = s2;
+        self.accelerations = accelerations;
+        Ok(())
+    }
+
+    /// S_n+1 = S_n + s_n + a_n/2
+    fn advance_second_sum(
+        s2: &mut Position,
+        s1: &Position,
+        acceleration: &Position,
+        scratch: &mut Position,
+    ) {
+        *s2 += s1;
+        scratch.clone_from(acceleration);
+        *scratch *= 0.5;
+        *s2 += scratch;
+    }
+
+    /// s_n+1 = s_n + (a_n + a_n+1)/2
+    fn advance_first_sum(
+        s1: &mut Position,
+        previous: &Position,
+        next: &Position,
+        scratch: &mut Position,
+    ) {
+        scratch.clone_from(previous);
+        *scratch += next;
+        *scratch *= 0.5;
+        *s1 += scratch;
+    }
+
+    /// Adds scale * sum_k weights_k a_k to out
+    fn accumulate(
+        out: &mut Position,
+        accelerations: &[Position],
+        weights: &[f64; POINTS],
+        scale: f64,
+        scratch: &mut Position,
+    ) {
+        for (acceleration, weight) in accelerations
+            .iter()
+            .zip(weights)
+        {
+            scratch.clone_from(acceleration);
+            *scratch *= weight * scale;
+            *out += scratch;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        OdeModel, OdeProblem,
+        events::PeriodicEvent,
+        solution::Solution,
+        solvers::{OdeSolver, RungeKuttaMethods, SecondOrderMethods},
+        state::state_array::StateArray,
+        stepping::AdaptiveStepControl,
+    };
+    use std::f64::consts::PI;
+
+    /// Planar two-body problem with mu = 1
+    #[derive(Debug)]
+    struct Kepler;
+
+    impl OdeModel for Kepler {
+        type State = SecondOrderState<StateArray<2>>;
+        fn f(
+            &mut self,
+            t: f64,
+            x: &Self::State,
+            dx: &mut Self::State,
+        ) -> Result<(), Box<dyn Error>> {
+            self.second_order_f(t, x, dx)
+        }
+    }
+
+    impl SecondOrderModel for Kepler {
+        type Position = StateArray<2>;
+        fn acceleration(
+            &mut self,
+            _t: f64,
+            position: &StateArray<2>,
+            acceleration: &mut StateArray<2>,
+        ) -> Result<(), Box<dyn Error>> {
+            let r3 = (position[0] * position[0] + position[1] * position[1]).powf(1.5);
+            acceleration[0] = -position[0] / r3;
+            acceleration[1] = -position[1] / r3;
+            Ok(())
+        }
+    }
+
+    /// Periapsis of an orbit with eccentricity 0.1 and unit semi-major axis, so the period is 2 pi
+    fn periapsis() -> SecondOrderState<StateArray<2>> {
+        SecondOrderState::new(
+            StateArray::new([0.9, 0.0]),
+            StateArray::new([0.0, (1.1_f64 / 0.9).sqrt()]),
+        )
+    }
+
+    fn final_state(
+        solution: Solution<SecondOrderState<StateArray<2>>>,
+    ) -> SecondOrderState<StateArray<2>> {
+        solution
+            .result
+            .unwrap()
+            .y
+            .pop()
+            .unwrap()
+    }
+
+    fn difference(a: &SecondOrderState<StateArray<2>>, b: &SecondOrderState<StateArray<2>>) -> f64 {
+        (0..2)
+            .map(|i| {
+                (a.position[i] - b.position[i])
+                    .abs()
+                    .max((a.velocity[i] - b.velocity[i]).abs())
+            })
+            .fold(0.0, f64::max)
+    }
+
+    fn reference(tspan: (f64, f64)) -> SecondOrderState<StateArray<2>> {
+        let controller = AdaptiveStepControl::default()
+            .with_abs_tol(1e-14)
+            .with_rel_tol(1e-14);
+        final_state(
+            OdeSolver::new(RungeKuttaMethods::Verner9.into())
+                .solve_adaptive(
+                    OdeProblem::new(Kepler),
+                    periapsis(),
+                    tspan,
+                    controller,
+                )
+                .unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_two_body_against_runge_kutta() {
+        let tspan = (0.0, 10.0 * PI);
+        let solution = OdeSolver::new(SecondOrderMethods::GaussJackson8.into())
+            .solve_second_order_fixed(
+                OdeProblem::new(Kepler),
+                periapsis(),
+                tspan,
+                2.0 * PI / 200.0,
+            )
+            .unwrap();
+        // without events the method only starts once
+        assert_eq!(
+            solution
+                .stats
+                .restarts,
+            1
+        );
+        let error = difference(
+            &final_state(solution),
+            &reference(tspan),
+        );
+        assert!(
+            error < 1e-9,
+            "error {}",
+            error
+        );
+    }
+
+    #[test]
+    fn test_restarts_after_events() {
+        let tspan = (0.0, 4.0 * PI);
+        let solution = OdeSolver::new(SecondOrderMethods::GaussJackson8.into())
+            .solve_second_order_fixed(
+                OdeProblem::new(Kepler).with_periodic_event(PeriodicEvent::new(
+                    PI,
+                    PI,
+                    |_, _, _| {},
+                )),
+                periapsis(),
+                tspan,
+                2.0 * PI / 200.0,
+            )
+            .unwrap();
+        // the initial start and one after each of the events before the final time
+        assert_eq!(
+            solution
+                .stats
+                .restarts,
+            4
+        );
+        let error = difference(
+            &final_state(solution),
+            &reference(tspan),
+        );
+        assert!(
+            error < 1e-9,
+            "error {}",
+            error
+        );
+    }
+}
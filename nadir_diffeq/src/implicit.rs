@@ -0,0 +1,1202 @@
+//! Implicit Runge-Kutta solvers for stiff systems.
+//!
+//! Stiff springs, contact penalties and thermal states force explicit methods to take steps limited
+//! by stability rather than accuracy. Implicit methods remain stable at large steps by solving for the
+//! stages with Newton iterations, using either a Jacobian supplied by the model through
+//! `OdeModel::jacobian` or one computed by finite differences through `OdeModel::f`.
+
+use std::{error::Error, mem::take};
+
+use indicatif::ProgressBar;
+
+use crate::{
+    OdeModel,
+    events::EventManager,
+    saving::{MemoryResult, WriterManager},
+    solution::{DenseOutput, lagrange_basis},
+    state::{Adaptive, OdeState},
+    stats::SolverStats,
+    stepping::AdaptiveStepControl,
+};
+
+const SQRT6: f64 = 2.449489742783178;
+
+/// Stage times of the 3 stage Radau IIA method
+const C: [f64; 3] = [(4.0 - SQRT6) / 10.0, (4.0 + SQRT6) / 10.0, 1.0];
+
+/// Weights of the stage increments in the embedded error estimate (Hairer & Wanner, RADAU5)
+const DD: [f64; 3] = [-(13.0 + 7.0 * SQRT6) / 3.0, (-13.0 + 7.0 * SQRT6) / 3.0, -1.0 / 3.0];
+
+/// Real eigenvalue of the inverse of the coefficient matrix A, 30 / (6 + 81^(1/3) - 9^(1/3))
+const GAMMA: f64 = 3.637834252744496;
+
+/// Real and imaginary parts of the complex eigenvalues of the inverse of A
+const ALPHA: f64 = 2.681082873627752;
+const BETA: f64 = 3.0504301992474105;
+
+/// Eigenvectors of the inverse of the Radau IIA coefficient matrix A, with
+/// T^-1 A^-1 T = [[gamma, 0, 0], [0, alpha, -beta], [0, beta, alpha]] (Hairer & Wanner, RADAU5).
+/// The stage equations are only ever solved in this basis, so A itself is not needed.
+const T: [[f64; 3]; 3] = [
+    [9.123239487089294e-2, -0.1412552950209542, -3.0029194105147424e-2],
+    [0.241717932707107, 0.20412935229379993, 0.3829421127572619],
+    [0.966048182615093, 1.0, 0.0],
+];
+const TI: [[f64; 3]; 3] = [
+    [4.325579890063155, 0.3391992518158099, 0.5417705399358749],
+    [-4.178718591551905, -0.3276828207610624, 0.47662355450055046],
+    [-0.5028726349457869, 2.571926949855605, -0.596039204828225],
+];
+
+/// Order used by the step size controller. The error estimate is O(h^4).
+const ORDER: usize = 5;
+
+/// Maximum number of Newton iterations before the step is retried with a smaller step size
+const MAX_NEWTON_ITERS: usize = 7;
+
+/// A contraction rate above this means the Newton iterations are diverging
+const MAX_NEWTON_RATE: f64 = 0.99;
+
+/// The Jacobian is reused for the next step while the Newton iterations contract faster than this
+const JACOBIAN_RATE: f64 = 1e-3;
+
+/// Step size ratios within this range keep the current step size, so the Newton matrices can be reused
+const HOLD_DT: (f64, f64) = (1.0, 1.2);
+
+/// Dense LU factorization with partial pivoting, used to solve the Newton systems.
+#[derive(Debug, Default)]
+struct LuDecomposition {
+    n: usize,
+    lu: Vec<f64>,
+    pivots: Vec<usize>,
+    work: Vec<f64>,
+}
+
+impl LuDecomposition {
+    /// Factorizes the row-major n x n matrix. Returns false if the matrix is singular.
+    fn factor(&mut self, matrix: &[f64], n: usize) -> bool {
+        self.n = n;
+        self.lu
+            .clone_from_slice(matrix);
+        self.pivots
+            .clear();
+        self.pivots
+            .extend(0..n);
+
+        for k in 0..n {
+            let mut pivot_row = k;
+            let mut max = self.lu[k * n + k].abs();
+            for i in k + 1..n {
+                let value = self.lu[i * n + k].abs();
+                if value > max {
+                    max = value;
+                    pivot_row = i;
+                }
+            }
+            if max == 0.0 {
+                return false;
+            }
+            if pivot_row != k {
+                for j in 0..n {
+                    self.lu
+                        .swap(k * n + j, pivot_row * n + j);
+                }
+                self.pivots
+                    .swap(k, pivot_row);
+            }
+
+            let pivot = self.lu[k * n + k];
+            for i in k + 1..n {
+                let factor = self.lu[i * n + k] / pivot;
+                self.lu[i * n + k] = factor;
+                if factor != 0.0 {
+                    for j in k + 1..n {
+                        self.lu[i * n + j] -= factor * self.lu[k * n + j];
+                    }
+                }
+            }
+        }
+        true
+    }
+
+    /// Solves A x = b, overwriting b with x.
+    fn solve(&mut self, b: &mut [f64]) {
+        let n = self.n;
+        self.work
+            .clear();
+        self.work
+            .extend(
+                self.pivots
+                    .iter()
+                    .map(|&p| b[p]),
+            );
+
+        // forward substitution with the unit lower triangle
+        for i in 0..n {
+            let mut sum = self.work[i];
+            for j in 0..i {
+                sum -= self.lu[i * n + j] * self.work[j];
+            }
+            self.work[i] = sum;
+        }
+        // back substitution with the upper triangle
+        for i in (0..n).rev() {
+            let mut sum = self.work[i];
+            for j in i + 1..n {
+                sum -= self.lu[i * n + j] * self.work[j];
+            }
+            self.work[i] = sum / self.lu[i * n + i];
+        }
+        b.copy_from_slice(&self.work);
+    }
+}
+
+/// Dense complex LU factorization with partial pivoting of (alpha + i beta) I - J, used to solve
+/// the complex pair of the transformed Newton systems.
+#[derive(Debug, Default)]
+struct ComplexLuDecomposition {
+    n: usize,
+    re: Vec<f64>,
+    im: Vec<f64>,
+    pivots: Vec<usize>,
+    work_re: Vec<f64>,
+    work_im: Vec<f64>,
+}
+
+impl ComplexLuDecomposition {
+    /// Factorizes (alpha + i beta) I - J for the row-major n x n Jacobian.
+    /// Returns false if the matrix is singular.
+    fn factor(&mut self, jacobian: &[f64], n: usize, alpha: f64, beta: f64) -> bool {
+        self.n = n;
+        self.re
+            .clear();
+        self.im
+            .clear();
+        for p in 0..n {
+            for q in 0..n {
+                let diagonal = p == q;
+                self.re
+                    .push(
+                        if diagonal {
+                            alpha
+                        } else {
+                            0.0
+                        } - jacobian[p * n + q],
+                    );
+                self.im
+                    .push(if diagonal {
+                        beta
+                    } else {
+                        0.0
+                    });
+            }
+        }
+        self.pivots
+            .clear();
+        self.pivots
+            .extend(0..n);
+
+        for k in 0..n {
+            let mut pivot_row = k;
+            let mut max = self.re[k * n + k].hypot(self.im[k * n + k]);
+            for i in k + 1..n {
+                let value = self.re[i * n + k].hypot(self.im[i * n + k]);
+                if value > max {
+                    max = value;
+                    pivot_row = i;
+                }
+            }
+            if max == 0.0 {
+                return false;
+            }
+            if pivot_row != k {
+                for j in 0..n {
+                    self.re
+                        .swap(k * n + j, pivot_row * n + j);
+                    self.im
+                        .swap(k * n + j, pivot_row * n + j);
+                }
+                self.pivots
+                    .swap(k, pivot_row);
+            }
+
+            let (pivot_re, pivot_im) = (
+                self.re[k * n + k],
+                self.im[k * n + k],
+            );
+            let pivot_norm = pivot_re * pivot_re + pivot_im * pivot_im;
+            for i in k + 1..n {
+                let (a_re, a_im) = (
+                    self.re[i * n + k],
+                    self.im[i * n + k],
+                );
+                let factor_re = (a_re * pivot_re + a_im * pivot_im) / pivot_norm;
+                let factor_im = (a_im * pivot_re - a_re * pivot_im) / pivot_norm;
+                self.re[i * n + k] = factor_re;
+                self.im[i * n + k] = factor_im;
+                if factor_re != 0.0 || factor_im != 0.0 {
+                    for j in k + 1..n {
+                        let (b_re, b_im) = (
+                            self.re[k * n + j],
+                            self.im[k * n + j],
+                        );
+                        self.re[i * n + j] -= factor_re * b_re - factor_im * b_im;
+                        self.im[i * n + j] -= factor_re * b_im + factor_im * b_re;
+                    }
+                }
+            }
+        }
+        true
+    }
+
+    /// Solves A x = b for complex b = b_re + i b_im, overwriting b with x.
+    fn solve(&mut self, b_re: &mut [f64], b_im: &mut [f64]) {
+        let n = self.n;
+        self.work_re
+            .clear();
+        self.work_re
+            .extend(
+                self.pivots
+                    .iter()
+                    .map(|&p| b_re[p]),
+            );
+        self.work_im
+            .clear();
+        self.work_im
+            .extend(
+                self.pivots
+                    .iter()
+                    .map(|&p| b_im[p]),
+            );
+
+        // forward substitution with the unit lower triangle
+        for i in 0..n {
+            let (mut sum_re, mut sum_im) = (
+                self.work_re[i],
+                self.work_im[i],
+            );
+            for j in 0..i {
+                let (l_re, l_im) = (
+                    self.re[i * n + j],
+                    self.im[i * n + j],
+                );
+                sum_re -= l_re * self.work_re[j] - l_im * self.work_im[j];
+                sum_im -= l_re * self.work_im[j] + l_im * self.work_re[j];
+            }
+            self.work_re[i] = sum_re;
+            self.work_im[i] = sum_im;
+        }
+        // back substitution with the upper triangle
+        for i in (0..n).rev() {
+            let (mut sum_re, mut sum_im) = (
+                self.work_re[i],
+                self.work_im[i],
+            );
+            for j in i + 1..n {
+                let (u_re, u_im) = (
+                    self.re[i * n + j],
+                    self.im[i * n + j],
+                );
+                sum_re -= u_re * self.work_re[j] - u_im * self.work_im[j];
+                sum_im -= u_re * self.work_im[j] + u_im * self.work_re[j];
+            }
+            let (d_re, d_im) = (
+                self.re[i * n + i],
+                self.im[i * n + i],
+            );
+            let norm = d_re * d_re + d_im * d_im;
+            self.work_re[i] = (sum_re * d_re + sum_im * d_im) / norm;
+            self.work_im[i] = (sum_im * d_re - sum_re * d_im) / norm;
+        }
+        b_re.copy_from_slice(&self.work_re);
+        b_im.copy_from_slice(&self.work_im);
+    }
+}
+
+fn elements<State: Adaptive>(state: &State) -> Result<&[f64], Box<dyn Error>> {
+    state
+        .elements()
+        .ok_or_else(|| "implicit solvers require a state that implements Adaptive::elements".into())
+}
+
+fn elements_mut<State: Adaptive>(state: &mut State) -> Result<&mut [f64], Box<dyn Error>> {
+    state
+        .elements_mut()
+        .ok_or_else(|| {
+            "implicit solvers require a state that implements Adaptive::elements_mut".into()
+        })
+}
+
+/// Result of attempting a single implicit step
+enum StepOutcome {
+    /// The Newton iterations converged, with the normalized error estimate of the step
+    Converged(f64),
+    /// The Newton iterations diverged or the Newton matrix was singular
+    Failed,
+}
+
+/// The 3 stage, 5th order Radau IIA method with an embedded 3rd order error estimate.
+///
+/// Radau IIA is L-stable and stiffly accurate, so it damps stiff modes at any step size.
+/// The stage equations are solved with simplified Newton iterations, transformed by the
+/// eigenvectors of A into one real and one complex n x n system as in RADAU5, rather than one
+/// 3n x 3n system. The Jacobian is reused across steps while the iterations converge quickly.
+/// The collocation polynomial provides dense output for locating continuous events.
+#[derive(Debug)]
+pub struct RadauIIA5<State: OdeState> {
+    x: State,
+    y: State,
+    y_tilde: State,
+    stage: State,
+    derivative: State,
+    interpolant: State,
+    /// Number of elements in the state
+    n: usize,
+    /// Stage increments Y_i - x, stored stage after stage
+    z: Vec<f64>,
+    /// Stage increments transformed by T^-1
+    w: Vec<f64>,
+    /// Newton corrections to w
+    dw: Vec<f64>,
+    /// Stage derivatives f(t + c_i h, x + z_i)
+    fz: Vec<f64>,
+    /// Derivative at the start of the step
+    f0: Vec<f64>,
+    /// Whether f0 needs to be recomputed for the current start of the step
+    f0_stale: bool,
+    /// Row-major Jacobian of f, evaluated at the start of this or an earlier step
+    jacobian: Vec<f64>,
+    /// Whether the Jacobian needs to be recomputed before the next step
+    jacobian_stale: bool,
+    /// Whether the Jacobian was evaluated at the current start of the step
+    jacobian_current: bool,
+    /// Largest contraction rate of the Newton iterations in the last step
+    newton_rate: f64,
+    /// Step size the Newton matrices were factored for
+    factored_dt: Option<f64>,
+    /// gamma/h I - J, also used for the error estimate
+    real_matrix: Vec<f64>,
+    real_lu: LuDecomposition,
+    /// (alpha + i beta)/h I - J
+    complex_lu: ComplexLuDecomposition,
+    stats: SolverStats,
+}
+
+impl<State: OdeState + Adaptive> Default for RadauIIA5<State> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<State: OdeState + Adaptive> RadauIIA5<State> {
+    pub fn new() -> Self {
+        Self {
+            x: State::default(),
+            y: State::default(),
+            y_tilde: State::default(),
+            stage: State::default(),
+            derivative: State::default(),
+            interpolant: State::default(),
+            n: 0,
+            z: Vec::new(),
+            w: Vec::new(),
+            dw: Vec::new(),
+            fz: Vec::new(),
+            f0: Vec::new(),
+            f0_stale: true,
+            jacobian: Vec::new(),
+            jacobian_stale: true,
+            jacobian_current: false,
+            newton_rate: 0.0,
+            factored_dt: None,
+            real_matrix: Vec::new(),
+            real_lu: LuDecomposition::default(),
+            complex_lu: ComplexLuDecomposition::default(),
+            stats: SolverStats::default(),
+        }
+    }
+
+    /// Copies the initial state to all buffers and sizes the Newton storage to match it
+    fn init(&mut self, x0: &State) -> Result<(), Box<dyn Error>> {
+        self.x
+            .clone_from(x0);
+        self.y
+            .clone_from(x0);
+        self.y_tilde
+            .clone_from(x0);
+        self.stage
+            .clone_from(x0);
+        self.derivative
+            .clone_from(x0);
+        self.interpolant
+            .clone_from(x0);
+
+        let n = elements(x0)?.len();
+        self.n = n;
+        self.z = vec![0.0; 3 * n];
+        self.w = vec![0.0; 3 * n];
+        self.dw = vec![0.0; 3 * n];
+        self.fz = vec![0.0; 3 * n];
+        self.f0 = vec![0.0; n];
+        self.f0_stale = true;
+        self.jacobian = vec![0.0; n * n];
+        self.jacobian_stale = true;
+        self.jacobian_current = false;
+        self.newton_rate = 0.0;
+        self.factored_dt = None;
+        self.real_matrix = vec![0.0; n * n];
+        self.real_lu
+            .lu = vec![0.0; n * n];
+        Ok(())
+    }
+
+    /// Solves the system using adaptive step size control with event detection.
+    ///
+    /// Supports both periodic and continuous events. Continuous events are located by bisection
+    /// on the collocation polynomial of the step.
+    pub fn solve_adaptive<Model>(
+        &mut self,
+        model: &mut Model,
+        x0: &State,
+        tspan: (f64, f64),
+        controller: &mut AdaptiveStepControl,
+        events: &mut EventManager<Model, State>,
+        result: &mut Option<MemoryResult<State>>,
+        dense_output: &mut Option<DenseOutput<State>>,
+        writer_manager: &mut Option<WriterManager>,
+        progress_bar: &mut ProgressBar,
+    ) -> Result<SolverStats, Box<dyn Error>>
+    where
+        Model: OdeModel<State = State>,
+    {
+        self.stats = SolverStats::default();
+        let mut t = tspan.0;
+        self.init(x0)?;
+
+        let mut dt = 1e-3; // initial dt
+        // the error estimate is refined on the first step and after rejections, where it is least reliable
+        let mut refine_error = true;
+
+        // Save the true initial state before any processing
+        if let Some(result) = result {
+            result.insert(t, &self.x);
+        }
+        if let Some(manager) = writer_manager {
+            // run the model function to update any mutable internal algebraic/kinematic states
+            model.f(t, x0, &mut self.derivative)?;
+            self.stats
+                .function_evaluations += 1;
+            events.process_save_events(model, &self.x, t, manager);
+        }
+
+        while t < tspan.1 {
+            // Determine step size - standard dt or adjusted for upcoming event
+            let next_event_time = events.next_time();
+            if next_event_time > t && next_event_time < t + dt {
+                // Adjust step size to land exactly on the event
+                dt = next_event_time - t
+            };
+
+            // Ensure we don't step past the end
+            if t + dt > tspan.1 {
+                dt = tspan.1 - t;
+            }
+
+            if self.f0_stale {
+                self.update_derivative(model, t)?;
+            }
+            if self.jacobian_stale {
+                self.update_jacobian(model, t)?;
+            }
+
+            // Trial step
+            let error = match self.step(
+                model,
+                t,
+                dt,
+                controller,
+                refine_error,
+            )? {
+                StepOutcome::Converged(error) => error,
+                StepOutcome::Failed => {
+                    // Newton failed to converge, retry with a smaller step and a fresh Jacobian
+                    dt *= 0.5;
+                    self.jacobian_stale = !self.jacobian_current;
+                    self.stats
+                        .rejected_steps += 1;
+                    refine_error = true;
+                    if let Some(min) = controller.min_dt {
+                        if dt <= min {
+                            return Err(format!(
+                                "Newton iterations failed to converge at t = {} with the minimum step size",
+                                t
+                            )
+                            .into());
+                        }
+                    }
+                    check_emergency_dt(t, dt, f64::INFINITY);
+                    continue;
+                }
+            };
+
+            // Calculate new step size based on dt, limiting the change so a single
+            // small error estimate doesn't throw the step size far outside the Newton convergence region
+            let mut new_dt = controller
+                .step(dt, error, ORDER)
+                .clamp(0.2 * dt, 8.0 * dt);
+
+            // Apply controller limits
+            new_dt = controller.limit(new_dt, &mut self.stats);
+
+            // Check if step is accepted
+            if error <= 1.0 {
+                // Step ACCEPTED: advance time, save result, and update state
+
+                // First determine if any continuous events occurred that require us to step back in time
+                let continuous_event_time =
+                    if events.detect_continuous_events(t, &self.x, t + dt, &self.y) {
+                        events.locate_continuous_events(|condition, s| {
+                            self.interpolate(t, dt, s)?;
+                            Ok(condition.call(&self.interpolant, s))
+                        })?
+                    } else {
+                        None
+                    };
+                if let Some(dense_output) = dense_output {
+                    // the step ends at the event time if one occurred
+                    self.save_dense_output(
+                        dense_output,
+                        t,
+                        dt,
+                        continuous_event_time.unwrap_or(t + dt),
+                    )?;
+                }
+                if let Some(continuous_event_time) = continuous_event_time {
+                    self.interpolate(t, dt, continuous_event_time)?;
+                    // save the result prior to the event action
+                    if let Some(result) = result {
+                        result.insert(
+                            continuous_event_time,
+                            &self.interpolant,
+                        );
+                    }
+                    // perform the event actions
+                    events.perform_continuous_events(
+                        model,
+                        &mut self.interpolant,
+                        continuous_event_time,
+                    );
+                    // save after the actions
+                    if let Some(result) = result {
+                        result.insert(
+                            continuous_event_time,
+                            &self.interpolant,
+                        );
+                    }
+                    // update dt for the continuous event time
+                    dt = continuous_event_time - t;
+                    // update state for the interpolated state after all events have occurred
+                    self.y
+                        .clone_from(&self.interpolant);
+                    // the event may have changed the model or state
+                    self.jacobian_stale = true;
+                }
+                t += dt;
+                self.stats
+                    .accept(dt);
+
+                // Increment progress bar
+                let percent_complete = ((t - tspan.0) / (tspan.1 - tspan.0) * 100.0).floor() as u64;
+                progress_bar.set_position(percent_complete);
+
+                // Save the true state before any event processing
+                if let Some(result) = result {
+                    result.insert(t, &self.y);
+                }
+                if let Some(manager) = writer_manager {
+                    // run the model function to update internal algebraic/kinematic states
+                    model.f(
+                        t,
+                        &self.y,
+                        &mut self.derivative,
+                    )?;
+                    self.stats
+                        .function_evaluations += 1;
+                    events.process_save_events(model, &self.y, t, manager);
+                }
+
+                // reuse the Jacobian while the Newton iterations converge quickly
+                if self.newton_rate > JACOBIAN_RATE {
+                    self.jacobian_stale = true;
+                }
+
+                // Process scheduled events if any occurred
+                if events.process_scheduled_events(model, &mut self.y, t) {
+                    // Events changed state, save the updated state
+                    if let Some(result) = result {
+                        result.insert(t, &self.y);
+                    }
+                    // the events may have changed the model
+                    self.jacobian_stale = true;
+                };
+
+                self.x
+                    .clone_from(&self.y);
+                self.f0_stale = true;
+                self.jacobian_current = false;
+                refine_error = false;
+                // keep the step size when it barely changes, so the Newton matrices are reused
+                let ratio = new_dt / dt;
+                if self.jacobian_stale || ratio < HOLD_DT.0 || ratio > HOLD_DT.1 {
+                    dt = new_dt;
+                }
+                if events.terminated() {
+                    // a terminal event stopped the integration
+                    break;
+                }
+            } else {
+                // Step REJECTED: try again with reduced step size and a fresh Jacobian
+                dt = new_dt;
+                refine_error = true;
+                self.jacobian_stale = !self.jacobian_current;
+                self.stats
+                    .rejected_steps += 1;
+
+                // Safety check for minimum step size
+                if let Some(min) = controller.min_dt {
+                    if dt <= min {
+                        return Err(format!(
+                            "Minimum step size reached at t = {} but the error is still too large",
+                            t
+                        )
+                        .into());
+                    }
+                }
+            }
+            check_emergency_dt(t, dt, error);
+        }
+        // write the last state
+        if let Some(manager) = writer_manager {
+            events.process_save_events(model, &self.x, t, manager);
+        }
+        Ok(self.stats)
+    }
+
+    /// Evaluates f0 = f(t, x) at the start of the step.
+    fn update_derivative<Model: OdeModel<State = State>>(
+        &mut self,
+        model: &mut Model,
+        t: f64,
+    ) -> Result<(), Box<dyn Error>> {
+        model.f(
+            t,
+            &self.x,
+            &mut self.derivative,
+        )?;
+        self.stats
+            .function_evaluations += 1;
+        self.f0
+            .copy_from_slice(elements(&self.derivative)?);
+        self.f0_stale = false;
+        Ok(())
+    }
+
+    /// Evaluates the Jacobian of f at the start of the step, after f0.
+    /// Uses the model's Jacobian if it provides one, otherwise forward finite differences.
+    fn update_jacobian<Model: OdeModel<State = State>>(
+        &mut self,
+        model: &mut Model,
+        t: f64,
+    ) -> Result<(), Box<dyn Error>> {
+        let n = self.n;
+        if !model.jacobian(t, &self.x, &mut self.jacobian)? {
+            self.stage
+                .clone_from(&self.x);
+            for j in 0..n {
+                let xj = elements(&self.x)?[j];
+                let delta = (f64::EPSILON
+                    * xj.abs()
+                        .max(1e-5))
+                .sqrt();
+                elements_mut(&mut self.stage)?[j] = xj + delta;
+                model.f(
+                    t,
+                    &self.stage,
+                    &mut self.derivative,
+                )?;
+                self.stats
+                    .function_evaluations += 1;
+                let derivative = elements(&self.derivative)?;
+                for (i, (derivative, f0)) in derivative
+                    .iter()
+                    .zip(&self.f0)
+                    .enumerate()
+                {
+                    self.jacobian[i * n + j] = (derivative - f0) / delta;
+                }
+                elements_mut(&mut self.stage)?[j] = xj;
+            }
+        }
+        self.jacobian_stale = false;
+        self.jacobian_current = true;
+        self.factored_dt = None;
+        Ok(())
+    }
+
+    /// Attempts a step of size h from (t, x), leaving the new state in y.
+    fn step<Model: OdeModel<State = State>>(
+        &mut self,
+        model: &mut Model,
+        t: f64,
+        h: f64,
+        controller: &AdaptiveStepControl,
+        refine_error: bool,
+    ) -> Result<StepOutcome, Box<dyn Error>> {
+        let n = self.n;
+
+        // Newton matrices gamma/h I - J and (alpha + i beta)/h I - J of the transformed system
+        if self.factored_dt != Some(h) {
+            for p in 0..n {
+                for q in 0..n {
+                    let identity = if p == q {
+                        GAMMA / h
+                    } else {
+                        0.0
+                    };
+                    self.real_matrix[p * n + q] = identity - self.jacobian[p * n + q];
+                }
+            }
+            if !self
+                .real_lu
+                .factor(&self.real_matrix, n)
+                || !self
+                    .complex_lu
+                    .factor(
+                        &self.jacobian,
+                        n,
+                        ALPHA / h,
+                        BETA / h,
+                    )
+            {
+                self.factored_dt = None;
+                return Ok(StepOutcome::Failed);
+            }
+            self.factored_dt = Some(h);
+        }
+
+        // Simplified Newton iterations on the stage increments, starting from z = 0
+        let newton_tol = (10.0 * f64::EPSILON / controller.rel_tol).max(
+            0.03_f64.min(
+                controller
+                    .rel_tol
+                    .sqrt(),
+            ),
+        );
+        self.z
+            .fill(0.0);
+        self.w
+            .fill(0.0);
+        self.newton_rate = 0.0;
+        let mut previous_norm: Option<f64> = None;
+        let mut converged = false;
+        for _ in 0..MAX_NEWTON_ITERS {
+            for (i, c) in C
+                .iter()
+                .enumerate()
+            {
+                self.stage
+                    .clone_from(&self.x);
+                for (stage, z) in elements_mut(&mut self.stage)?
+                    .iter_mut()
+                    .zip(&self.z[i * n..(i + 1) * n])
+                {
+                    *stage += z;
+                }
+                model.f(
+                    t + c * h,
+                    &self.stage,
+                    &mut self.derivative,
+                )?;
+                self.stats
+                    .function_evaluations += 1;
+                self.fz[i * n..(i + 1) * n].copy_from_slice(elements(&self.derivative)?);
+            }
+
+            // residual of the stage equations A^-1 z / h = f(t + c h, x + z) after transforming
+            // by T^-1, where T^-1 A^-1 T is block diagonal with the eigenvalues gamma and alpha ± i beta
+            for p in 0..n {
+                let f = [self.fz[p], self.fz[n + p], self.fz[2 * n + p]];
+                let w = [self.w[p], self.w[n + p], self.w[2 * n + p]];
+                let g: [f64; 3] =
+                    std::array::from_fn(|i| TI[i][0] * f[0] + TI[i][1] * f[1] + TI[i][2] * f[2]);
+                self.dw[p] = g[0] - GAMMA * w[0] / h;
+                self.dw[n + p] = g[1] - (ALPHA * w[1] - BETA * w[2]) / h;
+                self.dw[2 * n + p] = g[2] - (BETA * w[1] + ALPHA * w[2]) / h;
+            }
+            let (real, complex) = self
+                .dw
+                .split_at_mut(n);
+            self.real_lu
+                .solve(real);
+            let (re, im) = complex.split_at_mut(n);
+            self.complex_lu
+                .solve(re, im);
+
+            let x = elements(&self.x)?;
+            let mut accum = 0.0;
+            for (p, x) in x
+                .iter()
+                .enumerate()
+            {
+                let dw = [self.dw[p], self.dw[n + p], self.dw[2 * n + p]];
+                let scale = controller.abs_tol + controller.rel_tol * x.abs();
+                for (i, row) in T
+                    .iter()
+                    .enumerate()
+                {
+                    self.w[i * n + p] += dw[i];
+                    let dz = row[0] * dw[0] + row[1] * dw[1] + row[2] * dw[2];
+                    self.z[i * n + p] += dz;
+                    accum += (dz / scale).powi(2);
+                }
+            }
+            let norm = (accum / (3 * n) as f64).sqrt();
+
+            let rate = previous_norm.map(|previous_norm| norm / previous_norm);
+            if let Some(rate) = rate {
+                self.newton_rate = self
+                    .newton_rate
+                    .max(rate);
+            }
+            if norm <= newton_tol {
+                converged = true;
+                break;
+            }
+            if rate.is_some_and(|rate| rate >= MAX_NEWTON_RATE) {
+                break;
+            }
+            previous_norm = Some(norm);
+        }
+        if !converged {
+            return Ok(StepOutcome::Failed);
+        }
+
+        // the method is stiffly accurate, so the solution is the last stage
+        self.y
+            .clone_from(&self.x);
+        for (y, z) in elements_mut(&mut self.y)?
+            .iter_mut()
+            .zip(&self.z[2 * n..])
+        {
+            *y += z;
+        }
+
+        // Embedded error estimate, (gamma/h I - J)^-1 (f0 + sum_i dd_i z_i / h)
+        let f0 = take(&mut self.f0);
+        self.error_estimate(&f0, h)?;
+        self.f0 = f0;
+        let mut error = self
+            .y
+            .compute_error(
+                &self.x,
+                &self.y_tilde,
+                controller.abs_tol,
+                controller.rel_tol,
+            );
+
+        if error >= 1.0 && refine_error {
+            // the estimate can be pessimistic for very stiff components, so filter it once more
+            // through the derivative at x + err as in RADAU5
+            self.stage
+                .clone_from(&self.x);
+            self.stage += &self.y_tilde;
+            model.f(
+                t,
+                &self.stage,
+                &mut self.derivative,
+            )?;
+            self.stats
+                .function_evaluations += 1;
+            // the stage derivatives are no longer needed, so reuse them as storage
+            self.fz[..n].copy_from_slice(elements(&self.derivative)?);
+            let fz = take(&mut self.fz);
+            self.error_estimate(&fz[..n], h)?;
+            self.fz = fz;
+            error = self
+                .y
+                .compute_error(
+                    &self.x,
+                    &self.y_tilde,
+                    controller.abs_tol,
+                    controller.rel_tol,
+                );
+        }
+
+        Ok(StepOutcome::Converged(error))
+    }
+
+    /// Solves (gamma/h I - J) err = derivative + sum_i dd_i z_i / h, storing err in y_tilde.
+    fn error_estimate(&mut self, derivative: &[f64], h: f64) -> Result<(), Box<dyn Error>> {
+        let n = self.n;
+        let error = elements_mut(&mut self.y_tilde)?;
+        for p in 0..n {
+            error[p] = derivative[p]
+                + (DD[0] * self.z[p] + DD[1] * self.z[n + p] + DD[2] * self.z[2 * n + p]) / h;
+        }
+        self.real_lu
+            .solve(error);
+        Ok(())
+    }
+
+    /// Computes the state at time `t` within the last step from the collocation polynomial,
+    /// storing it in the interpolant buffer.
+    pub fn interpolate(&mut self, t0: f64, dt: f64, t: f64) -> Result<(), Box<dyn Error>> {
+        if t < t0 || t > t0 + dt {
+            panic!("t out of range for interpolation - todo extrapolation?");
+        }
+        let n = self.n;
+        let theta = (t - t0) / dt;
+
+        // Lagrange basis through the nodes 0, c1, c2, 1, where the increment at 0 is zero
+        let nodes = [0.0, C[0], C[1], C[2]];
+        let mut weights = [0.0; 3];
+        for (i, weight) in weights
+            .iter_mut()
+            .enumerate()
+        {
+            let node = nodes[i + 1];
+            *weight = nodes
+                .iter()
+                .filter(|&&other| other != node)
+                .map(|other| (theta - other) / (node - other))
+                .product();
+        }
+
+        self.interpolant
+            .clone_from(&self.x);
+        let interpolant = elements_mut(&mut self.interpolant)?;
+        for (p, interpolant) in interpolant
+            .iter_mut()
+            .enumerate()
+        {
+            *interpolant += weights[0] * self.z[p]
+                + weights[1] * self.z[n + p]
+                + weights[2] * self.z[2 * n + p];
+        }
+        Ok(())
+    }
+
+    /// Stores the collocation polynomial of the step from `t0` with step size `dt` in the dense
+    /// output, valid until `t1`.
+    fn save_dense_output(
+        &self,
+        dense_output: &mut DenseOutput<State>,
+        t0: f64,
+        dt: f64,
+        t1: f64,
+    ) -> Result<(), Box<dyn Error>> {
+        let n = self.n;
+        let nodes = [0.0, C[0], C[1], C[2]];
+        let bases: Vec<Vec<f64>> = (1..nodes.len())
+            .map(|i| lagrange_basis(&nodes, i))
+            .collect();
+        // the basis polynomials of the stages vanish at 0, so the weights of the stages in
+        // coefficient k are their basis coefficients of theta^(k + 1)
+        let weights: Vec<[f64; 3]> = (1..nodes.len())
+            .map(|k| [bases[0][k], bases[1][k], bases[2][k]])
+            .collect();
+        let mut coefficients = Vec::with_capacity(weights.len());
+        for weights in &weights {
+            let mut coefficient = self
+                .x
+                .clone();
+            coefficient *= 0.0;
+            for (p, coefficient) in elements_mut(&mut coefficient)?
+                .iter_mut()
+                .enumerate()
+            {
+                *coefficient = weights[0] * self.z[p]
+                    + weights[1] * self.z[n + p]
+                    + weights[2] * self.z[2 * n + p];
+            }
+            coefficients.push(coefficient);
+        }
+        dense_output.push(
+            t0,
+            t1,
+            dt,
+            &self.x,
+            coefficients,
+        );
+        Ok(())
+    }
+}
+
+/// Add a constant minimum step size regardless of min_dt parameter
+fn check_emergency_dt(t: f64, dt: f64, error: f64) {
+    const EMERGENCY_MIN_DT: f64 = 1e-10;
+
+    if dt < EMERGENCY_MIN_DT {
+        panic!(
+            "Emergency minimum step size reached at t = {}, error = {}",
+            t, error
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        OdeProblem,
+        solvers::{ImplicitMethods, OdeSolver},
+        state::state_array::StateArray,
+    };
+
+    /// Robertson's chemical kinetics problem, with rate constants spanning 9 orders of magnitude
+    #[derive(Debug)]
+    struct Robertson;
+
+    impl OdeModel for Robertson {
+        type State = StateArray<3>;
+        fn f(
+            &mut self,
+            _t: f64,
+            x: &StateArray<3>,
+            dx: &mut StateArray<3>,
+        ) -> Result<(), Box<dyn Error>> {
+            dx[0] = -0.04 * x[0] + 1e4 * x[1] * x[2];
+            dx[1] = 0.04 * x[0] - 1e4 * x[1] * x[2] - 3e7 * x[1] * x[1];
+            dx[2] = 3e7 * x[1] * x[1];
+            Ok(())
+        }
+    }
+
+    #[derive(Debug)]
+    struct Oscillator;
+
+    impl OdeModel for Oscillator {
+        type State = StateArray<2>;
+        fn f(
+            &mut self,
+            _t: f64,
+            x: &StateArray<2>,
+            dx: &mut StateArray<2>,
+        ) -> Result<(), Box<dyn Error>> {
+            dx[0] = x[1];
+            dx[1] = -x[0];
+            Ok(())
+        }
+    }
+
+    /// Error at t = 1 of the oscillator integrated with n fixed steps
+    fn oscillator_error(n: usize) -> f64 {
+        let mut model = Oscillator;
+        let mut solver = RadauIIA5::new();
+        let controller = AdaptiveStepControl::default()
+            .with_abs_tol(1e-12)
+            .with_rel_tol(1e-12);
+        solver
+            .init(&StateArray::new([1.0, 0.0]))
+            .unwrap();
+        let h = 1.0 / n as f64;
+        for i in 0..n {
+            let t = i as f64 * h;
+            solver
+                .update_derivative(&mut model, t)
+                .unwrap();
+            if solver.jacobian_stale {
+                solver
+                    .update_jacobian(&mut model, t)
+                    .unwrap();
+            }
+            match solver
+                .step(
+                    &mut model,
+                    t,
+                    h,
+                    &controller,
+                    false,
+                )
+                .unwrap()
+            {
+                StepOutcome::Converged(_) => {}
+                StepOutcome::Failed => panic!(
+                    "Newton iterations failed at t = {}",
+                    t
+                ),
+            }
+            solver
+                .x
+                .clone_from(&solver.y);
+        }
+        ((solver.x[0] - 1.0_f64.cos()).powi(2) + (solver.x[1] + 1.0_f64.sin()).powi(2)).sqrt()
+    }
+
+    #[test]
+    fn test_robertson() {
+        let problem = OdeProblem::new(Robertson);
+        let solver = OdeSolver::new(ImplicitMethods::RadauIIA5.into());
+        let controller = AdaptiveStepControl::default()
+            .with_abs_tol(1e-12)
+            .with_rel_tol(1e-8);
+        let solution = solver
+            .solve_adaptive(
+                problem,
+                StateArray::new([1.0, 0.0, 0.0]),
+                (0.0, 40.0),
+                controller,
+            )
+            .unwrap();
+
+        // reference solution from Hairer and Wanner, Solving Ordinary Differential Equations II
+        let reference = [0.7158271, 9.185535e-6, 0.2841637];
+        let result = solution
+            .result
+            .unwrap();
+        let x = result
+            .y
+            .last()
+            .unwrap();
+        for (x, reference) in x
+            .iter()
+            .zip(reference)
+        {
+            assert!(
+                ((x - reference) / reference).abs() < 1e-5,
+                "{} != {}",
+                x,
+                reference
+            );
+        }
+        // the reactions conserve mass
+        assert!(
+            (x.iter()
+                .sum::<f64>()
+                - 1.0)
+                .abs()
+                < 1e-10
+        );
+        // an explicit method would need on the order of 1e5 steps to stay stable
+        assert!(
+            solution
+                .stats
+                .accepted_steps
+                < 500
+        );
+    }
+
+    #[test]
+    fn test_convergence_order() {
+        let coarse = oscillator_error(5);
+        let fine = oscillator_error(10);
+        let order = (coarse / fine).log2();
+        assert!(
+            (order - ORDER as f64).abs() < 0.5,
+            "observed order {} from errors {} and {}",
+            order,
+            coarse,
+            fine
+        );
+    }
+}
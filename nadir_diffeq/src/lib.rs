@@ -1,17 +1,24 @@
 use std::path::PathBuf;
 pub mod events;
+pub mod gauss_jackson;
+pub mod implicit;
+pub mod lie_group;
 pub mod model;
 pub mod monte_carlo;
+pub mod multistep;
 pub mod rk;
 pub mod saving;
+pub mod second_order;
+pub mod solution;
 pub mod solvers;
 pub mod state;
+pub mod stats;
 pub mod stepping;
 pub mod tableau;
 
 use crate::events::{PostSimEvent, PreSimEvent, SaveEvent};
 use crate::model::OdeModel;
-use events::{ContinuousEvent, EventManager, PeriodicEvent};
+use events::{ContinuousEvent, DiscreteEvent, EventManager, PeriodicEvent};
 use state::OdeState;
 
 /// Container for a complete ODE simulation problem, including model, solver configuration,
@@ -32,19 +39,33 @@ where
     State: OdeState,
 {
     /// Creates a new `OdeProblem` instance with the specified configuration.    
+    /// Any periodic events required by the model are added automatically.
     pub fn new(model: Model) -> Self {
-        Self { model, events: EventManager::new(), save_folder: None }
+        let mut problem = Self { model, events: EventManager::new(), save_folder: None };
+        problem.add_model_events();
+        problem
     }
 
     /// Sets the events of the OdeProblem
-    /// This will override any existing events!
+    /// This will override any existing events, other than those required by the model!
     /// This is mostly just used for example in monte carlo to clone events from the builder to the instances
     /// You probably don't want to use this, just use the individualized event methods like with_periodic_events, etc.
     pub fn with_events(mut self, events: EventManager<Model, State>) -> Self {
         self.events = events;
+        self.add_model_events();
         self
     }
 
+    fn add_model_events(&mut self) {
+        for event in self
+            .model
+            .periodic_events()
+        {
+            self.events
+                .add_periodic(event);
+        }
+    }
+
     /// Adds a periodic event to the simulation.
     pub fn with_periodic_event(mut self, event: PeriodicEvent<Model, State>) -> Self {
         self.events
@@ -52,6 +73,13 @@ where
         self
     }
 
+    /// Adds a one-shot event to the simulation, which the solver steps exactly to.
+    pub fn with_discrete_event(mut self, event: DiscreteEvent<Model, State>) -> Self {
+        self.events
+            .add_discrete(event);
+        self
+    }
+
     // Adds a presim event to the simulation.
     pub fn with_presim_event(mut self, event: PreSimEvent<Model, State>) -> Self {
         self.events
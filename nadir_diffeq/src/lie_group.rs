@@ -0,0 +1,711 @@
+//! Runge-Kutta-Munthe-Kaas methods for states with components on a Lie group.
+//!
+//! Each step integrates an increment in the tangent space from zero, with the stages evaluated at
+//! the state retracted along the stage increments. Group components such as attitude quaternions
+//! are only ever updated through the exponential map, so they stay on the group to roundoff
+//! regardless of the tolerance, and the embedded error estimate is computed in the tangent space.
+//! Using the exact dexp^-1 keeps the order of the underlying Runge-Kutta tableau.
+
+use std::error::Error;
+
+use indicatif::ProgressBar;
+
+use crate::{
+    OdeModel,
+    events::EventManager,
+    saving::{MemoryResult, WriterManager},
+    state::{Adaptive, LieGroup, OdeState},
+    stats::SolverStats,
+    stepping::{AdaptiveStepControl, FixedStepControl},
+    tableau::ButcherTableau,
+};
+
+/// A Runge-Kutta-Munthe-Kaas solver built on a Butcher tableau, capable of fixed or adaptive
+/// integration with periodic and continuous events.
+#[derive(Debug)]
+pub struct RungeKuttaMuntheKaas<State: OdeState + LieGroup, const ORDER: usize, const STAGES: usize>
+{
+    x: State,
+    y: State,
+    /// Error estimate in the tangent space
+    y_tilde: State,
+    tableau: ButcherTableau<ORDER, STAGES>,
+    /// Stage derivatives of the increment
+    k: Vec<State>,
+    /// Increment of the current stage
+    increment: State,
+    stage: State,
+    derivative: State,
+    /// Model derivative at x
+    x_derivative: State,
+    /// Model derivative at y, which FSAL tableaus carry over to the next step
+    y_derivative: State,
+    interpolant: State,
+    first_step: bool,
+    stats: SolverStats,
+}
+
+impl<State: OdeState + LieGroup, const ORDER: usize, const STAGES: usize>
+    RungeKuttaMuntheKaas<State, ORDER, STAGES>
+{
+    /// Constructs a new Runge-Kutta-Munthe-Kaas solver using a specific Butcher tableau.
+    pub fn new(tableau: ButcherTableau<ORDER, STAGES>) -> Self {
+        Self {
+            x: State::default(),
+            y: State::default(),
+            y_tilde: State::default(),
+            tableau,
+            k: vec![State::default(); STAGES],
+            increment: State::default(),
+            stage: State::default(),
+            derivative: State::default(),
+            x_derivative: State::default(),
+            y_derivative: State::default(),
+            interpolant: State::default(),
+            first_step: true,
+            stats: SolverStats::default(),
+        }
+    }
+
+    fn init(&mut self, x0: &State) {
+        // Copy initial state to all buffers to make sure length matches initial size of dynamically sized State
+        self.x
+            .clone_from(x0);
+        self.y
+            .clone_from(x0);
+        self.y_tilde
+            .clone_from(x0);
+        for k in &mut self.k {
+            k.clone_from(x0);
+        }
+        self.increment
+            .clone_from(x0);
+        self.stage
+            .clone_from(x0);
+        self.derivative
+            .clone_from(x0);
+        self.x_derivative
+            .clone_from(x0);
+        self.y_derivative
+            .clone_from(x0);
+        self.interpolant
+            .clone_from(x0);
+        self.first_step = true;
+    }
+
+    /// Solves the system using fixed step size control, handling periodic events.
+    pub fn solve_fixed<Model: OdeModel<State = State>>(
+        &mut self,
+        model: &mut Model,
+        x0: &State,
+        tspan: (f64, f64),
+        controller: &mut FixedStepControl,
+        events: &mut EventManager<Model, State>,
+        result: &mut Option<MemoryResult<State>>,
+        writer_manager: &mut Option<WriterManager>,
+        progress_bar: &mut ProgressBar,
+    ) -> Result<SolverStats, Box<dyn Error>> {
+        self.stats = SolverStats::default();
+        let mut t = tspan.0;
+        self.init(x0);
+
+        // Save the true initial state before any processing
+        if let Some(result) = result {
+            result.insert(t, &self.x);
+        }
+
+        if let Some(manager) = writer_manager {
+            // run the model function to update internal algebraic/kinematic states
+            model.f(t, x0, &mut self.derivative)?;
+            self.stats
+                .function_evaluations += 1;
+            events.process_save_events(model, &self.x, t, manager);
+        }
+
+        // Process initial events if any are scheduled at t0
+        if events.process_scheduled_events(model, &mut self.x, t) {
+            // If initial events changed state, save the updated state
+            if let Some(result) = result {
+                result.insert(t, &self.x);
+            }
+        };
+
+        while t < tspan.1 {
+            // Determine step size - standard dt or adjusted for upcoming event
+            let next_event_time = events.next_time();
+            let mut dt = if next_event_time > t && next_event_time < t + controller.dt {
+                // Adjust step size to land exactly on the event
+                next_event_time - t
+            } else {
+                controller.dt
+            };
+
+            // Ensure we don't step past the end
+            if t + dt > tspan.1 {
+                dt = tspan.1 - t;
+            }
+
+            // Take a step
+            self.step(model, t, dt, false)?;
+
+            // Update time based on dt
+            t += dt;
+            self.stats
+                .accept(dt);
+
+            // Increment progress bar
+            let percent_complete = ((t - tspan.0) / (tspan.1 - tspan.0) * 100.0).floor() as u64;
+            progress_bar.set_position(percent_complete);
+
+            // Save the memory result state
+            if let Some(result) = result {
+                result.insert(t, &self.y);
+            }
+            if let Some(manager) = writer_manager {
+                // run the model function to update internal algebraic/kinematic states
+                model.f(
+                    t,
+                    &self.y,
+                    &mut self.derivative,
+                )?;
+                self.stats
+                    .function_evaluations += 1;
+                events.process_save_events(model, &self.y, t, manager);
+            }
+
+            // Run any events
+            if events.process_scheduled_events(model, &mut self.y, t) {
+                // Save the result after events
+                if let Some(result) = result {
+                    result.insert(t, &self.y);
+                }
+                // the stored derivative no longer matches the state or model
+                self.first_step = true;
+            };
+
+            // Update state
+            self.advance();
+        }
+
+        // write the last state
+        if let Some(manager) = writer_manager {
+            events.process_save_events(model, &self.x, t, manager);
+        }
+        Ok(self.stats)
+    }
+
+    /// Solves the system using adaptive step size control with event detection.
+    ///
+    /// Supports both periodic and continuous events. Continuous events are located by bisection
+    /// on the dense output of the step.
+    pub fn solve_adaptive<Model: OdeModel<State = State>>(
+        &mut self,
+        model: &mut Model,
+        x0: &State,
+        tspan: (f64, f64),
+        controller: &mut AdaptiveStepControl,
+        events: &mut EventManager<Model, State>,
+        result: &mut Option<MemoryResult<State>>,
+        writer_manager: &mut Option<WriterManager>,
+        progress_bar: &mut ProgressBar,
+    ) -> Result<SolverStats, Box<dyn Error>>
+    where
+        State: Adaptive,
+    {
+        if self
+            .tableau
+            .b_tilde
+            .is_none()
+        {
+            return Err("tableau has no embedded method for adaptive step control".into());
+        }
+        self.stats = SolverStats::default();
+        let mut t = tspan.0;
+        self.init(x0);
+
+        let mut dt = 1e-3; // initial dt
+
+        // Save the true initial state before any processing
+        if let Some(result) = result {
+            result.insert(t, &self.x);
+        }
+        if let Some(manager) = writer_manager {
+            // run the model function to update any mutable internal algebraic/kinematic states
+            model.f(t, x0, &mut self.derivative)?;
+            self.stats
+                .function_evaluations += 1;
+            events.process_save_events(model, &self.x, t, manager);
+        }
+
+        while t < tspan.1 {
+            // Determine step size - standard dt or adjusted for upcoming event
+            let next_event_time = events.next_time();
+            if next_event_time > t && next_event_time < t + dt {
+                // Adjust step size to land exactly on the event
+                dt = next_event_time - t
+            };
+
+            // Ensure we don't step past the end
+            if t + dt > tspan.1 {
+                dt = tspan.1 - t;
+            }
+
+            // Trial step
+            self.step(model, t, dt, true)?;
+
+            // Calculate error
+            let error = self
+                .y
+                .compute_error(
+                    &self.x,
+                    &self.y_tilde,
+                    controller.abs_tol,
+                    controller.rel_tol,
+                );
+
+            // Calculate new step size based on dt
+            let mut new_dt = controller.step(dt, error, ORDER);
+
+            // Apply controller limits
+            new_dt = controller.limit(new_dt, &mut self.stats);
+
+            // Check if step is accepted
+            if error <= 1.0 {
+                // Step ACCEPTED: advance time, save result, and update state
+
+                // First determine if any continuous events occurred that require us to step back in time
+                let continuous_event_time =
+                    if events.detect_continuous_events(t, &self.x, t + dt, &self.y) {
+                        events.locate_continuous_events(|condition, s| {
+                            self.interpolate(t, dt, s)?;
+                            Ok(condition.call(&self.interpolant, s))
+                        })?
+                    } else {
+                        None
+                    };
+                let continuous_event_occurred = continuous_event_time.is_some();
+                if let Some(continuous_event_time) = continuous_event_time {
+                    self.interpolate(t, dt, continuous_event_time)?;
+                    // save the result prior to the event action
+                    if let Some(result) = result {
+                        result.insert(
+                            continuous_event_time,
+                            &self.interpolant,
+                        );
+                    }
+                    // perform the event actions
+                    events.perform_continuous_events(
+                        model,
+                        &mut self.interpolant,
+                        continuous_event_time,
+                    );
+                    // save after the actions
+                    if let Some(result) = result {
+                        result.insert(
+                            continuous_event_time,
+                            &self.interpolant,
+                        );
+                    }
+                    // update dt for the continuous event time
+                    dt = continuous_event_time - t;
+                    // update state for the interpolated state after all events have occurred
+                    self.y
+                        .clone_from(&self.interpolant);
+                }
+                t += dt;
+                self.stats
+                    .accept(dt);
+
+                // Increment progress bar
+                let percent_complete = ((t - tspan.0) / (tspan.1 - tspan.0) * 100.0).floor() as u64;
+                progress_bar.set_position(percent_complete);
+
+                // Save the true state before any event processing
+                if let Some(result) = result {
+                    result.insert(t, &self.y);
+                }
+                if let Some(manager) = writer_manager {
+                    // run the model function to update internal algebraic/kinematic states
+                    model.f(
+                        t,
+                        &self.y,
+                        &mut self.derivative,
+                    )?;
+                    self.stats
+                        .function_evaluations += 1;
+                    events.process_save_events(model, &self.y, t, manager);
+                }
+
+                // Process scheduled events if any occurred
+                let scheduled_event_occurred =
+                    events.process_scheduled_events(model, &mut self.y, t);
+                if scheduled_event_occurred {
+                    // Events changed state, save the updated state
+                    if let Some(result) = result {
+                        result.insert(t, &self.y);
+                    }
+                };
+
+                self.advance();
+                if continuous_event_occurred || scheduled_event_occurred {
+                    // the stored derivative no longer matches the state or model
+                    self.first_step = true;
+                }
+                dt = new_dt;
+                if events.terminated() {
+                    // a terminal event stopped the integration
+                    break;
+                }
+            } else {
+                // Step REJECTED: try again with reduced step size
+                dt = new_dt;
+                self.stats
+                    .rejected_steps += 1;
+
+                // Safety check for minimum step size
+                if let Some(min) = controller.min_dt {
+                    if dt <= min {
+                        panic!("Minimum step size reached but error is still too large");
+                    }
+                }
+            }
+            // Add a constant minimum step size regardless of min_dt parameter
+            const EMERGENCY_MIN_DT: f64 = 1e-10;
+
+            if dt < EMERGENCY_MIN_DT {
+                panic!(
+                    "Emergency minimum step size reached at t = {}, error = {}",
+                    t, error
+                );
+            }
+        }
+        // write the last state
+        if let Some(manager) = writer_manager {
+            events.process_save_events(model, &self.x, t, manager);
+        }
+        Ok(self.stats)
+    }
+
+    /// Performs a single step of size h from (t, x), leaving the new state in y and,
+    /// when `adaptive` is true, the tangent space error estimate in y_tilde.
+    pub fn step<Model: OdeModel<State = State>>(
+        &mut self,
+        model: &mut Model,
+        t: f64,
+        h: f64,
+        adaptive: bool,
+    ) -> Result<(), Box<dyn Error>> {
+        let fsal = self
+            .tableau
+            .fsal;
+
+        // the first stage has a zero increment, where the tangent map is just the body rate
+        self.increment *= 0.0;
+        if !fsal || self.first_step {
+            model.f(
+                t,
+                &self.x,
+                &mut self.x_derivative,
+            )?;
+            self.stats
+                .function_evaluations += 1;
+            self.first_step = false;
+        }
+        self.k[0].clone_from(&self.x_derivative);
+        self.x
+            .tangent(
+                &self.increment,
+                &mut self.k[0],
+            );
+
+        // FSAL tableaus evaluate their last stage at the solution below
+        let stages = if fsal {
+            STAGES - 1
+        } else {
+            STAGES
+        };
+        for s in 1..stages {
+            self.combine(
+                s,
+                |tableau, i| tableau.a[s][i],
+                h,
+            );
+            self.stage
+                .retract(&self.x, &self.increment);
+            model.f(
+                t + self
+                    .tableau
+                    .c[s]
+                    * h,
+                &self.stage,
+                &mut self.k[s],
+            )?;
+            self.stats
+                .function_evaluations += 1;
+            self.stage
+                .tangent(
+                    &self.increment,
+                    &mut self.k[s],
+                );
+        }
+
+        self.combine(
+            STAGES,
+            |tableau, i| tableau.b[i],
+            h,
+        );
+        self.y
+            .retract(&self.x, &self.increment);
+
+        if fsal {
+            model.f(
+                t + h,
+                &self.y,
+                &mut self.y_derivative,
+            )?;
+            self.stats
+                .function_evaluations += 1;
+            self.k[STAGES - 1].clone_from(&self.y_derivative);
+            self.y
+                .tangent(
+                    &self.increment,
+                    &mut self.k[STAGES - 1],
+                );
+        }
+
+        if adaptive {
+            if let Some(b_tilde) = self
+                .tableau
+                .b_tilde
+            {
+                self.y_tilde *= 0.0; //reset
+                for (k, b) in self
+                    .k
+                    .iter()
+                    .zip(b_tilde)
+                {
+                    self.derivative
+                        .clone_from(k);
+                    self.derivative *= b;
+                    self.y_tilde += &self.derivative;
+                }
+                self.y_tilde *= h;
+            }
+        }
+        Ok(())
+    }
+
+    /// Moves to the accepted state y, carrying its derivative over for FSAL tableaus.
+    fn advance(&mut self) {
+        self.x
+            .clone_from(&self.y);
+        std::mem::swap(
+            &mut self.x_derivative,
+            &mut self.y_derivative,
+        );
+    }
+
+    /// Sets the increment to h * sum_i weight(i) k_i over the first `stages` stages
+    fn combine(
+        &mut self,
+        stages: usize,
+        weight: impl Fn(&ButcherTableau<ORDER, STAGES>, usize) -> f64,
+        h: f64,
+    ) {
+        self.increment *= 0.0;
+        for i in 0..stages {
+            let w = weight(&self.tableau, i);
+            if w != 0.0 {
+                self.derivative
+                    .clone_from(&self.k[i]);
+                self.derivative *= w;
+                self.increment += &self.derivative;
+            }
+        }
+        self.increment *= h;
+    }
+
+    /// Computes the state at time `t` within the last step by retracting the dense output
+    /// increment, storing it in the interpolant buffer.
+    ///
+    /// Returns an error if the tableau has no interpolation coefficients, which continuous events
+    /// need, or if `t` is outside `[t0, t0+dt]`.
+    pub fn interpolate(&mut self, t0: f64, dt: f64, t: f64) -> Result<(), Box<dyn Error>> {
+        let Some(bi) = self
+            .tableau
+            .bi
+        else {
+            return Err("continuous events require a method with interpolation coefficients, such as Tsit5 or Verner9".into());
+        };
+        if t < t0 || t > t0 + dt {
+            return Err(format!(
+                "t ({t}) out of range of the step ({t0}, {})",
+                t0 + dt
+            )
+            .into());
+        }
+        let theta = (t - t0) / dt;
+        self.combine(
+            STAGES,
+            |_, s| {
+                // Evaluate polynomial using Horner's method
+                let mut b = bi[s][ORDER - 2]; // highest degree coefficient
+                for i in (0..ORDER - 2).rev() {
+                    b = b * theta + bi[s][i];
+                }
+                b * theta
+            },
+            dt,
+        );
+        self.interpolant
+            .retract(&self.x, &self.increment);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        OdeProblem,
+        events::ContinuousEvent,
+        solution::Solution,
+        solvers::{LieGroupMethods, OdeSolver, RungeKuttaMethods, SolverMethods},
+        state::state_vector::StateVector,
+    };
+
+    /// Torque free rigid body, with the state [qx, qy, qz, qw, wx, wy, wz]
+    #[derive(Debug)]
+    struct RigidBody;
+
+    impl OdeModel for RigidBody {
+        type State = StateVector;
+        fn f(
+            &mut self,
+            _t: f64,
+            x: &StateVector,
+            dx: &mut StateVector,
+        ) -> Result<(), Box<dyn Error>> {
+            let (q, w) = (&x[0..4], &x[4..7]);
+            dx[0] = 0.5 * (q[3] * w[0] + q[1] * w[2] - q[2] * w[1]);
+            dx[1] = 0.5 * (q[3] * w[1] + q[2] * w[0] - q[0] * w[2]);
+            dx[2] = 0.5 * (q[3] * w[2] + q[0] * w[1] - q[1] * w[0]);
+            dx[3] = -0.5 * (q[0] * w[0] + q[1] * w[1] + q[2] * w[2]);
+            let [i1, i2, i3] = [1.0, 2.0, 3.0];
+            dx[4] = (i2 - i3) * w[1] * w[2] / i1;
+            dx[5] = (i3 - i1) * w[2] * w[0] / i2;
+            dx[6] = (i1 - i2) * w[0] * w[1] / i3;
+            Ok(())
+        }
+    }
+
+    /// Spinning at about 5 rev/s about the major axis, with some nutation
+    fn spinning() -> StateVector {
+        StateVector::new(vec![
+            0.0, 0.0, 0.0, 1.0, 0.5, 0.3, 30.0,
+        ])
+        .with_quaternion(0)
+    }
+
+    const TSPAN: (f64, f64) = (0.0, 60.0);
+
+    /// Largest departure of the quaternion norm from one over the saved states
+    fn norm_drift(solution: Solution<StateVector>) -> f64 {
+        solution
+            .result
+            .unwrap()
+            .y
+            .iter()
+            .map(|x| ((x[0] * x[0] + x[1] * x[1] + x[2] * x[2] + x[3] * x[3]).sqrt() - 1.0).abs())
+            .fold(0.0, f64::max)
+    }
+
+    #[test]
+    fn test_spinning_body_unit_norm() {
+        let rk4 = |method: SolverMethods| {
+            OdeSolver::new(method)
+                .solve_lie_group_fixed(
+                    OdeProblem::new(RigidBody),
+                    spinning(),
+                    TSPAN,
+                    0.005,
+                )
+                .unwrap()
+        };
+        let munthe_kaas = norm_drift(rk4(
+            LieGroupMethods::RungeKuttaMuntheKaas(RungeKuttaMethods::Rk4).into(),
+        ));
+        let plain = norm_drift(rk4(
+            RungeKuttaMethods::Rk4.into()
+        ));
+        assert!(
+            munthe_kaas < 1e-14,
+            "quaternion norm drift {}",
+            munthe_kaas
+        );
+        // the plain method drifts off the unit sphere at the same step
+        assert!(
+            plain > 1e3 * munthe_kaas.max(f64::EPSILON),
+            "quaternion norm drift {}",
+            plain
+        );
+
+        let adaptive =
+            OdeSolver::new(LieGroupMethods::RungeKuttaMuntheKaas(RungeKuttaMethods::Tsit5).into())
+                .solve_lie_group_adaptive(
+                    OdeProblem::new(RigidBody),
+                    spinning(),
+                    TSPAN,
+                    AdaptiveStepControl::default()
+                        .with_abs_tol(1e-6)
+                        .with_rel_tol(1e-6),
+                )
+                .unwrap();
+        let drift = norm_drift(adaptive);
+        assert!(
+            drift < 1e-14,
+            "quaternion norm drift {}",
+            drift
+        );
+    }
+
+    #[test]
+    fn test_interpolation_errors() {
+        // Dormand-Prince has no interpolation coefficients to locate a continuous event with
+        let problem = OdeProblem::new(RigidBody).with_continuous_event(ContinuousEvent::new(
+            |_, t| t - 1.0,
+            |_, _, _| {},
+        ));
+        let result = OdeSolver::new(
+            LieGroupMethods::RungeKuttaMuntheKaas(RungeKuttaMethods::DoPri45).into(),
+        )
+        .solve_lie_group_adaptive(
+            problem,
+            spinning(),
+            TSPAN,
+            AdaptiveStepControl::default(),
+        );
+        let error = result.unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .contains("interpolation coefficients"),
+            "{}",
+            error
+        );
+
+        // outside the step
+        let mut solver = RungeKuttaMuntheKaas::new(ButcherTableau::<5, 7>::TSITOURAS5);
+        solver.init(&spinning());
+        assert!(
+            solver
+                .interpolate(0.0, 0.1, 0.2)
+                .is_err()
+        );
+        assert!(
+            solver
+                .interpolate(0.0, 0.1, 0.05)
+                .is_ok()
+        );
+    }
+}
@@ -1,6 +1,9 @@
 use std::{error::Error, fmt::Debug};
 
-use crate::state::OdeState;
+use crate::{
+    events::PeriodicEvent,
+    state::{OdeState, second_order::SecondOrderState},
+};
 
 /// Trait for defining a dynamical system model that can be numerically integrated.
 ///
@@ -15,6 +18,69 @@ pub trait OdeModel: Debug {
         state: &Self::State,
         derivative: &mut Self::State,
     ) -> Result<(), Box<dyn Error>>;
+
+    /// Jacobian of `f` with respect to the state at time `t`, written row-major into `jacobian`
+    /// (n x n for a state with n elements). Only used by implicit solvers.
+    /// Returns false when the model does not provide one, and the solver falls back to finite differences.
+    fn jacobian(
+        &mut self,
+        _t: f64,
+        _state: &Self::State,
+        _jacobian: &mut [f64],
+    ) -> Result<bool, Box<dyn Error>> {
+        Ok(false)
+    }
+
+    /// Time of the next discontinuity in `f` after `t` that the model knows about, such as a
+    /// valve opening at a commanded time. Called at the end of every accepted step, after any
+    /// events. Solvers end a step exactly at the returned time and restart from it, so `f` is
+    /// never integrated across the discontinuity. Models should switch to the values after a
+    /// discontinuity in this call rather than in `f`, so that every stage of the step ending
+    /// there sees the values before it.
+    fn next_discontinuity(&mut self, _t: f64) -> Option<f64> {
+        None
+    }
+
+    /// Periodic events the model needs to run, such as scheduled flight software.
+    /// These are added automatically when an `OdeProblem` is built from the model.
+    fn periodic_events(&self) -> Vec<PeriodicEvent<Self, Self::State>>
+    where
+        Self: Sized,
+    {
+        Vec::new()
+    }
+}
+
+/// Trait for second order models x'' = a(t, x), where the acceleration depends only on position.
+///
+/// These can be integrated with the symplectic and Runge-Kutta-Nyström methods in `SecondOrderMethods`,
+/// as well as any first order method through `OdeModel`, whose `f` can simply call `second_order_f`.
+pub trait SecondOrderModel: OdeModel<State = SecondOrderState<Self::Position>> {
+    type Position: OdeState;
+    /// Compute the acceleration at time `t` and `position`, storing the result in `acceleration`.
+    fn acceleration(
+        &mut self,
+        t: f64,
+        position: &Self::Position,
+        acceleration: &mut Self::Position,
+    ) -> Result<(), Box<dyn Error>>;
+
+    /// The first order derivative (velocity, acceleration) of the state, for implementing `OdeModel::f`.
+    fn second_order_f(
+        &mut self,
+        t: f64,
+        state: &SecondOrderState<Self::Position>,
+        derivative: &mut SecondOrderState<Self::Position>,
+    ) -> Result<(), Box<dyn Error>> {
+        derivative
+            .position
+            .clone_from(&state.velocity);
+        self.acceleration(
+            t,
+            &state.position,
+            &mut derivative.velocity,
+        )
+    }
 }
 
 /// Allows users to have the state defined from the model rather than providing directly
@@ -2,7 +2,8 @@ use crate::{
     OdeModel, OdeProblem,
     events::{ContinuousEvent, EventManager, PeriodicEvent, PostSimEvent, PreSimEvent, SaveEvent},
     model::{StateFromModel, StateFromModelMut},
-    saving::{MemoryResult, SaveMethods},
+    saving::SaveMethods,
+    solution::Solution,
     solvers::{OdeSolver, RungeKuttaMethods, SolverMethods},
     state::{Adaptive, OdeState},
     stepping::AdaptiveStepControl,
@@ -49,7 +50,7 @@ impl MonteCarloSolver {
         x0: StateBuilder,
         tspan: (f64, f64),
         solve_fn: F,
-    ) -> Result<Option<Vec<MemoryResult<State>>>, Box<dyn Error>>
+    ) -> Result<Vec<Solution<State>>, Box<dyn Error>>
     where
         ModelBuilder: Uncertainty + Clone + Send + Sync, // Need Send + Sync to share across threads
         ModelBuilder::Output: OdeModel<State = State>,
@@ -61,7 +62,7 @@ impl MonteCarloSolver {
                 State,
                 (f64, f64),
                 &mut ProgressBar,
-            ) -> Result<Option<MemoryResult<State>>, Box<dyn Error>>
+            ) -> Result<Solution<State>, Box<dyn Error>>
             + Send
             + Sync,
     {
@@ -122,13 +123,7 @@ impl MonteCarloSolver {
         let results: Vec<_> = indices
             .into_par_iter()
             .map(
-                |i| -> Result<
-                    (
-                        usize,
-                        Option<MemoryResult<State>>,
-                    ),
-                    MonteCarloError,
-                > {
+                |i| -> Result<(usize, Solution<State>), MonteCarloError> {
                     // Acquire a progress bar
                     let bar_index = {
                         let mut bars_in_use = bars_in_use
@@ -231,13 +226,16 @@ impl MonteCarloSolver {
                         bars_in_use[bar_index] = false;
                     }
 
-                    // Only return results if we need to save them
+                    // Only return the states if we need to save them
                     Ok((
                         i,
-                        if should_save {
-                            result
-                        } else {
-                            None
+                        Solution {
+                            result: if should_save {
+                                result.result
+                            } else {
+                                None
+                            },
+                            ..result
                         },
                     ))
                 },
@@ -246,26 +244,14 @@ impl MonteCarloSolver {
 
         overall_pb.finish_with_message("Monte Carlo simulation completed!");
 
-        // If we're saving results, assemble them in the correct order
-        if should_save {
-            // Sort the results by index
-            let mut result_pairs: Vec<(usize, MemoryResult<State>)> = results
-                .into_iter()
-                .filter_map(|(idx, result_opt)| result_opt.map(|r| (idx, r)))
-                .collect();
-
-            // Sort by run index
-            result_pairs.sort_by_key(|(idx, _)| *idx);
-
-            // Extract just the results in correct order
-            let final_results = result_pairs
-                .into_iter()
-                .map(|(_, r)| r)
-                .collect();
-            Ok(Some(final_results))
-        } else {
-            Ok(None)
-        }
+        // Sort the solutions by run index
+        let mut solution_pairs = results;
+        solution_pairs.sort_by_key(|(idx, _)| *idx);
+
+        Ok(solution_pairs
+            .into_iter()
+            .map(|(_, solution)| solution)
+            .collect())
     }
 
     // Private helper method that handles the common logic
@@ -274,7 +260,7 @@ impl MonteCarloSolver {
         problem: MonteCarloProblem<ModelBuilder>,
         tspan: (f64, f64),
         solve_fn: F,
-    ) -> Result<Option<Vec<MemoryResult<State>>>, Box<dyn Error>>
+    ) -> Result<Vec<Solution<State>>, Box<dyn Error>>
     where
         ModelBuilder: Uncertainty + Clone + Send + Sync, // Need Send + Sync to share across threads
         ModelBuilder::Output: OdeModel<State = State> + StateFromModel<State = State>,
@@ -284,7 +270,7 @@ impl MonteCarloSolver {
                 OdeProblem<ModelBuilder::Output, State>,
                 (f64, f64),
                 &mut ProgressBar,
-            ) -> Result<Option<MemoryResult<State>>, Box<dyn Error>>
+            ) -> Result<Solution<State>, Box<dyn Error>>
             + Send
             + Sync,
     {
@@ -432,38 +418,29 @@ impl MonteCarloSolver {
                     bars_in_use[bar_index] = false;
                 }
 
-                // Only return results if we need to save them
+                // Only return the states if we need to save them
                 Ok((
                     i,
-                    if should_save {
-                        result
-                    } else {
-                        None
+                    Solution {
+                        result: if should_save {
+                            result.result
+                        } else {
+                            None
+                        },
+                        ..result
                     },
                 ))
             })
             .collect::<Result<Vec<_>, MonteCarloError>>()?;
 
-        // If we're saving results, assemble them in the correct order
-        if should_save {
-            // Sort the results by index
-            let mut result_pairs: Vec<(usize, MemoryResult<State>)> = results
-                .into_iter()
-                .filter_map(|(idx, result_opt)| result_opt.map(|r| (idx, r)))
-                .collect();
-
-            // Sort by run index
-            result_pairs.sort_by_key(|(idx, _)| *idx);
-
-            // Extract just the results in correct order
-            let final_results = result_pairs
-                .into_iter()
-                .map(|(_, r)| r)
-                .collect();
-            Ok(Some(final_results))
-        } else {
-            Ok(None)
-        }
+        // Sort the solutions by run index
+        let mut solution_pairs = results;
+        solution_pairs.sort_by_key(|(idx, _)| *idx);
+
+        Ok(solution_pairs
+            .into_iter()
+            .map(|(_, solution)| solution)
+            .collect())
     }
 
     // Private helper method that handles the common logic
@@ -472,7 +449,7 @@ impl MonteCarloSolver {
         problem: MonteCarloProblem<ModelBuilder>,
         tspan: (f64, f64),
         solve_fn: F,
-    ) -> Result<Option<Vec<MemoryResult<State>>>, Box<dyn Error>>
+    ) -> Result<Vec<Solution<State>>, Box<dyn Error>>
     where
         ModelBuilder: Uncertainty + Clone + Send + Sync, // Need Send + Sync to share across threads
         ModelBuilder::Output: OdeModel<State = State> + StateFromModelMut,
@@ -482,7 +459,7 @@ impl MonteCarloSolver {
                 OdeProblem<ModelBuilder::Output, State>,
                 (f64, f64),
                 &mut ProgressBar,
-            ) -> Result<Option<MemoryResult<State>>, Box<dyn Error>>
+            ) -> Result<Solution<State>, Box<dyn Error>>
             + Send
             + Sync,
     {
@@ -631,38 +608,29 @@ impl MonteCarloSolver {
                     bars_in_use[bar_index] = false;
                 }
 
-                // Only return results if we need to save them
+                // Only return the states if we need to save them
                 Ok((
                     i,
-                    if should_save {
-                        result
-                    } else {
-                        None
+                    Solution {
+                        result: if should_save {
+                            result.result
+                        } else {
+                            None
+                        },
+                        ..result
                     },
                 ))
             })
             .collect::<Result<Vec<_>, MonteCarloError>>()?;
 
-        // If we're saving results, assemble them in the correct order
-        if should_save {
-            // Sort the results by index
-            let mut result_pairs: Vec<(usize, MemoryResult<State>)> = results
-                .into_iter()
-                .filter_map(|(idx, result_opt)| result_opt.map(|r| (idx, r)))
-                .collect();
-
-            // Sort by run index
-            result_pairs.sort_by_key(|(idx, _)| *idx);
-
-            // Extract just the results in correct order
-            let final_results = result_pairs
-                .into_iter()
-                .map(|(_, r)| r)
-                .collect();
-            Ok(Some(final_results))
-        } else {
-            Ok(None)
-        }
+        // Sort the solutions by run index
+        let mut solution_pairs = results;
+        solution_pairs.sort_by_key(|(idx, _)| *idx);
+
+        Ok(solution_pairs
+            .into_iter()
+            .map(|(_, solution)| solution)
+            .collect())
     }
 
     pub fn solve_adaptive<ModelBuilder, StateBuilder, State>(
@@ -671,7 +639,7 @@ impl MonteCarloSolver {
         x0: StateBuilder,
         tspan: (f64, f64),
         controller: AdaptiveStepControl,
-    ) -> Result<Option<Vec<MemoryResult<State>>>, Box<dyn Error>>
+    ) -> Result<Vec<Solution<State>>, Box<dyn Error>>
     where
         ModelBuilder: Uncertainty + Clone + Send + Sync,
         ModelBuilder::Output: OdeModel<State = State>,
@@ -699,7 +667,7 @@ impl MonteCarloSolver {
         problem: MonteCarloProblem<ModelBuilder>,
         tspan: (f64, f64),
         controller: AdaptiveStepControl,
-    ) -> Result<Option<Vec<MemoryResult<State>>>, Box<dyn Error>>
+    ) -> Result<Vec<Solution<State>>, Box<dyn Error>>
     where
         ModelBuilder: Uncertainty + Clone + Send + Sync,
         ModelBuilder::Output: OdeModel<State = State> + StateFromModel<State = State>,
@@ -728,7 +696,7 @@ impl MonteCarloSolver {
         problem: MonteCarloProblem<ModelBuilder>,
         tspan: (f64, f64),
         controller: AdaptiveStepControl,
-    ) -> Result<Option<Vec<MemoryResult<State>>>, Box<dyn Error>>
+    ) -> Result<Vec<Solution<State>>, Box<dyn Error>>
     where
         ModelBuilder: Uncertainty + Clone + Send + Sync,
         ModelBuilder::Output: OdeModel<State = State> + StateFromModelMut<State = State>,
@@ -758,7 +726,7 @@ impl MonteCarloSolver {
         x0: StateBuilder,
         tspan: (f64, f64),
         dt: f64,
-    ) -> Result<Option<Vec<MemoryResult<State>>>, Box<dyn Error>>
+    ) -> Result<Vec<Solution<State>>, Box<dyn Error>>
     where
         ModelBuilder: Uncertainty + Clone + Send + Sync,
         ModelBuilder::Output: OdeModel<State = State>,
@@ -786,7 +754,7 @@ impl MonteCarloSolver {
         problem: MonteCarloProblem<ModelBuilder>,
         tspan: (f64, f64),
         dt: f64,
-    ) -> Result<Option<Vec<MemoryResult<State>>>, Box<dyn Error>>
+    ) -> Result<Vec<Solution<State>>, Box<dyn Error>>
     where
         ModelBuilder: Uncertainty + Clone + Send + Sync,
         ModelBuilder::Output: OdeModel<State = State> + StateFromModel<State = State>,
@@ -815,7 +783,7 @@ impl MonteCarloSolver {
         problem: MonteCarloProblem<ModelBuilder>,
         tspan: (f64, f64),
         dt: f64,
-    ) -> Result<Option<Vec<MemoryResult<State>>>, Box<dyn Error>>
+    ) -> Result<Vec<Solution<State>>, Box<dyn Error>>
     where
         ModelBuilder: Uncertainty + Clone + Send + Sync,
         ModelBuilder::Output: OdeModel<State = State> + StateFromModelMut<State = State>,
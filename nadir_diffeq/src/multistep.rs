@@ -0,0 +1,873 @@
+//! Variable-step, variable-order Adams-Bashforth-Moulton predictor-corrector, in the style of
+//! ODE113 and Shampine & Gordon's DE/STEP.
+//!
+//! Multistep methods reuse the derivatives of previous steps, so each step costs two evaluations
+//! of `OdeModel::f` regardless of order. This makes them far cheaper than Runge-Kutta methods for
+//! smooth problems with expensive derivatives, like long orbit propagations with high degree gravity
+//! fields. Every periodic or continuous event restarts the method at first order, since the event may
+//! have changed the state or the model and the history no longer describes a smooth solution. Frequent
+//! periodic events, like flight software running at a high rate, erase most of the savings.
+
+use std::{collections::VecDeque, error::Error};
+
+use indicatif::ProgressBar;
+
+use crate::{
+    OdeModel,
+    events::EventManager,
+    saving::{MemoryResult, WriterManager},
+    solution::{DenseOutput, lagrange_basis},
+    state::{Adaptive, OdeState},
+    stats::SolverStats,
+    stepping::AdaptiveStepControl,
+};
+
+/// Maximum order of the Adams methods
+const MAX_ORDER: usize = 12;
+
+/// Gauss-Legendre nodes on [-1, 1]. Seven points integrate polynomials up to degree 13 exactly,
+/// which covers interpolating polynomials through up to MAX_ORDER + 1 points.
+const GAUSS_NODES: [f64; 7] = [
+    -0.9491079123427585,
+    -0.7415311855993945,
+    -0.4058451513773972,
+    0.0,
+    0.4058451513773972,
+    0.7415311855993945,
+    0.9491079123427585,
+];
+const GAUSS_WEIGHTS: [f64; 7] = [
+    0.1294849661688697,
+    0.2797053914892766,
+    0.3818300505051189,
+    0.4179591836734694,
+    0.3818300505051189,
+    0.2797053914892766,
+    0.1294849661688697,
+];
+
+/// Computes the integrals from a to b of the Lagrange basis polynomials through `nodes`.
+fn integration_weights(nodes: &[f64], a: f64, b: f64, weights: &mut Vec<f64>) {
+    weights.clear();
+    let half_width = 0.5 * (b - a);
+    let midpoint = 0.5 * (a + b);
+    for (j, node) in nodes
+        .iter()
+        .enumerate()
+    {
+        let mut integral = 0.0;
+        for (gauss_node, gauss_weight) in GAUSS_NODES
+            .iter()
+            .zip(&GAUSS_WEIGHTS)
+        {
+            let s = midpoint + half_width * gauss_node;
+            let mut basis = 1.0;
+            for (m, other) in nodes
+                .iter()
+                .enumerate()
+            {
+                if m != j {
+                    basis *= (s - other) / (node - other);
+                }
+            }
+            integral += gauss_weight * basis;
+        }
+        weights.push(half_width * integral);
+    }
+}
+
+/// Sets `out` to base + h * (weights[0] * new + sum_j weights[j+1] * history[j]) when `new` is
+/// provided, or base + h * sum_j weights[j] * history[j] otherwise. A missing base is zero.
+fn combine<State: OdeState>(
+    out: &mut State,
+    base: Option<&State>,
+    h: f64,
+    weights: &[f64],
+    new: Option<&State>,
+    history: &VecDeque<(f64, State)>,
+    scratch: &mut State,
+) {
+    let mut derivatives = new
+        .into_iter()
+        .chain(
+            history
+                .iter()
+                .map(|(_, derivative)| derivative),
+        );
+    out.clone_from(
+        derivatives
+            .next()
+            .unwrap(),
+    );
+    *out *= weights[0];
+    for (derivative, weight) in derivatives.zip(&weights[1..]) {
+        scratch.clone_from(derivative);
+        *scratch *= *weight;
+        *out += scratch;
+    }
+    *out *= h;
+    if let Some(base) = base {
+        *out += base;
+    }
+}
+
+/// Variable-step, variable-order Adams-Bashforth-Moulton method in PECE mode.
+///
+/// Each step predicts with the Adams-Bashforth formula of the current order, evaluates the
+/// derivative at the prediction, corrects with the Adams-Moulton formula of the same order, and
+/// evaluates the derivative at the corrected state for the history. The difference between the
+/// predictor and corrector estimates the local error. The coefficients are computed for the actual
+/// step history, so the step size can change every step. The order starts at one and rises each step
+/// while the method is starting, until raising it no longer reduces the error estimate. It then moves
+/// by at most one per step to whichever neighboring order allows the largest next step.
+#[derive(Debug)]
+pub struct AdamsBashforthMoulton<State: OdeState> {
+    x: State,
+    y: State,
+    y_predicted: State,
+    y_tilde: State,
+    /// Derivative at the predicted state
+    derivative: State,
+    interpolant: State,
+    scratch: State,
+    /// Times and derivatives of previous steps, most recent first
+    history: VecDeque<(f64, State)>,
+    /// Order of the current step
+    order: usize,
+    /// Whether the order is still being raised every step after a restart
+    starting: bool,
+    /// Consecutive rejected steps
+    rejections: usize,
+    nodes: Vec<f64>,
+    weights: Vec<f64>,
+    predictor_weights: Vec<f64>,
+    stats: SolverStats,
+}
+
+impl<State: OdeState + Adaptive> Default for AdamsBashforthMoulton<State> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<State: OdeState + Adaptive> AdamsBashforthMoulton<State> {
+    pub fn new() -> Self {
+        Self {
+            x: State::default(),
+            y: State::default(),
+            y_predicted: State::default(),
+            y_tilde: State::default(),
+            derivative: State::default(),
+            interpolant: State::default(),
+            scratch: State::default(),
+            history: VecDeque::with_capacity(MAX_ORDER + 1),
+            order: 1,
+            starting: true,
+            rejections: 0,
+            nodes: Vec::with_capacity(MAX_ORDER + 1),
+            weights: Vec::with_capacity(MAX_ORDER + 1),
+            predictor_weights: Vec::with_capacity(MAX_ORDER + 1),
+            stats: SolverStats::default(),
+        }
+    }
+
+    /// Solves the system using adaptive step size and order control with event detection.
+    ///
+    /// Supports both periodic and continuous events. Continuous events are located by bisection
+    /// on the interpolating polynomial of the corrector.
+    pub fn solve_adaptive<Model>(
+        &mut self,
+        model: &mut Model,
+        x0: &State,
+        tspan: (f64, f64),
+        controller: &mut AdaptiveStepControl,
+        events: &mut EventManager<Model, State>,
+        result: &mut Option<MemoryResult<State>>,
+        dense_output: &mut Option<DenseOutput<State>>,
+        writer_manager: &mut Option<WriterManager>,
+        progress_bar: &mut ProgressBar,
+    ) -> Result<SolverStats, Box<dyn Error>>
+    where
+        Model: OdeModel<State = State>,
+    {
+        self.stats = SolverStats::default();
+        let mut t = tspan.0;
+
+        // Copy initial state to all buffers to make sure length matches initial size of dynamically sized State
+        self.x
+            .clone_from(x0);
+        self.y
+            .clone_from(x0);
+        self.y_predicted
+            .clone_from(x0);
+        self.y_tilde
+            .clone_from(x0);
+        self.derivative
+            .clone_from(x0);
+        self.interpolant
+            .clone_from(x0);
+        self.scratch
+            .clone_from(x0);
+
+        let mut dt = 1e-3; // initial dt
+
+        // Save the true initial state before any processing
+        if let Some(result) = result {
+            result.insert(t, &self.x);
+        }
+        if let Some(manager) = writer_manager {
+            // run the model function to update any mutable internal algebraic/kinematic states
+            model.f(t, x0, &mut self.derivative)?;
+            self.stats
+                .function_evaluations += 1;
+            events.process_save_events(model, &self.x, t, manager);
+        }
+
+        self.restart(model, t)?;
+
+        while t < tspan.1 {
+            // Determine step size - standard dt or adjusted for upcoming event
+            let next_event_time = events.next_time();
+            if next_event_time > t && next_event_time < t + dt {
+                // Adjust step size to land exactly on the event
+                dt = next_event_time - t
+            };
+
+            // Ensure we don't step past the end
+            if t + dt > tspan.1 {
+                dt = tspan.1 - t;
+            }
+
+            // Trial step
+            let error = self.step(model, t, dt, controller)?;
+
+            if error <= 1.0 {
+                // Step ACCEPTED: choose the order and step size for the next step
+                let (order, order_error) = self.select_order(t, dt, error, controller);
+                let mut new_dt = controller
+                    .step(dt, order_error, order + 2)
+                    .clamp(0.2 * dt, 2.0 * dt);
+                new_dt = controller.limit(new_dt, &mut self.stats);
+
+                // First determine if any continuous events occurred that require us to step back in time
+                let continuous_event_time =
+                    if events.detect_continuous_events(t, &self.x, t + dt, &self.y) {
+                        events.locate_continuous_events(|condition, s| {
+                            self.interpolate(t, dt, s);
+                            Ok(condition.call(&self.interpolant, s))
+                        })?
+                    } else {
+                        None
+                    };
+                if let Some(dense_output) = dense_output {
+                    // the step ends at the event time if one occurred
+                    self.save_dense_output(
+                        dense_output,
+                        t,
+                        dt,
+                        continuous_event_time.unwrap_or(t + dt),
+                    );
+                }
+                let continuous_event_occurred = continuous_event_time.is_some();
+                if let Some(continuous_event_time) = continuous_event_time {
+                    self.interpolate(t, dt, continuous_event_time);
+                    // save the result prior to the event action
+                    if let Some(result) = result {
+                        result.insert(
+                            continuous_event_time,
+                            &self.interpolant,
+                        );
+                    }
+                    // perform the event actions
+                    events.perform_continuous_events(
+                        model,
+                        &mut self.interpolant,
+                        continuous_event_time,
+                    );
+                    // save after the actions
+                    if let Some(result) = result {
+                        result.insert(
+                            continuous_event_time,
+                            &self.interpolant,
+                        );
+                    }
+                    // update dt for the continuous event time
+                    dt = continuous_event_time - t;
+                    // update state for the interpolated state after all events have occurred
+                    self.y
+                        .clone_from(&self.interpolant);
+                }
+                t += dt;
+                self.stats
+                    .accept(dt);
+
+                // Increment progress bar
+                let percent_complete = ((t - tspan.0) / (tspan.1 - tspan.0) * 100.0).floor() as u64;
+                progress_bar.set_position(percent_complete);
+
+                // Save the true state before any event processing
+                if let Some(result) = result {
+                    result.insert(t, &self.y);
+                }
+                if let Some(manager) = writer_manager {
+                    // run the model function to update internal algebraic/kinematic states
+                    model.f(
+                        t,
+                        &self.y,
+                        &mut self.derivative,
+                    )?;
+                    self.stats
+                        .function_evaluations += 1;
+                    events.process_save_events(model, &self.y, t, manager);
+                }
+
+                // Process scheduled events if any occurred
+                let scheduled_event_occurred =
+                    events.process_scheduled_events(model, &mut self.y, t);
+                if scheduled_event_occurred {
+                    // Events changed state, save the updated state
+                    if let Some(result) = result {
+                        result.insert(t, &self.y);
+                    }
+                };
+
+                self.x
+                    .clone_from(&self.y);
+                self.rejections = 0;
+                if continuous_event_occurred || scheduled_event_occurred {
+                    // the state or model may have changed discontinuously, so the history is no longer valid
+                    self.restart(model, t)?;
+                } else {
+                    self.push_history(model, t)?;
+                    self.order = order;
+                }
+                dt = new_dt;
+                if events.terminated() {
+                    // a terminal event stopped the integration
+                    break;
+                }
+            } else {
+                // Step REJECTED: try again with reduced step size, falling back to first order
+                // if the method keeps failing
+                self.rejections += 1;
+                self.stats
+                    .rejected_steps += 1;
+                if self.rejections >= 3 {
+                    self.order = 1;
+                }
+                dt = controller
+                    .step(dt, error, self.order + 2)
+                    .clamp(0.1 * dt, 0.9 * dt);
+                dt = controller.limit(dt, &mut self.stats);
+
+                // Safety check for minimum step size
+                if let Some(min) = controller.min_dt {
+                    if dt <= min {
+                        panic!("Minimum step size reached but error is still too large");
+                    }
+                }
+            }
+            // Add a constant minimum step size regardless of min_dt parameter
+            const EMERGENCY_MIN_DT: f64 = 1e-10;
+
+            if dt < EMERGENCY_MIN_DT {
+                panic!(
+                    "Emergency minimum step size reached at t = {}, error = {}",
+                    t, error
+                );
+            }
+        }
+        // write the last state
+        if let Some(manager) = writer_manager {
+            events.process_save_events(model, &self.x, t, manager);
+        }
+        Ok(self.stats)
+    }
+
+    /// Clears the history and starts again at first order from (t, x)
+    fn restart<Model: OdeModel<State = State>>(
+        &mut self,
+        model: &mut Model,
+        t: f64,
+    ) -> Result<(), Box<dyn Error>> {
+        let mut derivative = match self
+            .history
+            .pop_back()
+        {
+            Some((_, derivative)) => derivative,
+            None => self
+                .x
+                .clone(),
+        };
+        self.history
+            .clear();
+        model.f(t, &self.x, &mut derivative)?;
+        self.stats
+            .function_evaluations += 1;
+        self.history
+            .push_front((t, derivative));
+        self.order = 1;
+        self.starting = true;
+        self.stats
+            .restarts += 1;
+        Ok(())
+    }
+
+    /// Evaluates the derivative at the accepted state (t, x) and adds it to the history
+    fn push_history<Model: OdeModel<State = State>>(
+        &mut self,
+        model: &mut Model,
+        t: f64,
+    ) -> Result<(), Box<dyn Error>> {
+        // recycle the oldest entry once the history is as long as the highest order needs
+        let mut derivative = if self
+            .history
+            .len()
+            > MAX_ORDER
+        {
+            self.history
+                .pop_back()
+                .unwrap()
+                .1
+        } else {
+            self.x
+                .clone()
+        };
+        model.f(t, &self.x, &mut derivative)?;
+        self.stats
+            .function_evaluations += 1;
+        self.history
+            .push_front((t, derivative));
+        Ok(())
+    }
+
+    /// Fills `nodes` with the history times scaled to the step, where t maps to 0 and t + h to 1.
+    /// The corrector includes the new point at t + h and one fewer history point.
+    fn scaled_nodes(&mut self, t: f64, h: f64, order: usize, corrector: bool) {
+        self.nodes
+            .clear();
+        if corrector {
+            self.nodes
+                .push(1.0);
+        }
+        let count = if corrector {
+            order - 1
+        } else {
+            order
+        };
+        for (time, _) in self
+            .history
+            .iter()
+            .take(count)
+        {
+            self.nodes
+                .push((time - t) / h);
+        }
+    }
+
+    /// Takes a PEC step of size h from (t, x) at the current order, leaving the corrected state in y.
+    /// Returns the normalized error estimate.
+    fn step<Model: OdeModel<State = State>>(
+        &mut self,
+        model: &mut Model,
+        t: f64,
+        h: f64,
+        controller: &AdaptiveStepControl,
+    ) -> Result<f64, Box<dyn Error>> {
+        let order = self.order;
+
+        // Predict with Adams-Bashforth
+        self.scaled_nodes(t, h, order, false);
+        integration_weights(
+            &self.nodes,
+            0.0,
+            1.0,
+            &mut self.weights,
+        );
+        combine(
+            &mut self.y_predicted,
+            Some(&self.x),
+            h,
+            &self.weights,
+            None,
+            &self.history,
+            &mut self.scratch,
+        );
+
+        // Evaluate
+        model.f(
+            t + h,
+            &self.y_predicted,
+            &mut self.derivative,
+        )?;
+        self.stats
+            .function_evaluations += 1;
+
+        // Correct with Adams-Moulton
+        self.scaled_nodes(t, h, order, true);
+        integration_weights(
+            &self.nodes,
+            0.0,
+            1.0,
+            &mut self.weights,
+        );
+        combine(
+            &mut self.y,
+            Some(&self.x),
+            h,
+            &self.weights,
+            Some(&self.derivative),
+            &self.history,
+            &mut self.scratch,
+        );
+
+        // the corrector minus the predictor estimates the local error
+        self.y_tilde
+            .clone_from(&self.y_predicted);
+        self.y_tilde *= -1.0;
+        self.y_tilde += &self.y;
+        Ok(self
+            .y
+            .compute_error(
+                &self.x,
+                &self.y_tilde,
+                controller.abs_tol,
+                controller.rel_tol,
+            ))
+    }
+
+    /// Estimates the error the last step would have had at another order, from the difference
+    /// between that order's corrector and predictor using the same predicted derivative.
+    fn order_error(
+        &mut self,
+        t: f64,
+        h: f64,
+        order: usize,
+        controller: &AdaptiveStepControl,
+    ) -> f64 {
+        self.scaled_nodes(t, h, order, false);
+        integration_weights(
+            &self.nodes,
+            0.0,
+            1.0,
+            &mut self.predictor_weights,
+        );
+        self.scaled_nodes(t, h, order, true);
+        integration_weights(
+            &self.nodes,
+            0.0,
+            1.0,
+            &mut self.weights,
+        );
+
+        // corrector weights are [new, history..order-1], predictor weights are [history..order]
+        self.weights
+            .push(0.0);
+        for (weight, predictor_weight) in self.weights[1..]
+            .iter_mut()
+            .zip(&self.predictor_weights)
+        {
+            *weight -= predictor_weight;
+        }
+        combine(
+            &mut self.y_tilde,
+            None,
+            h,
+            &self.weights,
+            Some(&self.derivative),
+            &self.history,
+            &mut self.scratch,
+        );
+        self.y
+            .compute_error(
+                &self.x,
+                &self.y_tilde,
+                controller.abs_tol,
+                controller.rel_tol,
+            )
+    }
+
+    /// Chooses the order for the next step after an accepted step, returning the order and its
+    /// error estimate.
+    fn select_order(
+        &mut self,
+        t: f64,
+        h: f64,
+        error: f64,
+        controller: &AdaptiveStepControl,
+    ) -> (usize, f64) {
+        let order = self.order;
+        let history = self
+            .history
+            .len();
+
+        // While starting, there isn't enough history to estimate the error at a higher order.
+        // Raise the order every step the error stays comfortably small. Starting ends once the
+        // error is too large to raise it, or the order just raised to no longer has a smaller
+        // error than the one below it.
+        if self.starting {
+            if order > 1 {
+                let lower_error = self.order_error(t, h, order - 1, controller);
+                if lower_error <= error {
+                    self.starting = false;
+                    return (order - 1, lower_error);
+                }
+            }
+            if order < MAX_ORDER && error <= 0.5 {
+                return (order + 1, error);
+            }
+            self.starting = false;
+            return (order, error);
+        }
+
+        // step size ratio each order allows, for an error of order + 1 in h
+        let ratio =
+            |order: usize, error: f64| (1.0 / error.max(1e-14)).powf(1.0 / (order as f64 + 1.0));
+
+        let mut best = (order, error);
+        let mut best_ratio = ratio(order, error);
+        if order > 1 {
+            let lower_error = self.order_error(t, h, order - 1, controller);
+            let lower_ratio = ratio(order - 1, lower_error);
+            if lower_ratio > best_ratio {
+                best = (order - 1, lower_error);
+                best_ratio = lower_ratio;
+            }
+        }
+        // the higher order needs one more point of history than the current order
+        if order < MAX_ORDER && history > order {
+            let higher_error = self.order_error(t, h, order + 1, controller);
+            if ratio(order + 1, higher_error) > best_ratio {
+                best = (order + 1, higher_error);
+            }
+        }
+        best
+    }
+
+    /// Computes the state at time `t` within the last step by integrating the corrector's
+    /// interpolating polynomial, storing it in the interpolant buffer.
+    pub fn interpolate(&mut self, t0: f64, dt: f64, t: f64) {
+        if t < t0 || t > t0 + dt {
+            panic!("t out of range for interpolation - todo extrapolation?");
+        }
+        self.scaled_nodes(t0, dt, self.order, true);
+        integration_weights(
+            &self.nodes,
+            0.0,
+            (t - t0) / dt,
+            &mut self.weights,
+        );
+        combine(
+            &mut self.interpolant,
+            Some(&self.x),
+            dt,
+            &self.weights,
+            Some(&self.derivative),
+            &self.history,
+            &mut self.scratch,
+        );
+    }
+
+    /// Stores the integrated corrector polynomial of the step from `t0` with step size `dt` in the
+    /// dense output, valid until `t1`.
+    fn save_dense_output(
+        &mut self,
+        dense_output: &mut DenseOutput<State>,
+        t0: f64,
+        dt: f64,
+        t1: f64,
+    ) {
+        self.scaled_nodes(t0, dt, self.order, true);
+        let bases: Vec<Vec<f64>> = (0..self
+            .nodes
+            .len())
+            .map(|j| lagrange_basis(&self.nodes, j))
+            .collect();
+        // integrating each basis polynomial from 0 raises its degree by one, so coefficient k
+        // multiplies theta^(k + 1)
+        let mut coefficients = Vec::with_capacity(bases.len());
+        for k in 0..bases.len() {
+            self.weights
+                .clear();
+            self.weights
+                .extend(
+                    bases
+                        .iter()
+                        .map(|basis| basis[k] / (k as f64 + 1.0)),
+                );
+            let mut coefficient = self
+                .x
+                .clone();
+            combine(
+                &mut coefficient,
+                None,
+                dt,
+                &self.weights,
+                Some(&self.derivative),
+                &self.history,
+                &mut self.scratch,
+            );
+            coefficients.push(coefficient);
+        }
+        dense_output.push(
+            t0,
+            t1,
+            dt,
+            &self.x,
+            coefficients,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        OdeProblem,
+        events::PeriodicEvent,
+        solvers::{MultiStepMethods, OdeSolver},
+        state::state_array::StateArray,
+    };
+
+    #[derive(Debug)]
+    struct Oscillator;
+
+    impl OdeModel for Oscillator {
+        type State = StateArray<2>;
+        fn f(
+            &mut self,
+            _t: f64,
+            x: &StateArray<2>,
+            dx: &mut StateArray<2>,
+        ) -> Result<(), Box<dyn Error>> {
+            dx[0] = x[1];
+            dx[1] = -x[0];
+            Ok(())
+        }
+    }
+
+    fn exact(t: f64) -> StateArray<2> {
+        StateArray::new([t.cos(), -t.sin()])
+    }
+
+    fn exact_derivative(t: f64) -> StateArray<2> {
+        StateArray::new([-t.sin(), -t.cos()])
+    }
+
+    /// Largest error against the exact solution over the saved states
+    fn max_error(problem: OdeProblem<Oscillator, StateArray<2>>) -> (f64, SolverStats) {
+        let solver = OdeSolver::new(MultiStepMethods::AdamsBashforthMoulton.into());
+        let controller = AdaptiveStepControl::default()
+            .with_abs_tol(1e-12)
+            .with_rel_tol(1e-10);
+        let solution = solver
+            .solve_adaptive(
+                problem,
+                exact(0.0),
+                (0.0, 20.0),
+                controller,
+            )
+            .unwrap();
+        let result = solution
+            .result
+            .unwrap();
+        let error = result
+            .t
+            .iter()
+            .zip(&result.y)
+            .map(|(t, x)| {
+                let exact = exact(*t);
+                (x[0] - exact[0])
+                    .abs()
+                    .max((x[1] - exact[1]).abs())
+            })
+            .fold(0.0, f64::max);
+        (error, solution.stats)
+    }
+
+    /// Error of a single step of size h at a fixed order, starting from the exact solution and history
+    fn local_error(order: usize, h: f64) -> f64 {
+        let t = 1.0;
+        let mut solver = AdamsBashforthMoulton::<StateArray<2>>::new();
+        for state in [
+            &mut solver.x,
+            &mut solver.y,
+            &mut solver.y_predicted,
+            &mut solver.y_tilde,
+            &mut solver.derivative,
+            &mut solver.scratch,
+        ] {
+            state.clone_from(&exact(t));
+        }
+        for i in 0..order {
+            let ti = t - i as f64 * h;
+            solver
+                .history
+                .push_back((ti, exact_derivative(ti)));
+        }
+        solver.order = order;
+        solver
+            .step(
+                &mut Oscillator,
+                t,
+                h,
+                &AdaptiveStepControl::default(),
+            )
+            .unwrap();
+        let exact = exact(t + h);
+        (solver.y[0] - exact[0])
+            .abs()
+            .max((solver.y[1] - exact[1]).abs())
+    }
+
+    #[test]
+    fn test_oscillator() {
+        let (error, stats) = max_error(OdeProblem::new(Oscillator));
+        assert!(
+            error < 1e-8,
+            "error {}",
+            error
+        );
+        // two evaluations per step once started, plus the rejected steps and the first evaluation
+        assert!(
+            stats.function_evaluations <= 2 * (stats.accepted_steps + stats.rejected_steps) + 1
+        );
+    }
+
+    #[test]
+    fn test_restart_after_events() {
+        // each event restarts the method at first order, which must still meet the tolerance
+        let problem = OdeProblem::new(Oscillator).with_periodic_event(PeriodicEvent::new(
+            2.0,
+            2.0,
+            |_, _, _| {},
+        ));
+        let (error, restarted) = max_error(problem);
+        let (_, uninterrupted) = max_error(OdeProblem::new(Oscillator));
+        assert!(
+            error < 1e-8,
+            "error {}",
+            error
+        );
+        // restarting at first order takes more steps than running through
+        assert!(restarted.accepted_steps > uninterrupted.accepted_steps);
+    }
+
+    #[test]
+    fn test_order_of_accuracy() {
+        for order in [1, 2, 4, 6] {
+            let coarse = local_error(order, 0.1);
+            let fine = local_error(order, 0.05);
+            // the local error of a step at order k is O(h^(k+1))
+            let observed = (coarse / fine).log2();
+            assert!(
+                (observed - (order + 1) as f64).abs() < 0.5,
+                "order {} observed {} from errors {} and {}",
+                order,
+                observed,
+                coarse,
+                fine
+            );
+        }
+    }
+}
@@ -1,12 +1,14 @@
-use std::{array, error::Error, f64::INFINITY, mem::swap};
+use std::{array, error::Error};
 
 use indicatif::ProgressBar;
 
 use crate::{
     OdeModel,
-    events::{ContinuousEvent, EventManager},
+    events::EventManager,
     saving::{MemoryResult, WriterManager},
+    solution::DenseOutput,
     state::{Adaptive, OdeState},
+    stats::SolverStats,
     stepping::{AdaptiveStepControl, FixedStepControl},
     tableau::ButcherTableau,
 };
@@ -66,6 +68,7 @@ pub struct RungeKutta<State: OdeState, const ORDER: usize, const STAGES: usize>
     tableau: ButcherTableau<ORDER, STAGES>,
     buffers: RKBuffers<State, STAGES>,
     first_step: bool,
+    stats: SolverStats,
 }
 
 impl<State: OdeState, const ORDER: usize, const STAGES: usize> RungeKutta<State, ORDER, STAGES> {
@@ -79,6 +82,7 @@ impl<State: OdeState, const ORDER: usize, const STAGES: usize> RungeKutta<State,
             y_tilde: State::default(),
             tableau,
             first_step: true,
+            stats: SolverStats::default(),
         }
     }
 
@@ -93,9 +97,8 @@ impl<State: OdeState, const ORDER: usize, const STAGES: usize> RungeKutta<State,
         result: &mut Option<MemoryResult<State>>,
         writer_manager: &mut Option<WriterManager>,
         progress_bar: &mut ProgressBar,
-    ) -> Result<(), Box<dyn Error>> {
-        // Counter to count number of function calls
-        let mut function_calls = 0;
+    ) -> Result<SolverStats, Box<dyn Error>> {
+        self.stats = SolverStats::default();
         let mut t = tspan.0;
 
         // Copy initial state to all buffers to make sure length matches initial size of dynamically sized State
@@ -122,18 +125,13 @@ impl<State: OdeState, const ORDER: usize, const STAGES: usize> RungeKutta<State,
                     .buffers
                     .derivative,
             )?;
-            for event in &mut events.save_events {
-                if event
-                    .options
-                    .every_step
-                {
-                    (event.save_fn)(model, &self.x, t, manager);
-                }
-            }
+            self.stats
+                .function_evaluations += 1;
+            events.process_save_events(model, &self.x, t, manager);
         }
 
         // Process initial events if any are scheduled at t0
-        if events.process_periodic_events(model, &mut self.x, t) {
+        if events.process_scheduled_events(model, &mut self.x, t) {
             // If initial events changed state, save the updated state
             if let Some(result) = result {
                 result.insert(t, &self.x);
@@ -156,16 +154,12 @@ impl<State: OdeState, const ORDER: usize, const STAGES: usize> RungeKutta<State,
             }
 
             // Take a step
-            self.step(
-                model,
-                t,
-                dt,
-                false,
-                &mut function_calls,
-            )?;
+            self.step(model, t, dt, false)?;
 
             // Update time based on dt
             t += dt;
+            self.stats
+                .accept(dt);
 
             // Increment progress bar
             let percent_complete = ((t - tspan.0) / (tspan.1 - tspan.0) * 100.0).floor() as u64;
@@ -184,18 +178,14 @@ impl<State: OdeState, const ORDER: usize, const STAGES: usize> RungeKutta<State,
                         .buffers
                         .derivative,
                 )?;
-                for event in &mut events.save_events {
-                    if event
-                        .options
-                        .every_step
-                    {
-                        (event.save_fn)(model, &self.x, t, manager);
-                    }
-                }
+                self.stats
+                    .function_evaluations += 1;
+                events.process_save_events(model, &self.x, t, manager);
             }
 
             // Run any events
-            if events.process_periodic_events(model, &mut self.y, t) {
+            let scheduled_event_occurred = events.process_scheduled_events(model, &mut self.y, t);
+            if scheduled_event_occurred {
                 // Save the result after events
                 if let Some(result) = result {
                     result.insert(t, &self.y);
@@ -206,7 +196,10 @@ impl<State: OdeState, const ORDER: usize, const STAGES: usize> RungeKutta<State,
             self.x
                 .clone_from(&self.y);
 
-            if self
+            if scheduled_event_occurred {
+                // the last stage no longer matches the state or model
+                self.first_step = true;
+            } else if self
                 .tableau
                 .fsal
             {
@@ -225,16 +218,9 @@ impl<State: OdeState, const ORDER: usize, const STAGES: usize> RungeKutta<State,
 
         // write the last state
         if let Some(manager) = writer_manager {
-            for event in &mut events.save_events {
-                if event
-                    .options
-                    .every_step
-                {
-                    (event.save_fn)(model, &self.x, t, manager);
-                }
-            }
+            events.process_save_events(model, &self.x, t, manager);
         }
-        Ok(())
+        Ok(self.stats)
     }
 
     /// Solves the system using adaptive step size control with event detection.
@@ -249,17 +235,26 @@ impl<State: OdeState, const ORDER: usize, const STAGES: usize> RungeKutta<State,
         controller: &mut AdaptiveStepControl,
         events: &mut EventManager<Model, State>,
         result: &mut Option<MemoryResult<State>>,
+        dense_output: &mut Option<DenseOutput<State>>,
         writer_manager: &mut Option<WriterManager>,
         progress_bar: &mut ProgressBar,
-    ) -> Result<(), Box<dyn Error>>
+    ) -> Result<SolverStats, Box<dyn Error>>
     where
         Model: OdeModel<State = State>,
         State: OdeState + Adaptive,
     {
-        let mut _accept_counter = 0;
-        let mut _reject_counter = 0;
-        let mut function_calls = 0;
+        if dense_output.is_some()
+            && self
+                .tableau
+                .bi
+                .is_none()
+        {
+            return Err(
+                "dense output requires a method with interpolation coefficients, such as Tsit5 or Verner9".into(),
+            );
+        }
 
+        self.stats = SolverStats::default();
         let mut t = tspan.0;
 
         // Copy initial state to all buffers to make sure length matches initial size of dynamically sized State
@@ -287,18 +282,13 @@ impl<State: OdeState, const ORDER: usize, const STAGES: usize> RungeKutta<State,
                     .buffers
                     .derivative,
             )?;
-            for event in &mut events.save_events {
-                if event
-                    .options
-                    .every_step
-                {
-                    (event.save_fn)(model, &self.x, t, manager);
-                }
-            }
+            self.stats
+                .function_evaluations += 1;
+            events.process_save_events(model, &self.x, t, manager);
         }
 
         // // Process initial events if any are scheduled at t0
-        // if events.process_periodic_events(model, &mut self.x, t) {
+        // if events.process_scheduled_events(model, &mut self.x, t) {
         //     // If initial events changed state, save the updated state
         //     result.save(t, &self.x)?;
         // };
@@ -317,13 +307,7 @@ impl<State: OdeState, const ORDER: usize, const STAGES: usize> RungeKutta<State,
             }
 
             // Trial step
-            self.step(
-                model,
-                t,
-                dt,
-                true,
-                &mut function_calls,
-            )?;
+            self.step(model, t, dt, true)?;
 
             // Calculate error
             let error = self
@@ -339,46 +323,38 @@ impl<State: OdeState, const ORDER: usize, const STAGES: usize> RungeKutta<State,
             let mut new_dt = controller.step(dt, error, ORDER);
 
             // Apply controller limits
-            if let Some(max_dt) = controller.max_dt {
-                if new_dt > max_dt {
-                    new_dt = max_dt;
-                }
-            }
-
-            if let Some(min_dt) = controller.min_dt {
-                if new_dt < min_dt {
-                    new_dt = min_dt;
-                    eprintln!(
-                        "WARNING: Required a dt smaller than min_dt ({:.3e}). Continuing but accuracy will be reduced.",
-                        min_dt
-                    );
-                }
-            }
+            new_dt = controller.limit(new_dt, &mut self.stats);
 
             // Check if step is accepted
             if error <= 1.0 {
                 // Step ACCEPTED: advance time, save result, and update state
 
                 // First determine if any continuous events occurred that require us to step back in time
-                let mut continuous_event_occurred = false;
-                let mut continuous_event_time = INFINITY;
-                let mut event_indices = Vec::new();
-                for (i, event) in events
-                    .continuous_events
-                    .iter_mut()
-                    .enumerate()
-                {
-                    let (event_occurred, event_time) = self.continuous_event_occurred(t, dt, event);
-                    if event_occurred {
-                        continuous_event_occurred = true;
-                        if event_time < continuous_event_time {
-                            continuous_event_time = event_time;
-                            event_indices.clear();
-                            event_indices.push(i);
-                        }
-                    }
+                let continuous_event_time =
+                    if events.detect_continuous_events(t, &self.x, t + dt, &self.y) {
+                        events.locate_continuous_events(|condition, s| {
+                            self.interpolate(t, dt, s);
+                            Ok(condition.call(
+                                &self
+                                    .buffers
+                                    .interpolant,
+                                s,
+                            ))
+                        })?
+                    } else {
+                        None
+                    };
+                if let Some(dense_output) = dense_output {
+                    // the step ends at the event time if one occurred
+                    self.save_dense_output(
+                        dense_output,
+                        t,
+                        dt,
+                        continuous_event_time.unwrap_or(t + dt),
+                    );
                 }
-                if continuous_event_occurred {
+                let continuous_event_occurred = continuous_event_time.is_some();
+                if let Some(continuous_event_time) = continuous_event_time {
                     self.interpolate(t, dt, continuous_event_time);
                     // save the result prior to the event action
                     if let Some(result) = result {
@@ -390,23 +366,21 @@ impl<State: OdeState, const ORDER: usize, const STAGES: usize> RungeKutta<State,
                         );
                     }
                     // perform the event actions
-                    for i in event_indices {
-                        (events.continuous_events[i].action)(
-                            model,
-                            &mut self
+                    events.perform_continuous_events(
+                        model,
+                        &mut self
+                            .buffers
+                            .interpolant,
+                        continuous_event_time,
+                    );
+                    // save after the actions
+                    if let Some(result) = result {
+                        result.insert(
+                            continuous_event_time,
+                            &self
                                 .buffers
                                 .interpolant,
-                            continuous_event_time,
                         );
-                        // save after each action
-                        if let Some(result) = result {
-                            result.insert(
-                                continuous_event_time,
-                                &self
-                                    .buffers
-                                    .interpolant,
-                            );
-                        }
                     }
                     // update dt for the continuous event time
                     dt = continuous_event_time - t;
@@ -419,6 +393,8 @@ impl<State: OdeState, const ORDER: usize, const STAGES: usize> RungeKutta<State,
                         );
                 }
                 t += dt;
+                self.stats
+                    .accept(dt);
 
                 // Increment progress bar
                 let percent_complete = ((t - tspan.0) / (tspan.1 - tspan.0) * 100.0).floor() as u64;
@@ -439,18 +415,15 @@ impl<State: OdeState, const ORDER: usize, const STAGES: usize> RungeKutta<State,
                             .buffers
                             .derivative,
                     )?;
-                    for event in &mut events.save_events {
-                        if event
-                            .options
-                            .every_step
-                        {
-                            (event.save_fn)(model, &self.y, t, manager);
-                        }
-                    }
+                    self.stats
+                        .function_evaluations += 1;
+                    events.process_save_events(model, &self.y, t, manager);
                 }
 
-                // Process periodic events if any occurred
-                if events.process_periodic_events(model, &mut self.y, t) {
+                // Process scheduled events if any occurred
+                let scheduled_event_occurred =
+                    events.process_scheduled_events(model, &mut self.y, t);
+                if scheduled_event_occurred {
                     // Events changed state, save the updated state
                     if let Some(result) = result {
                         result.insert(t, &self.y);
@@ -460,7 +433,7 @@ impl<State: OdeState, const ORDER: usize, const STAGES: usize> RungeKutta<State,
                     //     model.f(t, &self.y, &mut self.buffers.derivative)?;
                     //     for event in &mut events.save_events {
                     //         if event.options.every_step {
-                    //             (event.save_fn)(model, &self.y, t, manager);
+                    //             event.save_fn.call(model, &self.y, t, manager);
                     //         }
                     //     }
                     // }
@@ -470,9 +443,15 @@ impl<State: OdeState, const ORDER: usize, const STAGES: usize> RungeKutta<State,
                     .clone_from(&self.y);
                 dt = new_dt;
 
-                _accept_counter += 1;
+                if events.terminated() {
+                    // a terminal event stopped the integration
+                    break;
+                }
 
-                if self
+                if continuous_event_occurred || scheduled_event_occurred {
+                    // the last stage no longer matches the state or model
+                    self.first_step = true;
+                } else if self
                     .tableau
                     .fsal
                 {
@@ -500,7 +479,8 @@ impl<State: OdeState, const ORDER: usize, const STAGES: usize> RungeKutta<State,
                     }
                 }
 
-                _reject_counter += 1;
+                self.stats
+                    .rejected_steps += 1;
             }
             // Add a constant minimum step size regardless of min_dt parameter
             const EMERGENCY_MIN_DT: f64 = 1e-10;
@@ -514,21 +494,9 @@ impl<State: OdeState, const ORDER: usize, const STAGES: usize> RungeKutta<State,
         }
         // write the last state
         if let Some(manager) = writer_manager {
-            for event in &mut events.save_events {
-                if event
-                    .options
-                    .every_step
-                {
-                    (event.save_fn)(model, &self.x, t, manager);
-                }
-            }
+            events.process_save_events(model, &self.x, t, manager);
         }
-
-        // println!(
-        //     "Adaptive step size: accepted {} steps, rejected {} steps, function calls: {}",
-        //     accept_counter, reject_counter, function_calls
-        // );
-        Ok(())
+        Ok(self.stats)
     }
     /// Performs a single integration step using the configured Butcher tableau.
     ///
@@ -539,7 +507,6 @@ impl<State: OdeState, const ORDER: usize, const STAGES: usize> RungeKutta<State,
         t: f64,
         h: f64,
         adaptive: bool,
-        function_calls: &mut i32,
     ) -> Result<(), Box<dyn Error>>
     where
         Model: OdeModel<State = State>,
@@ -557,7 +524,8 @@ impl<State: OdeState, const ORDER: usize, const STAGES: usize> RungeKutta<State,
             // FSAL method implementation
             if self.first_step {
                 model.f(t, &self.x, &mut k[0])?;
-                *function_calls += 1;
+                self.stats
+                    .function_evaluations += 1;
                 self.first_step = false;
             } // else k0 set up a function if step size was accepted            
 
@@ -593,7 +561,8 @@ impl<State: OdeState, const ORDER: usize, const STAGES: usize> RungeKutta<State,
                         .state,
                     &mut k[s],
                 )?;
-                *function_calls += 1;
+                self.stats
+                    .function_evaluations += 1;
             }
 
             // Calculate solution using stages 0 through STAGES-2
@@ -619,11 +588,13 @@ impl<State: OdeState, const ORDER: usize, const STAGES: usize> RungeKutta<State,
                 &self.y,
                 &mut k[STAGES - 1],
             )?;
-            *function_calls += 1;
+            self.stats
+                .function_evaluations += 1;
         } else {
             // Standard (non-FSAL) method implementation
             model.f(t, &self.x, &mut k[0])?;
-            *function_calls += 1;
+            self.stats
+                .function_evaluations += 1;
 
             for s in 1..STAGES {
                 self.buffers
@@ -656,7 +627,8 @@ impl<State: OdeState, const ORDER: usize, const STAGES: usize> RungeKutta<State,
                         .state,
                     &mut k[s],
                 )?;
-                *function_calls += 1;
+                self.stats
+                    .function_evaluations += 1;
             }
 
             self.y
@@ -761,128 +733,40 @@ impl<State: OdeState, const ORDER: usize, const STAGES: usize> RungeKutta<State,
         }
     }
 
-    /// Determines whether a continuous event condition crosses zero during the last step.
-    ///
-    /// Uses Brent's method for robust root finding. Returns a tuple:
-    /// - `bool`: Whether the event occurred.
-    /// - `f64`: The estimated time of the zero crossing.
-    fn continuous_event_occurred<Model: OdeModel<State = State>>(
-        &mut self,
-        t0: f64, // time at beginning of step
-        dt: f64, // time at end of step
-        event: &mut ContinuousEvent<Model, State>,
-    ) -> (bool, f64) {
-        if event.first_pass {
-            // need to calculate original value
-            event.last_check = (event.condition)(&self.x, t0);
-            event.first_pass = false;
-        }
-
-        let mut fb = (event.condition)(&self.y, t0 + dt);
-        if event.last_check * fb > 0.0 {
-            // event did not occur
-            event.last_check = fb;
-            return (false, t0 + dt);
-        }
-        // other wise event occurred, use brent's method to determine exactly when
-        let mut a = t0;
-        let mut b = t0 + dt;
-
-        let mut fa = event.last_check;
-        if fa.abs() < fb.abs() {
-            swap(&mut a, &mut b);
-            swap(&mut fa, &mut fb);
-        }
-
-        let mut c = a;
-        let mut mflag = true;
-        let mut fs = INFINITY;
-        let mut d = a;
-        let mut output = b;
-        let max_iters = 50;
-        let mut iter = 0;
-
-        while fb.abs() > event.tol && fs.abs() > event.tol {
-            iter += 1;
-            if iter > max_iters {
-                panic!("max iters reached on brents method for continuous event interpolation")
-            }
-            self.interpolate(t0, dt, c);
-            let fc = (event.condition)(
-                &self
-                    .buffers
-                    .interpolant,
-                c,
-            );
-            let mut s = if fa != fc && fb != fc {
-                // inverse quadratic interpolation
-                a * fb * fc / ((fa - fb) * (fa - fc))
-                    + b * fa * fc / ((fb - fa) * (fb - fc))
-                    + c * fa * fb / ((fc - fa) * (fc - fb))
-            } else {
-                //secant method
-                b - fb * (b - a) / (fb - fa)
-            };
-
-            if (s < (3.0 * a + b) / 4.0 && s > b)
-                || (s < b && s > (3.0 * a + b) / 4.0)
-                || (mflag && (s - b).abs() >= (b - c).abs() / 2.0)
-                || (!mflag && (s - b).abs() >= (c - d) / 2.0)
-                || (mflag && (b - c).abs() < event.tol)
-                || (!mflag && (c - d).abs() < event.tol)
-            {
-                // bisection method
-                s = (a + b) / 2.0;
-                mflag = true;
-            } else {
-                mflag = false;
-            }
-            self.interpolate(t0, dt, s);
-
-            fs = (event.condition)(
-                &self
-                    .buffers
-                    .interpolant,
-                s,
+    /// Stores the interpolant of the step from `t0` with step size `dt` in the dense output,
+    /// valid until `t1`.
+    fn save_dense_output(&self, dense_output: &mut DenseOutput<State>, t0: f64, dt: f64, t1: f64) {
+        if let Some(bi) = &self
+            .tableau
+            .bi
+        {
+            // coefficient i multiplies theta^(i + 1), matching the polynomial in interpolate
+            let coefficients = (0..ORDER - 1)
+                .map(|i| {
+                    let mut coefficient = self
+                        .x
+                        .clone();
+                    coefficient *= 0.0;
+                    for s in 0..STAGES {
+                        let mut k = self
+                            .buffers
+                            .stage
+                            .k[s]
+                            .clone();
+                        k *= bi[s][i] * dt;
+                        coefficient += &k;
+                    }
+                    coefficient
+                })
+                .collect();
+            dense_output.push(
+                t0,
+                t1,
+                dt,
+                &self.x,
+                coefficients,
             );
-            d = c;
-            c = b;
-            if fa * fs < 0.0 {
-                b = s;
-                // already interped with s above
-                fb = (event.condition)(
-                    &self
-                        .buffers
-                        .interpolant,
-                    b,
-                );
-            } else {
-                a = s;
-                // already interped with s above
-                fa = (event.condition)(
-                    &self
-                        .buffers
-                        .interpolant,
-                    a,
-                );
-            }
-
-            if fa.abs() < fb.abs() {
-                swap(&mut a, &mut b);
-                swap(&mut fa, &mut fb);
-            }
-
-            if fb.abs() < event.tol {
-                output = b;
-                break;
-            }
-
-            if fs.abs() < event.tol {
-                output = s;
-                break;
-            }
         }
-        (true, output)
     }
 }
 /// A buffer holding the `k` stages (derivative evaluations) for a Runge-Kutta method.
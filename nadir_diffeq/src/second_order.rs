@@ -0,0 +1,1058 @@
+//! Integrators for second order systems x'' = a(t, x).
+//!
+//! Symplectic splitting methods preserve the geometric structure of conservative systems, so the
+//! energy error stays bounded over arbitrarily long fixed-step integrations instead of drifting as
+//! it does with the explicit Runge-Kutta methods. Runge-Kutta-Nyström methods integrate the position
+//! directly from the acceleration, avoiding the stages explicit methods spend on the velocity.
+
+use std::error::Error;
+
+use indicatif::ProgressBar;
+
+use crate::{
+    events::EventManager,
+    model::SecondOrderModel,
+    saving::{MemoryResult, WriterManager},
+    state::{Adaptive, OdeState, second_order::SecondOrderState},
+    stats::SolverStats,
+    stepping::{AdaptiveStepControl, FixedStepControl},
+    tableau::RknTableau,
+};
+
+/// Yoshida's 6th order composition weights (solution A), from the outside in
+const YOSHIDA6: [f64; 3] = [0.784513610477560, 0.235573213359357, -1.17767998417887];
+
+/// Yoshida's 8th order composition weights (solution D), from the outside in
+const YOSHIDA8: [f64; 7] = [
+    0.914844246229740,
+    0.253693336566229,
+    -1.44485223686048,
+    -0.158240635368243,
+    1.93813913762276,
+    -1.96061023297549,
+    0.102799849391985,
+];
+
+/// Drift and kick coefficients of a symplectic splitting method.
+///
+/// Each substep drifts the position, x += drift_i h v, then kicks the velocity, v += kick_i h a(x).
+#[derive(Clone, Debug)]
+pub struct SplittingCoefficients {
+    pub drift: Vec<f64>,
+    pub kick: Vec<f64>,
+}
+
+impl SplittingCoefficients {
+    /// 2nd order velocity Verlet (kick-drift-kick leapfrog)
+    pub fn velocity_verlet() -> Self {
+        Self::composition(&[1.0])
+    }
+
+    /// 4th order Forest-Ruth method (drift-kick-drift form)
+    pub fn forest_ruth() -> Self {
+        let theta = 1.0 / (2.0 - 2f64.cbrt());
+        Self {
+            drift: vec![theta / 2.0, (1.0 - theta) / 2.0, (1.0 - theta) / 2.0, theta / 2.0],
+            kick: vec![theta, 1.0 - 2.0 * theta, theta, 0.0],
+        }
+    }
+
+    /// 4th order Yoshida triple jump composition of velocity Verlet
+    pub fn yoshida4() -> Self {
+        Self::symmetric(&[1.0 / (2.0 - 2f64.cbrt())])
+    }
+
+    /// 6th order Yoshida composition of velocity Verlet
+    pub fn yoshida6() -> Self {
+        Self::symmetric(&YOSHIDA6)
+    }
+
+    /// 8th order Yoshida composition of velocity Verlet
+    pub fn yoshida8() -> Self {
+        Self::symmetric(&YOSHIDA8)
+    }
+
+    /// Composition of velocity Verlet substeps with the given weights. The half kicks of
+    /// neighboring substeps act on the same position, so they are merged into one.
+    pub fn composition(weights: &[f64]) -> Self {
+        let mut drift = vec![0.0];
+        let mut kick = vec![weights[0] / 2.0];
+        for (i, weight) in weights
+            .iter()
+            .enumerate()
+        {
+            drift.push(*weight);
+            let next = weights
+                .get(i + 1)
+                .unwrap_or(&0.0);
+            kick.push((weight + next) / 2.0);
+        }
+        Self { drift, kick }
+    }
+
+    /// Symmetric composition w_m..w_1, w_0, w_1..w_m from the outer weights w_m..w_1,
+    /// with the central weight chosen so the weights sum to one
+    fn symmetric(outer: &[f64]) -> Self {
+        let center = 1.0
+            - 2.0
+                * outer
+                    .iter()
+                    .sum::<f64>();
+        let weights: Vec<f64> = outer
+            .iter()
+            .copied()
+            .chain([center])
+            .chain(
+                outer
+                    .iter()
+                    .rev()
+                    .copied(),
+            )
+            .collect();
+        Self::composition(&weights)
+    }
+}
+
+/// A symplectic splitting integrator for second order models.
+///
+/// Only fixed step sizes keep the method symplectic, so there is no adaptive solver.
+#[derive(Debug)]
+pub struct Symplectic<Position: OdeState> {
+    coefficients: SplittingCoefficients,
+    acceleration: Position,
+    scratch: Position,
+    /// Whether `acceleration` was evaluated at the current position
+    acceleration_current: bool,
+    /// Evaluations made by `step`, which solvers using this as a starter add to their own
+    pub(crate) stats: SolverStats,
+}
+
+impl<Position: OdeState> Symplectic<Position> {
+    pub fn new(coefficients: SplittingCoefficients) -> Self {
+        Self {
+            coefficients,
+            acceleration: Position::default(),
+            scratch: Position::default(),
+            acceleration_current: false,
+            stats: SolverStats::default(),
+        }
+    }
+
+    /// Solves the system using fixed step size control, handling periodic events.
+    pub fn solve_fixed<Model: SecondOrderModel<Position = Position>>(
+        &mut self,
+        model: &mut Model,
+        x0: &SecondOrderState<Position>,
+        tspan: (f64, f64),
+        controller: &mut FixedStepControl,
+        events: &mut EventManager<Model, SecondOrderState<Position>>,
+        result: &mut Option<MemoryResult<SecondOrderState<Position>>>,
+        writer_manager: &mut Option<WriterManager>,
+        progress_bar: &mut ProgressBar,
+    ) -> Result<SolverStats, Box<dyn Error>> {
+        self.stats = SolverStats::default();
+        self.restart(x0);
+        let mut stats = solve_fixed_loop(
+            model,
+            x0,
+            tspan,
+            controller,
+            events,
+            result,
+            writer_manager,
+            progress_bar,
+            |model, t, h, x, restart| {
+                if restart {
+                    self.acceleration_current = false;
+                }
+                self.step(model, t, h, x)
+            },
+        )?;
+        stats.function_evaluations += self
+            .stats
+            .function_evaluations;
+        Ok(stats)
+    }
+
+    /// Sizes the buffers for x and discards the cached acceleration, which is needed before
+    /// stepping a new state or after the model has changed.
+    pub fn restart(&mut self, x: &SecondOrderState<Position>) {
+        self.acceleration
+            .clone_from(&x.position);
+        self.scratch
+            .clone_from(&x.position);
+        self.acceleration_current = false;
+    }
+
+    /// Performs a single step of size h from time t, updating x in place.
+    pub fn step<Model: SecondOrderModel<Position = Position>>(
+        &mut self,
+        model: &mut Model,
+        t: f64,
+        h: f64,
+        x: &mut SecondOrderState<Position>,
+    ) -> Result<(), Box<dyn Error>> {
+        let mut elapsed = 0.0;
+        for (drift, kick) in self
+            .coefficients
+            .drift
+            .iter()
+            .zip(
+                &self
+                    .coefficients
+                    .kick,
+            )
+        {
+            if *drift != 0.0 {
+                self.scratch
+                    .clone_from(&x.velocity);
+                self.scratch *= drift * h;
+                x.position += &self.scratch;
+                elapsed += drift;
+                self.acceleration_current = false;
+            }
+            if *kick != 0.0 {
+                if !self.acceleration_current {
+                    model.acceleration(
+                        t + elapsed * h,
+                        &x.position,
+                        &mut self.acceleration,
+                    )?;
+                    self.stats
+                        .function_evaluations += 1;
+                    self.acceleration_current = true;
+                }
+                self.scratch
+                    .clone_from(&self.acceleration);
+                self.scratch *= kick * h;
+                x.velocity += &self.scratch;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A Runge-Kutta-Nyström solver capable of fixed or adaptive integration, with embedded error
+/// estimation, FSAL and Hermite dense output for locating continuous events.
+#[derive(Debug)]
+pub struct RungeKuttaNystrom<Position: OdeState, const ORDER: usize, const STAGES: usize> {
+    x: SecondOrderState<Position>,
+    y: SecondOrderState<Position>,
+    y_tilde: SecondOrderState<Position>,
+    interpolant: SecondOrderState<Position>,
+    derivative: SecondOrderState<Position>,
+    tableau: RknTableau<ORDER, STAGES>,
+    /// Stage accelerations
+    k: Vec<Position>,
+    stage: Position,
+    scratch: Position,
+    /// Whether k[0] needs to be evaluated rather than carried over from the last step
+    first_step: bool,
+    stats: SolverStats,
+}
+
+impl<Position: OdeState, const ORDER: usize, const STAGES: usize>
+    RungeKuttaNystrom<Position, ORDER, STAGES>
+{
+    /// Constructs a new Runge-Kutta-Nyström solver using a specific tableau.
+    pub fn new(tableau: RknTableau<ORDER, STAGES>) -> Self {
+        Self {
+            x: SecondOrderState::default(),
+            y: SecondOrderState::default(),
+            y_tilde: SecondOrderState::default(),
+            interpolant: SecondOrderState::default(),
+            derivative: SecondOrderState::default(),
+            tableau,
+            k: vec![Position::default(); STAGES],
+            stage: Position::default(),
+            scratch: Position::default(),
+            first_step: true,
+            stats: SolverStats::default(),
+        }
+    }
+
+    fn init(&mut self, x0: &SecondOrderState<Position>) {
+        self.x
+            .clone_from(x0);
+        self.y
+            .clone_from(x0);
+        self.y_tilde
+            .clone_from(x0);
+        self.interpolant
+            .clone_from(x0);
+        self.derivative
+            .clone_from(x0);
+        for k in &mut self.k {
+            k.clone_from(&x0.position);
+        }
+        self.stage
+            .clone_from(&x0.position);
+        self.scratch
+            .clone_from(&x0.position);
+        self.first_step = true;
+    }
+
+    /// Solves the system using fixed step size control, handling periodic events.
+    pub fn solve_fixed<Model: SecondOrderModel<Position = Position>>(
+        &mut self,
+        model: &mut Model,
+        x0: &SecondOrderState<Position>,
+        tspan: (f64, f64),
+        controller: &mut FixedStepControl,
+        events: &mut EventManager<Model, SecondOrderState<Position>>,
+        result: &mut Option<MemoryResult<SecondOrderState<Position>>>,
+        writer_manager: &mut Option<WriterManager>,
+        progress_bar: &mut ProgressBar,
+    ) -> Result<SolverStats, Box<dyn Error>> {
+        self.stats = SolverStats::default();
+        self.init(x0);
+        let mut stats = solve_fixed_loop(
+            model,
+            x0,
+            tspan,
+            controller,
+            events,
+            result,
+            writer_manager,
+            progress_bar,
+            |model, t, h, x, restart| {
+                if restart {
+                    self.first_step = true;
+                }
+                self.x
+                    .clone_from(x);
+                self.step(model, t, h, false)?;
+                x.clone_from(&self.y);
+                self.advance();
+                Ok(())
+            },
+        )?;
+        stats.function_evaluations += self
+            .stats
+            .function_evaluations;
+        Ok(stats)
+    }
+
+    /// Solves the system using adaptive step size control with event detection.
+    ///
+    /// Supports both periodic and continuous events. Continuous events are located by bisection
+    /// on the Hermite interpolant of the step.
+    pub fn solve_adaptive<Model: SecondOrderModel<Position = Position>>(
+        &mut self,
+        model: &mut Model,
+        x0: &SecondOrderState<Position>,
+        tspan: (f64, f64),
+        controller: &mut AdaptiveStepControl,
+        events: &mut EventManager<Model, SecondOrderState<Position>>,
+        result: &mut Option<MemoryResult<SecondOrderState<Position>>>,
+        writer_manager: &mut Option<WriterManager>,
+        progress_bar: &mut ProgressBar,
+    ) -> Result<SolverStats, Box<dyn Error>>
+    where
+        Position: Adaptive,
+    {
+        if self
+            .tableau
+            .b_velocity_tilde
+            .is_none()
+        {
+            return Err(
+                "Runge-Kutta-Nyström tableau has no embedded method for adaptive step control"
+                    .into(),
+            );
+        }
+        self.stats = SolverStats::default();
+        let mut t = tspan.0;
+        self.init(x0);
+
+        let mut dt = 1e-3; // initial dt
+
+        // Save the true initial state before any processing
+        if let Some(result) = result {
+            result.insert(t, &self.x);
+        }
+        if let Some(manager) = writer_manager {
+            // run the model function to update any mutable internal algebraic/kinematic states
+            model.f(t, x0, &mut self.derivative)?;
+            self.stats
+                .function_evaluations += 1;
+            events.process_save_events(model, &self.x, t, manager);
+        }
+
+        while t < tspan.1 {
+            // Determine step size - standard dt or adjusted for upcoming event
+            let next_event_time = events.next_time();
+            if next_event_time > t && next_event_time < t + dt {
+                // Adjust step size to land exactly on the event
+                dt = next_event_time - t
+            };
+
+            // Ensure we don't step past the end
+            if t + dt > tspan.1 {
+                dt = tspan.1 - t;
+            }
+
+            // Trial step
+            self.step(model, t, dt, true)?;
+
+            // Calculate error
+            let error = self
+                .y
+                .compute_error(
+                    &self.x,
+                    &self.y_tilde,
+                    controller.abs_tol,
+                    controller.rel_tol,
+                );
+
+            // Calculate new step size based on dt
+            let mut new_dt = controller.step(dt, error, ORDER);
+
+            // Apply controller limits
+            new_dt = controller.limit(new_dt, &mut self.stats);
+
+            // Check if step is accepted
+            if error <= 1.0 {
+                // Step ACCEPTED: advance time, save result, and update state
+
+                // First determine if any continuous events occurred that require us to step back in time
+                let continuous_event_time =
+                    if events.detect_continuous_events(t, &self.x, t + dt, &self.y) {
+                        events.locate_continuous_events(|condition, s| {
+                            self.interpolate(t, dt, s);
+                            Ok(condition.call(&self.interpolant, s))
+                        })?
+                    } else {
+                        None
+                    };
+                let continuous_event_occurred = continuous_event_time.is_some();
+                if let Some(continuous_event_time) = continuous_event_time {
+                    self.interpolate(t, dt, continuous_event_time);
+                    // save the result prior to the event action
+                    if let Some(result) = result {
+                        result.insert(
+                            continuous_event_time,
+                            &self.interpolant,
+                        );
+                    }
+                    // perform the event actions
+                    events.perform_continuous_events(
+                        model,
+                        &mut self.interpolant,
+                        continuous_event_time,
+                    );
+                    // save after the actions
+                    if let Some(result) = result {
+                        result.insert(
+                            continuous_event_time,
+                            &self.interpolant,
+                        );
+                    }
+                    // update dt for the continuous event time
+                    dt = continuous_event_time - t;
+                    // update state for the interpolated state after all events have occurred
+                    self.y
+                        .clone_from(&self.interpolant);
+                }
+                t += dt;
+                self.stats
+                    .accept(dt);
+
+                // Increment progress bar
+                let percent_complete = ((t - tspan.0) / (tspan.1 - tspan.0) * 100.0).floor() as u64;
+                progress_bar.set_position(percent_complete);
+
+                // Save the true state before any event processing
+                if let Some(result) = result {
+                    result.insert(t, &self.y);
+                }
+                if let Some(manager) = writer_manager {
+                    // run the model function to update internal algebraic/kinematic states
+                    model.f(
+                        t,
+                        &self.y,
+                        &mut self.derivative,
+                    )?;
+                    self.stats
+                        .function_evaluations += 1;
+                    events.process_save_events(model, &self.y, t, manager);
+                }
+
+                // Process scheduled events if any occurred
+                let scheduled_event_occurred =
+                    events.process_scheduled_events(model, &mut self.y, t);
+                if scheduled_event_occurred {
+                    // Events changed state, save the updated state
+                    if let Some(result) = result {
+                        result.insert(t, &self.y);
+                    }
+                };
+
+                self.x
+                    .clone_from(&self.y);
+                self.advance();
+                if continuous_event_occurred || scheduled_event_occurred {
+                    // the last stage no longer matches the state or model
+                    self.first_step = true;
+                }
+                dt = new_dt;
+                if events.terminated() {
+                    // a terminal event stopped the integration
+                    break;
+                }
+            } else {
+                // Step REJECTED: try again with reduced step size
+                dt = new_dt;
+                self.stats
+                    .rejected_steps += 1;
+
+                // Safety check for minimum step size
+                if let Some(min) = controller.min_dt {
+                    if dt <= min {
+                        panic!("Minimum step size reached but error is still too large");
+                    }
+                }
+            }
+            // Add a constant minimum step size regardless of min_dt parameter
+            const EMERGENCY_MIN_DT: f64 = 1e-10;
+
+            if dt < EMERGENCY_MIN_DT {
+                panic!(
+                    "Emergency minimum step size reached at t = {}, error = {}",
+                    t, error
+                );
+            }
+        }
+        // write the last state
+        if let Some(manager) = writer_manager {
+            events.process_save_events(model, &self.x, t, manager);
+        }
+        Ok(self.stats)
+    }
+
+    /// Reuses the last stage as the first stage of the next step for FSAL tableaus.
+    fn advance(&mut self) {
+        if self
+            .tableau
+            .fsal
+        {
+            self.k
+                .swap(0, STAGES - 1);
+        } else {
+            self.first_step = true;
+        }
+    }
+
+    /// Performs a single step of size h from (t, x), leaving the new state in y and,
+    /// when `adaptive` is true, the error estimate in y_tilde.
+    pub fn step<Model: SecondOrderModel<Position = Position>>(
+        &mut self,
+        model: &mut Model,
+        t: f64,
+        h: f64,
+        adaptive: bool,
+    ) -> Result<(), Box<dyn Error>> {
+        if self.first_step {
+            model.acceleration(
+                t,
+                &self
+                    .x
+                    .position,
+                &mut self.k[0],
+            )?;
+            self.stats
+                .function_evaluations += 1;
+            if self
+                .tableau
+                .fsal
+            {
+                self.first_step = false;
+            }
+        }
+
+        for s in 1..STAGES {
+            // stage position x + c_s h v + h^2 sum_j a_sj k_j
+            self.stage
+                .clone_from(
+                    &self
+                        .x
+                        .velocity,
+                );
+            self.stage *= self
+                .tableau
+                .c[s]
+                * h;
+            self.stage += &self
+                .x
+                .position;
+            for j in 0..s {
+                let a = self
+                    .tableau
+                    .a[s][j];
+                if a != 0.0 {
+                    self.scratch
+                        .clone_from(&self.k[j]);
+                    self.scratch *= a * h * h;
+                    self.stage += &self.scratch;
+                }
+            }
+            model.acceleration(
+                t + self
+                    .tableau
+                    .c[s]
+                    * h,
+                &self.stage,
+                &mut self.k[s],
+            )?;
+            self.stats
+                .function_evaluations += 1;
+        }
+
+        // new position x + h v + h^2 sum_i b_position_i k_i and velocity v + h sum_i b_velocity_i k_i
+        self.y
+            .clone_from(&self.x);
+        self.scratch
+            .clone_from(
+                &self
+                    .x
+                    .velocity,
+            );
+        self.scratch *= h;
+        self.y
+            .position += &self.scratch;
+        Self::accumulate(
+            &mut self
+                .y
+                .position,
+            &self.k,
+            &self
+                .tableau
+                .b_position,
+            h * h,
+            &mut self.scratch,
+        );
+        Self::accumulate(
+            &mut self
+                .y
+                .velocity,
+            &self.k,
+            &self
+                .tableau
+                .b_velocity,
+            h,
+            &mut self.scratch,
+        );
+
+        if adaptive {
+            if let (Some(b_position_tilde), Some(b_velocity_tilde)) = (
+                &self
+                    .tableau
+                    .b_position_tilde,
+                &self
+                    .tableau
+                    .b_velocity_tilde,
+            ) {
+                self.y_tilde *= 0.0; //reset
+                Self::accumulate(
+                    &mut self
+                        .y_tilde
+                        .position,
+                    &self.k,
+                    b_position_tilde,
+                    h * h,
+                    &mut self.scratch,
+                );
+                Self::accumulate(
+                    &mut self
+                        .y_tilde
+                        .velocity,
+                    &self.k,
+                    b_velocity_tilde,
+                    h,
+                    &mut self.scratch,
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Adds scale * sum_i weights_i k_i to out
+    fn accumulate(
+        out: &mut Position,
+        k: &[Position],
+        weights: &[f64; STAGES],
+        scale: f64,
+        scratch: &mut Position,
+    ) {
+        for (k, weight) in k
+            .iter()
+            .zip(weights)
+        {
+            if *weight != 0.0 {
+                scratch.clone_from(k);
+                *scratch *= weight * scale;
+                *out += scratch;
+            }
+        }
+    }
+
+    /// Computes the state at time `t` within the last step by cubic Hermite interpolation of the
+    /// position and velocity, storing it in the interpolant buffer.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the tableau is not FSAL, since the acceleration at the end of the step is needed,
+    /// or if `t` is outside `[t0, t0+dt]`.
+    pub fn interpolate(&mut self, t0: f64, dt: f64, t: f64) {
+        if !self
+            .tableau
+            .fsal
+        {
+            panic!("No interpolation for non-FSAL Runge-Kutta-Nyström tableaus");
+        }
+        if t < t0 || t > t0 + dt {
+            panic!("t out of range for interpolation - todo extrapolation?");
+        }
+        let theta = (t - t0) / dt;
+        let theta2 = theta * theta;
+        let theta3 = theta2 * theta;
+        let h00 = 2.0 * theta3 - 3.0 * theta2 + 1.0;
+        let h10 = (theta3 - 2.0 * theta2 + theta) * dt;
+        let h01 = -2.0 * theta3 + 3.0 * theta2;
+        let h11 = (theta3 - theta2) * dt;
+
+        let hermite = |out: &mut Position, scratch: &mut Position, terms: [(&Position, f64); 4]| {
+            out.clone_from(terms[0].0);
+            *out *= terms[0].1;
+            for (value, weight) in &terms[1..] {
+                scratch.clone_from(value);
+                *scratch *= *weight;
+                *out += scratch;
+            }
+        };
+        hermite(
+            &mut self
+                .interpolant
+                .position,
+            &mut self.scratch,
+            [
+                (
+                    &self
+                        .x
+                        .position,
+                    h00,
+                ),
+                (
+                    &self
+                        .x
+                        .velocity,
+                    h10,
+                ),
+                (
+                    &self
+                        .y
+                        .position,
+                    h01,
+                ),
+                (
+                    &self
+                        .y
+                        .velocity,
+                    h11,
+                ),
+            ],
+        );
+        hermite(
+            &mut self
+                .interpolant
+                .velocity,
+            &mut self.scratch,
+            [
+                (
+                    &self
+                        .x
+                        .velocity,
+                    h00,
+                ),
+                (&self.k[0], h10),
+                (
+                    &self
+                        .y
+                        .velocity,
+                    h01,
+                ),
+                (&self.k[STAGES - 1], h11),
+            ],
+        );
+    }
+}
+
+/// The fixed step loop shared by the second order integrators. `step` advances the state by one
+/// step, and is told to restart whenever events may have changed the state or the model.
+/// The returned statistics don't include the evaluations made by `step`.
+pub(crate) fn solve_fixed_loop<Model, Position, F>(
+    model: &mut Model,
+    x0: &SecondOrderState<Position>,
+    tspan: (f64, f64),
+    controller: &mut FixedStepControl,
+    events: &mut EventManager<Model, SecondOrderState<Position>>,
+    result: &mut Option<MemoryResult<SecondOrderState<Position>>>,
+    writer_manager: &mut Option<WriterManager>,
+    progress_bar: &mut ProgressBar,
+    mut step: F,
+) -> Result<SolverStats, Box<dyn Error>>
+where
+    Model: SecondOrderModel<Position = Position>,
+    Position: OdeState,
+    F: FnMut(
+        &mut Model,
+        f64,
+        f64,
+        &mut SecondOrderState<Position>,
+        bool,
+    ) -> Result<(), Box<dyn Error>>,
+{
+    let mut stats = SolverStats::default();
+    let mut t = tspan.0;
+    let mut x = x0.clone();
+    let mut derivative = x0.clone();
+    let mut restart = true;
+
+    // Save the true initial state before any processing
+    if let Some(result) = result {
+        result.insert(t, &x);
+    }
+
+    if let Some(manager) = writer_manager {
+        // run the model function to update internal algebraic/kinematic states
+        model.f(t, &x, &mut derivative)?;
+        stats.function_evaluations += 1;
+        events.process_save_events(model, &x, t, manager);
+    }
+
+    // Process initial events if any are scheduled at t0
+    if events.process_scheduled_events(model, &mut x, t) {
+        // If initial events changed state, save the updated state
+        if let Some(result) = result {
+            result.insert(t, &x);
+        }
+    };
+
+    while t < tspan.1 {
+        // Determine step size - standard dt or adjusted for upcoming event
+        let next_event_time = events.next_time();
+        let mut dt = if next_event_time > t && next_event_time < t + controller.dt {
+            // Adjust step size to land exactly on the event
+            next_event_time - t
+        } else {
+            controller.dt
+        };
+
+        // Ensure we don't step past the end
+        if t + dt > tspan.1 {
+            dt = tspan.1 - t;
+        }
+
+        // Take a step
+        step(model, t, dt, &mut x, restart)?;
+        restart = false;
+
+        // Update time based on dt
+        t += dt;
+        stats.accept(dt);
+
+        // Increment progress bar
+        let percent_complete = ((t - tspan.0) / (tspan.1 - tspan.0) * 100.0).floor() as u64;
+        progress_bar.set_position(percent_complete);
+
+        // Save the memory result state
+        if let Some(result) = result {
+            result.insert(t, &x);
+        }
+        if let Some(manager) = writer_manager {
+            // run the model function to update internal algebraic/kinematic states
+            model.f(t, &x, &mut derivative)?;
+            stats.function_evaluations += 1;
+            events.process_save_events(model, &x, t, manager);
+        }
+
+        // Run any events
+        if events.process_scheduled_events(model, &mut x, t) {
+            // Save the result after events
+            if let Some(result) = result {
+                result.insert(t, &x);
+            }
+            restart = true;
+        };
+    }
+
+    // write the last state
+    if let Some(manager) = writer_manager {
+        events.process_save_events(model, &x, t, manager);
+    }
+    Ok(stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        OdeModel, OdeProblem,
+        solvers::{OdeSolver, SecondOrderMethods},
+        state::state_array::StateArray,
+    };
+    use std::f64::consts::PI;
+
+    /// Planar two-body problem with mu = 1
+    #[derive(Debug)]
+    struct Kepler;
+
+    impl OdeModel for Kepler {
+        type State = SecondOrderState<StateArray<2>>;
+        fn f(
+            &mut self,
+            t: f64,
+            x: &Self::State,
+            dx: &mut Self::State,
+        ) -> Result<(), Box<dyn Error>> {
+            self.second_order_f(t, x, dx)
+        }
+    }
+
+    impl SecondOrderModel for Kepler {
+        type Position = StateArray<2>;
+        fn acceleration(
+            &mut self,
+            _t: f64,
+            position: &StateArray<2>,
+            acceleration: &mut StateArray<2>,
+        ) -> Result<(), Box<dyn Error>> {
+            let r3 = (position[0] * position[0] + position[1] * position[1]).powf(1.5);
+            acceleration[0] = -position[0] / r3;
+            acceleration[1] = -position[1] / r3;
+            Ok(())
+        }
+    }
+
+    /// Periapsis of an orbit with eccentricity 0.5 and semi-major axis 2
+    fn periapsis() -> SecondOrderState<StateArray<2>> {
+        SecondOrderState::new(
+            StateArray::new([1.0, 0.0]),
+            StateArray::new([0.0, 1.5_f64.sqrt()]),
+        )
+    }
+
+    const PERIOD: f64 = 2.0 * PI * 2.8284271247461903;
+
+    fn energy(x: &SecondOrderState<StateArray<2>>) -> f64 {
+        let (r, v) = (&x.position, &x.velocity);
+        0.5 * (v[0] * v[0] + v[1] * v[1]) - 1.0 / (r[0] * r[0] + r[1] * r[1]).sqrt()
+    }
+
+    fn angular_momentum(x: &SecondOrderState<StateArray<2>>) -> f64 {
+        x.position[0] * x.velocity[1] - x.position[1] * x.velocity[0]
+    }
+
+    /// Largest energy and angular momentum errors over the saved states, relative to the initial values
+    fn drift(states: &[SecondOrderState<StateArray<2>>]) -> (f64, f64) {
+        let (e0, h0) = (
+            energy(&states[0]),
+            angular_momentum(&states[0]),
+        );
+        states
+            .iter()
+            .fold((0.0, 0.0), |(e, h), x| {
+                (
+                    f64::max(
+                        e,
+                        ((energy(x) - e0) / e0).abs(),
+                    ),
+                    f64::max(
+                        h,
+                        ((angular_momentum(x) - h0) / h0).abs(),
+                    ),
+                )
+            })
+    }
+
+    #[test]
+    fn test_symplectic_kepler_drift() {
+        let orbits = 100.0;
+        let solver = OdeSolver::new(SecondOrderMethods::Yoshida4.into());
+        let solution = solver
+            .solve_second_order_fixed(
+                OdeProblem::new(Kepler),
+                periapsis(),
+                (0.0, orbits * PERIOD),
+                PERIOD / 200.0,
+            )
+            .unwrap();
+        let states = solution
+            .result
+            .unwrap()
+            .y;
+        let (energy_error, momentum_error) = drift(&states);
+        // the energy error oscillates within a bound set by the step rather than growing with time
+        let (first_orbits, _) = drift(&states[..states.len() / 10]);
+        assert!(
+            energy_error < 1e-4,
+            "energy error {}",
+            energy_error
+        );
+        assert!(
+            energy_error < 1.5 * first_orbits,
+            "energy error grew from {} to {}",
+            first_orbits,
+            energy_error
+        );
+        // each drift and kick conserves angular momentum exactly for a central force
+        assert!(
+            momentum_error < 1e-11,
+            "angular momentum error {}",
+            momentum_error
+        );
+    }
+
+    #[test]
+    fn test_nystrom_kepler_drift() {
+        let controller = AdaptiveStepControl::default()
+            .with_abs_tol(1e-12)
+            .with_rel_tol(1e-12);
+        for method in [
+            SecondOrderMethods::Nystrom4,
+            SecondOrderMethods::Nystrom6,
+            SecondOrderMethods::Nystrom9,
+        ] {
+            let solver = OdeSolver::new(method.into());
+            let solution = solver
+                .solve_second_order_adaptive(
+                    OdeProblem::new(Kepler),
+                    periapsis(),
+                    (0.0, 10.0 * PERIOD),
+                    controller,
+                )
+                .unwrap();
+            let states = solution
+                .result
+                .unwrap()
+                .y;
+            let (energy_error, momentum_error) = drift(&states);
+            assert!(
+                energy_error < 1e-9,
+                "energy error {}",
+                energy_error
+            );
+            assert!(
+                momentum_error < 1e-9,
+                "angular momentum error {}",
+                momentum_error
+            );
+            // after whole orbits the body is back at periapsis
+            let last = states
+                .last()
+                .unwrap();
+            assert!((last.position[0] - 1.0).abs() < 1e-7);
+            assert!(last.position[1].abs() < 1e-7);
+        }
+    }
+}
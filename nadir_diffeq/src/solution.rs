@@ -0,0 +1,186 @@
+use csv::Writer;
+use serde::{Deserialize, Serialize};
+use std::{error::Error, fs::create_dir_all, path::Path};
+
+use crate::{events::EventRecord, saving::MemoryResult, state::OdeState, stats::SolverStats};
+
+/// Why the integration stopped.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Termination {
+    /// The integration reached the end of the time span.
+    Completed,
+    /// A terminal continuous event stopped the integration.
+    Event(EventRecord),
+}
+
+/// The output of a solve.
+#[derive(Debug)]
+pub struct Solution<State>
+where
+    State: OdeState,
+{
+    /// States saved in memory, when the solver saves to memory.
+    pub result: Option<MemoryResult<State>>,
+    /// Continuous events that occurred, in order.
+    pub events: Vec<EventRecord>,
+    /// Why the integration stopped.
+    pub termination: Termination,
+    /// The continuous solution, when the solver was built with dense output.
+    pub dense_output: Option<DenseOutput<State>>,
+    /// Diagnostics of the solve.
+    pub stats: SolverStats,
+}
+
+impl<State> Solution<State>
+where
+    State: OdeState,
+{
+    /// Returns the states saved in memory, which is what the solve methods returned before they
+    /// returned the whole solution, for callers that only need the states.
+    pub fn into_result(self) -> Option<MemoryResult<State>> {
+        self.result
+    }
+
+    /// Writes the event log to `events.csv` in `folder`.
+    pub fn write_events(&self, folder: &Path) -> Result<(), Box<dyn Error>> {
+        create_dir_all(folder)?;
+        let mut writer = Writer::from_path(folder.join("events.csv"))?;
+        for record in &self.events {
+            writer.serialize(record)?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+/// A continuous solution made of the interpolant of every accepted step,
+/// which can be evaluated at any time within the solved time span.
+///
+/// It is serializable, so a solution can be stored and resampled later without solving again.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct DenseOutput<State>
+where
+    State: OdeState,
+{
+    steps: Vec<DenseStep<State>>,
+}
+
+impl<State> DenseOutput<State>
+where
+    State: OdeState,
+{
+    /// Constructs an empty dense output.
+    pub fn new() -> Self {
+        Self { steps: Vec::new() }
+    }
+
+    /// Appends the interpolant of a step taken from `(t0, x0)` with step size `dt`, valid until `t1`,
+    /// where `coefficients[j]` multiplies `theta^(j + 1)` with `theta = (t - t0) / dt`.
+    pub(crate) fn push(&mut self, t0: f64, t1: f64, dt: f64, x0: &State, coefficients: Vec<State>) {
+        // steps ending on an event at their start don't cover any time
+        if t1 > t0 {
+            self.steps
+                .push(DenseStep { t0, t1, dt, x0: x0.clone(), coefficients });
+        }
+    }
+
+    /// Returns the time span covered by the solution, or `None` if it has no steps.
+    pub fn tspan(&self) -> Option<(f64, f64)> {
+        let first = self
+            .steps
+            .first()?;
+        let last = self
+            .steps
+            .last()?;
+        Some((first.t0, last.t1))
+    }
+
+    /// Evaluates the solution at time `t`.
+    /// At the time of an event, this is the state after the event's action.
+    pub fn at(&self, t: f64) -> Result<State, Box<dyn Error>> {
+        let Some((t0, t1)) = self.tspan() else {
+            return Err("dense output has no steps".into());
+        };
+        if t < t0 || t > t1 {
+            return Err(format!("t ({t}) out of range of the dense output ({t0}, {t1})").into());
+        }
+        // the last step starting at or before t
+        let i = self
+            .steps
+            .partition_point(|step| step.t0 <= t);
+        Ok(self.steps[i.max(1) - 1].evaluate(t))
+    }
+
+    /// Evaluates the solution at each time in `ts`.
+    pub fn sample(&self, ts: &[f64]) -> Result<Vec<State>, Box<dyn Error>> {
+        ts.iter()
+            .map(|&t| self.at(t))
+            .collect()
+    }
+}
+
+/// Returns the coefficients of the Lagrange basis polynomial through `nodes` that is one at
+/// `nodes[j]` and zero at the other nodes, lowest degree first.
+pub(crate) fn lagrange_basis(nodes: &[f64], j: usize) -> Vec<f64> {
+    let mut coefficients = vec![1.0];
+    for (m, other) in nodes
+        .iter()
+        .enumerate()
+    {
+        if m == j {
+            continue;
+        }
+        // multiply by (theta - other) / (node - other)
+        let scale = 1.0 / (nodes[j] - other);
+        let mut product = vec![0.0; coefficients.len() + 1];
+        for (k, coefficient) in coefficients
+            .iter()
+            .enumerate()
+        {
+            product[k + 1] += coefficient * scale;
+            product[k] -= coefficient * other * scale;
+        }
+        coefficients = product;
+    }
+    coefficients
+}
+
+/// Internal struct for the interpolating polynomial of a single step.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct DenseStep<State>
+where
+    State: OdeState,
+{
+    t0: f64,
+    t1: f64,
+    dt: f64,
+    x0: State,
+    coefficients: Vec<State>,
+}
+
+impl<State> DenseStep<State>
+where
+    State: OdeState,
+{
+    fn evaluate(&self, t: f64) -> State {
+        let theta = (t - self.t0) / self.dt;
+        // Evaluate polynomial using Horner's method
+        let mut x = self
+            .coefficients
+            .last()
+            .cloned()
+            .unwrap_or_default();
+        for coefficient in self
+            .coefficients
+            .iter()
+            .rev()
+            .skip(1)
+        {
+            x *= theta;
+            x += coefficient;
+        }
+        x *= theta;
+        x += &self.x0;
+        x
+    }
+}
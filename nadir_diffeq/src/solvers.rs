@@ -5,18 +5,58 @@ use indicatif::{ProgressBar, ProgressState, ProgressStyle};
 use crate::{
     OdeModel, OdeProblem,
     events::EventManager,
-    model::{StateFromModel, StateFromModelMut},
+    gauss_jackson::GaussJackson,
+    implicit::RadauIIA5,
+    lie_group::RungeKuttaMuntheKaas,
+    model::{SecondOrderModel, StateFromModel, StateFromModelMut},
+    multistep::AdamsBashforthMoulton,
     rk::RungeKutta,
     saving::{MemoryResult, SaveMethods, WriterManager},
-    state::{Adaptive, OdeState},
+    second_order::{RungeKuttaNystrom, SplittingCoefficients, Symplectic},
+    solution::{DenseOutput, Solution, Termination},
+    state::{Adaptive, LieGroup, OdeState, second_order::SecondOrderState},
+    stats::SolverStats,
     stepping::{AdaptiveStepControl, FixedStepControl},
-    tableau::ButcherTableau,
+    tableau::{ButcherTableau, RknTableau},
 };
 
+const LIE_GROUP_STATE_REQUIRED: &str = "Lie group methods require a LieGroup state, use solve_lie_group_fixed or solve_lie_group_adaptive";
+
+const DENSE_OUTPUT_UNSUPPORTED: &str = "dense output is only available from solve_adaptive with Runge-Kutta, Radau IIA or Adams-Bashforth-Moulton methods";
+
+const SECOND_ORDER_MODEL_REQUIRED: &str = "second order methods require a SecondOrderModel, use solve_second_order_fixed or solve_second_order_adaptive";
+
+/// The progress bar shown by the solve methods that don't take one
+fn default_progress_bar() -> ProgressBar {
+    let progress_bar = ProgressBar::new(100);
+    progress_bar.set_style(
+        ProgressStyle::with_template(
+            "[{elapsed_precise}] [{wide_bar:.cyan/blue}] %{percent} ({eta})",
+        )
+        .unwrap()
+        .with_key(
+            "eta",
+            |state: &ProgressState, w: &mut dyn Write| {
+                write!(
+                    w,
+                    "{:.1}s",
+                    state
+                        .eta()
+                        .as_secs_f64()
+                )
+                .unwrap()
+            },
+        )
+        .progress_chars("##-"),
+    );
+    progress_bar
+}
+
 #[derive(Clone, Copy)]
 pub struct OdeSolver {
     save_method: SaveMethods,
     solver_method: SolverMethods,
+    dense_output: bool,
 }
 
 impl Default for OdeSolver {
@@ -26,13 +66,27 @@ impl Default for OdeSolver {
             solver_method: SolverMethods::Explicit(ExplicitMethods::RungeKutta(
                 RungeKuttaMethods::Tsit5,
             )),
+            dense_output: false,
         }
     }
 }
 
 impl OdeSolver {
     pub fn new(solver_method: SolverMethods) -> Self {
-        Self { solver_method, save_method: SaveMethods::Memory }
+        Self {
+            solver_method,
+            save_method: SaveMethods::Memory,
+            dense_output: false,
+        }
+    }
+
+    /// Sets whether adaptive solves return a `DenseOutput` in the solution, which can be evaluated
+    /// at any time in the time span. Requires Radau IIA, Adams-Bashforth-Moulton or an explicit
+    /// Runge-Kutta method with interpolation coefficients, such as Tsit5 or Verner9. Fixed step
+    /// solves return an error when it is set.
+    pub fn with_dense_output(mut self, dense_output: bool) -> Self {
+        self.dense_output = dense_output;
+        self
     }
 
     pub fn solve_adaptive<Model: OdeModel<State = State>, State: OdeState + Adaptive>(
@@ -41,29 +95,9 @@ impl OdeSolver {
         x0: State,
         tspan: (f64, f64),
         controller: AdaptiveStepControl,
-    ) -> Result<Option<MemoryResult<State>>, Box<dyn Error>> {
+    ) -> Result<Solution<State>, Box<dyn Error>> {
         // Create progress bar
-        let mut progress_bar = ProgressBar::new(100);
-        progress_bar.set_style(
-            ProgressStyle::with_template(
-                "[{elapsed_precise}] [{wide_bar:.cyan/blue}] %{percent} ({eta})",
-            )
-            .unwrap()
-            .with_key(
-                "eta",
-                |state: &ProgressState, w: &mut dyn Write| {
-                    write!(
-                        w,
-                        "{:.1}s",
-                        state
-                            .eta()
-                            .as_secs_f64()
-                    )
-                    .unwrap()
-                },
-            )
-            .progress_chars("##-"),
-        );
+        let mut progress_bar = default_progress_bar();
 
         let result = self.solve_adaptive_progress(
             problem,
@@ -78,12 +112,12 @@ impl OdeSolver {
 
     pub fn solve_adaptive_progress<Model: OdeModel<State = State>, State: OdeState + Adaptive>(
         &self,
-        mut problem: OdeProblem<Model, State>,
+        problem: OdeProblem<Model, State>,
         x0: State,
         tspan: (f64, f64),
-        mut controller: AdaptiveStepControl,
+        controller: AdaptiveStepControl,
         progress_bar: &mut ProgressBar,
-    ) -> Result<Option<MemoryResult<State>>, Box<dyn Error>> {
+    ) -> Result<Solution<State>, Box<dyn Error>> {
         // handle inappropriate combos
         match &self.solver_method {
             SolverMethods::Explicit(method) => match method {
@@ -94,158 +128,484 @@ impl OdeSolver {
                     _ => {}
                 },
             },
-            _ => {}
+            SolverMethods::Implicit(_) | SolverMethods::MultiStep(_) => {}
+            SolverMethods::SecondOrder(_) => {
+                return Err(SECOND_ORDER_MODEL_REQUIRED.into());
+            }
+            SolverMethods::LieGroup(_) => {
+                return Err(LIE_GROUP_STATE_REQUIRED.into());
+            }
+        }
+
+        self.run_adaptive(
+            problem,
+            x0,
+            tspan,
+            controller,
+            progress_bar,
+            |model,
+             x0,
+             tspan,
+             controller,
+             events,
+             result,
+             dense_output,
+             writer_manager,
+             progress_bar| {
+                self.solver_method
+                    .solve_adaptive(
+                        model,
+                        x0,
+                        tspan,
+                        controller,
+                        events,
+                        result,
+                        dense_output,
+                        writer_manager,
+                        progress_bar,
+                    )
+            },
+        )
+    }
+
+    pub fn solve_fixed<Model: OdeModel<State = State>, State: OdeState>(
+        &self,
+        problem: OdeProblem<Model, State>,
+        x0: State,
+        tspan: (f64, f64),
+        dt: f64,
+    ) -> Result<Solution<State>, Box<dyn Error>> {
+        // Create progress bar
+        let mut progress_bar = default_progress_bar();
+        let result = self.solve_fixed_progress(
+            problem,
+            x0,
+            tspan,
+            dt,
+            &mut progress_bar,
+        )?;
+
+        Ok(result)
+    }
+
+    pub fn solve_fixed_progress<Model: OdeModel<State = State>, State: OdeState>(
+        &self,
+        problem: OdeProblem<Model, State>,
+        x0: State,
+        tspan: (f64, f64),
+        dt: f64,
+        progress_bar: &mut ProgressBar,
+    ) -> Result<Solution<State>, Box<dyn Error>> {
+        match &self.solver_method {
+            SolverMethods::Implicit(_) | SolverMethods::MultiStep(_) => {
+                return Err("implicit and multistep methods require adaptive step control".into());
+            }
+            SolverMethods::SecondOrder(_) => {
+                return Err(SECOND_ORDER_MODEL_REQUIRED.into());
+            }
+            SolverMethods::LieGroup(_) => {
+                return Err(LIE_GROUP_STATE_REQUIRED.into());
+            }
+            SolverMethods::Explicit(_) => {}
+        }
+
+        self.run_fixed(
+            problem,
+            x0,
+            tspan,
+            dt,
+            progress_bar,
+            |model, x0, tspan, controller, events, result, writer_manager, progress_bar| {
+                self.solver_method
+                    .solve_fixed(
+                        model,
+                        x0,
+                        tspan,
+                        controller,
+                        events,
+                        result,
+                        writer_manager,
+                        progress_bar,
+                    )
+            },
+        )
+    }
+
+    /// Solves a second order problem with adaptive step control. Runge-Kutta-Nyström methods
+    /// integrate the position and velocity directly, while first order methods integrate the
+    /// state through `OdeModel::f`. Symplectic methods require a fixed step.
+    pub fn solve_second_order_adaptive<Model, Position>(
+        &self,
+        problem: OdeProblem<Model, SecondOrderState<Position>>,
+        x0: SecondOrderState<Position>,
+        tspan: (f64, f64),
+        controller: AdaptiveStepControl,
+    ) -> Result<Solution<SecondOrderState<Position>>, Box<dyn Error>>
+    where
+        Model: SecondOrderModel<Position = Position>,
+        Position: OdeState + Adaptive,
+    {
+        let SolverMethods::SecondOrder(method) = self.solver_method else {
+            return self.solve_adaptive(problem, x0, tspan, controller);
+        };
+        if self.dense_output {
+            return Err(DENSE_OUTPUT_UNSUPPORTED.into());
+        }
+        let mut progress_bar = default_progress_bar();
+        self.run_adaptive(
+            problem,
+            x0,
+            tspan,
+            controller,
+            &mut progress_bar,
+            |model,
+             x0,
+             tspan,
+             controller,
+             events,
+             result,
+             _dense_output,
+             writer_manager,
+             progress_bar| {
+                method.solve_adaptive(
+                    model,
+                    x0,
+                    tspan,
+                    controller,
+                    events,
+                    result,
+                    writer_manager,
+                    progress_bar,
+                )
+            },
+        )
+    }
+
+    /// Solves a second order problem with a fixed step size. Symplectic and Runge-Kutta-Nyström
+    /// methods integrate the position and velocity directly, while first order methods integrate
+    /// the state through `OdeModel::f`.
+    ///
+    /// Gauss-Jackson restarts whenever a periodic or discrete event fires, since the event may
+    /// change the state or the model, so periodic events such as flight software ticks every few
+    /// steps can make it more expensive than Yoshida8. The number of restarts is reported in
+    /// `SolverStats::restarts`.
+    pub fn solve_second_order_fixed<Model, Position>(
+        &self,
+        problem: OdeProblem<Model, SecondOrderState<Position>>,
+        x0: SecondOrderState<Position>,
+        tspan: (f64, f64),
+        dt: f64,
+    ) -> Result<Solution<SecondOrderState<Position>>, Box<dyn Error>>
+    where
+        Model: SecondOrderModel<Position = Position>,
+        Position: OdeState,
+    {
+        let SolverMethods::SecondOrder(method) = self.solver_method else {
+            return self.solve_fixed(problem, x0, tspan, dt);
+        };
+        let mut progress_bar = default_progress_bar();
+        self.run_fixed(
+            problem,
+            x0,
+            tspan,
+            dt,
+            &mut progress_bar,
+            |model, x0, tspan, controller, events, result, writer_manager, progress_bar| {
+                method.solve_fixed(
+                    model,
+                    x0,
+                    tspan,
+                    controller,
+                    events,
+                    result,
+                    writer_manager,
+                    progress_bar,
+                )
+            },
+        )
+    }
+
+    /// Solves the problem with adaptive step control, integrating the group components of the state
+    /// (such as attitude quaternions) on their Lie group. Other methods fall back to `solve_adaptive`.
+    pub fn solve_lie_group_adaptive<Model, State>(
+        &self,
+        problem: OdeProblem<Model, State>,
+        x0: State,
+        tspan: (f64, f64),
+        controller: AdaptiveStepControl,
+    ) -> Result<Solution<State>, Box<dyn Error>>
+    where
+        Model: OdeModel<State = State>,
+        State: OdeState + Adaptive + LieGroup,
+    {
+        let SolverMethods::LieGroup(method) = self.solver_method else {
+            return self.solve_adaptive(problem, x0, tspan, controller);
+        };
+        if self.dense_output {
+            return Err(DENSE_OUTPUT_UNSUPPORTED.into());
         }
+        let mut progress_bar = default_progress_bar();
+        self.run_adaptive(
+            problem,
+            x0,
+            tspan,
+            controller,
+            &mut progress_bar,
+            |model,
+             x0,
+             tspan,
+             controller,
+             events,
+             result,
+             _dense_output,
+             writer_manager,
+             progress_bar| {
+                method.solve_adaptive(
+                    model,
+                    x0,
+                    tspan,
+                    controller,
+                    events,
+                    result,
+                    writer_manager,
+                    progress_bar,
+                )
+            },
+        )
+    }
 
+    /// Solves the problem with a fixed step size, integrating the group components of the state
+    /// (such as attitude quaternions) on their Lie group. Other methods fall back to `solve_fixed`.
+    pub fn solve_lie_group_fixed<Model, State>(
+        &self,
+        problem: OdeProblem<Model, State>,
+        x0: State,
+        tspan: (f64, f64),
+        dt: f64,
+    ) -> Result<Solution<State>, Box<dyn Error>>
+    where
+        Model: OdeModel<State = State>,
+        State: OdeState + LieGroup,
+    {
+        let SolverMethods::LieGroup(method) = self.solver_method else {
+            return self.solve_fixed(problem, x0, tspan, dt);
+        };
+        let mut progress_bar = default_progress_bar();
+        self.run_fixed(
+            problem,
+            x0,
+            tspan,
+            dt,
+            &mut progress_bar,
+            |model, x0, tspan, controller, events, result, writer_manager, progress_bar| {
+                method.solve_fixed(
+                    model,
+                    x0,
+                    tspan,
+                    controller,
+                    events,
+                    result,
+                    writer_manager,
+                    progress_bar,
+                )
+            },
+        )
+    }
+
+    /// Runs an adaptive solve, handling the writers, result storage and presim/postsim events
+    /// around the integration itself.
+    fn run_adaptive<Model, State, F>(
+        &self,
+        mut problem: OdeProblem<Model, State>,
+        x0: State,
+        tspan: (f64, f64),
+        mut controller: AdaptiveStepControl,
+        progress_bar: &mut ProgressBar,
+        solve: F,
+    ) -> Result<Solution<State>, Box<dyn Error>>
+    where
+        Model: OdeModel<State = State>,
+        State: OdeState,
+        F: FnOnce(
+            &mut Model,
+            &State,
+            (f64, f64),
+            &mut AdaptiveStepControl,
+            &mut EventManager<Model, State>,
+            &mut Option<MemoryResult<State>>,
+            &mut Option<DenseOutput<State>>,
+            &mut Option<WriterManager>,
+            &mut ProgressBar,
+        ) -> Result<SolverStats, Box<dyn Error>>,
+    {
         // Initialize the manager for writing results to a file
         let mut writer_manager = self.initialize_writer(&mut problem, &x0)?;
         // Preallocate memory for result storage if needed
         let mut result = self.initialize_adaptive_result(&x0, &tspan, &controller);
+        let mut dense_output = self
+            .dense_output
+            .then(DenseOutput::new);
 
         progress_bar.set_position(0);
 
         // process any presim events
-        for event in &problem
+        problem
             .events
-            .presim_events
-        {
-            (event.f)(
+            .process_presim_events(
                 &mut problem.model,
                 &x0,
                 tspan.0,
                 &writer_manager,
             )?;
-        }
 
-        self.solver_method
-            .solve_adaptive(
-                &mut problem.model,
-                &x0,
-                tspan,
-                &mut controller,
-                &mut problem.events,
-                &mut result,
-                &mut writer_manager,
-                progress_bar,
-            )?;
+        let stats = solve(
+            &mut problem.model,
+            &x0,
+            tspan,
+            &mut controller,
+            &mut problem.events,
+            &mut result,
+            &mut dense_output,
+            &mut writer_manager,
+            progress_bar,
+        )?;
 
         // process any postsim events
-        for event in &problem
+        problem
             .events
-            .postsim_events
-        {
-            (event.f)(
+            .process_postsim_events(
                 &mut problem.model,
                 &writer_manager,
-            );
-        }
-
-        // Finalize and return the results
-        if let Some(result) = &mut result {
-            result.truncate();
-        }
+            )?;
 
         progress_bar.finish();
 
-        Ok(result)
-    }
-
-    pub fn solve_fixed<Model: OdeModel<State = State>, State: OdeState>(
-        &self,
-        problem: OdeProblem<Model, State>,
-        x0: State,
-        tspan: (f64, f64),
-        dt: f64,
-    ) -> Result<Option<MemoryResult<State>>, Box<dyn Error>> {
-        // Create progress bar
-        let mut progress_bar = ProgressBar::new(100);
-        progress_bar.set_style(
-            ProgressStyle::with_template(
-                "[{elapsed_precise}] [{wide_bar:.cyan/blue}] %{percent} ({eta})",
-            )
-            .unwrap()
-            .with_key(
-                "eta",
-                |state: &ProgressState, w: &mut dyn Write| {
-                    write!(
-                        w,
-                        "{:.1}s",
-                        state
-                            .eta()
-                            .as_secs_f64()
-                    )
-                    .unwrap()
-                },
-            )
-            .progress_chars("##-"),
-        );
-        let result = self.solve_fixed_progress(
+        self.finish(
             problem,
-            x0,
-            tspan,
-            dt,
-            &mut progress_bar,
-        )?;
-
-        Ok(result)
+            result,
+            dense_output,
+            stats,
+        )
     }
 
-    pub fn solve_fixed_progress<Model: OdeModel<State = State>, State: OdeState>(
+    /// Runs a fixed step solve, handling the writers, result storage and presim/postsim events
+    /// around the integration itself.
+    fn run_fixed<Model, State, F>(
         &self,
         mut problem: OdeProblem<Model, State>,
         x0: State,
         tspan: (f64, f64),
         dt: f64,
         progress_bar: &mut ProgressBar,
-    ) -> Result<Option<MemoryResult<State>>, Box<dyn Error>> {
+        solve: F,
+    ) -> Result<Solution<State>, Box<dyn Error>>
+    where
+        Model: OdeModel<State = State>,
+        State: OdeState,
+        F: FnOnce(
+            &mut Model,
+            &State,
+            (f64, f64),
+            &mut FixedStepControl,
+            &mut EventManager<Model, State>,
+            &mut Option<MemoryResult<State>>,
+            &mut Option<WriterManager>,
+            &mut ProgressBar,
+        ) -> Result<SolverStats, Box<dyn Error>>,
+    {
+        if self.dense_output {
+            return Err(DENSE_OUTPUT_UNSUPPORTED.into());
+        }
         let mut controller = FixedStepControl::new(dt);
         // Initialize the manager for writing results to a file
         let mut writer_manager = self.initialize_writer(&mut problem, &x0)?;
         // Preallocate memory for result storage if needed
         let mut result = self.initialize_fixed_result(&x0, &tspan, &controller);
 
+        progress_bar.set_position(0);
+
         // process any presim events
-        for event in &problem
+        problem
             .events
-            .presim_events
-        {
-            (event.f)(
+            .process_presim_events(
                 &mut problem.model,
                 &x0,
                 tspan.0,
                 &writer_manager,
             )?;
-        }
 
-        self.solver_method
-            .solve_fixed(
-                &mut problem.model,
-                &x0,
-                tspan,
-                &mut controller,
-                &mut problem.events,
-                &mut result,
-                &mut writer_manager,
-                progress_bar,
-            )?;
+        let stats = solve(
+            &mut problem.model,
+            &x0,
+            tspan,
+            &mut controller,
+            &mut problem.events,
+            &mut result,
+            &mut writer_manager,
+            progress_bar,
+        )?;
 
         // process any postsim events
-        for event in &problem
+        problem
             .events
-            .postsim_events
-        {
-            (event.f)(
+            .process_postsim_events(
                 &mut problem.model,
                 &writer_manager,
-            );
-        }
+            )?;
+
+        progress_bar.finish();
 
+        self.finish(problem, result, None, stats)
+    }
+
+    /// Collects the results, event log, dense output and statistics into the solution,
+    /// writing the event log and statistics to the results folder if saving.
+    fn finish<Model, State>(
+        &self,
+        mut problem: OdeProblem<Model, State>,
+        mut result: Option<MemoryResult<State>>,
+        dense_output: Option<DenseOutput<State>>,
+        mut stats: SolverStats,
+    ) -> Result<Solution<State>, Box<dyn Error>>
+    where
+        Model: OdeModel<State = State>,
+        State: OdeState,
+    {
         // Finalize and return the results
         if let Some(result) = &mut result {
             result.truncate();
         }
 
-        Ok(result)
+        let termination = match problem
+            .events
+            .termination()
+        {
+            Some(record) => Termination::Event(record.clone()),
+            None => Termination::Completed,
+        };
+        stats.event_time = problem
+            .events
+            .elapsed()
+            .as_secs_f64();
+
+        let solution = Solution {
+            result,
+            events: problem
+                .events
+                .take_log(),
+            termination,
+            dense_output,
+            stats,
+        };
+
+        if let Some(save_folder) = &problem.save_folder {
+            solution.write_events(save_folder)?;
+            solution
+                .stats
+                .write(save_folder)?;
+        }
+
+        Ok(solution)
     }
 
     /// Solves the ODE problem, but with the initial state coming from the model rather than provided directly
@@ -254,7 +614,7 @@ impl OdeSolver {
         problem: OdeProblem<Model, State>,
         tspan: (f64, f64),
         controller: AdaptiveStepControl,
-    ) -> Result<Option<MemoryResult<State>>, Box<dyn Error>>
+    ) -> Result<Solution<State>, Box<dyn Error>>
     where
         Model: OdeModel<State = State> + StateFromModel<State = State>,
         State: OdeState + Adaptive,
@@ -272,7 +632,7 @@ impl OdeSolver {
         mut problem: OdeProblem<Model, State>,
         tspan: (f64, f64),
         controller: AdaptiveStepControl,
-    ) -> Result<Option<MemoryResult<State>>, Box<dyn Error>>
+    ) -> Result<Solution<State>, Box<dyn Error>>
     where
         Model: OdeModel<State = State> + StateFromModelMut<State = State>,
         State: OdeState + Adaptive,
@@ -289,7 +649,7 @@ impl OdeSolver {
         problem: OdeProblem<Model, State>,
         tspan: (f64, f64),
         dt: f64,
-    ) -> Result<Option<MemoryResult<State>>, Box<dyn Error>>
+    ) -> Result<Solution<State>, Box<dyn Error>>
     where
         Model: OdeModel<State = State> + StateFromModel<State = State>,
         State: OdeState,
@@ -307,7 +667,7 @@ impl OdeSolver {
         mut problem: OdeProblem<Model, State>,
         tspan: (f64, f64),
         dt: f64,
-    ) -> Result<Option<MemoryResult<State>>, Box<dyn Error>>
+    ) -> Result<Solution<State>, Box<dyn Error>>
     where
         Model: OdeModel<State = State> + StateFromModelMut<State = State>,
         State: OdeState,
@@ -318,6 +678,42 @@ impl OdeSolver {
         self.solve_fixed(problem, x0, tspan, dt)
     }
 
+    /// Solves the problem on the Lie group with adaptive step control, but with the initial state
+    /// coming from the model rather than provided directly
+    pub fn solve_model_lie_group_adaptive_mut<Model, State>(
+        &self,
+        mut problem: OdeProblem<Model, State>,
+        tspan: (f64, f64),
+        controller: AdaptiveStepControl,
+    ) -> Result<Solution<State>, Box<dyn Error>>
+    where
+        Model: OdeModel<State = State> + StateFromModelMut<State = State>,
+        State: OdeState + Adaptive + LieGroup,
+    {
+        let x0 = problem
+            .model
+            .initial_state();
+        self.solve_lie_group_adaptive(problem, x0, tspan, controller)
+    }
+
+    /// Solves the problem on the Lie group with a fixed step size, but with the initial state
+    /// coming from the model rather than provided directly
+    pub fn solve_model_lie_group_fixed_mut<Model, State>(
+        &self,
+        mut problem: OdeProblem<Model, State>,
+        tspan: (f64, f64),
+        dt: f64,
+    ) -> Result<Solution<State>, Box<dyn Error>>
+    where
+        Model: OdeModel<State = State> + StateFromModelMut<State = State>,
+        State: OdeState + LieGroup,
+    {
+        let x0 = problem
+            .model
+            .initial_state();
+        self.solve_lie_group_fixed(problem, x0, tspan, dt)
+    }
+
     fn initialize_writer<Model: OdeModel<State = State>, State: OdeState>(
         &self,
         problem: &mut OdeProblem<Model, State>,
@@ -331,11 +727,13 @@ impl OdeSolver {
                 .events
                 .save_events
             {
-                (event.init_fn)(
-                    &mut problem.model,
-                    x0,
-                    &mut writer_manager,
-                );
+                event
+                    .init_fn
+                    .call(
+                        &mut problem.model,
+                        x0,
+                        &mut writer_manager,
+                    );
             }
             // Initialize the writers from the builders
             writer_manager.initialize(save_folder)?;
@@ -369,49 +767,176 @@ impl OdeSolver {
             _ => None,
         }
     }
-
-    fn initialize_fixed_result<State>(
+
+    fn initialize_fixed_result<State>(
+        &self,
+        _x0: &State,
+        tspan: &(f64, f64),
+        controller: &FixedStepControl,
+    ) -> Option<MemoryResult<State>>
+    where
+        State: OdeState,
+    {
+        // Preallocate memory for result storage if needed
+        match self.save_method {
+            SaveMethods::Memory => {
+                let n = ((tspan.1 - tspan.0) / controller.dt).ceil() as usize;
+                Some(MemoryResult::new(n))
+            }
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub enum SolverMethods {
+    Explicit(ExplicitMethods),
+    Implicit(ImplicitMethods),
+    MultiStep(MultiStepMethods),
+    SecondOrder(SecondOrderMethods),
+    LieGroup(LieGroupMethods),
+}
+
+impl From<ExplicitMethods> for SolverMethods {
+    fn from(value: ExplicitMethods) -> Self {
+        Self::Explicit(value)
+    }
+}
+
+impl From<ImplicitMethods> for SolverMethods {
+    fn from(value: ImplicitMethods) -> Self {
+        Self::Implicit(value)
+    }
+}
+
+impl From<MultiStepMethods> for SolverMethods {
+    fn from(value: MultiStepMethods) -> Self {
+        Self::MultiStep(value)
+    }
+}
+
+impl From<SecondOrderMethods> for SolverMethods {
+    fn from(value: SecondOrderMethods) -> Self {
+        Self::SecondOrder(value)
+    }
+}
+
+impl From<LieGroupMethods> for SolverMethods {
+    fn from(value: LieGroupMethods) -> Self {
+        Self::LieGroup(value)
+    }
+}
+
+impl From<RungeKuttaMethods> for SolverMethods {
+    fn from(value: RungeKuttaMethods) -> Self {
+        Self::Explicit(ExplicitMethods::RungeKutta(
+            value,
+        ))
+    }
+}
+
+impl SolverMethods {
+    fn solve_adaptive<Model, State>(
+        &self,
+        model: &mut Model,
+        x0: &State,
+        tspan: (f64, f64),
+        controller: &mut AdaptiveStepControl,
+        events: &mut EventManager<Model, State>,
+        result: &mut Option<MemoryResult<State>>,
+        dense_output: &mut Option<DenseOutput<State>>,
+        writer_manager: &mut Option<WriterManager>,
+        progress_bar: &mut ProgressBar,
+    ) -> Result<SolverStats, Box<dyn Error>>
+    where
+        Model: OdeModel<State = State>,
+        State: OdeState + Adaptive,
+    {
+        match self {
+            SolverMethods::Explicit(method) => method.solve_adaptive(
+                model,
+                x0,
+                tspan,
+                controller,
+                events,
+                result,
+                dense_output,
+                writer_manager,
+                progress_bar,
+            ),
+            SolverMethods::Implicit(method) => method.solve_adaptive(
+                model,
+                x0,
+                tspan,
+                controller,
+                events,
+                result,
+                dense_output,
+                writer_manager,
+                progress_bar,
+            ),
+            SolverMethods::MultiStep(method) => method.solve_adaptive(
+                model,
+                x0,
+                tspan,
+                controller,
+                events,
+                result,
+                dense_output,
+                writer_manager,
+                progress_bar,
+            ),
+            SolverMethods::SecondOrder(_) => Err(SECOND_ORDER_MODEL_REQUIRED.into()),
+            SolverMethods::LieGroup(_) => Err(LIE_GROUP_STATE_REQUIRED.into()),
+        }
+    }
+    fn solve_fixed<Model, State>(
         &self,
-        _x0: &State,
-        tspan: &(f64, f64),
-        controller: &FixedStepControl,
-    ) -> Option<MemoryResult<State>>
+        model: &mut Model,
+        x0: &State,
+        tspan: (f64, f64),
+        controller: &mut FixedStepControl,
+        events: &mut EventManager<Model, State>,
+        result: &mut Option<MemoryResult<State>>,
+        writer_manager: &mut Option<WriterManager>,
+        progress_bar: &mut ProgressBar,
+    ) -> Result<SolverStats, Box<dyn Error>>
     where
+        Model: OdeModel<State = State>,
         State: OdeState,
     {
-        // Preallocate memory for result storage if needed
-        match self.save_method {
-            SaveMethods::Memory => {
-                let n = ((tspan.1 - tspan.0) / controller.dt).ceil() as usize;
-                Some(MemoryResult::new(n))
+        match self {
+            SolverMethods::Explicit(method) => method.solve_fixed(
+                model,
+                x0,
+                tspan,
+                controller,
+                events,
+                result,
+                writer_manager,
+                progress_bar,
+            ),
+            SolverMethods::Implicit(_) | SolverMethods::MultiStep(_) => {
+                Err("implicit and multistep methods require adaptive step control".into())
             }
-            _ => None,
+            SolverMethods::SecondOrder(_) => Err(SECOND_ORDER_MODEL_REQUIRED.into()),
+            SolverMethods::LieGroup(_) => Err(LIE_GROUP_STATE_REQUIRED.into()),
         }
     }
 }
 
 #[derive(Clone, Copy)]
-pub enum SolverMethods {
-    Explicit(ExplicitMethods),
-    Implicit,
-    MultiStep,
-}
-
-impl From<ExplicitMethods> for SolverMethods {
-    fn from(value: ExplicitMethods) -> Self {
-        Self::Explicit(value)
-    }
+pub enum ExplicitMethods {
+    RungeKutta(RungeKuttaMethods),
 }
 
-impl From<RungeKuttaMethods> for SolverMethods {
+impl From<RungeKuttaMethods> for ExplicitMethods {
     fn from(value: RungeKuttaMethods) -> Self {
-        Self::Explicit(ExplicitMethods::RungeKutta(
-            value,
-        ))
+        Self::RungeKutta(value)
     }
 }
 
-impl SolverMethods {
+impl ExplicitMethods {
     fn solve_adaptive<Model, State>(
         &self,
         model: &mut Model,
@@ -420,27 +945,29 @@ impl SolverMethods {
         controller: &mut AdaptiveStepControl,
         events: &mut EventManager<Model, State>,
         result: &mut Option<MemoryResult<State>>,
+        dense_output: &mut Option<DenseOutput<State>>,
         writer_manager: &mut Option<WriterManager>,
         progress_bar: &mut ProgressBar,
-    ) -> Result<(), Box<dyn Error>>
+    ) -> Result<SolverStats, Box<dyn Error>>
     where
         Model: OdeModel<State = State>,
         State: OdeState + Adaptive,
     {
         match self {
-            SolverMethods::Explicit(method) => method.solve_adaptive(
+            ExplicitMethods::RungeKutta(method) => method.solve_adaptive(
                 model,
                 x0,
                 tspan,
                 controller,
                 events,
                 result,
+                dense_output,
                 writer_manager,
                 progress_bar,
             ),
-            _ => todo!(),
         }
     }
+
     fn solve_fixed<Model, State>(
         &self,
         model: &mut Model,
@@ -451,13 +978,13 @@ impl SolverMethods {
         result: &mut Option<MemoryResult<State>>,
         writer_manager: &mut Option<WriterManager>,
         progress_bar: &mut ProgressBar,
-    ) -> Result<(), Box<dyn Error>>
+    ) -> Result<SolverStats, Box<dyn Error>>
     where
         Model: OdeModel<State = State>,
         State: OdeState,
     {
         match self {
-            SolverMethods::Explicit(method) => method.solve_fixed(
+            ExplicitMethods::RungeKutta(method) => method.solve_fixed(
                 model,
                 x0,
                 tspan,
@@ -467,23 +994,60 @@ impl SolverMethods {
                 writer_manager,
                 progress_bar,
             ),
-            _ => todo!(),
         }
     }
 }
 
+/// Implicit methods for stiff systems. These need the state to provide its elements
+/// through `Adaptive::elements`, and are only available with adaptive step control.
 #[derive(Clone, Copy)]
-pub enum ExplicitMethods {
-    RungeKutta(RungeKuttaMethods),
+pub enum ImplicitMethods {
+    /// 3 stage, 5th order Radau IIA method.
+    RadauIIA5,
 }
 
-impl From<RungeKuttaMethods> for ExplicitMethods {
-    fn from(value: RungeKuttaMethods) -> Self {
-        Self::RungeKutta(value)
+impl ImplicitMethods {
+    fn solve_adaptive<Model, State>(
+        &self,
+        model: &mut Model,
+        x0: &State,
+        tspan: (f64, f64),
+        controller: &mut AdaptiveStepControl,
+        events: &mut EventManager<Model, State>,
+        result: &mut Option<MemoryResult<State>>,
+        dense_output: &mut Option<DenseOutput<State>>,
+        writer_manager: &mut Option<WriterManager>,
+        progress_bar: &mut ProgressBar,
+    ) -> Result<SolverStats, Box<dyn Error>>
+    where
+        Model: OdeModel<State = State>,
+        State: OdeState + Adaptive,
+    {
+        match self {
+            ImplicitMethods::RadauIIA5 => RadauIIA5::new().solve_adaptive(
+                model,
+                x0,
+                tspan,
+                controller,
+                events,
+                result,
+                dense_output,
+                writer_manager,
+                progress_bar,
+            ),
+        }
     }
 }
 
-impl ExplicitMethods {
+/// Multistep methods, which reuse derivatives from previous steps. These are only available
+/// with adaptive step control.
+#[derive(Clone, Copy)]
+pub enum MultiStepMethods {
+    /// Variable-step, variable-order Adams-Bashforth-Moulton predictor-corrector (orders 1-12).
+    AdamsBashforthMoulton,
+}
+
+impl MultiStepMethods {
     fn solve_adaptive<Model, State>(
         &self,
         model: &mut Model,
@@ -492,28 +1056,40 @@ impl ExplicitMethods {
         controller: &mut AdaptiveStepControl,
         events: &mut EventManager<Model, State>,
         result: &mut Option<MemoryResult<State>>,
+        dense_output: &mut Option<DenseOutput<State>>,
         writer_manager: &mut Option<WriterManager>,
         progress_bar: &mut ProgressBar,
-    ) -> Result<(), Box<dyn Error>>
+    ) -> Result<SolverStats, Box<dyn Error>>
     where
         Model: OdeModel<State = State>,
         State: OdeState + Adaptive,
     {
         match self {
-            ExplicitMethods::RungeKutta(method) => method.solve_adaptive(
+            MultiStepMethods::AdamsBashforthMoulton => AdamsBashforthMoulton::new().solve_adaptive(
                 model,
                 x0,
                 tspan,
                 controller,
                 events,
                 result,
+                dense_output,
                 writer_manager,
                 progress_bar,
             ),
         }
     }
+}
 
-    fn solve_fixed<Model, State>(
+/// Methods for states with components on a Lie group, such as attitude quaternions, used through
+/// `OdeSolver::solve_lie_group_fixed` and `OdeSolver::solve_lie_group_adaptive`.
+#[derive(Clone, Copy)]
+pub enum LieGroupMethods {
+    /// Runge-Kutta-Munthe-Kaas version of a Runge-Kutta method, with the same order.
+    RungeKuttaMuntheKaas(RungeKuttaMethods),
+}
+
+impl LieGroupMethods {
+    pub fn solve_fixed<Model, State>(
         &self,
         model: &mut Model,
         x0: &State,
@@ -523,13 +1099,193 @@ impl ExplicitMethods {
         result: &mut Option<MemoryResult<State>>,
         writer_manager: &mut Option<WriterManager>,
         progress_bar: &mut ProgressBar,
-    ) -> Result<(), Box<dyn Error>>
+    ) -> Result<SolverStats, Box<dyn Error>>
     where
         Model: OdeModel<State = State>,
-        State: OdeState,
+        State: OdeState + LieGroup,
+    {
+        let LieGroupMethods::RungeKuttaMuntheKaas(method) = self;
+        macro_rules! solve {
+            ($tableau:expr) => {
+                RungeKuttaMuntheKaas::new($tableau).solve_fixed(
+                    model,
+                    x0,
+                    tspan,
+                    controller,
+                    events,
+                    result,
+                    writer_manager,
+                    progress_bar,
+                )
+            };
+        }
+        match method {
+            RungeKuttaMethods::DoPri45 => solve!(ButcherTableau::<5, 7>::DORMANDPRINCE45),
+            RungeKuttaMethods::New45 => solve!(ButcherTableau::<5, 7>::NEW45),
+            RungeKuttaMethods::Rk4 => solve!(ButcherTableau::<4, 4>::RK4),
+            RungeKuttaMethods::Tsit5 => solve!(ButcherTableau::<5, 7>::TSITOURAS5),
+            RungeKuttaMethods::Verner6 => solve!(ButcherTableau::<6, 9>::VERNER6),
+            RungeKuttaMethods::Verner9 => solve!(ButcherTableau::<9, 26>::VERNER9),
+        }
+    }
+
+    pub fn solve_adaptive<Model, State>(
+        &self,
+        model: &mut Model,
+        x0: &State,
+        tspan: (f64, f64),
+        controller: &mut AdaptiveStepControl,
+        events: &mut EventManager<Model, State>,
+        result: &mut Option<MemoryResult<State>>,
+        writer_manager: &mut Option<WriterManager>,
+        progress_bar: &mut ProgressBar,
+    ) -> Result<SolverStats, Box<dyn Error>>
+    where
+        Model: OdeModel<State = State>,
+        State: OdeState + Adaptive + LieGroup,
+    {
+        let LieGroupMethods::RungeKuttaMuntheKaas(method) = self;
+        macro_rules! solve {
+            ($tableau:expr) => {
+                RungeKuttaMuntheKaas::new($tableau).solve_adaptive(
+                    model,
+                    x0,
+                    tspan,
+                    controller,
+                    events,
+                    result,
+                    writer_manager,
+                    progress_bar,
+                )
+            };
+        }
+        match method {
+            RungeKuttaMethods::DoPri45 => solve!(ButcherTableau::<5, 7>::DORMANDPRINCE45),
+            RungeKuttaMethods::New45 => solve!(ButcherTableau::<5, 7>::NEW45),
+            RungeKuttaMethods::Rk4 => Err("RK4 cannot be used with adaptive step methods".into()),
+            RungeKuttaMethods::Tsit5 => solve!(ButcherTableau::<5, 7>::TSITOURAS5),
+            RungeKuttaMethods::Verner6 => solve!(ButcherTableau::<6, 9>::VERNER6),
+            RungeKuttaMethods::Verner9 => solve!(ButcherTableau::<9, 26>::VERNER9),
+        }
+    }
+}
+
+/// Methods for second order models x'' = a(t, x), used through `OdeSolver::solve_second_order_fixed`
+/// and `OdeSolver::solve_second_order_adaptive`. The symplectic methods are only available with a
+/// fixed step, since adapting the step size breaks their long term energy behavior. Gauss-Jackson
+/// is also fixed step only.
+#[derive(Clone, Copy)]
+pub enum SecondOrderMethods {
+    /// 2nd order symplectic velocity Verlet.
+    VelocityVerlet,
+    /// 4th order symplectic Forest-Ruth method.
+    ForestRuth,
+    /// 4th order symplectic Yoshida composition.
+    Yoshida4,
+    /// 6th order symplectic Yoshida composition.
+    Yoshida6,
+    /// 8th order symplectic Yoshida composition.
+    Yoshida8,
+    /// 4th order Runge-Kutta-Nyström method with an embedded 3rd order error estimate.
+    Nystrom4,
+    /// Verner's 6(5) pair applied as a Runge-Kutta-Nyström method, with embedded position and
+    /// velocity error estimates.
+    Nystrom6,
+    /// Verner's 9(8) pair applied as a Runge-Kutta-Nyström method, with embedded position and
+    /// velocity error estimates, for tight tolerance orbit propagation. This is the highest order
+    /// Runge-Kutta-Nyström method provided, there is no RKN12(10).
+    Nystrom9,
+    /// 8th order Gauss-Jackson summed form multistep method, started with Yoshida8.
+    /// The method restarts after any step shorter than dt and after any scheduled event fires,
+    /// at a cost of roughly 200 acceleration evaluations, against 2 per step otherwise. It only
+    /// keeps its efficiency when events are at least a hundred or so steps apart.
+    GaussJackson8,
+}
+
+impl SecondOrderMethods {
+    fn coefficients(&self) -> Option<SplittingCoefficients> {
+        match self {
+            SecondOrderMethods::VelocityVerlet => Some(SplittingCoefficients::velocity_verlet()),
+            SecondOrderMethods::ForestRuth => Some(SplittingCoefficients::forest_ruth()),
+            SecondOrderMethods::Yoshida4 => Some(SplittingCoefficients::yoshida4()),
+            SecondOrderMethods::Yoshida6 => Some(SplittingCoefficients::yoshida6()),
+            SecondOrderMethods::Yoshida8 => Some(SplittingCoefficients::yoshida8()),
+            SecondOrderMethods::Nystrom4
+            | SecondOrderMethods::Nystrom6
+            | SecondOrderMethods::Nystrom9
+            | SecondOrderMethods::GaussJackson8 => None,
+        }
+    }
+
+    pub fn solve_fixed<Model, Position>(
+        &self,
+        model: &mut Model,
+        x0: &SecondOrderState<Position>,
+        tspan: (f64, f64),
+        controller: &mut FixedStepControl,
+        events: &mut EventManager<Model, SecondOrderState<Position>>,
+        result: &mut Option<MemoryResult<SecondOrderState<Position>>>,
+        writer_manager: &mut Option<WriterManager>,
+        progress_bar: &mut ProgressBar,
+    ) -> Result<SolverStats, Box<dyn Error>>
+    where
+        Model: SecondOrderModel<Position = Position>,
+        Position: OdeState,
     {
+        if let Some(coefficients) = self.coefficients() {
+            let mut solver = Symplectic::new(coefficients);
+            return solver.solve_fixed(
+                model,
+                x0,
+                tspan,
+                controller,
+                events,
+                result,
+                writer_manager,
+                progress_bar,
+            );
+        }
         match self {
-            ExplicitMethods::RungeKutta(method) => method.solve_fixed(
+            SecondOrderMethods::Nystrom4 => {
+                let mut solver = RungeKuttaNystrom::new(RknTableau::<4, 4>::NYSTROM4);
+                solver.solve_fixed(
+                    model,
+                    x0,
+                    tspan,
+                    controller,
+                    events,
+                    result,
+                    writer_manager,
+                    progress_bar,
+                )
+            }
+            SecondOrderMethods::Nystrom6 => {
+                let mut solver = RungeKuttaNystrom::new(RknTableau::<6, 9>::VERNER6);
+                solver.solve_fixed(
+                    model,
+                    x0,
+                    tspan,
+                    controller,
+                    events,
+                    result,
+                    writer_manager,
+                    progress_bar,
+                )
+            }
+            SecondOrderMethods::Nystrom9 => {
+                let mut solver = RungeKuttaNystrom::new(RknTableau::<9, 26>::VERNER9);
+                solver.solve_fixed(
+                    model,
+                    x0,
+                    tspan,
+                    controller,
+                    events,
+                    result,
+                    writer_manager,
+                    progress_bar,
+                )
+            }
+            SecondOrderMethods::GaussJackson8 => GaussJackson::new().solve_fixed(
                 model,
                 x0,
                 tspan,
@@ -539,6 +1295,69 @@ impl ExplicitMethods {
                 writer_manager,
                 progress_bar,
             ),
+            _ => unreachable!("symplectic methods are handled above"),
+        }
+    }
+
+    pub fn solve_adaptive<Model, Position>(
+        &self,
+        model: &mut Model,
+        x0: &SecondOrderState<Position>,
+        tspan: (f64, f64),
+        controller: &mut AdaptiveStepControl,
+        events: &mut EventManager<Model, SecondOrderState<Position>>,
+        result: &mut Option<MemoryResult<SecondOrderState<Position>>>,
+        writer_manager: &mut Option<WriterManager>,
+        progress_bar: &mut ProgressBar,
+    ) -> Result<SolverStats, Box<dyn Error>>
+    where
+        Model: SecondOrderModel<Position = Position>,
+        Position: OdeState + Adaptive,
+    {
+        match self {
+            SecondOrderMethods::Nystrom4 => {
+                let mut solver = RungeKuttaNystrom::new(RknTableau::<4, 4>::NYSTROM4);
+                solver.solve_adaptive(
+                    model,
+                    x0,
+                    tspan,
+                    controller,
+                    events,
+                    result,
+                    writer_manager,
+                    progress_bar,
+                )
+            }
+            SecondOrderMethods::Nystrom6 => {
+                let mut solver = RungeKuttaNystrom::new(RknTableau::<6, 9>::VERNER6);
+                solver.solve_adaptive(
+                    model,
+                    x0,
+                    tspan,
+                    controller,
+                    events,
+                    result,
+                    writer_manager,
+                    progress_bar,
+                )
+            }
+            SecondOrderMethods::Nystrom9 => {
+                let mut solver = RungeKuttaNystrom::new(RknTableau::<9, 26>::VERNER9);
+                solver.solve_adaptive(
+                    model,
+                    x0,
+                    tspan,
+                    controller,
+                    events,
+                    result,
+                    writer_manager,
+                    progress_bar,
+                )
+            }
+            SecondOrderMethods::GaussJackson8 => {
+                Err("Gauss-Jackson requires a fixed step size".into())
+            }
+            _ => Err("symplectic methods require a fixed step size".into()),
         }
     }
 }
@@ -571,7 +1390,7 @@ impl RungeKuttaMethods {
         result: &mut Option<MemoryResult<State>>,
         writer_manager: &mut Option<WriterManager>,
         progress_bar: &mut ProgressBar,
-    ) -> Result<(), Box<dyn Error>>
+    ) -> Result<SolverStats, Box<dyn Error>>
     where
         Model: OdeModel<State = State>,
         State: OdeState,
@@ -666,9 +1485,10 @@ impl RungeKuttaMethods {
         controller: &mut AdaptiveStepControl,
         events: &mut EventManager<Model, State>,
         result: &mut Option<MemoryResult<State>>,
+        dense_output: &mut Option<DenseOutput<State>>,
         writer_manager: &mut Option<WriterManager>,
         progress_bar: &mut ProgressBar,
-    ) -> Result<(), Box<dyn Error>>
+    ) -> Result<SolverStats, Box<dyn Error>>
     where
         Model: OdeModel<State = State>,
         State: OdeState + Adaptive,
@@ -683,6 +1503,7 @@ impl RungeKuttaMethods {
                     controller,
                     events,
                     result,
+                    dense_output,
                     writer_manager,
                     progress_bar,
                 )
@@ -696,6 +1517,7 @@ impl RungeKuttaMethods {
                     controller,
                     events,
                     result,
+                    dense_output,
                     writer_manager,
                     progress_bar,
                 )
@@ -712,6 +1534,7 @@ impl RungeKuttaMethods {
                     controller,
                     events,
                     result,
+                    dense_output,
                     writer_manager,
                     progress_bar,
                 )
@@ -725,6 +1548,7 @@ impl RungeKuttaMethods {
                     controller,
                     events,
                     result,
+                    dense_output,
                     writer_manager,
                     progress_bar,
                 )
@@ -738,6 +1562,7 @@ impl RungeKuttaMethods {
                     controller,
                     events,
                     result,
+                    dense_output,
                     writer_manager,
                     progress_bar,
                 )
@@ -745,3 +1570,56 @@ impl RungeKuttaMethods {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::state_array::StateArray;
+
+    #[derive(Debug)]
+    struct Decay;
+
+    impl OdeModel for Decay {
+        type State = StateArray<1>;
+        fn f(
+            &mut self,
+            _t: f64,
+            x: &StateArray<1>,
+            dx: &mut StateArray<1>,
+        ) -> Result<(), Box<dyn Error>> {
+            dx[0] = -x[0];
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_fixed_step_dense_output_unsupported() {
+        let solver = OdeSolver::new(RungeKuttaMethods::Tsit5.into()).with_dense_output(true);
+        let error = solver
+            .solve_fixed(
+                OdeProblem::new(Decay),
+                StateArray::new([1.0]),
+                (0.0, 1.0),
+                0.1,
+            )
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            DENSE_OUTPUT_UNSUPPORTED
+        );
+
+        let solution = solver
+            .solve_adaptive(
+                OdeProblem::new(Decay),
+                StateArray::new([1.0]),
+                (0.0, 1.0),
+                AdaptiveStepControl::default(),
+            )
+            .unwrap();
+        assert!(
+            solution
+                .dense_output
+                .is_some()
+        );
+    }
+}
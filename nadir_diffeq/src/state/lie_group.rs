@@ -0,0 +1,71 @@
+//! Lie group operations for the unit quaternions in a state, used by `LieGroup` implementations.
+//!
+//! Quaternions are stored scalar last, [x, y, z, w], with kinematics q' = q ⊗ (ω, 0) / 2 for the
+//! Hamilton product, matching the multibody joints. Their tangent vectors are rotation vectors in
+//! the frame of ω, stored in the first three slots with the fourth unused.
+
+/// Sets `q` to `base ⊗ exp(θ)` for the rotation vector `θ`, renormalizing to remove roundoff.
+pub fn quaternion_retract(q: &mut [f64], base: &[f64], theta: &[f64]) {
+    let angle = (theta[0] * theta[0] + theta[1] * theta[1] + theta[2] * theta[2]).sqrt();
+    // sin(angle/2)/angle, with its series near zero
+    let scale = if angle < 1e-4 {
+        0.5 - angle * angle / 48.0
+    } else {
+        (0.5 * angle).sin() / angle
+    };
+    let e = [scale * theta[0], scale * theta[1], scale * theta[2], (0.5 * angle).cos()];
+    let product = hamilton(base, &e);
+    let norm = product
+        .iter()
+        .map(|v| v * v)
+        .sum::<f64>()
+        .sqrt();
+    for (q, p) in q
+        .iter_mut()
+        .zip(product)
+    {
+        *q = p / norm;
+    }
+}
+
+/// Replaces the quaternion rate `dq` at `q` with the rate of the rotation vector `θ`, where
+/// `q = base ⊗ exp(θ)`, which is dexp^-1 of the angular velocity ω = 2 vec(q* ⊗ q').
+pub fn quaternion_tangent(q: &[f64], theta: &[f64], dq: &mut [f64]) {
+    let norm_squared = q
+        .iter()
+        .map(|v| v * v)
+        .sum::<f64>();
+    let conjugate = [-q[0], -q[1], -q[2], q[3]];
+    let rate = hamilton(&conjugate, dq);
+    let w =
+        [2.0 * rate[0] / norm_squared, 2.0 * rate[1] / norm_squared, 2.0 * rate[2] / norm_squared];
+
+    let angle_squared = theta[0] * theta[0] + theta[1] * theta[1] + theta[2] * theta[2];
+    // (1 - (angle/2) cot(angle/2)) / angle^2, with its series near zero
+    let c = if angle_squared < 1e-8 {
+        1.0 / 12.0 + angle_squared / 720.0
+    } else {
+        let angle = angle_squared.sqrt();
+        (1.0 - 0.5 * angle / (0.5 * angle).tan()) / angle_squared
+    };
+    let theta_cross_w = cross(theta, &w);
+    let theta_cross_theta_cross_w = cross(theta, &theta_cross_w);
+    for i in 0..3 {
+        dq[i] = w[i] + 0.5 * theta_cross_w[i] + c * theta_cross_theta_cross_w[i];
+    }
+    dq[3] = 0.0;
+}
+
+/// Hamilton product of scalar last quaternions
+fn hamilton(a: &[f64], b: &[f64]) -> [f64; 4] {
+    [
+        a[3] * b[0] + b[3] * a[0] + a[1] * b[2] - a[2] * b[1],
+        a[3] * b[1] + b[3] * a[1] + a[2] * b[0] - a[0] * b[2],
+        a[3] * b[2] + b[3] * a[2] + a[0] * b[1] - a[1] * b[0],
+        a[3] * b[3] - a[0] * b[0] - a[1] * b[1] - a[2] * b[2],
+    ]
+}
+
+fn cross(a: &[f64], b: &[f64]) -> [f64; 3] {
+    [a[1] * b[2] - a[2] * b[1], a[2] * b[0] - a[0] * b[2], a[0] * b[1] - a[1] * b[0]]
+}
@@ -10,6 +10,8 @@ use tolerance::Tolerances;
 
 use crate::saving::StateWriterBuilder;
 
+pub mod lie_group;
+pub mod second_order;
 pub mod state_array;
 pub mod state_vector;
 //pub mod state_vectors;
@@ -76,4 +78,31 @@ where
 }
 pub trait Adaptive {
     fn compute_error(&self, _x_prev: &Self, _x_tilde: &Self, _abs_tol: f64, _rel_tol: f64) -> f64;
+
+    /// The state as a flat slice of elements, used by implicit solvers to build their Newton systems.
+    /// States that return None can only be integrated with explicit methods.
+    fn elements(&self) -> Option<&[f64]> {
+        None
+    }
+
+    /// Mutable access to the flat slice of elements returned by `elements`.
+    fn elements_mut(&mut self) -> Option<&mut [f64]> {
+        None
+    }
+}
+
+/// States with components on a Lie group, such as attitude quaternions, which the
+/// Runge-Kutta-Munthe-Kaas methods integrate so that they stay on the group exactly.
+///
+/// Increments have the same layout as the state, with each group component's tangent vector
+/// stored in its place. Vector space components are simply added, so a state without group
+/// components is integrated exactly as by the matching Runge-Kutta method.
+pub trait LieGroup {
+    /// Sets `self` to `base` moved along `increment`: the exponential map for group components
+    /// and addition for the rest.
+    fn retract(&mut self, base: &Self, increment: &Self);
+
+    /// Converts the model `derivative` at `self`, which is `base` retracted along `increment`, into
+    /// the rate of change of the increment. Vector space components are left unchanged.
+    fn tangent(&self, increment: &Self, derivative: &mut Self);
 }
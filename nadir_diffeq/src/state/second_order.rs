@@ -0,0 +1,59 @@
+use crate::state::{Adaptive, OdeState};
+use serde::{Deserialize, Serialize};
+use std::ops::{AddAssign, MulAssign};
+
+/// A state split into position and velocity, for second order systems x'' = a(t, x).
+///
+/// Symplectic and Runge-Kutta-Nyström methods update the two halves separately, while first
+/// order methods treat the pair as a single state with derivative (velocity, acceleration).
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct SecondOrderState<State: OdeState> {
+    pub position: State,
+    pub velocity: State,
+}
+
+impl<State: OdeState> SecondOrderState<State> {
+    /// Constructs a new `SecondOrderState` from a position and velocity.
+    pub fn new(position: State, velocity: State) -> Self {
+        Self { position, velocity }
+    }
+}
+
+impl<State: OdeState> AddAssign<&Self> for SecondOrderState<State> {
+    /// Adds the position and velocity of the right-hand side into `self` in-place.
+    fn add_assign(&mut self, rhs: &Self) {
+        self.position += &rhs.position;
+        self.velocity += &rhs.velocity;
+    }
+}
+
+impl<State: OdeState> MulAssign<f64> for SecondOrderState<State> {
+    /// Multiplies the position and velocity in-place by the given scalar.
+    fn mul_assign(&mut self, rhs: f64) {
+        self.position *= rhs;
+        self.velocity *= rhs;
+    }
+}
+
+impl<State: OdeState + Adaptive> Adaptive for SecondOrderState<State> {
+    /// Root-mean-square of the position and velocity errors
+    fn compute_error(&self, x_prev: &Self, x_tilde: &Self, abs_tol: f64, rel_tol: f64) -> f64 {
+        let position_error = self
+            .position
+            .compute_error(
+                &x_prev.position,
+                &x_tilde.position,
+                abs_tol,
+                rel_tol,
+            );
+        let velocity_error = self
+            .velocity
+            .compute_error(
+                &x_prev.velocity,
+                &x_tilde.velocity,
+                abs_tol,
+                rel_tol,
+            );
+        ((position_error * position_error + velocity_error * velocity_error) / 2.0).sqrt()
+    }
+}
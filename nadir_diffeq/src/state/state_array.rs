@@ -1,4 +1,5 @@
 use crate::state::Adaptive;
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de};
 use std::ops::{AddAssign, Deref, DerefMut, MulAssign, SubAssign};
 use tolerance::{Tolerance, Tolerances, compute_error};
 use uncertainty::{UncertainValue, Uncertainty};
@@ -27,6 +28,31 @@ impl<const N: usize> Default for StateArray<N> {
     }
 }
 
+// serde only derives arrays up to 32 elements, so the array is (de)serialized as a sequence
+impl<const N: usize> Serialize for StateArray<N> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0
+            .as_slice()
+            .serialize(serializer)
+    }
+}
+
+impl<'de, const N: usize> Deserialize<'de> for StateArray<N> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = Vec::<f64>::deserialize(deserializer)?;
+        let n = value.len();
+        let array = value
+            .try_into()
+            .map_err(|_| {
+                de::Error::invalid_length(
+                    n,
+                    &format!("{N} elements").as_str(),
+                )
+            })?;
+        Ok(Self(array))
+    }
+}
+
 impl<const N: usize> AddAssign<&Self> for StateArray<N> {
     /// Adds each element from the right-hand side into `self` in-place.
     ///
@@ -69,6 +95,14 @@ impl<const N: usize> Adaptive for StateArray<N> {
         }
         (accum_error / N as f64).sqrt()
     }
+
+    fn elements(&self) -> Option<&[f64]> {
+        Some(&self[..])
+    }
+
+    fn elements_mut(&mut self) -> Option<&mut [f64]> {
+        Some(&mut self[..])
+    }
 }
 
 impl<const N: usize> Deref for StateArray<N> {
@@ -1,4 +1,8 @@
-use crate::state::Adaptive;
+use crate::state::{
+    Adaptive, LieGroup,
+    lie_group::{quaternion_retract, quaternion_tangent},
+};
+use serde::{Deserialize, Serialize};
 use std::ops::{AddAssign, Deref, DerefMut, MulAssign};
 use tolerance::compute_error;
 use uncertainty::{UncertainValue, Uncertainty};
@@ -6,12 +10,14 @@ use uncertainty::{UncertainValue, Uncertainty};
 /// A dynamic-sized vector type for use in ODE solvers.
 ///
 /// Unlike `StateArray`, this type supports arbitrary lengths and stores its data in a `Vec<f64>`.
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct StateVector {
     /// Internal storage for the vector values.
     value: Vec<f64>,
     /// Cached length of the vector to avoid repeated calls to `.len()`.
     n: usize,
+    /// Start indices of the unit quaternions [x, y, z, w] in the vector, for `LieGroup`.
+    quaternions: Vec<usize>,
 }
 
 impl StateVector {
@@ -22,7 +28,30 @@ impl StateVector {
     /// * `value` - A `Vec<f64>` representing the initial state.
     pub fn new(value: Vec<f64>) -> Self {
         let n = value.len();
-        Self { value, n }
+        Self { value, n, quaternions: Vec::new() }
+    }
+
+    /// Marks the 4 elements starting at `index` as a unit quaternion [x, y, z, w],
+    /// which Lie group methods then integrate on the unit sphere.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the quaternion does not fit in the vector.
+    pub fn with_quaternion(mut self, index: usize) -> Self {
+        if index + 4 > self.n {
+            panic!(
+                "quaternion at {} does not fit in state of length {}",
+                index, self.n
+            )
+        }
+        self.quaternions
+            .push(index);
+        self
+    }
+
+    /// Start indices of the elements marked as unit quaternions.
+    pub fn quaternions(&self) -> &[usize] {
+        &self.quaternions
     }
 
     /// Extends a `StateVector` with the content of another.
@@ -31,6 +60,10 @@ impl StateVector {
     ///
     /// * `n` - usize for the number of elements.
     pub fn extend(&mut self, other: &Self) {
+        for index in &other.quaternions {
+            self.quaternions
+                .push(self.n + index);
+        }
         self.value
             .extend_from_slice(&other.value);
         self.n = self
@@ -44,7 +77,7 @@ impl StateVector {
     ///
     /// * `n` - usize for the number of elements.
     pub fn with_capacity(n: usize) -> Self {
-        Self { value: Vec::with_capacity(n), n }
+        Self { value: Vec::with_capacity(n), n, quaternions: Vec::new() }
     }
 }
 
@@ -103,6 +136,38 @@ impl Adaptive for StateVector {
         }
         (accum_error / self.len() as f64).sqrt()
     }
+
+    fn elements(&self) -> Option<&[f64]> {
+        Some(&self[..])
+    }
+
+    fn elements_mut(&mut self) -> Option<&mut [f64]> {
+        Some(&mut self[..])
+    }
+}
+
+impl LieGroup for StateVector {
+    fn retract(&mut self, base: &Self, increment: &Self) {
+        self.clone_from(base);
+        *self += increment;
+        for &i in &self.quaternions {
+            quaternion_retract(
+                &mut self.value[i..i + 4],
+                &base.value[i..i + 4],
+                &increment.value[i..i + 3],
+            );
+        }
+    }
+
+    fn tangent(&self, increment: &Self, derivative: &mut Self) {
+        for &i in &self.quaternions {
+            quaternion_tangent(
+                &self.value[i..i + 4],
+                &increment.value[i..i + 3],
+                &mut derivative.value[i..i + 4],
+            );
+        }
+    }
 }
 
 // /// Stores optional component-wise tolerance rules for a `StateVector`.
@@ -0,0 +1,58 @@
+use csv::Writer;
+use serde::{Deserialize, Serialize};
+use std::{error::Error, fs::create_dir_all, path::Path};
+
+/// Diagnostics of a solve, for tuning the step control tolerances and limits.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct SolverStats {
+    /// Number of evaluations of the model's derivative, or acceleration for second order models.
+    pub function_evaluations: usize,
+    /// Number of steps taken.
+    pub accepted_steps: usize,
+    /// Number of steps rejected by the error control.
+    pub rejected_steps: usize,
+    /// Smallest step taken.
+    pub min_dt: f64,
+    /// Largest step taken.
+    pub max_dt: f64,
+    /// Mean of the steps taken.
+    pub mean_dt: f64,
+    /// Wall clock time spent processing events, including saving each step, in seconds.
+    pub event_time: f64,
+    /// Number of times the step size was raised to `AdaptiveStepControl::min_dt`.
+    pub min_dt_clamps: usize,
+    /// Number of times the step size was limited to `AdaptiveStepControl::max_dt`.
+    pub max_dt_clamps: usize,
+    /// Number of times a multistep method started again from its starter or from first order,
+    /// including at the initial time. Events restart Adams-Bashforth-Moulton and Gauss-Jackson.
+    pub restarts: usize,
+}
+
+impl SolverStats {
+    /// Records a step of size `dt` taken by the solver.
+    pub(crate) fn accept(&mut self, dt: f64) {
+        if self.accepted_steps == 0 {
+            self.min_dt = dt;
+            self.max_dt = dt;
+        } else {
+            self.min_dt = self
+                .min_dt
+                .min(dt);
+            self.max_dt = self
+                .max_dt
+                .max(dt);
+        }
+        self.accepted_steps += 1;
+        // running mean, to avoid storing every step
+        self.mean_dt += (dt - self.mean_dt) / self.accepted_steps as f64;
+    }
+
+    /// Writes the statistics to `stats.csv` in `folder`.
+    pub fn write(&self, folder: &Path) -> Result<(), Box<dyn Error>> {
+        create_dir_all(folder)?;
+        let mut writer = Writer::from_path(folder.join("stats.csv"))?;
+        writer.serialize(self)?;
+        writer.flush()?;
+        Ok(())
+    }
+}
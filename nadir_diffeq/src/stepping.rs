@@ -1,3 +1,5 @@
+use crate::stats::SolverStats;
+
 /// Specifies the type of step size control strategy used by the ODE solver.
 ///
 /// - `Fixed`: Uses a constant step size throughout integration.
@@ -81,6 +83,24 @@ impl AdaptiveStepControl {
         }
     }
 
+    /// Applies `min_dt` and `max_dt` to a new step size, counting each clamp in `stats`.
+    /// A step raised to `min_dt` is taken anyway with reduced accuracy.
+    pub(crate) fn limit(&self, mut dt: f64, stats: &mut SolverStats) -> f64 {
+        if let Some(max_dt) = self.max_dt
+            && dt > max_dt
+        {
+            dt = max_dt;
+            stats.max_dt_clamps += 1;
+        }
+        if let Some(min_dt) = self.min_dt
+            && dt < min_dt
+        {
+            dt = min_dt;
+            stats.min_dt_clamps += 1;
+        }
+        dt
+    }
+
     pub fn with_abs_tol(mut self, abs_tol: f64) -> Self {
         self.abs_tol = abs_tol;
         self
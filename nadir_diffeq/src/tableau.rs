@@ -1282,3 +1282,95 @@ impl ButcherTableau<9, 26> {
         ]),
     };
 }
+
+/// Coefficients of a Runge-Kutta-Nyström method for x'' = a(t, x).
+///
+/// Stage positions are x + c_i h v + h^2 sum_j a_ij k_j, where k_j are the stage accelerations.
+/// The new position is x + h v + h^2 sum_i b_position_i k_i and the new velocity is
+/// v + h sum_i b_velocity_i k_i. The tilde weights are the differences from the embedded method.
+#[derive(Debug)]
+pub struct RknTableau<const ORDER: usize, const STAGES: usize> {
+    pub a: [[f64; STAGES]; STAGES],
+    pub b_position: [f64; STAGES],
+    pub b_velocity: [f64; STAGES],
+    pub b_position_tilde: Option<[f64; STAGES]>,
+    pub b_velocity_tilde: Option<[f64; STAGES]>,
+    pub c: [f64; STAGES],
+    pub fsal: bool,
+}
+
+impl<const ORDER: usize, const STAGES: usize> RknTableau<ORDER, STAGES> {
+    /// The Runge-Kutta-Nyström method equivalent to applying an explicit Runge-Kutta method to the
+    /// first order system (x, v)' = (v, a(t, x)). Its coefficients are a = A^2, b_position = b A and
+    /// b_velocity = b, and the embedded weights follow the same way, so both the position and the
+    /// velocity have an error estimate. It takes the same number of acceleration evaluations as the
+    /// original method but skips the velocity stages, and it is FSAL when the original method is.
+    pub const fn from_runge_kutta(tableau: &ButcherTableau<ORDER, STAGES>) -> Self {
+        let mut a = [[0.0; STAGES]; STAGES];
+        let mut b_position = [0.0; STAGES];
+        let mut b_position_tilde = [0.0; STAGES];
+        let mut i = 0;
+        while i < STAGES {
+            let mut j = 0;
+            while j < STAGES {
+                let mut k = 0;
+                while k < STAGES {
+                    a[i][j] += tableau.a[i][k] * tableau.a[k][j];
+                    k += 1;
+                }
+                b_position[j] += tableau.b[i] * tableau.a[i][j];
+                if let Some(b_tilde) = &tableau.b_tilde {
+                    b_position_tilde[j] += b_tilde[i] * tableau.a[i][j];
+                }
+                j += 1;
+            }
+            i += 1;
+        }
+        let (b_position_tilde, b_velocity_tilde) = match tableau.b_tilde {
+            Some(b_tilde) => (
+                Some(b_position_tilde),
+                Some(b_tilde),
+            ),
+            None => (None, None),
+        };
+        Self {
+            a,
+            b_position,
+            b_velocity: tableau.b,
+            b_position_tilde,
+            b_velocity_tilde,
+            c: tableau.c,
+            fsal: tableau.fsal,
+        }
+    }
+}
+
+impl RknTableau<4, 4> {
+    // Nyström's 4th order method, with the new position as an FSAL 4th stage. The embedded
+    // 3rd order velocity uses the acceleration at the new position in place of the 3rd stage,
+    // and the embedded 3rd order position weights [0, 2/3, 0, -1/6] drop the c^2 condition.
+    pub const NYSTROM4: Self = Self {
+        a: [
+            [0., 0., 0., 0.],
+            [1. / 8., 0., 0., 0.],
+            [0., 1. / 2., 0., 0.],
+            [1. / 6., 1. / 3., 0., 0.],
+        ],
+        b_position: [1. / 6., 1. / 3., 0., 0.],
+        b_velocity: [1. / 6., 2. / 3., 1. / 6., 0.],
+        b_position_tilde: Some([1. / 6., -1. / 3., 0., 1. / 6.]),
+        b_velocity_tilde: Some([0., 0., 1. / 6., -1. / 6.]),
+        c: [0., 1. / 2., 1., 1.],
+        fsal: true,
+    };
+}
+
+impl RknTableau<6, 9> {
+    /// Verner's 6(5) pair as a Runge-Kutta-Nyström method
+    pub const VERNER6: Self = Self::from_runge_kutta(&ButcherTableau::<6, 9>::VERNER6);
+}
+
+impl RknTableau<9, 26> {
+    /// Verner's 9(8) pair as a Runge-Kutta-Nyström method
+    pub const VERNER9: Self = Self::from_runge_kutta(&ButcherTableau::<9, 26>::VERNER9);
+}
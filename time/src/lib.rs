@@ -272,6 +272,18 @@ impl Add<f64> for Time {
     }
 }
 
+impl Sub<f64> for Time {
+    type Output = Self;
+    fn sub(self, rhs: f64) -> Self::Output {
+        Time::from_sec_j2k(
+            self.value
+                .0
+                - rhs,
+            self.system,
+        )
+    }
+}
+
 impl Sub<Time> for Time {
     type Output = f64;
     fn sub(self, rhs: Time) -> f64 {